@@ -27,9 +27,8 @@ try:
         array_indexes.append(index)
         print(f"Index: {index}, Value: {fibonacci_numbers[index]}")
         prove_input = {
-            "a": 0,
-            "b": 1,
-            "result": str(fibonacci_numbers[index]),
+            "a": "0",
+            "b": "1",
             "num_of_rounds": index
         }
 
@@ -43,10 +42,11 @@ try:
 
             # שלח בקשה לשרת לאימות הוכחה
             verify_input = {
-                'pvk': prove_output['pvk'],
+                'key_id': prove_output['key_id'],
                 'proof': prove_output['proof'],
-                "a": 0,
-                "b": 1
+                "a": "0",
+                "b": "1",
+                "fib_number": prove_output['fib_number']
             }
 
             response = requests.post(f'{base_url}/fibbonaci/verify', json=verify_input)
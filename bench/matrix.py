@@ -41,11 +41,11 @@ try:
 
             # שלח בקשה לשרת לאימות הוכחה
             verify_input = {
-                'pvk': prove_output['pvk'],
+                'key_id': prove_output['key_id'],
                 'proof': prove_output['proof'],
-                'hash_a': prove_output['hash_a'],
-                'hash_b': prove_output['hash_b'],
-                'hash_c': prove_output['hash_c']
+                'com_a': prove_output['com_a'],
+                'com_b': prove_output['com_b'],
+                'com_c': prove_output['com_c']
             }
 
             response = requests.post(f'{base_url}/matrix_prove/verify', json=verify_input)
@@ -54,7 +54,7 @@ try:
                         'x': random_i_bit_number, #Seed number (x)
                         'j': prove_output['j'],
                         'proof': prove_output['proof'],
-                        'pvk': prove_output['pvk'],
+                        'key_id': prove_output['key_id'],
                     }
     
                     response = requests.post(f'{base_url}/prime_arkworks/verify', json=verify_input)
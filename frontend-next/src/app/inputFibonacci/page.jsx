@@ -42,32 +42,31 @@ const InputFibonacciPage = () => {
 
   const handleProve = () => {
     if (rounds && a && b) {
-      if (rounds > 183) {
-        setError("The maximum number of rounds is 186.");
-        return; // Exit the function if the condition is met
-      }
       setError("");
       // Reset previous stats
       setProvingTime(null);
       const requestData = {
-        a: parseInt(a),
-        b: parseInt(b),
+        // field elements are sent as decimal (or 0x hex) strings
+        a: String(a),
+        b: String(b),
         num_of_rounds: parseInt(rounds),
       };
-      localStorage.setItem("first_number", JSON.stringify(parseInt(a)));
-      localStorage.setItem("second_number", JSON.stringify(parseInt(b)));
+      localStorage.setItem("first_number", JSON.stringify(String(a)));
+      localStorage.setItem("second_number", JSON.stringify(String(b)));
+      localStorage.setItem("num_of_rounds", JSON.stringify(parseInt(rounds)));
       setIsLoadingProof(true);
       axios
         .post("http://127.0.0.1:8080/api/fibbonaci/prove", requestData)
         .then((response) => {
-          const { proof, pvk, fib_number, proving_time } = response.data;
+          const { proof, key_id, fib_number, proving_time } = response.data;
           localStorage.setItem("proof", JSON.stringify(proof));
-          localStorage.setItem("pvk", JSON.stringify(pvk));
+          localStorage.setItem("key_id", JSON.stringify(key_id));
           setProvingTime(proving_time);
           setNumber(fib_number);
           console.log(proof);
           setRecProof(proof);
-          localStorage.setItem("fib_number", JSON.stringify(parseInt(fib_number)));
+          // fib_number is F(n) mod r, a decimal string that may not fit in a js number
+          localStorage.setItem("fib_number", JSON.stringify(fib_number));
         })
         .catch((error) => {
           console.error("Error proving fibonacci:", error);
@@ -84,15 +83,16 @@ const InputFibonacciPage = () => {
 
   const handleVerify = () => {
     setVerifyResult("");
-    const pvk = JSON.parse(localStorage.getItem("pvk"));
+    // the server picks the keys of the number of rounds
+    const num_of_rounds = JSON.parse(localStorage.getItem("num_of_rounds"));
     const proof = JSON.parse(localStorage.getItem("proof"));
 
     const requestData = {
       proof: proof,
-      pvk: pvk,
-      a: parseInt(verifyA), // Send the edited 'a' value from verify input
-      b: parseInt(verifyB), // Send the edited 'b' value from verify input
-      fib_number: parseInt(verifyNumber), // Send the edited Fibonacci number from verify input
+      num_of_rounds: num_of_rounds,
+      a: String(verifyA), // Send the edited 'a' value from verify input
+      b: String(verifyB), // Send the edited 'b' value from verify input
+      fib_number: String(verifyNumber), // Send the edited Fibonacci number from verify input
     };
 
     setIsLoadingVerify(true);
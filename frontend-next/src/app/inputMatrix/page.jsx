@@ -3,6 +3,7 @@ import { useState, useEffect } from "react";
 import styles from "../../styles/matrix.module.css";
 import Link from "next/link";
 import axios from "axios";
+import { runJob } from "../jobs";
 
 const InputMatrixPage = () => {
   const [size, setSize] = useState(2);
@@ -18,6 +19,7 @@ const InputMatrixPage = () => {
   const [verifyResult, setVerifyResult] = useState("");
   const [verifyingTime, setVerifyingTime] = useState("");
   const [isLoadingProof, setIsLoadingProof] = useState(false);
+  const [proofPhase, setProofPhase] = useState("");
   const [isLoadingVerify, setIsLoadingVerify] = useState(false);
   const [setupTime, setSetupTime] = useState(null);
   const [provingTime, setProvingTime] = useState(null);
@@ -91,23 +93,23 @@ const InputMatrixPage = () => {
     };
 
     setIsLoadingProof(true);
-    axios
-      .post("http://127.0.0.1:8080/api/matrix_prove/prove", requestData)
-      .then((response) => {
-        console.log("Matrix prove response:", response.data);
+    setProofPhase("queued");
+    // large matrices take a while to prove, the proof runs as a job
+    runJob("matrix", requestData, setProofPhase)
+      .then((result) => {
+        console.log("Matrix prove response:", result);
         const {
           proof,
-          pvk,
-          hash_a,
-          hash_b,
-          hash_c,
+          com_a: hash_a,
+          com_b: hash_b,
+          com_c: hash_c,
           setup_time,
           proving_time,
           num_constraints,
           num_variables,
-        } = response.data;
+        } = result;
         localStorage.setItem("proof", JSON.stringify(proof));
-        localStorage.setItem("pvk", JSON.stringify(pvk));
+        localStorage.setItem("size", JSON.stringify(size));
         setHashes({ hash_a, hash_b, hash_c });
         setRecProof(proof);
         localStorage.setItem("hash_a", JSON.stringify(hash_a));
@@ -128,15 +130,16 @@ const InputMatrixPage = () => {
   };
 
   const handleVerify = () => {
-    const pvk = JSON.parse(localStorage.getItem("pvk"));
+    // the server picks the keys of n x n matrices
+    const size = JSON.parse(localStorage.getItem("size"));
     const proof = JSON.parse(localStorage.getItem("proof"));
 
     const requestData = {
-      pvk,
+      size,
       proof,
-      hash_a: verifyHashA,
-      hash_b: verifyHashB,
-      hash_c: verifyHashC,
+      com_a: verifyHashA,
+      com_b: verifyHashB,
+      com_c: verifyHashC,
     };
 
     setIsLoadingVerify(true);
@@ -233,7 +236,7 @@ const InputMatrixPage = () => {
           </button>
           {isLoadingProof && (
             <div className={styles.loading}>
-              <p>Loading proof...</p>
+              <p>Loading proof... ({proofPhase})</p>
             </div>
           )}
           {hashes.hash_a && !isLoadingProof && (
@@ -3,6 +3,7 @@ import { useState } from "react";
 import styles from "../../styles/verify.module.css";
 import Link from "next/link";
 import axios from "axios";
+import { runJob } from "../jobs";
 
 const InputPrimePage = () => {
   const [currentOption, setCurrentOption] = useState("prove");
@@ -10,6 +11,7 @@ const InputPrimePage = () => {
   const [rounds, setRounds] = useState(""); // Input number of rounds
   const [primeResult, setPrimeResult] = useState(null); // Store prime result from the proof
   const [isLoading, setIsLoading] = useState(false); // Loading state for proof
+  const [phase, setPhase] = useState(""); // What the proof job is doing
   const [verifyResult, setVerifyResult] = useState(""); // Verification result
   const [verifyingTime, setVerifyingTime] = useState(""); // Verification time
   const [recProof, setRecProof] = useState("");
@@ -22,14 +24,15 @@ const InputPrimePage = () => {
       };
 
       setIsLoading(true);
-      axios
-        .post("http://127.0.0.1:8080/api/prime_arkworks/prove", requestData)
-        .then((response) => {
-          console.log("Prime prove response:", response.data);
-          setPrimeResult(response.data);
+      setPhase("queued");
+      // the search and the proof can take minutes, they run as a job
+      runJob("prime", requestData, setPhase)
+        .then((result) => {
+          console.log("Prime prove response:", result);
+          setPrimeResult(result);
           const {
             proof,
-            pvk,
+            key_id,
             setup_time,
             proving_time,
             num_constraints,
@@ -37,9 +40,9 @@ const InputPrimePage = () => {
             j,
             found_prime,
             prime_num,
-          } = response.data;
+          } = result;
           localStorage.setItem("prime_proof", JSON.stringify(proof)); // Store proof as JSON
-          localStorage.setItem("pvk", JSON.stringify(pvk)); // Store pvk as JSON
+          localStorage.setItem("key_id", JSON.stringify(key_id)); // Store the key id as JSON
           localStorage.setItem("prime_num", prime_num); // Store prime number
           localStorage.setItem("prime_round", j); // Store the round where prime was found
           localStorage.setItem("seed_number", number); // Save the seed number (x)
@@ -58,7 +61,7 @@ const InputPrimePage = () => {
 
   const handleVerifyPrime = () => {
     const proof = JSON.parse(localStorage.getItem("prime_proof")); // Parse proof from localStorage
-    const pvk = JSON.parse(localStorage.getItem("pvk")); // Parse pvk from localStorage
+    const key_id = JSON.parse(localStorage.getItem("key_id")); // Parse the key id from localStorage
     // read the pvk from pvk.txt :
     // const fs = require("fs");
     // const pvk = fs.readFileSync("pvk.txt", "utf8");
@@ -70,7 +73,7 @@ const InputPrimePage = () => {
       x: parseInt(seed_number), // Seed number (x)
       j: parseInt(prime_round), // The round where the prime was found (j)
       proof, // Proof
-      pvk, // PVK
+      key_id, // the id of the verifying key
     };
 
     setIsLoading(true);
@@ -138,7 +141,7 @@ const InputPrimePage = () => {
             Prove
           </button>
 
-          {isLoading && <p>Loading proof...</p>}
+          {isLoading && <p>Loading proof... ({phase})</p>}
 
           {primeResult && !isLoading && (
             <div className={styles.resultContainer}>
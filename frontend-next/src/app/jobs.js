@@ -0,0 +1,20 @@
+import axios from "axios";
+
+const API = "http://127.0.0.1:8080/api";
+
+// Runs a proof as a job on the backend and polls it until it finishes, so a long proof
+// doesn't hold a request open. onPhase gets "queued", then "witness", "setup" and "prove".
+export const runJob = async (circuit, input, onPhase = () => {}) => {
+  const { data: job } = await axios.post(`${API}/jobs`, { circuit, input });
+  for (;;) {
+    const { data } = await axios.get(`${API}/jobs/${job.id}`);
+    if (data.status === "done") {
+      return data.result;
+    }
+    if (data.status === "failed" || data.status === "cancelled") {
+      throw new Error(data.error ? data.error.message : "the job was cancelled");
+    }
+    onPhase(data.phase || data.status);
+    await new Promise((resolve) => setTimeout(resolve, 1000));
+  }
+};
@@ -0,0 +1,129 @@
+use ark_ec::pairing::{Pairing, PairingOutput};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use std::ops::{Add, Mul};
+
+// the pairing commitments of SnarkPack. a vector of G1 elements is committed with a key v of
+// G2 elements, a vector of G2 elements with a key w of G1 elements, both are the pairing
+// product of the vector with the key. every key has a part for a and a part for b, which
+// makes the commitment a pair of target group elements.
+
+// h^{a^i} and h^{b^i}, scaled by the verifier challenge r^{-i} once it is known
+#[derive(Clone, Debug)]
+pub struct VKey<E: Pairing> {
+    pub a: Vec<E::G2>,
+    pub b: Vec<E::G2>,
+}
+
+// g^{a^{n+i}} and g^{b^{n+i}}
+#[derive(Clone, Debug)]
+pub struct WKey<E: Pairing> {
+    pub a: Vec<E::G1>,
+    pub b: Vec<E::G1>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Commitment<E: Pairing> {
+    pub t: PairingOutput<E>,
+    pub u: PairingOutput<E>,
+}
+
+impl<E: Pairing> Add for Commitment<E> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            t: self.t + other.t,
+            u: self.u + other.u,
+        }
+    }
+}
+
+impl<E: Pairing> Mul<E::ScalarField> for Commitment<E> {
+    type Output = Self;
+
+    fn mul(self, scalar: E::ScalarField) -> Self {
+        Self {
+            t: self.t * scalar,
+            u: self.u * scalar,
+        }
+    }
+}
+
+// the commitment to the A and B of the proofs, e(A, v) e(w, B) for both parts of the keys
+pub fn commit_pair<E: Pairing>(
+    v: &VKey<E>,
+    w: &WKey<E>,
+    a: &[E::G1],
+    b: &[E::G2],
+) -> Commitment<E> {
+    Commitment {
+        t: E::multi_pairing(a.iter().chain(&w.a), v.a.iter().chain(b)),
+        u: E::multi_pairing(a.iter().chain(&w.b), v.b.iter().chain(b)),
+    }
+}
+
+// the commitment to the C of the proofs, e(C, v)
+pub fn commit_single<E: Pairing>(v: &VKey<E>, c: &[E::G1]) -> Commitment<E> {
+    Commitment {
+        t: E::multi_pairing(c, &v.a),
+        u: E::multi_pairing(c, &v.b),
+    }
+}
+
+impl<E: Pairing> VKey<E> {
+    pub fn split(&self, at: usize) -> (Self, Self) {
+        let (a_left, a_right) = self.a.split_at(at);
+        let (b_left, b_right) = self.b.split_at(at);
+        (
+            Self {
+                a: a_left.to_vec(),
+                b: b_left.to_vec(),
+            },
+            Self {
+                a: a_right.to_vec(),
+                b: b_right.to_vec(),
+            },
+        )
+    }
+
+    // left + x right, the verifier challenges are inverted for the keys of G1 vectors
+    pub fn fold(&self, right: &Self, x: E::ScalarField) -> Self {
+        Self {
+            a: fold(&self.a, &right.a, x),
+            b: fold(&self.b, &right.b, x),
+        }
+    }
+}
+
+impl<E: Pairing> WKey<E> {
+    pub fn split(&self, at: usize) -> (Self, Self) {
+        let (a_left, a_right) = self.a.split_at(at);
+        let (b_left, b_right) = self.b.split_at(at);
+        (
+            Self {
+                a: a_left.to_vec(),
+                b: b_left.to_vec(),
+            },
+            Self {
+                a: a_right.to_vec(),
+                b: b_right.to_vec(),
+            },
+        )
+    }
+
+    pub fn fold(&self, right: &Self, x: E::ScalarField) -> Self {
+        Self {
+            a: fold(&self.a, &right.a, x),
+            b: fold(&self.b, &right.b, x),
+        }
+    }
+}
+
+// left + x right, element by element
+pub fn fold<T, S>(left: &[T], right: &[T], x: S) -> Vec<T>
+where
+    T: Copy + Add<Output = T> + Mul<S, Output = T>,
+    S: Copy,
+{
+    left.iter().zip(right).map(|(l, r)| *l + *r * x).collect()
+}
@@ -0,0 +1,387 @@
+pub mod commitment;
+pub mod srs;
+pub mod tipp_mipp;
+
+use ark_ec::pairing::{Pairing, PairingOutput};
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{Field, PrimeField};
+use ark_groth16::{PreparedVerifyingKey, Proof};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use commitment::{commit_pair, commit_single, Commitment};
+use sha2::{Digest, Sha256};
+use srs::{AggregationSrs, VerifierSrs};
+use std::fmt;
+use tipp_mipp::TippMippProof;
+
+// SnarkPack: n groth16 proofs (A_i, B_i, C_i) of one verifying key in a proof of O(log n)
+// group elements. with a challenge r the n verification equations become one,
+//   prod e(A_i, B_i)^{r^i} = e(alpha, beta)^{sum r^i} e(sum r^i PI_i, gamma) e(sum r^i C_i, delta)
+// the prover commits to the A, B and C of the proofs before r is known, and shows with TIPP
+// and MIPP that Z_ab = prod e(A_i, B_i)^{r^i} and Z_c = sum r^i C_i are the products of the
+// committed vectors. the verifier checks the equation with Z_ab and Z_c, its work is
+// logarithmic in n apart from the combination of the public inputs. the number of proofs is
+// padded to a power of two with copies of the last proof.
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AggregationError {
+    TooFewProofs(usize),
+    // the public inputs of a proof don't have the length of the verifying key
+    PublicInputs(usize),
+    SrsSize { proofs: usize, srs: usize },
+}
+
+impl fmt::Display for AggregationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AggregationError::TooFewProofs(n) => {
+                write!(f, "at least two proofs are aggregated, not {}", n)
+            }
+            AggregationError::PublicInputs(i) => write!(
+                f,
+                "the public inputs of proof {} don't match the verifying key",
+                i
+            ),
+            AggregationError::SrsSize { proofs, srs } => write!(
+                f,
+                "{} proofs need an srs of size {}, not {}",
+                proofs,
+                proofs.next_power_of_two(),
+                srs
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AggregationError {}
+
+// a Fiat-Shamir transcript over sha256, every challenge is the hash of all the messages
+// before it and is non zero, so it can be inverted
+pub struct Transcript(Vec<u8>);
+
+impl Transcript {
+    pub fn new(label: &[u8]) -> Self {
+        Self(label.to_vec())
+    }
+
+    pub fn append<T: CanonicalSerialize>(&mut self, message: &T) {
+        message.serialize_compressed(&mut self.0).unwrap();
+    }
+
+    pub fn challenge<F: PrimeField>(&mut self) -> F {
+        for counter in 0u32.. {
+            let digest = Sha256::new()
+                .chain_update(&self.0)
+                .chain_update(counter.to_le_bytes())
+                .finalize();
+            let challenge = F::from_le_bytes_mod_order(&digest);
+            if !challenge.is_zero() {
+                self.0 = digest.to_vec();
+                return challenge;
+            }
+        }
+        unreachable!()
+    }
+}
+
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AggregateProof<E: Pairing> {
+    pub com_ab: Commitment<E>,
+    pub com_c: Commitment<E>,
+    // prod e(A_i, B_i)^{r^i}
+    pub z_ab: PairingOutput<E>,
+    // sum r^i C_i
+    pub z_c: E::G1Affine,
+    pub tmipp: TippMippProof<E>,
+}
+
+// the statement of every proof, padded like the proofs. r is bound to the verifying key and
+// the public inputs, an aggregate proof can't be replayed for other statements
+fn statement_transcript<E: Pairing>(
+    pvk: &PreparedVerifyingKey<E>,
+    public_inputs: &[Vec<E::ScalarField>],
+    com_ab: &Commitment<E>,
+    com_c: &Commitment<E>,
+) -> Transcript {
+    let mut transcript = Transcript::new(b"snarkpack");
+    transcript.append(&pvk.vk);
+    transcript.append(&public_inputs.to_vec());
+    transcript.append(com_ab);
+    transcript.append(com_c);
+    transcript
+}
+
+fn padded<T: Clone>(values: &[T], n: usize) -> Vec<T> {
+    let mut padded = values.to_vec();
+    padded.resize(n, values[values.len() - 1].clone());
+    padded
+}
+
+// 1, r, r^2, .. r^{n-1}
+fn powers<F: Field>(r: F, n: usize) -> Vec<F> {
+    std::iter::successors(Some(F::one()), |power| Some(*power * r))
+        .take(n)
+        .collect()
+}
+
+fn check_public_inputs<E: Pairing>(
+    pvk: &PreparedVerifyingKey<E>,
+    public_inputs: &[Vec<E::ScalarField>],
+) -> Result<(), AggregationError> {
+    match public_inputs
+        .iter()
+        .position(|inputs| inputs.len() + 1 != pvk.vk.gamma_abc_g1.len())
+    {
+        Some(i) => Err(AggregationError::PublicInputs(i)),
+        None => Ok(()),
+    }
+}
+
+// aggregates the proofs of the public inputs. it doesn't check the proofs, an invalid one
+// makes the aggregate proof invalid
+pub fn aggregate_proofs<E: Pairing>(
+    srs: &AggregationSrs<E>,
+    pvk: &PreparedVerifyingKey<E>,
+    public_inputs: &[Vec<E::ScalarField>],
+    proofs: &[Proof<E>],
+) -> Result<AggregateProof<E>, AggregationError> {
+    assert_eq!(public_inputs.len(), proofs.len());
+    if proofs.len() < 2 {
+        return Err(AggregationError::TooFewProofs(proofs.len()));
+    }
+    check_public_inputs(pvk, public_inputs)?;
+    let n = proofs.len().next_power_of_two();
+    if srs.size() != n {
+        return Err(AggregationError::SrsSize {
+            proofs: proofs.len(),
+            srs: srs.size(),
+        });
+    }
+    let proofs = padded(proofs, n);
+    let a = proofs.iter().map(|p| p.a.into_group()).collect::<Vec<_>>();
+    let b = proofs.iter().map(|p| p.b.into_group()).collect::<Vec<_>>();
+    let c = proofs.iter().map(|p| p.c.into_group()).collect::<Vec<_>>();
+
+    let (v, w) = srs.commitment_keys();
+    let com_ab = commit_pair(&v, &w, &a, &b);
+    let com_c = commit_single(&v, &c);
+    let mut transcript = statement_transcript(pvk, &padded(public_inputs, n), &com_ab, &com_c);
+    let r = transcript.challenge::<E::ScalarField>();
+    let r_inv = r.inverse().unwrap();
+
+    // A and C scaled by r^i and v by r^-i keep the commitments of the unscaled vectors
+    let r_powers = powers(r, n);
+    let a_r = a
+        .iter()
+        .zip(&r_powers)
+        .map(|(a, r)| *a * r)
+        .collect::<Vec<_>>();
+    let c_r = c
+        .iter()
+        .zip(&r_powers)
+        .map(|(c, r)| *c * r)
+        .collect::<Vec<_>>();
+    let r_inv_powers = powers(r_inv, n);
+    let scale = |key: &[E::G2]| key.iter().zip(&r_inv_powers).map(|(v, r)| *v * r).collect();
+    let v_r = commitment::VKey {
+        a: scale(&v.a),
+        b: scale(&v.b),
+    };
+
+    let z_ab = E::multi_pairing(&a_r, &b);
+    let z_c = c_r.iter().sum::<E::G1>().into_affine();
+    transcript.append(&(z_ab, z_c));
+    let tmipp = tipp_mipp::prove(srs, &mut transcript, a_r, b, c_r, v_r, w, r_inv);
+    Ok(AggregateProof {
+        com_ab,
+        com_c,
+        z_ab,
+        z_c,
+        tmipp,
+    })
+}
+
+// verifies the aggregate proof of the proofs of the public inputs, in the order they were
+// aggregated
+pub fn verify_aggregate_proof<E: Pairing>(
+    srs: &VerifierSrs<E>,
+    pvk: &PreparedVerifyingKey<E>,
+    public_inputs: &[Vec<E::ScalarField>],
+    proof: &AggregateProof<E>,
+) -> bool {
+    if public_inputs.len() < 2 || check_public_inputs(pvk, public_inputs).is_err() {
+        return false;
+    }
+    let n = public_inputs.len().next_power_of_two();
+    let public_inputs = padded(public_inputs, n);
+    let mut transcript = statement_transcript(pvk, &public_inputs, &proof.com_ab, &proof.com_c);
+    let r = transcript.challenge::<E::ScalarField>();
+    let r_inv = r.inverse().unwrap();
+    transcript.append(&(proof.z_ab, proof.z_c));
+    if !tipp_mipp::verify(
+        srs,
+        &mut transcript,
+        &proof.tmipp,
+        n,
+        proof.com_ab,
+        proof.com_c,
+        proof.z_ab,
+        proof.z_c.into(),
+        r_inv,
+    ) {
+        return false;
+    }
+
+    // sum r^i PI_i = (sum r^i) gamma_abc_0 + sum_j (sum_i r^i x_ij) gamma_abc_{j+1}
+    let r_powers = powers(r, n);
+    let r_sum = r_powers.iter().sum::<E::ScalarField>();
+    let mut scalars = vec![r_sum];
+    for j in 0..pvk.vk.gamma_abc_g1.len() - 1 {
+        scalars.push(
+            public_inputs
+                .iter()
+                .zip(&r_powers)
+                .map(|(inputs, r)| inputs[j] * r)
+                .sum(),
+        );
+    }
+    let pi = E::G1::msm_unchecked(&pvk.vk.gamma_abc_g1, &scalars);
+    // e(sum r^i PI_i, -gamma) e(Z_c, -delta) Z_ab = e(alpha, beta)^{sum r^i}
+    E::multi_pairing(
+        [pi, proof.z_c.into()],
+        [pvk.gamma_g2_neg_pc.clone(), pvk.delta_g2_neg_pc.clone()],
+    ) + proof.z_ab
+        == PairingOutput(pvk.alpha_g1_beta_g2) * r_sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::backend::fibbonaci_handler::fibonacci;
+    use crate::arkworks::constraints::fibbonaci::FibonacciCircuit;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_groth16::Groth16;
+    use ark_snark::{CircuitSpecificSetupSNARK, SNARK};
+    use rand::rngs::OsRng;
+
+    fn circuit(a: u64, b: u64) -> FibonacciCircuit<Fr> {
+        let (a, b) = (Fr::from(a), Fr::from(b));
+        FibonacciCircuit {
+            a: Some(a),
+            b: Some(b),
+            num_of_steps: 10,
+            result: Some(fibonacci(10, a, b)),
+        }
+    }
+
+    fn proofs(
+        count: u64,
+    ) -> (
+        PreparedVerifyingKey<Bls12_381>,
+        Vec<Vec<Fr>>,
+        Vec<Proof<Bls12_381>>,
+    ) {
+        let (pk, vk) = Groth16::<Bls12_381>::setup(circuit(0, 1), &mut OsRng).unwrap();
+        let inputs = (0..count)
+            .map(|i| {
+                let (a, b) = (Fr::from(i), Fr::from(i + 1));
+                vec![a, b, fibonacci(10, a, b)]
+            })
+            .collect::<Vec<_>>();
+        let proofs = (0..count)
+            .map(|i| Groth16::<Bls12_381>::prove(&pk, circuit(i, i + 1), &mut OsRng).unwrap())
+            .collect();
+        (ark_groth16::prepare_verifying_key(&vk), inputs, proofs)
+    }
+
+    #[test]
+    fn test_aggregate_proofs() {
+        // 5 proofs are padded to 8
+        let (pvk, inputs, proofs) = proofs(5);
+        let srs = AggregationSrs::<Bls12_381>::setup(8, &mut OsRng);
+        let proof = aggregate_proofs(&srs, &pvk, &inputs, &proofs).unwrap();
+        assert_eq!(proof.tmipp.rounds.len(), 3);
+        assert!(verify_aggregate_proof(
+            &srs.verifier(),
+            &pvk,
+            &inputs,
+            &proof
+        ));
+
+        // through its bytes
+        let mut bytes = vec![];
+        proof.serialize_compressed(&mut bytes).unwrap();
+        let decoded = AggregateProof::<Bls12_381>::deserialize_compressed(&bytes[..]).unwrap();
+        assert!(verify_aggregate_proof(
+            &srs.verifier(),
+            &pvk,
+            &inputs,
+            &decoded
+        ));
+
+        // other statements, another order, fewer proofs and another srs
+        let mut wrong = inputs.clone();
+        wrong[2][2] += Fr::from(1u64);
+        assert!(!verify_aggregate_proof(
+            &srs.verifier(),
+            &pvk,
+            &wrong,
+            &proof
+        ));
+        let mut swapped = inputs.clone();
+        swapped.swap(0, 1);
+        assert!(!verify_aggregate_proof(
+            &srs.verifier(),
+            &pvk,
+            &swapped,
+            &proof
+        ));
+        assert!(!verify_aggregate_proof(
+            &srs.verifier(),
+            &pvk,
+            &inputs[..4],
+            &proof
+        ));
+        let other_srs = AggregationSrs::<Bls12_381>::setup(8, &mut OsRng);
+        assert!(!verify_aggregate_proof(
+            &other_srs.verifier(),
+            &pvk,
+            &inputs,
+            &proof
+        ));
+    }
+
+    #[test]
+    fn test_invalid_proofs_dont_aggregate() {
+        let (pvk, inputs, proofs) = proofs(4);
+        let srs = AggregationSrs::<Bls12_381>::setup(4, &mut OsRng);
+        // a proof of another statement
+        let mut invalid = proofs.clone();
+        invalid[3] = proofs[2].clone();
+        let proof = aggregate_proofs(&srs, &pvk, &inputs, &invalid).unwrap();
+        assert!(!verify_aggregate_proof(
+            &srs.verifier(),
+            &pvk,
+            &inputs,
+            &proof
+        ));
+
+        // a changed aggregate doesn't verify either
+        let mut proof = aggregate_proofs(&srs, &pvk, &inputs, &proofs).unwrap();
+        proof.z_c = (proof.z_c + pvk.vk.gamma_abc_g1[0]).into_affine();
+        assert!(!verify_aggregate_proof(
+            &srs.verifier(),
+            &pvk,
+            &inputs,
+            &proof
+        ));
+
+        assert_eq!(
+            aggregate_proofs(&srs, &pvk, &inputs[..1], &proofs[..1]).err(),
+            Some(AggregationError::TooFewProofs(1))
+        );
+        assert_eq!(
+            aggregate_proofs(&srs, &pvk, &inputs[..2], &proofs[..2]).err(),
+            Some(AggregationError::SrsSize { proofs: 2, srs: 4 })
+        );
+    }
+}
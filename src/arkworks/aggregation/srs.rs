@@ -0,0 +1,86 @@
+use super::commitment::{VKey, WKey};
+use ark_ec::pairing::Pairing;
+use ark_ec::scalar_mul::fixed_base::FixedBase;
+use ark_ec::{CurveGroup, Group};
+use ark_ff::{Field, PrimeField, UniformRand};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand::{CryptoRng, RngCore};
+
+// the structured reference string of the aggregation of n proofs: the powers of two secrets a
+// and b in both groups, g^{a^i}, g^{b^i} for i < 2n and h^{a^i}, h^{b^i} for i < n. the
+// setup uses OsRng like the groth16 keys, the secrets are dropped once the powers are known.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AggregationSrs<E: Pairing> {
+    pub g_alpha: Vec<E::G1Affine>,
+    pub g_beta: Vec<E::G1Affine>,
+    pub h_alpha: Vec<E::G2Affine>,
+    pub h_beta: Vec<E::G2Affine>,
+}
+
+// the part of the srs a verifier needs to check the openings of the final commitment keys
+#[derive(Clone, Debug)]
+pub struct VerifierSrs<E: Pairing> {
+    pub g: E::G1,
+    pub h: E::G2,
+    pub g_alpha: E::G1,
+    pub g_beta: E::G1,
+    pub h_alpha: E::G2,
+    pub h_beta: E::G2,
+}
+
+fn powers<G: CurveGroup>(generator: G, secret: G::ScalarField, len: usize) -> Vec<G::Affine> {
+    let scalars = std::iter::successors(Some(G::ScalarField::ONE), |power| Some(*power * secret))
+        .take(len)
+        .collect::<Vec<_>>();
+    let scalar_size = G::ScalarField::MODULUS_BIT_SIZE as usize;
+    let window = FixedBase::get_mul_window_size(len);
+    let table = FixedBase::get_window_table(scalar_size, window, generator);
+    G::normalize_batch(&FixedBase::msm::<G>(scalar_size, window, &table, &scalars))
+}
+
+impl<E: Pairing> AggregationSrs<E> {
+    // n is the number of proofs, a power of two and at least two
+    pub fn setup<R: RngCore + CryptoRng>(n: usize, rng: &mut R) -> Self {
+        assert!(n >= 2 && n.is_power_of_two());
+        let (a, b) = (E::ScalarField::rand(rng), E::ScalarField::rand(rng));
+        let (g, h) = (E::G1::generator(), E::G2::generator());
+        Self {
+            g_alpha: powers(g, a, 2 * n),
+            g_beta: powers(g, b, 2 * n),
+            h_alpha: powers(h, a, n),
+            h_beta: powers(h, b, n),
+        }
+    }
+
+    // the number of proofs the srs aggregates
+    pub fn size(&self) -> usize {
+        self.h_alpha.len()
+    }
+
+    // the keys that commit to the n proofs: v = (h^{a^i}, h^{b^i}) and w = (g^{a^{n+i}},
+    // g^{b^{n+i}}) for i < n
+    pub fn commitment_keys(&self) -> (VKey<E>, WKey<E>) {
+        let n = self.size();
+        let projective = |points: &[E::G1Affine]| points.iter().map(|p| (*p).into()).collect();
+        let v = VKey {
+            a: self.h_alpha.iter().map(|p| (*p).into()).collect(),
+            b: self.h_beta.iter().map(|p| (*p).into()).collect(),
+        };
+        let w = WKey {
+            a: projective(&self.g_alpha[n..]),
+            b: projective(&self.g_beta[n..]),
+        };
+        (v, w)
+    }
+
+    pub fn verifier(&self) -> VerifierSrs<E> {
+        VerifierSrs {
+            g: self.g_alpha[0].into(),
+            h: self.h_alpha[0].into(),
+            g_alpha: self.g_alpha[1].into(),
+            g_beta: self.g_beta[1].into(),
+            h_alpha: self.h_alpha[1].into(),
+            h_beta: self.h_beta[1].into(),
+        }
+    }
+}
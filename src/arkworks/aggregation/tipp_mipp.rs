@@ -0,0 +1,259 @@
+use super::commitment::{commit_pair, commit_single, fold, Commitment, VKey, WKey};
+use super::srs::{AggregationSrs, VerifierSrs};
+use super::Transcript;
+use ark_ec::pairing::{Pairing, PairingOutput};
+use ark_ec::{CurveGroup, VariableBaseMSM};
+use ark_ff::{Field, One, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+// TIPP and MIPP of SnarkPack, run together with the same challenges so they share their
+// commitment keys. TIPP shows that Z = e(A, B) for the A and B committed in com_ab, MIPP that
+// Z = <C, s> for the C committed in com_c. every round halves the vectors: the prover sends
+// the cross terms of the halves, the verifier answers with a challenge x and both fold
+//   A' = A_l + x A_r, B' = B_l + x^-1 B_r, C' = C_l + x C_r, s' = s_l + x^-1 s_r,
+//   v' = v_l + x^-1 v_r, w' = w_l + x w_r
+// after log n rounds the vectors have one element, and the final keys are checked with KZG
+// openings of the polynomials whose coefficients are the products of the challenges.
+
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct GipaRound<E: Pairing> {
+    // e(A_r, v_l) e(w_r, B_l) and e(A_l, v_r) e(w_l, B_r)
+    pub comm_ab: [Commitment<E>; 2],
+    // e(C_r, v_l) and e(C_l, v_r)
+    pub comm_c: [Commitment<E>; 2],
+    // e(A_r, B_l) and e(A_l, B_r)
+    pub z_ab: [PairingOutput<E>; 2],
+    // <C_r, s_l> and <C_l, s_r>
+    pub z_c: [E::G1Affine; 2],
+}
+
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct TippMippProof<E: Pairing> {
+    pub rounds: Vec<GipaRound<E>>,
+    pub final_a: E::G1Affine,
+    pub final_b: E::G2Affine,
+    pub final_c: E::G1Affine,
+    pub final_v: [E::G2Affine; 2],
+    pub final_w: [E::G1Affine; 2],
+    // the KZG openings of the final keys, for a and for b
+    pub v_opening: [E::G2Affine; 2],
+    pub w_opening: [E::G1Affine; 2],
+}
+
+// the coefficients of prod_j (1 + x_j X^{n / 2^{j+1}}), the first challenge folds the halves
+// of the whole vector
+fn challenge_coefficients<F: Field>(challenges: &[F]) -> Vec<F> {
+    let mut coefficients = vec![F::one()];
+    for x in challenges.iter().rev() {
+        let scaled = coefficients.iter().map(|c| *c * x).collect::<Vec<_>>();
+        coefficients.extend(scaled);
+    }
+    coefficients
+}
+
+// prod_j (1 + x_j z^{n / 2^{j+1}})
+fn evaluate_challenges<F: Field>(challenges: &[F], z: F) -> F {
+    let mut power = z;
+    let mut value = F::one();
+    for x in challenges.iter().rev() {
+        value *= F::one() + *x * power;
+        power.square_in_place();
+    }
+    value
+}
+
+// the quotient of f(X) - f(z) by X - z
+fn divide_by_linear<F: Field>(coefficients: &[F], z: F) -> Vec<F> {
+    let mut quotient = vec![F::zero(); coefficients.len().saturating_sub(1)];
+    let mut carry = F::zero();
+    for i in (1..coefficients.len()).rev() {
+        carry = coefficients[i] + carry * z;
+        quotient[i - 1] = carry;
+    }
+    quotient
+}
+
+// the KZG opening at z of the polynomial committed with the powers of a secret
+fn open<G: VariableBaseMSM>(
+    powers: &[G::MulBase],
+    coefficients: &[G::ScalarField],
+    z: G::ScalarField,
+) -> G {
+    let quotient = divide_by_linear(coefficients, z);
+    G::msm_unchecked(&powers[..quotient.len()], &quotient)
+}
+
+// the prover of TIPP and MIPP for A and C scaled by r^i, s of ones and v scaled by r^-i, so
+// that the commitments are the ones of the unscaled vectors
+#[allow(clippy::too_many_arguments)]
+pub fn prove<E: Pairing>(
+    srs: &AggregationSrs<E>,
+    transcript: &mut Transcript,
+    a: Vec<E::G1>,
+    b: Vec<E::G2>,
+    c: Vec<E::G1>,
+    v: VKey<E>,
+    w: WKey<E>,
+    r_inv: E::ScalarField,
+) -> TippMippProof<E> {
+    let n = a.len();
+    let (mut a, mut b, mut c, mut v, mut w) = (a, b, c, v, w);
+    let mut s = vec![E::ScalarField::one(); n];
+    let mut rounds = vec![];
+    let mut challenges = vec![];
+    while a.len() > 1 {
+        let half = a.len() / 2;
+        let ((a_l, a_r), (b_l, b_r)) = (a.split_at(half), b.split_at(half));
+        let ((c_l, c_r), (s_l, s_r)) = (c.split_at(half), s.split_at(half));
+        let ((v_l, v_r), (w_l, w_r)) = (v.split(half), w.split(half));
+        let round = GipaRound {
+            comm_ab: [
+                commit_pair(&v_l, &w_r, a_r, b_l),
+                commit_pair(&v_r, &w_l, a_l, b_r),
+            ],
+            comm_c: [commit_single(&v_l, c_r), commit_single(&v_r, c_l)],
+            z_ab: [E::multi_pairing(a_r, b_l), E::multi_pairing(a_l, b_r)],
+            z_c: [
+                E::G1::msm_unchecked(&E::G1::normalize_batch(c_r), s_l).into_affine(),
+                E::G1::msm_unchecked(&E::G1::normalize_batch(c_l), s_r).into_affine(),
+            ],
+        };
+        transcript.append(&round);
+        let x = transcript.challenge::<E::ScalarField>();
+        let x_inv = x.inverse().unwrap();
+
+        a = fold(a_l, a_r, x);
+        b = fold(b_l, b_r, x_inv);
+        c = fold(c_l, c_r, x);
+        s = fold(s_l, s_r, x_inv);
+        v = v_l.fold(&v_r, x_inv);
+        w = w_l.fold(&w_r, x);
+        rounds.push(round);
+        challenges.push(x);
+    }
+
+    let final_v = [v.a[0].into_affine(), v.b[0].into_affine()];
+    let final_w = [w.a[0].into_affine(), w.b[0].into_affine()];
+    transcript.append(&(final_v, final_w));
+    let z = transcript.challenge::<E::ScalarField>();
+
+    // v is the key of (X / r)^i with the inverse challenges, w the key of X^{n+i}
+    let inverses = challenges
+        .iter()
+        .map(|x| x.inverse().unwrap())
+        .collect::<Vec<_>>();
+    let mut r_power = E::ScalarField::one();
+    let v_poly = challenge_coefficients(&inverses)
+        .into_iter()
+        .map(|coefficient| {
+            let scaled = coefficient * r_power;
+            r_power *= r_inv;
+            scaled
+        })
+        .collect::<Vec<_>>();
+    let w_poly = [
+        vec![E::ScalarField::zero(); n],
+        challenge_coefficients(&challenges),
+    ]
+    .concat();
+
+    TippMippProof {
+        rounds,
+        final_a: a[0].into_affine(),
+        final_b: b[0].into_affine(),
+        final_c: c[0].into_affine(),
+        final_v,
+        final_w,
+        v_opening: [
+            open::<E::G2>(&srs.h_alpha, &v_poly, z).into_affine(),
+            open::<E::G2>(&srs.h_beta, &v_poly, z).into_affine(),
+        ],
+        w_opening: [
+            open::<E::G1>(&srs.g_alpha, &w_poly, z).into_affine(),
+            open::<E::G1>(&srs.g_beta, &w_poly, z).into_affine(),
+        ],
+    }
+}
+
+// the verifier of prove for the commitments com_ab and com_c, Z_ab = e(A, B) and Z_c = <C, s>
+// of n proofs
+#[allow(clippy::too_many_arguments)]
+pub fn verify<E: Pairing>(
+    srs: &VerifierSrs<E>,
+    transcript: &mut Transcript,
+    proof: &TippMippProof<E>,
+    n: usize,
+    com_ab: Commitment<E>,
+    com_c: Commitment<E>,
+    z_ab: PairingOutput<E>,
+    z_c: E::G1,
+    r_inv: E::ScalarField,
+) -> bool {
+    if proof.rounds.len() != n.trailing_zeros() as usize {
+        return false;
+    }
+    let (mut com_ab, mut com_c, mut z_ab, mut z_c) = (com_ab, com_c, z_ab, z_c);
+    let mut challenges = vec![];
+    for round in &proof.rounds {
+        transcript.append(round);
+        let x = transcript.challenge::<E::ScalarField>();
+        let x_inv = x.inverse().unwrap();
+        com_ab = com_ab + round.comm_ab[0] * x + round.comm_ab[1] * x_inv;
+        com_c = com_c + round.comm_c[0] * x + round.comm_c[1] * x_inv;
+        z_ab = z_ab + round.z_ab[0] * x + round.z_ab[1] * x_inv;
+        z_c = z_c + round.z_c[0] * x + round.z_c[1] * x_inv;
+        challenges.push(x);
+    }
+    transcript.append(&(proof.final_v, proof.final_w));
+    let z = transcript.challenge::<E::ScalarField>();
+
+    // the folded commitments and products of the final elements
+    let (v, w) = (
+        VKey::<E> {
+            a: vec![proof.final_v[0].into()],
+            b: vec![proof.final_v[1].into()],
+        },
+        WKey::<E> {
+            a: vec![proof.final_w[0].into()],
+            b: vec![proof.final_w[1].into()],
+        },
+    );
+    let (a, b, c) = (
+        proof.final_a.into(),
+        proof.final_b.into(),
+        proof.final_c.into(),
+    );
+    // the ones of s fold to prod_j (1 + x_j^-1)
+    let inverses = challenges
+        .iter()
+        .map(|x| x.inverse().unwrap())
+        .collect::<Vec<_>>();
+    let s = evaluate_challenges(&inverses, E::ScalarField::one());
+    let folded = com_ab == commit_pair(&v, &w, &[a], &[b])
+        && com_c == commit_single(&v, &[c])
+        && z_ab == E::pairing(a, b)
+        && z_c == c * s;
+
+    // the final keys are the commitments to the challenge polynomials, opened at z
+    let v_eval = evaluate_challenges(&inverses, z * r_inv);
+    let w_eval = z.pow([n as u64]) * evaluate_challenges(&challenges, z);
+    let v_opens = |key: E::G2Affine, opening: E::G2Affine, g_secret: E::G1| {
+        E::multi_pairing(
+            [srs.g, srs.g * z - g_secret],
+            [E::G2::from(key) - srs.h * v_eval, opening.into()],
+        )
+        .is_zero()
+    };
+    let w_opens = |key: E::G1Affine, opening: E::G1Affine, h_secret: E::G2| {
+        E::multi_pairing(
+            [E::G1::from(key) - srs.g * w_eval, opening.into()],
+            [srs.h, srs.h * z - h_secret],
+        )
+        .is_zero()
+    };
+    folded
+        && v_opens(proof.final_v[0], proof.v_opening[0], srs.g_alpha)
+        && v_opens(proof.final_v[1], proof.v_opening[1], srs.g_beta)
+        && w_opens(proof.final_w[0], proof.w_opening[0], srs.h_alpha)
+        && w_opens(proof.final_w[1], proof.w_opening[1], srs.h_beta)
+}
@@ -0,0 +1,397 @@
+use crate::arkworks::aggregation::{
+    aggregate_proofs, verify_aggregate_proof, AggregateProof, AggregationError,
+};
+use crate::arkworks::backend::proving::stored_verifying_key;
+use crate::arkworks::curves::{on_curve, SnarkCurve};
+use crate::arkworks::key_store::{key_id, KeyStore};
+use crate::arkworks::proof_system::Groth16;
+use crate::bundle::{
+    base64_bytes, verifying_key_bytes, BundleError, ProofBundle, PublicInput, VerifyingKeyRef,
+    BUNDLE_VERSION,
+};
+use crate::error::{check_len, Validate, ZkError};
+use crate::proof_system::{Backend, Curve, ProofSystem};
+use actix_web::{web, HttpResponse};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+// SnarkPack aggregation of the groth16 proofs of bundles that share a verifying key, like the
+// proofs of /prime_arkworks/prove or /matrix_prove/prove of many inputs. the aggregate proof
+// is logarithmic in the number of proofs and so is most of its verification. the srs of the
+// aggregation is in the key store, see KeyStore::aggregation_srs.
+
+// the most proofs in one aggregate, the srs grows with the number of proofs
+pub const MAX_AGGREGATED_PROOFS: usize = 1024;
+
+impl From<AggregationError> for ZkError {
+    fn from(e: AggregationError) -> Self {
+        ZkError::invalid("bundles", e.to_string())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AggregateInput {
+    pub bundles: Vec<ProofBundle>,
+}
+
+// the bundles without their proofs, in the order they were aggregated, and the aggregate
+// proof in place of theirs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AggregateBundle {
+    pub version: u32,
+    pub circuit: String,
+    pub params: Vec<String>,
+    pub curve: Curve,
+    pub public_inputs: Vec<Vec<PublicInput>>,
+    #[serde(with = "base64_bytes")]
+    pub proof: Vec<u8>,
+    pub verifying_key: VerifyingKeyRef,
+}
+
+impl AggregateBundle {
+    pub fn key_id(&self) -> String {
+        key_id(&self.circuit, &self.params)
+    }
+
+    pub fn num_proofs(&self) -> usize {
+        self.public_inputs.len()
+    }
+}
+
+#[derive(Serialize)]
+pub struct AggregateOutput {
+    pub aggregate: AggregateBundle,
+    pub num_proofs: usize,
+    pub proof_size: usize,
+    pub aggregation_time: f64,
+}
+
+#[derive(Serialize)]
+pub struct VerifyAggregateOutput {
+    pub circuit: String,
+    pub num_proofs: usize,
+    pub valid: bool,
+    pub verifying_time: f64,
+}
+
+fn check_num_proofs(field: &str, num_proofs: usize) -> Result<(), ZkError> {
+    if num_proofs < 2 {
+        return Err(ZkError::invalid(
+            field,
+            "at least two proofs are aggregated",
+        ));
+    }
+    Ok(())
+}
+
+impl Validate for AggregateInput {
+    fn validate(&self) -> Result<(), ZkError> {
+        check_len("bundles", &self.bundles, MAX_AGGREGATED_PROOFS)?;
+        check_num_proofs("bundles", self.bundles.len())?;
+        let first = &self.bundles[0];
+        for (i, bundle) in self.bundles.iter().enumerate() {
+            if bundle.backend != Backend::Groth16 {
+                return Err(ZkError::invalid(
+                    "bundles",
+                    format!(
+                        "bundle {} is a {} proof, only groth16 proofs are aggregated",
+                        i, bundle.backend
+                    ),
+                ));
+            }
+            if (&bundle.circuit, &bundle.params, bundle.curve)
+                != (&first.circuit, &first.params, first.curve)
+            {
+                return Err(ZkError::invalid(
+                    "bundles",
+                    format!(
+                        "bundle {} is a proof of {} on {}, not of {} on {}",
+                        i,
+                        bundle.key_id(),
+                        bundle.curve,
+                        first.key_id(),
+                        first.curve
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Validate for AggregateBundle {
+    fn validate(&self) -> Result<(), ZkError> {
+        if self.version > BUNDLE_VERSION {
+            return Err(BundleError::Version(self.version).into());
+        }
+        check_len("public_inputs", &self.public_inputs, MAX_AGGREGATED_PROOFS)?;
+        check_num_proofs("public_inputs", self.num_proofs())
+    }
+}
+
+// POST /aggregate with the bundles to aggregate
+pub async fn aggregate(
+    keys: web::Data<KeyStore>,
+    data: web::Json<AggregateInput>,
+) -> Result<HttpResponse, ZkError> {
+    Ok(HttpResponse::Ok().json(aggregate_bundles(&keys, &data)?))
+}
+
+pub fn aggregate_bundles(
+    keys: &KeyStore,
+    data: &AggregateInput,
+) -> Result<AggregateOutput, ZkError> {
+    data.validate()?;
+    on_curve!(data.bundles[0].curve, E => aggregate_on::<E>(keys, &data.bundles))
+}
+
+fn aggregate_on<E: SnarkCurve>(
+    keys: &KeyStore,
+    bundles: &[ProofBundle],
+) -> Result<AggregateOutput, ZkError> {
+    // the bundles all have the key_id of the first one, and must reference its stored key
+    let first = &bundles[0];
+    let vk = stored_verifying_key::<E>(Backend::Groth16, keys, &first.key_id())?;
+    let mut public_inputs = vec![];
+    let mut proofs = vec![];
+    for (i, bundle) in bundles.iter().enumerate() {
+        if !bundle.verifying_key.references(&vk) {
+            return Err(ZkError::invalid(
+                "bundles",
+                format!(
+                    "bundle {} doesn't reference the verifying key of {}",
+                    i,
+                    first.key_id()
+                ),
+            ));
+        }
+        public_inputs.push(bundle.field_inputs::<E::ScalarField>()?);
+        proofs.push(Groth16::<E>::proof_from_bytes(&bundle.proof).map_err(|_| {
+            ZkError::invalid("bundles", format!("bundle {} has an invalid proof", i))
+        })?);
+    }
+    let pvk = Groth16::<E>::verifying_key_from_bytes(&vk)
+        .map_err(|_| ZkError::invalid("verifying_key", "invalid verifying key"))?;
+    let srs = keys.aggregation_srs::<E>(proofs.len().next_power_of_two())?;
+
+    let start = Instant::now();
+    let proof = aggregate_proofs(&srs, &pvk, &public_inputs, &proofs)?;
+    let aggregation_time = start.elapsed().as_secs_f64();
+
+    let mut bytes = vec![];
+    proof
+        .serialize_compressed(&mut bytes)
+        .map_err(|e| ZkError::Internal(e.to_string()))?;
+    Ok(AggregateOutput {
+        num_proofs: bundles.len(),
+        proof_size: bytes.len(),
+        aggregation_time,
+        aggregate: AggregateBundle {
+            version: BUNDLE_VERSION,
+            circuit: first.circuit.clone(),
+            params: first.params.clone(),
+            curve: first.curve,
+            public_inputs: bundles.iter().map(|b| b.public_inputs.clone()).collect(),
+            proof: bytes,
+            verifying_key: VerifyingKeyRef::hash_of(&vk),
+        },
+    })
+}
+
+// POST /aggregate/verify with the aggregate of /aggregate
+pub async fn verify_aggregate(
+    keys: web::Data<KeyStore>,
+    data: web::Json<AggregateBundle>,
+) -> Result<HttpResponse, ZkError> {
+    data.validate()?;
+    let output = on_curve!(data.curve, E => verify_aggregate_on::<E>(&keys, &data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+// the aggregate is only checked against the stored keys of its key_id, like the bundles
+pub fn verify_aggregate_on<E: SnarkCurve>(
+    keys: &KeyStore,
+    aggregate: &AggregateBundle,
+) -> Result<VerifyAggregateOutput, ZkError> {
+    let vk = verifying_key_bytes::<E>(
+        keys,
+        Backend::Groth16,
+        &aggregate.key_id(),
+        &aggregate.verifying_key,
+    )?;
+    let pvk = Groth16::<E>::verifying_key_from_bytes(&vk)
+        .map_err(|_| ZkError::invalid("verifying_key", "invalid verifying key"))?;
+    let public_inputs = aggregate
+        .public_inputs
+        .iter()
+        .map(|inputs| inputs.iter().map(PublicInput::to_field).collect())
+        .collect::<Result<Vec<Vec<E::ScalarField>>, _>>()?;
+    let proof = AggregateProof::<E>::deserialize_compressed(&aggregate.proof[..])
+        .map_err(|_| ZkError::invalid("proof", "invalid aggregate proof"))?;
+    let srs = keys.stored_aggregation_srs::<E>(aggregate.num_proofs().next_power_of_two())?;
+
+    let start = Instant::now();
+    let valid = verify_aggregate_proof(&srs.verifier(), &pvk, &public_inputs, &proof);
+    let verifying_time = start.elapsed().as_secs_f64();
+    Ok(VerifyAggregateOutput {
+        circuit: aggregate.circuit.clone(),
+        num_proofs: aggregate.num_proofs(),
+        valid,
+        verifying_time,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::backend::fibbonaci_handler::{fibonacci, fibonacci_proof, InputDataFib};
+    use crate::arkworks::constraints::fibbonaci::FibonacciCircuit;
+    use crate::arkworks::key_store::TestDir;
+    use crate::proof_system::Prover;
+    use ark_bls12_381::Fr;
+
+    fn fibonacci_bundle(keys: &KeyStore, a: u64, num_of_rounds: usize) -> ProofBundle {
+        let input = InputDataFib {
+            a: a.to_string(),
+            b: "1".to_string(),
+            num_of_rounds,
+            backend: Backend::Groth16,
+            curve: Curve::Bls12_381,
+        };
+        fibonacci_proof(keys, Backend::Groth16, &input)
+            .unwrap()
+            .bundle
+    }
+
+    fn verify(keys: &KeyStore, aggregate: &AggregateBundle) -> bool {
+        aggregate.validate().unwrap();
+        verify_aggregate_on::<ark_bls12_381::Bls12_381>(keys, aggregate)
+            .unwrap()
+            .valid
+    }
+
+    #[test]
+    fn test_aggregate_bundles() {
+        let dir = TestDir::new("aggregate_bundles");
+        let keys = dir.keys();
+        let bundles = (0..3)
+            .map(|a| fibonacci_bundle(&keys, a, 10))
+            .collect::<Vec<_>>();
+        let output = aggregate_bundles(
+            &keys,
+            &AggregateInput {
+                bundles: bundles.clone(),
+            },
+        )
+        .unwrap();
+        assert_eq!(output.num_proofs, 3);
+        let aggregate = output.aggregate;
+        assert_eq!(aggregate.key_id(), "fibonacci-10");
+        assert_eq!(aggregate.public_inputs[2], bundles[2].public_inputs);
+        assert!(verify(&keys, &aggregate));
+
+        // through json, and with another statement
+        let json = serde_json::to_string(&aggregate).unwrap();
+        let decoded: AggregateBundle = serde_json::from_str(&json).unwrap();
+        assert!(verify(&keys, &decoded));
+        let mut wrong = aggregate.clone();
+        wrong.public_inputs[1][2] = PublicInput::field("fib_number", ark_bls12_381::Fr::from(1u64));
+        assert!(!verify(&keys, &wrong));
+
+        // an aggregate of a size whose srs was never set up
+        let mut unknown = aggregate;
+        unknown
+            .public_inputs
+            .extend(bundles.iter().map(|b| b.public_inputs.clone()));
+        let error = verify_aggregate_on::<ark_bls12_381::Bls12_381>(&keys, &unknown)
+            .err()
+            .unwrap();
+        assert_eq!(error.code(), "unknown_key");
+    }
+
+    #[test]
+    fn test_bundles_share_a_verifying_key() {
+        let dir = TestDir::new("aggregate_shared_key");
+        let keys = dir.keys();
+        let bundle = fibonacci_bundle(&keys, 0, 10);
+        let aggregate = |bundles: Vec<ProofBundle>| {
+            aggregate_bundles(&keys, &AggregateInput { bundles })
+                .err()
+                .unwrap()
+        };
+
+        let error = aggregate(vec![bundle.clone()]);
+        assert_eq!(error.field(), Some("bundles"));
+        // another circuit shape
+        let error = aggregate(vec![bundle.clone(), fibonacci_bundle(&keys, 0, 11)]);
+        assert_eq!(error.field(), Some("bundles"));
+        // the same circuit with keys that aren't the stored ones
+        let mut other_keys = bundle.clone();
+        other_keys.verifying_key = VerifyingKeyRef::Embedded(vec![1, 2, 3]);
+        let error = aggregate(vec![bundle.clone(), other_keys]);
+        assert_eq!(error.field(), Some("bundles"));
+        let mut marlin = bundle.clone();
+        marlin.backend = Backend::Marlin;
+        let error = aggregate(vec![bundle, marlin]);
+        assert_eq!(error.field(), Some("bundles"));
+    }
+
+    #[test]
+    fn test_aggregates_under_a_foreign_key_are_rejected() {
+        type E = ark_bls12_381::Bls12_381;
+        let dir = TestDir::new("aggregate_foreign_key");
+        let keys = dir.keys();
+        let bundles = (0..2)
+            .map(|a| fibonacci_bundle(&keys, a, 10))
+            .collect::<Vec<_>>();
+        let aggregate = aggregate_bundles(&keys, &AggregateInput { bundles })
+            .unwrap()
+            .aggregate;
+
+        // a setup of 11 rounds under which the aggregate claims F(10) = F(11)
+        let (a, b) = (Fr::from(0u64), Fr::from(1u64));
+        let circuit = |a| FibonacciCircuit {
+            a: Some(a),
+            b: Some(b),
+            num_of_steps: 11,
+            result: Some(fibonacci(11, a, b)),
+        };
+        let system = Groth16::<E>::default();
+        let (pk, pvk) = system.setup(circuit(a)).unwrap();
+        let public_inputs = [a, Fr::from(1u64)]
+            .iter()
+            .map(|&a| vec![a, b, fibonacci(11, a, b)])
+            .collect::<Vec<_>>();
+        let proofs = [a, Fr::from(1u64)]
+            .iter()
+            .map(|&a| system.prove(&pk, circuit(a)).unwrap())
+            .collect::<Vec<_>>();
+        let srs = keys.aggregation_srs::<E>(2).unwrap();
+        let proof = aggregate_proofs(&srs, &pvk, &public_inputs, &proofs).unwrap();
+        assert!(verify_aggregate_proof(
+            &srs.verifier(),
+            &pvk,
+            &public_inputs,
+            &proof
+        ));
+
+        let stored = aggregate.verifying_key.clone();
+        let mut forged = aggregate;
+        for (inputs, claimed) in forged.public_inputs.iter_mut().zip(&public_inputs) {
+            inputs[2] = PublicInput::field("fib_number", claimed[2]);
+        }
+        forged.proof = vec![];
+        proof.serialize_compressed(&mut forged.proof).unwrap();
+        let foreign_vk = Groth16::<E>::verifying_key_to_bytes(&pvk).unwrap();
+        forged.verifying_key = VerifyingKeyRef::Embedded(foreign_vk.clone());
+        let error = verify_aggregate_on::<E>(&keys, &forged).err().unwrap();
+        assert_eq!(error.field(), Some("verifying_key"));
+        forged.verifying_key = VerifyingKeyRef::hash_of(&foreign_vk);
+        let error = verify_aggregate_on::<E>(&keys, &forged).err().unwrap();
+        assert_eq!(error.field(), Some("verifying_key"));
+        // and under the stored key the aggregate doesn't verify
+        forged.verifying_key = stored;
+        assert!(!verify(&keys, &forged));
+    }
+}
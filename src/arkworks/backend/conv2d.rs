@@ -0,0 +1,233 @@
+use crate::arkworks::backend::proving::{prove_with_keys, verify_with_keys};
+use crate::arkworks::curves::{on_curve, SnarkCurve};
+use crate::arkworks::key_store::{key_id, KeyStore};
+use crate::arkworks::matrix_proof_of_work::conv2d::{Conv2dCircuit, Padding};
+use crate::arkworks::matrix_proof_of_work::fixed_point::{from_fixed_matrix, to_fixed_matrix};
+use crate::arkworks::matrix_proof_of_work::io::{decode_hash, encode_hash};
+use crate::error::{check_len, Validate, ZkError};
+use crate::proof_system::{Backend, Curve};
+use actix_web::{web, HttpResponse};
+use ark_ff::BigInteger;
+use ark_ff::PrimeField;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+use serde::{Deserialize, Serialize};
+
+fn default_stride() -> usize {
+    1
+}
+
+fn default_padding() -> Padding {
+    Padding::Valid
+}
+
+fn default_frac_bits() -> u32 {
+    8
+}
+
+// the image is integer pixels, the kernel is real numbers that are encoded as fixed-point
+// numbers with frac_bits fractional bits (e.g. a 3x3 box blur is 1/9 everywhere)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Conv2dInput {
+    image: Vec<Vec<u64>>,
+    kernel: Vec<Vec<f64>>,
+    #[serde(default = "default_stride")]
+    stride: usize,
+    #[serde(default = "default_padding")]
+    padding: Padding,
+    #[serde(default = "default_frac_bits")]
+    frac_bits: u32,
+    #[serde(default)]
+    backend: Backend,
+    #[serde(default)]
+    curve: Curve,
+}
+
+// output is Y decoded back to real numbers, hash_y is the hash of its fixed-point encoding.
+// the keys fit every image and kernel of the same shapes, stride and padding
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Conv2dOutput {
+    hash_x: String,
+    hash_k: String,
+    hash_y: String,
+    output: Vec<Vec<f64>>,
+    frac_bits: u32,
+    setup_time: f64,
+    proving_time: f64,
+    num_constraints: usize,
+    num_variables: usize,
+    proof: String,
+    key_id: String,
+}
+
+// the shapes, stride and padding of the proof pick its keys
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Conv2dVerifyInput {
+    proof: String,
+    hash_x: String,
+    hash_k: String,
+    hash_y: String,
+    image_shape: (usize, usize),
+    kernel_shape: (usize, usize),
+    #[serde(default = "default_stride")]
+    stride: usize,
+    #[serde(default = "default_padding")]
+    padding: Padding,
+    #[serde(default)]
+    backend: Backend,
+    #[serde(default)]
+    curve: Curve,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Conv2dVerifyOutput {
+    verifying_time: f64,
+    valid: bool,
+}
+
+fn encode_field<F: PrimeField>(value: F) -> String {
+    encode_hash(&value.into_bigint().to_bytes_le())
+}
+
+fn decode_field<F: PrimeField>(field: &str, value: &str) -> Result<F, ZkError> {
+    decode_hash(value)
+        .map(|bytes| F::from_le_bytes_mod_order(&bytes))
+        .map_err(|_| ZkError::invalid(field, format!("{} is not valid base64", field)))
+}
+
+// the keys are per shape, like the matrix keys
+const MAX_DIM: usize = 256;
+
+fn conv2d_key_id(
+    image_shape: (usize, usize),
+    kernel_shape: (usize, usize),
+    stride: usize,
+    padding: Padding,
+) -> String {
+    key_id(
+        "conv2d",
+        &[
+            format!("{}x{}", image_shape.0, image_shape.1),
+            format!("{}x{}", kernel_shape.0, kernel_shape.1),
+            stride.to_string(),
+            format!("{:?}", padding).to_lowercase(),
+        ],
+    )
+}
+
+fn shape<T>(matrix: &[Vec<T>]) -> (usize, usize) {
+    (matrix.len(), matrix[0].len())
+}
+
+fn check_rectangular<T>(field: &str, matrix: &[Vec<T>]) -> Result<(), ZkError> {
+    check_len(field, matrix, MAX_DIM)?;
+    check_len(field, &matrix[0], MAX_DIM)?;
+    if matrix.iter().any(|row| row.len() != matrix[0].len()) {
+        return Err(ZkError::invalid(
+            field,
+            format!("{} must be a non-empty rectangle", field),
+        ));
+    }
+    Ok(())
+}
+
+impl Validate for Conv2dInput {
+    fn validate(&self) -> Result<(), ZkError> {
+        check_rectangular("image", &self.image)?;
+        check_rectangular("kernel", &self.kernel)?;
+        if self.kernel.iter().flatten().any(|value| !value.is_finite()) {
+            return Err(ZkError::invalid(
+                "kernel",
+                "the kernel must be finite numbers",
+            ));
+        }
+        if self.frac_bits > 32 {
+            return Err(ZkError::invalid(
+                "frac_bits",
+                "frac_bits must be at most 32",
+            ));
+        }
+        if self.stride == 0 {
+            return Err(ZkError::invalid("stride", "the stride must be positive"));
+        }
+        Ok(())
+    }
+}
+
+// prove Y = X * K
+pub async fn prove_conv2d(
+    keys: web::Data<KeyStore>,
+    data: web::Json<Conv2dInput>,
+) -> Result<HttpResponse, ZkError> {
+    let data = data.into_inner();
+    data.validate()?;
+    let output = on_curve!(data.curve, E => conv2d_proof::<E>(&keys, data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn conv2d_proof<E: SnarkCurve>(
+    keys: &KeyStore,
+    data: Conv2dInput,
+) -> Result<Conv2dOutput, ZkError> {
+    let kernel = to_fixed_matrix::<E::ScalarField>(&data.kernel, data.frac_bits);
+    let circuit = Conv2dCircuit::new(data.image.clone(), kernel, data.stride, data.padding)
+        .ok_or_else(|| ZkError::invalid("kernel", "a valid kernel must fit in the image"))?;
+    let [hash_x, hash_k, hash_y] = circuit.public_inputs();
+
+    let key_id = conv2d_key_id(
+        shape(&data.image),
+        shape(&data.kernel),
+        data.stride,
+        data.padding,
+    );
+    let proved = prove_with_keys::<E, _>(data.backend, keys, &key_id, circuit.clone())?;
+
+    let output = from_fixed_matrix(&circuit.output(), data.frac_bits);
+    let cs = ConstraintSystem::<E::ScalarField>::new_ref();
+    circuit.generate_constraints(cs.clone())?;
+
+    Ok(Conv2dOutput {
+        hash_x: encode_field(hash_x),
+        hash_k: encode_field(hash_k),
+        hash_y: encode_field(hash_y),
+        output,
+        frac_bits: data.frac_bits,
+        setup_time: proved.setup_time,
+        proving_time: proved.proving_time,
+        num_constraints: cs.num_constraints(),
+        num_variables: cs.num_instance_variables(),
+        proof: proved.proof,
+        key_id,
+    })
+}
+
+pub async fn verify_conv2d(
+    keys: web::Data<KeyStore>,
+    data: web::Json<Conv2dVerifyInput>,
+) -> Result<HttpResponse, ZkError> {
+    let output = on_curve!(data.curve, E => conv2d_verify::<E>(&keys, &data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn conv2d_verify<E: SnarkCurve>(
+    keys: &KeyStore,
+    data: &Conv2dVerifyInput,
+) -> Result<Conv2dVerifyOutput, ZkError> {
+    let public_inputs = [
+        decode_field("hash_x", &data.hash_x)?,
+        decode_field("hash_k", &data.hash_k)?,
+        decode_field("hash_y", &data.hash_y)?,
+    ];
+
+    let key_id = conv2d_key_id(
+        data.image_shape,
+        data.kernel_shape,
+        data.stride,
+        data.padding,
+    );
+    let verified = verify_with_keys::<E>(data.backend, keys, &key_id, &public_inputs, &data.proof)?;
+
+    Ok(Conv2dVerifyOutput {
+        verifying_time: verified.verifying_time,
+        valid: verified.valid,
+    })
+}
@@ -0,0 +1,143 @@
+use crate::arkworks::backend::fibbonaci_handler::{fibonacci, InputDataFib, MAX_ROUNDS};
+use crate::arkworks::constraints::fibbonaci::FibonacciCircuit;
+use crate::arkworks::evm::calldata::{
+    field_word, to_hex, verify_proof_calldata, verify_proof_signature, word_to_decimal,
+};
+use crate::arkworks::evm::solidity::verifier_contract;
+use crate::arkworks::key_store::{key_id, KeyStore};
+use crate::arkworks::matrix_proof_of_work::io::encode_hash;
+use crate::arkworks::proof_system::Groth16Bn254Backend;
+use crate::error::{parse_field, Validate, ZkError};
+use crate::proof_system::{ProofSystem, Prover};
+use actix_web::{web, HttpResponse};
+use ark_bn254::Fr;
+use serde::Serialize;
+use std::time::Instant;
+
+// groth16 proofs over BN254 for a verifier contract. the keys are in the key store like the
+// others, under groth16_bn254, and GET /evm/verifier/{key_id} turns their verifying key into
+// the contract that checks the calldata of the proofs.
+
+#[derive(Serialize)]
+pub struct EvmProof {
+    pub key_id: String,
+    pub fib_number: String,
+    // base64 of the arkworks proof
+    pub proof: String,
+    // decimal, in the order of the input argument of verifyProof
+    pub public_inputs: Vec<String>,
+    // the selector and the ABI encoded arguments of verifyProof, 0x hex
+    pub calldata: String,
+    pub proving_time: f64,
+}
+
+#[derive(Serialize)]
+pub struct EvmVerifier {
+    pub key_id: String,
+    pub signature: String,
+    pub solidity: String,
+}
+
+pub async fn evm_fibonacci_proof(
+    keys: web::Data<KeyStore>,
+    data: web::Json<InputDataFib>,
+) -> Result<HttpResponse, ZkError> {
+    Ok(HttpResponse::Ok().json(fibonacci_evm_proof(&keys, &data)?))
+}
+
+// the circuit of /fibbonaci/prove over the scalar field of BN254, the backend and the curve
+// of the request are ignored
+pub fn fibonacci_evm_proof(keys: &KeyStore, data: &InputDataFib) -> Result<EvmProof, ZkError> {
+    data.validate()?;
+    // the contract verifies the per-round circuit, which has keys for every number of rounds
+    if data.num_of_rounds > MAX_ROUNDS {
+        return Err(ZkError::invalid(
+            "num_of_rounds",
+            format!("the evm proofs take at most {} rounds", MAX_ROUNDS),
+        ));
+    }
+    let a: Fr = parse_field("a", &data.a)?;
+    let b: Fr = parse_field("b", &data.b)?;
+    let fib_number = fibonacci(data.num_of_rounds, a, b);
+    let circuit = FibonacciCircuit {
+        a: Some(a),
+        b: Some(b),
+        num_of_steps: data.num_of_rounds,
+        result: Some(fib_number),
+    };
+    let key_id = key_id("fibonacci", &[data.num_of_rounds.to_string()]);
+    let groth16 = Groth16Bn254Backend::default();
+    let pk = keys.proving_key(&groth16, &key_id, circuit.clone())?;
+    let start = Instant::now();
+    let proof = groth16.prove(&pk, circuit)?;
+    let proving_time = start.elapsed().as_secs_f64();
+
+    let public_inputs = [a, b, fib_number];
+    Ok(EvmProof {
+        key_id,
+        fib_number: fib_number.to_string(),
+        proof: encode_hash(&Groth16Bn254Backend::proof_to_bytes(&proof)?),
+        public_inputs: public_inputs
+            .iter()
+            .map(|input| word_to_decimal(&field_word(*input)))
+            .collect(),
+        calldata: to_hex(&verify_proof_calldata(&proof, &public_inputs)),
+        proving_time,
+    })
+}
+
+pub async fn evm_verifier(
+    keys: web::Data<KeyStore>,
+    key_id: web::Path<String>,
+) -> Result<HttpResponse, ZkError> {
+    Ok(HttpResponse::Ok().json(verifier_of_key(&keys, &key_id)?))
+}
+
+pub fn verifier_of_key(keys: &KeyStore, key_id: &str) -> Result<EvmVerifier, ZkError> {
+    let pvk = keys.verifying_key::<Groth16Bn254Backend>(key_id)?;
+    Ok(EvmVerifier {
+        key_id: key_id.to_string(),
+        signature: verify_proof_signature(pvk.vk.gamma_abc_g1.len() - 1),
+        solidity: verifier_contract(&pvk.vk),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::key_store::TestDir;
+    use crate::proof_system::{Backend, Curve};
+
+    #[test]
+    fn test_evm_proof_and_verifier() {
+        let dir = TestDir::new("evm");
+        let keys = dir.keys();
+        let data = InputDataFib {
+            a: "0".to_string(),
+            b: "1".to_string(),
+            num_of_rounds: 10,
+            backend: Backend::Groth16,
+            curve: Curve::Bn254,
+        };
+        let proof = fibonacci_evm_proof(&keys, &data).unwrap();
+        assert_eq!(proof.key_id, "fibonacci-10");
+        assert_eq!(proof.fib_number, "89");
+        assert_eq!(proof.public_inputs, ["0", "1", "89"]);
+        assert_eq!(proof.calldata.len(), 2 + 2 * (4 + 32 * 11));
+
+        let verifier = verifier_of_key(&keys, &proof.key_id).unwrap();
+        assert_eq!(verifier.signature, verify_proof_signature(3));
+        assert!(verifier.solidity.contains("uint256[3] calldata input"));
+        // keys that were never set up
+        let error = verifier_of_key(&keys, "fibonacci-11").err();
+        assert_eq!(error, Some(ZkError::UnknownKey("fibonacci-11".to_string())));
+
+        // more rounds have no per-round keys
+        let data = InputDataFib {
+            num_of_rounds: MAX_ROUNDS + 1,
+            ..data
+        };
+        let error = fibonacci_evm_proof(&keys, &data).err().unwrap();
+        assert_eq!(error.field(), Some("num_of_rounds"));
+    }
+}
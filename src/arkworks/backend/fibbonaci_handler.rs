@@ -1,17 +1,20 @@
+use crate::arkworks::backend::proving::{prove_with_keys, verify_with_keys, BackendQuery};
+use crate::arkworks::constraints::fast_fibonacci::FastFibonacciCircuit;
 use crate::arkworks::constraints::fibbonaci::FibonacciCircuit;
-use crate::arkworks::matrix_proof_of_work::io::{
-    decode_proof, decode_pvk, encode_proof, encode_pvk,
-};
-use actix_web::{web, HttpResponse, Responder};
-use ark_bls12_381::{Bls12_381, Fr as BlsFr};
-use ark_groth16::{prepare_verifying_key, Groth16};
-
-use ark_snark::SNARK;
-use ark_std::rand::SeedableRng;
-use rand::rngs::StdRng;
+use crate::arkworks::curves::{on_curve, SnarkCurve};
+use crate::arkworks::key_store::{key_id, KeyStore};
+use crate::arkworks::matrix_proof_of_work::io::field_to_hex;
+use crate::bundle::{ProofBundle, PublicInput, PublicValue};
+use crate::error::{parse_field, Validate, ZkError};
+use crate::proof_system::{Backend, Curve};
+use actix_web::{web, HttpResponse};
+use ark_ff::PrimeField;
+
+use num_bigint::BigUint;
 use serde::{Deserialize, Serialize};
-pub fn fibonacci(num_of_steps: usize, a: u128, b: u128) -> u128 {
-    let mut fi = 0;
+// the fibonacci sequence over the field, so the result is F(n) mod r and never overflows
+pub fn fibonacci<F: PrimeField>(num_of_steps: usize, a: F, b: F) -> F {
+    let mut fi = F::zero();
     let mut fi_minus_one = b;
     let mut fi_minus_two = a;
 
@@ -30,63 +33,275 @@ pub fn fibonacci(num_of_steps: usize, a: u128, b: u128) -> u128 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::arkworks::key_store::TestDir;
+    use crate::arkworks::matrix_proof_of_work::io::parse_field_element;
+    use ark_bls12_381::{Bls12_381, Fr as BlsFr};
     #[test]
     fn test_fibonacci() {
-        let a = 0;
-        let b = 1;
+        let a = BlsFr::from(0u64);
+        let b = BlsFr::from(1u64);
         let num_of_steps = 10;
         let result = fibonacci(num_of_steps, a, b);
-        assert_eq!(result, 89);
+        assert_eq!(result, BlsFr::from(89u64));
     }
     #[test]
 
     fn test2_fibonacci() {
-        let a = 1;
-        let b = 1;
+        let a = BlsFr::from(1u64);
+        let b = BlsFr::from(1u64);
         let num_of_steps = 10;
         let result = fibonacci(num_of_steps, a, b);
-        assert_eq!(result, 89);
+        assert_eq!(result, BlsFr::from(144u64));
     }
     #[test]
     fn test3_fibonacci() {
-        let a = 1;
-        let b = 1;
+        let a = BlsFr::from(1u64);
+        let b = BlsFr::from(1u64);
         let num_of_steps = 1;
         let result = fibonacci(num_of_steps, a, b);
-        assert_eq!(result, 2);
+        assert_eq!(result, BlsFr::from(2u64));
     }
     #[test]
     fn test4_fibonacci() {
-        let a = 0;
-        let b = 1;
+        let a = BlsFr::from(0u64);
+        let b = BlsFr::from(1u64);
         let num_of_steps = 10;
         let result = fibonacci(num_of_steps, a, b);
-        assert_eq!(result, 89);
+        assert_eq!(result, BlsFr::from(89u64));
+    }
+    #[test]
+    fn test_fibonacci_beyond_u128() {
+        // F(500) doesn't fit in u128, compare with the exact value reduced mod r
+        let (mut x, mut y) = (BigUint::from(0u64), BigUint::from(1u64));
+        for _ in 0..500 {
+            let next = &x + &y;
+            x = y;
+            y = next;
+        }
+        let modulus: BigUint = BlsFr::MODULUS.into();
+        let result = fibonacci(500, BlsFr::from(0u64), BlsFr::from(1u64));
+        assert_eq!(BigUint::from(result), y % modulus);
+    }
+    #[test]
+    fn test_parse_field_element() {
+        assert_eq!(parse_field_element::<BlsFr>("89"), Ok(BlsFr::from(89u64)));
+        assert_eq!(parse_field_element::<BlsFr>("0x59"), Ok(BlsFr::from(89u64)));
+        assert_eq!(field_to_hex(BlsFr::from(89u64)), "0x59");
+        let modulus: BigUint = BlsFr::MODULUS.into();
+        assert!(parse_field_element::<BlsFr>(&modulus.to_string()).is_err());
+        assert!(parse_field_element::<BlsFr>("-1").is_err());
+        assert!(parse_field_element::<BlsFr>("0xzz").is_err());
+    }
+
+    fn store(dir: &TestDir) -> web::Data<KeyStore> {
+        web::Data::new(dir.keys())
+    }
+
+    fn query() -> web::Query<BackendQuery> {
+        web::Query(BackendQuery::default())
+    }
+
+    fn prove_input(a: &str, num_of_rounds: usize) -> web::Json<InputDataFib> {
+        web::Json(InputDataFib {
+            a: a.to_string(),
+            b: "1".to_string(),
+            num_of_rounds,
+            backend: Backend::Groth16,
+            curve: Curve::Bls12_381,
+        })
+    }
+
+    fn verify_input(proof: &str, num_of_rounds: usize) -> web::Json<InputDataFibVer> {
+        web::Json(InputDataFibVer {
+            proof: proof.to_string(),
+            num_of_rounds,
+            fib_number: "89".to_string(),
+            a: "0".to_string(),
+            b: "1".to_string(),
+            backend: Backend::Groth16,
+            curve: Curve::Bls12_381,
+        })
+    }
+
+    #[actix_web::test]
+    async fn test_invalid_requests() {
+        let dir = TestDir::new("fib_invalid");
+        let keys = store(&dir);
+        let error = fibbonaci_snark_proof(keys.clone(), query(), prove_input("0", 0))
+            .await
+            .unwrap_err();
+        assert_eq!(error.field(), Some("num_of_rounds"));
+        let error = fibbonaci_snark_proof(keys.clone(), query(), prove_input("0xzz", 10))
+            .await
+            .unwrap_err();
+        assert_eq!(error.field(), Some("a"));
+
+        // a proof that isn't base64 and one of the wrong length, against keys that exist
+        let response = fibbonaci_snark_proof(keys.clone(), query(), prove_input("0", 10)).await;
+        assert!(response.unwrap().status().is_success());
+        for proof in ["not base64!", "AAAA"] {
+            let error = fibbonaci_snark_verify(keys.clone(), query(), verify_input(proof, 10))
+                .await
+                .unwrap_err();
+            assert_eq!(error.code(), "invalid_input");
+            assert_eq!(error.field(), Some("proof"));
+        }
+        let error = fibbonaci_snark_verify(keys, query(), verify_input("AAAA", 11))
+            .await
+            .unwrap_err();
+        assert_eq!(error, ZkError::UnknownKey("fibonacci-11".to_string()));
+    }
+
+    #[test]
+    fn test_proofs_of_other_circuits_are_rejected() {
+        let dir = TestDir::new("fib_other_circuits");
+        let keys = store(&dir);
+        let verify = |proof: &str, fib_number: &str| {
+            let mut input = verify_input(proof, 10).into_inner();
+            input.fib_number = fib_number.to_string();
+            fibonacci_verify::<Bls12_381>(&keys, Backend::Groth16, &input).unwrap()
+        };
+        let ten = fibonacci_proof(&keys, Backend::Groth16, &prove_input("0", 10)).unwrap();
+        assert!(verify(&ten.proof, "89").is_res);
+
+        // F(5) = 8 is proven with the keys of 5 rounds, which a client can't pick anymore
+        let five = fibonacci_proof(&keys, Backend::Groth16, &prove_input("0", 5)).unwrap();
+        assert_eq!(five.fib_number, "8");
+        assert!(!verify(&five.proof, "8").is_res);
+
+        // and the fast circuit has its own keys
+        let fast = InputDataFastFib {
+            a: "0".to_string(),
+            b: "1".to_string(),
+            n: 10,
+            backend: Backend::Groth16,
+            curve: Curve::Bls12_381,
+        };
+        let fast = fast_fibonacci_proof(&keys, Backend::Groth16, &fast).unwrap();
+        assert_eq!(fast.key_id, "fast_fibonacci");
+        assert!(!verify(&fast.proof, "89").is_res);
+    }
+
+    #[test]
+    fn test_many_rounds_use_the_fast_circuit() {
+        let dir = TestDir::new("fib_many_rounds");
+        let keys = store(&dir);
+        let rounds = MAX_ROUNDS + 1;
+        let proved = fibonacci_proof(&keys, Backend::Groth16, &prove_input("0", rounds)).unwrap();
+        assert_eq!(proved.key_id, "fast_fibonacci");
+        let expected = fibonacci(rounds, BlsFr::from(0u64), BlsFr::from(1u64));
+        assert_eq!(proved.fib_number, expected.to_string());
+
+        let mut verify = verify_input(&proved.proof, rounds).into_inner();
+        verify.fib_number = proved.fib_number;
+        let verified = fibonacci_verify::<Bls12_381>(&keys, Backend::Groth16, &verify);
+        assert!(verified.unwrap().is_res);
+        // and they are no proofs of another number of rounds
+        verify.num_of_rounds += 1;
+        let verified = fibonacci_verify::<Bls12_381>(&keys, Backend::Groth16, &verify);
+        assert!(!verified.unwrap().is_res);
+    }
+
+    #[test]
+    fn test_every_curve() {
+        let dir = TestDir::new("fib_curves");
+        let keys = store(&dir);
+        let mut proofs = vec![];
+        for curve in [Curve::Bn254, Curve::Bls12_377] {
+            let mut input = prove_input("0", 10).into_inner();
+            input.curve = curve;
+            let proved = fibonacci_proof(&keys, Backend::Groth16, &input).unwrap();
+            assert_eq!(proved.fib_number, "89");
+            assert_eq!(proved.bundle.curve, curve);
+            let mut verify = verify_input(&proved.proof, 10).into_inner();
+            verify.curve = curve;
+            let verified =
+                on_curve!(curve, E => fibonacci_verify::<E>(&keys, Backend::Groth16, &verify));
+            assert!(verified.unwrap().is_res);
+            proofs.push((proved, verify));
+        }
+        let (bn254, mut verify) = proofs.remove(0);
+        assert_eq!(bn254.modulus, modulus::<ark_bn254::Fr>());
+        assert_ne!(bn254.modulus, proofs[0].0.modulus);
+        // a bn254 proof isn't even a proof over bls12_377
+        verify.curve = Curve::Bls12_377;
+        let error = fibonacci_verify::<ark_bls12_377::Bls12_377>(&keys, Backend::Groth16, &verify)
+            .err()
+            .unwrap();
+        assert_eq!(error.field(), Some("proof"));
+
+        let mut input = prove_input("0", 10).into_inner();
+        input.curve = Curve::Bn254;
+        let error = fibonacci_proof(&keys, Backend::Marlin, &input)
+            .err()
+            .unwrap();
+        assert_eq!(error.field(), Some("curve"));
     }
 }
 
+// a and b are decimal or 0x hex strings of field elements
 #[derive(Deserialize)]
 pub struct InputDataFib {
-    pub a: u64,
-    pub b: u64,
+    pub a: String,
+    pub b: String,
     pub num_of_rounds: usize,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub curve: Curve,
 }
 
+// num_of_rounds picks the keys, the same as for the proof
 #[derive(Deserialize)]
 pub struct InputDataFibVer {
     pub proof: String,
-    pub pvk: String,
-    pub fib_number: u128,
-    pub a: u64,
-    pub b: u64,
+    pub num_of_rounds: usize,
+    pub fib_number: String,
+    pub a: String,
+    pub b: String,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub curve: Curve,
 }
 
+// fib_number is F(n) mod r (decimal and hex), where r is the modulus of the scalar field of
+// the curve that the circuit works in, returned as `modulus`. key_id names the verifying key,
+// bundle is the proof for /verify.
 #[derive(Serialize)]
 pub struct OutputDataFib {
     pub proof: String,
-    pub pvk: String,
+    pub key_id: String,
     pub fib_number: String,
+    pub fib_number_hex: String,
+    pub modulus: String,
     pub proving_time: f64,
+    pub bundle: ProofBundle,
+}
+
+// n is the index of the result, any n < 2^64 uses the same verifying key
+#[derive(Deserialize)]
+pub struct InputDataFastFib {
+    pub a: String,
+    pub b: String,
+    pub n: u64,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+#[derive(Deserialize)]
+pub struct InputDataFastFibVer {
+    pub proof: String,
+    pub fib_number: String,
+    pub a: String,
+    pub b: String,
+    pub n: u64,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub curve: Curve,
 }
 
 #[derive(Serialize)]
@@ -95,53 +310,214 @@ pub struct OutputVerifyData {
     pub is_res: bool,
 }
 
-pub async fn fibbonaci_snark_proof(data: web::Json<InputDataFib>) -> impl Responder {
-    let mut rng = StdRng::seed_from_u64(42);
-    let fibo_num = fibonacci(data.num_of_rounds, data.a as u128, data.b as u128);
-    let circuit = FibonacciCircuit::<BlsFr> {
-        a: Some(BlsFr::from(data.a)),
-        b: Some(BlsFr::from(data.b)),
+// the names of the keys and the bundles, the verify endpoints derive the key ids from them
+const FIBONACCI_CIRCUIT: &str = "fibonacci";
+const FAST_FIBONACCI_CIRCUIT: &str = "fast_fibonacci";
+
+// the circuit has one constraint per round and keys for every number of rounds, so it only
+// proves up to MAX_ROUNDS rounds. more rounds are proven with the O(log n) circuit of
+// /fibbonaci/fast, whose keys serve every n
+pub const MAX_ROUNDS: usize = 1 << 10;
+
+impl Validate for InputDataFib {
+    fn validate(&self) -> Result<(), ZkError> {
+        if self.num_of_rounds == 0 {
+            return Err(ZkError::invalid(
+                "num_of_rounds",
+                "num_of_rounds must be at least 1",
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn parse_inputs<F: PrimeField>(a: &str, b: &str) -> Result<(F, F), ZkError> {
+    Ok((parse_field("a", a)?, parse_field("b", b)?))
+}
+
+pub async fn fibbonaci_snark_proof(
+    keys: web::Data<KeyStore>,
+    query: web::Query<BackendQuery>,
+    data: web::Json<InputDataFib>,
+) -> Result<HttpResponse, ZkError> {
+    let result = fibonacci_proof(&keys, query.or(data.backend), &data)?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+fn modulus<F: PrimeField>() -> String {
+    let modulus: BigUint = F::MODULUS.into();
+    modulus.to_string()
+}
+
+// the proof of /fibbonaci/prove, also run by the fibonacci jobs
+pub fn fibonacci_proof(
+    keys: &KeyStore,
+    backend: Backend,
+    data: &InputDataFib,
+) -> Result<OutputDataFib, ZkError> {
+    data.validate()?;
+    if data.num_of_rounds > MAX_ROUNDS {
+        let fast = InputDataFastFib {
+            a: data.a.clone(),
+            b: data.b.clone(),
+            n: data.num_of_rounds as u64,
+            backend: data.backend,
+            curve: data.curve,
+        };
+        return fast_fibonacci_proof(keys, backend, &fast);
+    }
+    on_curve!(data.curve, E => fibonacci_proof_on::<E>(keys, backend, data))
+}
+
+fn fibonacci_proof_on<E: SnarkCurve>(
+    keys: &KeyStore,
+    backend: Backend,
+    data: &InputDataFib,
+) -> Result<OutputDataFib, ZkError> {
+    let (a, b) = parse_inputs::<E::ScalarField>(&data.a, &data.b)?;
+    let fibo_num = fibonacci(data.num_of_rounds, a, b);
+    let circuit = FibonacciCircuit {
+        a: Some(a),
+        b: Some(b),
         num_of_steps: data.num_of_rounds,
-        result: Some(BlsFr::from(fibo_num)),
+        result: Some(fibo_num),
     };
-    let (pk, vk) = Groth16::<Bls12_381>::circuit_specific_setup(circuit.clone(), &mut rng).unwrap();
-    let pvk = prepare_verifying_key::<Bls12_381>(&vk);
-    let start = ark_std::time::Instant::now();
-    let proof = Groth16::<Bls12_381>::prove(&pk, circuit.clone(), &mut rng).unwrap();
+    // the circuit has one constraint per round
+    let params = vec![data.num_of_rounds.to_string()];
+    let key_id = key_id(FIBONACCI_CIRCUIT, &params);
+    let proved = prove_with_keys::<E, _>(backend, keys, &key_id, circuit)?;
+    let public_inputs = vec![
+        PublicInput::field("a", a),
+        PublicInput::field("b", b),
+        PublicInput::field("fib_number", fibo_num),
+    ];
+    let bundle = proved.bundle(backend, FIBONACCI_CIRCUIT, params, public_inputs);
 
-    let proving_time = start.elapsed().as_secs_f64();
+    Ok(OutputDataFib {
+        proof: proved.proof,
+        key_id,
+        fib_number: fibo_num.to_string(),
+        fib_number_hex: field_to_hex(fibo_num),
+        modulus: modulus::<E::ScalarField>(),
+        proving_time: proved.proving_time,
+        bundle,
+    })
+}
+
+pub async fn fibbonaci_snark_verify(
+    keys: web::Data<KeyStore>,
+    query: web::Query<BackendQuery>,
+    data: web::Json<InputDataFibVer>,
+) -> Result<HttpResponse, ZkError> {
+    let backend = query.or(data.backend);
+    let data = on_curve!(data.curve, E => fibonacci_verify::<E>(&keys, backend, &data))?;
+    Ok(HttpResponse::Ok().json(data))
+}
 
-    let result = OutputDataFib {
-        proof: encode_proof::<Bls12_381>(&proof),
-        pvk: encode_pvk::<Bls12_381>(&pvk),
+fn fibonacci_verify<E: SnarkCurve>(
+    keys: &KeyStore,
+    backend: Backend,
+    data: &InputDataFibVer,
+) -> Result<OutputVerifyData, ZkError> {
+    // the proofs of more rounds come from the fast circuit
+    if data.num_of_rounds > MAX_ROUNDS {
+        let fast = InputDataFastFibVer {
+            proof: data.proof.clone(),
+            fib_number: data.fib_number.clone(),
+            a: data.a.clone(),
+            b: data.b.clone(),
+            n: data.num_of_rounds as u64,
+            backend: data.backend,
+            curve: data.curve,
+        };
+        return fast_fibonacci_verify::<E>(keys, backend, &fast);
+    }
+    let (a, b) = parse_inputs::<E::ScalarField>(&data.a, &data.b)?;
+    let inputs = [a, b, parse_field("fib_number", &data.fib_number)?];
+    // the public inputs are a, b and F(n) mod r, the keys are the ones of the number of rounds
+    let key_id = key_id(FIBONACCI_CIRCUIT, &[data.num_of_rounds.to_string()]);
+    let verified = verify_with_keys::<E>(backend, keys, &key_id, &inputs, &data.proof)?;
+    Ok(OutputVerifyData {
+        verifying_time: verified.verifying_time,
+        is_res: verified.valid,
+    })
+}
+
+// the same result as /fibbonaci/prove with num_of_rounds = n, but with O(log n) constraints
+pub async fn fast_fibbonaci_snark_proof(
+    keys: web::Data<KeyStore>,
+    query: web::Query<BackendQuery>,
+    data: web::Json<InputDataFastFib>,
+) -> Result<HttpResponse, ZkError> {
+    let result = fast_fibonacci_proof(&keys, query.or(data.backend), &data)?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+pub fn fast_fibonacci_proof(
+    keys: &KeyStore,
+    backend: Backend,
+    data: &InputDataFastFib,
+) -> Result<OutputDataFib, ZkError> {
+    on_curve!(data.curve, E => fast_fibonacci_proof_on::<E>(keys, backend, data))
+}
+
+fn fast_fibonacci_proof_on<E: SnarkCurve>(
+    keys: &KeyStore,
+    backend: Backend,
+    data: &InputDataFastFib,
+) -> Result<OutputDataFib, ZkError> {
+    let (a, b) = parse_inputs::<E::ScalarField>(&data.a, &data.b)?;
+    let circuit = FastFibonacciCircuit::new(data.n, a, b);
+    let fibo_num = circuit.result.unwrap();
+    // the shape doesn't depend on n, every request shares the keys
+    let key_id = key_id(FAST_FIBONACCI_CIRCUIT, &[]);
+    let proved = prove_with_keys::<E, _>(backend, keys, &key_id, circuit)?;
+    let public_inputs = vec![
+        PublicInput::field("a", a),
+        PublicInput::field("b", b),
+        PublicInput::new("n", PublicValue::U64(data.n)),
+        PublicInput::field("fib_number", fibo_num),
+    ];
+    let bundle = proved.bundle(backend, FAST_FIBONACCI_CIRCUIT, vec![], public_inputs);
+
+    Ok(OutputDataFib {
+        proof: proved.proof,
+        key_id,
         fib_number: fibo_num.to_string(),
-        proving_time,
-    };
+        fib_number_hex: field_to_hex(fibo_num),
+        modulus: modulus::<E::ScalarField>(),
+        proving_time: proved.proving_time,
+        bundle,
+    })
+}
 
-    HttpResponse::Ok().json(result)
-}
-
-pub async fn fibbonaci_snark_verify(data: web::Json<InputDataFibVer>) -> impl Responder {
-    let pvk = decode_pvk::<Bls12_381>(&data.pvk).unwrap();
-    let proof = decode_proof::<Bls12_381>(&data.proof).unwrap();
-    let start = ark_std::time::Instant::now();
-    // the one is for dummy input!
-    let result = Groth16::<Bls12_381>::verify_with_processed_vk(
-        &pvk,
-        &[
-            BlsFr::from(data.a),
-            BlsFr::from(data.b),
-            BlsFr::from(data.fib_number),
-        ],
-        &proof,
-    )
-    .unwrap();
-
-    let verifying_time = start.elapsed().as_secs_f64();
-    let data = OutputVerifyData {
-        verifying_time,
-        is_res: result,
-    };
+pub async fn fast_fibbonaci_snark_verify(
+    keys: web::Data<KeyStore>,
+    query: web::Query<BackendQuery>,
+    data: web::Json<InputDataFastFibVer>,
+) -> Result<HttpResponse, ZkError> {
+    let backend = query.or(data.backend);
+    let data = on_curve!(data.curve, E => fast_fibonacci_verify::<E>(&keys, backend, &data))?;
+    Ok(HttpResponse::Ok().json(data))
+}
 
-    HttpResponse::Ok().json(data)
+fn fast_fibonacci_verify<E: SnarkCurve>(
+    keys: &KeyStore,
+    backend: Backend,
+    data: &InputDataFastFibVer,
+) -> Result<OutputVerifyData, ZkError> {
+    let (a, b) = parse_inputs::<E::ScalarField>(&data.a, &data.b)?;
+    // the public inputs are a, b, n and the result
+    let inputs = [
+        a,
+        b,
+        E::ScalarField::from(data.n),
+        parse_field("fib_number", &data.fib_number)?,
+    ];
+    let key_id = key_id(FAST_FIBONACCI_CIRCUIT, &[]);
+    let verified = verify_with_keys::<E>(backend, keys, &key_id, &inputs, &data.proof)?;
+    Ok(OutputVerifyData {
+        verifying_time: verified.verifying_time,
+        is_res: verified.valid,
+    })
 }
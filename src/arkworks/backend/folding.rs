@@ -0,0 +1,427 @@
+use crate::arkworks::constraints::fibbonaci::FibonacciCircuit;
+use crate::arkworks::curves::{on_curve, SnarkCurve};
+use crate::arkworks::folding::steps::{composite_factors, search_candidate, PrimeSearchStep};
+use crate::arkworks::folding::transcript::{
+    verify, FoldingParams, FoldingProver, FoldingTranscript, StepCircuit,
+};
+use crate::arkworks::matrix_proof_of_work::io::{decode_hash, encode_hash, field_to_hex};
+use crate::error::{parse_field, Validate, ZkError};
+use crate::proof_system::Curve;
+use actix_web::{web, HttpResponse};
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use serde::{Deserialize, Serialize};
+
+// num_of_rounds fibonacci rounds folded in steps of rounds_per_step rounds, and the first
+// num_steps candidates of the hash-to-prime search of x folded one per step. the cross terms
+// are committed in G1 of the curve. the answer is a FoldingTranscript, which grows with the
+// number of steps and reveals the folded witness
+
+// the additions of the fibonacci step are linear, so its circuit only has the constraints that
+// check its output, but the prover and the verifier build one linear combination per round
+pub const MAX_ROUNDS_PER_STEP: usize = 1 << 12;
+// the transcript has one commitment per step and the verifier refolds every one
+pub const MAX_FOLDING_STEPS: usize = 1 << 12;
+// every step of the prime search hashes its candidate in the circuit, so it takes fewer steps
+pub const MAX_PRIME_STEPS: usize = 1 << 8;
+
+#[derive(Deserialize)]
+pub struct InputDataFoldingFib {
+    pub a: String,
+    pub b: String,
+    pub num_of_rounds: usize,
+    pub rounds_per_step: usize,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+#[derive(Deserialize)]
+pub struct InputDataFoldingFibVer {
+    pub transcript: String,
+    pub a: String,
+    pub b: String,
+    pub fib_number: String,
+    pub rounds_per_step: usize,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+#[derive(Serialize)]
+pub struct OutputDataFoldingFib {
+    pub transcript: String,
+    pub fib_number: String,
+    pub fib_number_hex: String,
+    pub num_steps: usize,
+    pub constraints_per_step: usize,
+    pub transcript_size: usize,
+    pub proving_time: f64,
+}
+
+#[derive(Deserialize)]
+pub struct InputDataFoldingPrime {
+    pub x: u64,
+    pub num_steps: usize,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+#[derive(Deserialize)]
+pub struct InputDataFoldingPrimeVer {
+    pub transcript: String,
+    pub x: u64,
+    pub num_steps: usize,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+#[derive(Serialize)]
+pub struct OutputDataFoldingPrime {
+    pub transcript: String,
+    pub num_steps: usize,
+    pub constraints_per_step: usize,
+    pub transcript_size: usize,
+    pub proving_time: f64,
+}
+
+#[derive(Serialize)]
+pub struct OutputVerifyFolding {
+    pub verifying_time: f64,
+    pub is_res: bool,
+}
+
+fn fibonacci_step<F: PrimeField>(rounds_per_step: usize) -> FibonacciCircuit<F> {
+    FibonacciCircuit {
+        a: None,
+        b: None,
+        num_of_steps: rounds_per_step,
+        result: None,
+    }
+}
+
+fn check_rounds_per_step(rounds_per_step: usize) -> Result<(), ZkError> {
+    if rounds_per_step == 0 || rounds_per_step > MAX_ROUNDS_PER_STEP {
+        return Err(ZkError::invalid(
+            "rounds_per_step",
+            format!(
+                "rounds_per_step must be between 1 and {}",
+                MAX_ROUNDS_PER_STEP
+            ),
+        ));
+    }
+    Ok(())
+}
+
+impl Validate for InputDataFoldingFib {
+    fn validate(&self) -> Result<(), ZkError> {
+        check_rounds_per_step(self.rounds_per_step)?;
+        if self.num_of_rounds == 0 || self.num_of_rounds % self.rounds_per_step != 0 {
+            return Err(ZkError::invalid(
+                "num_of_rounds",
+                "num_of_rounds must be a positive multiple of rounds_per_step",
+            ));
+        }
+        if self.num_of_rounds / self.rounds_per_step > MAX_FOLDING_STEPS {
+            return Err(ZkError::invalid(
+                "num_of_rounds",
+                format!(
+                    "num_of_rounds can be at most {} steps of rounds_per_step rounds",
+                    MAX_FOLDING_STEPS
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Validate for InputDataFoldingFibVer {
+    fn validate(&self) -> Result<(), ZkError> {
+        check_rounds_per_step(self.rounds_per_step)
+    }
+}
+
+fn check_prime_steps(num_steps: usize) -> Result<(), ZkError> {
+    if num_steps == 0 || num_steps > MAX_PRIME_STEPS {
+        return Err(ZkError::invalid(
+            "num_steps",
+            format!("num_steps must be between 1 and {}", MAX_PRIME_STEPS),
+        ));
+    }
+    Ok(())
+}
+
+impl Validate for InputDataFoldingPrime {
+    fn validate(&self) -> Result<(), ZkError> {
+        check_prime_steps(self.num_steps)
+    }
+}
+
+impl Validate for InputDataFoldingPrimeVer {
+    fn validate(&self) -> Result<(), ZkError> {
+        check_prime_steps(self.num_steps)
+    }
+}
+
+// the transcript, its compressed bytes and the proving time
+type Folded<G> = (FoldingTranscript<G>, Vec<u8>, f64);
+
+// runs num_steps steps from z0 and encodes the transcript
+fn fold_steps<E: SnarkCurve, S: StepCircuit<E::ScalarField>>(
+    params: &FoldingParams<E::G1, S>,
+    z0: Vec<E::ScalarField>,
+    num_steps: usize,
+) -> Result<Folded<E::G1>, ZkError> {
+    let start = ark_std::time::Instant::now();
+    let mut prover = FoldingProver::new(params, z0);
+    for _ in 0..num_steps {
+        prover.prove_step()?;
+    }
+    let transcript = prover
+        .finish()
+        .ok_or_else(|| ZkError::Internal("no step was folded".to_string()))?;
+    let proving_time = start.elapsed().as_secs_f64();
+
+    let mut transcript_bytes = vec![];
+    transcript
+        .serialize_compressed(&mut transcript_bytes)
+        .map_err(|e| ZkError::Internal(e.to_string()))?;
+    Ok((transcript, transcript_bytes, proving_time))
+}
+
+fn decode_transcript<E: SnarkCurve>(
+    transcript: &str,
+    max_steps: usize,
+) -> Result<FoldingTranscript<E::G1>, ZkError> {
+    let transcript = decode_hash(transcript)
+        .ok()
+        .and_then(|bytes| FoldingTranscript::<E::G1>::deserialize_compressed(&bytes[..]).ok())
+        .ok_or_else(|| ZkError::invalid("transcript", "invalid folding transcript"))?;
+    if transcript.num_steps() > max_steps {
+        return Err(ZkError::invalid(
+            "transcript",
+            format!("the transcript can have at most {} steps", max_steps),
+        ));
+    }
+    Ok(transcript)
+}
+
+pub async fn folding_fibbonaci_proof(
+    data: web::Json<InputDataFoldingFib>,
+) -> Result<HttpResponse, ZkError> {
+    data.validate()?;
+    let output = on_curve!(data.curve, E => folding_proof::<E>(&data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn folding_proof<E: SnarkCurve>(
+    data: &InputDataFoldingFib,
+) -> Result<OutputDataFoldingFib, ZkError> {
+    let z0 = vec![parse_field("a", &data.a)?, parse_field("b", &data.b)?];
+    let params = FoldingParams::<E::G1, _>::setup(fibonacci_step(data.rounds_per_step))?;
+    let num_steps = data.num_of_rounds / data.rounds_per_step;
+    let (transcript, transcript_bytes, proving_time) = fold_steps::<E, _>(&params, z0, num_steps)?;
+
+    let fibo_num = transcript.output()[1];
+    Ok(OutputDataFoldingFib {
+        transcript: encode_hash(&transcript_bytes),
+        fib_number: fibo_num.to_string(),
+        fib_number_hex: field_to_hex(fibo_num),
+        num_steps: transcript.num_steps(),
+        constraints_per_step: params.shape.num_constraints,
+        transcript_size: transcript_bytes.len(),
+        proving_time,
+    })
+}
+
+pub async fn folding_fibbonaci_verify(
+    data: web::Json<InputDataFoldingFibVer>,
+) -> Result<HttpResponse, ZkError> {
+    data.validate()?;
+    let output = on_curve!(data.curve, E => folding_verify::<E>(&data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn folding_verify<E: SnarkCurve>(
+    data: &InputDataFoldingFibVer,
+) -> Result<OutputVerifyFolding, ZkError> {
+    let inputs = [
+        parse_field::<E::ScalarField>("a", &data.a)?,
+        parse_field("b", &data.b)?,
+        parse_field("fib_number", &data.fib_number)?,
+    ];
+    let transcript = decode_transcript::<E>(&data.transcript, MAX_FOLDING_STEPS)?;
+    let params = FoldingParams::<E::G1, _>::setup(fibonacci_step(data.rounds_per_step))?;
+
+    let start = ark_std::time::Instant::now();
+    // the transcript starts at (a, b) and ends at (F(n-1), F(n))
+    let result = transcript.z0 == inputs[..2]
+        && transcript.output()[1] == inputs[2]
+        && verify(&params, &transcript);
+    let verifying_time = start.elapsed().as_secs_f64();
+
+    Ok(OutputVerifyFolding {
+        verifying_time,
+        is_res: result,
+    })
+}
+
+pub async fn folding_prime_proof(
+    data: web::Json<InputDataFoldingPrime>,
+) -> Result<HttpResponse, ZkError> {
+    data.validate()?;
+    let output = on_curve!(data.curve, E => folding_prime::<E>(&data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+// x + j is hashed as an element of the scalar field of the curve like in the prime circuit, so
+// the candidates depend on the curve
+fn folding_prime<E: SnarkCurve>(
+    data: &InputDataFoldingPrime,
+) -> Result<OutputDataFoldingPrime, ZkError> {
+    let x = E::ScalarField::from(data.x);
+    // a prime candidate has no factors for the step circuit, so it can't be folded
+    if let Some(j) = (0..data.num_steps as u64)
+        .find(|j| composite_factors(search_candidate(x + E::ScalarField::from(*j))).is_none())
+    {
+        return Err(ZkError::invalid(
+            "num_steps",
+            format!(
+                "the candidate {} of x is prime, only the candidates before it can be folded",
+                j
+            ),
+        ));
+    }
+    let params = FoldingParams::<E::G1, _>::setup(PrimeSearchStep)?;
+    let z0 = vec![x, E::ScalarField::from(0u64)];
+    let (transcript, transcript_bytes, proving_time) =
+        fold_steps::<E, _>(&params, z0, data.num_steps)?;
+
+    Ok(OutputDataFoldingPrime {
+        transcript: encode_hash(&transcript_bytes),
+        num_steps: transcript.num_steps(),
+        constraints_per_step: params.shape.num_constraints,
+        transcript_size: transcript_bytes.len(),
+        proving_time,
+    })
+}
+
+pub async fn folding_prime_verify(
+    data: web::Json<InputDataFoldingPrimeVer>,
+) -> Result<HttpResponse, ZkError> {
+    data.validate()?;
+    let output = on_curve!(data.curve, E => folding_prime_verify_on::<E>(&data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn folding_prime_verify_on<E: SnarkCurve>(
+    data: &InputDataFoldingPrimeVer,
+) -> Result<OutputVerifyFolding, ZkError> {
+    let transcript = decode_transcript::<E>(&data.transcript, MAX_PRIME_STEPS)?;
+    let params = FoldingParams::<E::G1, _>::setup(PrimeSearchStep)?;
+
+    let start = ark_std::time::Instant::now();
+    // the transcript starts at (x, 0) and ends at (x, num_steps)
+    let x = E::ScalarField::from(data.x);
+    let result = transcript.z0 == [x, E::ScalarField::from(0u64)]
+        && transcript.output() == [x, E::ScalarField::from(data.num_steps as u64)]
+        && verify(&params, &transcript);
+    let verifying_time = start.elapsed().as_secs_f64();
+
+    Ok(OutputVerifyFolding {
+        verifying_time,
+        is_res: result,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::folding::steps::first_prime_index;
+    use ark_bls12_381::{Bls12_381, Fr};
+
+    fn input(num_of_rounds: usize, rounds_per_step: usize) -> InputDataFoldingFib {
+        InputDataFoldingFib {
+            a: "0".to_string(),
+            b: "1".to_string(),
+            num_of_rounds,
+            rounds_per_step,
+            curve: Curve::Bls12_381,
+        }
+    }
+
+    #[test]
+    fn test_folding_transcript_round_trips() {
+        let data = input(12, 4);
+        data.validate().unwrap();
+        let output = folding_proof::<Bls12_381>(&data).unwrap();
+        assert_eq!(output.fib_number, "233");
+        assert_eq!(output.num_steps, 3);
+        // the two outputs, the additions need no constraints
+        assert_eq!(output.constraints_per_step, 2);
+        let verify = |fib_number: &str| {
+            folding_verify::<Bls12_381>(&InputDataFoldingFibVer {
+                transcript: output.transcript.clone(),
+                a: "0".to_string(),
+                b: "1".to_string(),
+                fib_number: fib_number.to_string(),
+                rounds_per_step: 4,
+                curve: Curve::Bls12_381,
+            })
+            .unwrap()
+            .is_res
+        };
+        assert!(verify("233"));
+        assert!(!verify("234"));
+    }
+
+    #[test]
+    fn test_folding_limits() {
+        let error = input(MAX_ROUNDS_PER_STEP + 1, MAX_ROUNDS_PER_STEP + 1)
+            .validate()
+            .err()
+            .unwrap();
+        assert_eq!(error.field(), Some("rounds_per_step"));
+        let error = input(2 * (MAX_FOLDING_STEPS + 1), 2)
+            .validate()
+            .err()
+            .unwrap();
+        assert_eq!(error.field(), Some("num_of_rounds"));
+        assert!(input(2 * MAX_FOLDING_STEPS, 2).validate().is_ok());
+        assert!(input(10, 3).validate().is_err());
+    }
+
+    #[test]
+    fn test_prime_search_folding() {
+        // a seed whose first two candidates are composite and whose third is prime
+        let x = (0u64..)
+            .find(|x| first_prime_index(Fr::from(*x), 3) == Some(2))
+            .unwrap();
+        let prime_input = |num_steps| InputDataFoldingPrime {
+            x,
+            num_steps,
+            curve: Curve::Bls12_381,
+        };
+        let data = prime_input(2);
+        data.validate().unwrap();
+        let output = folding_prime::<Bls12_381>(&data).unwrap();
+        assert_eq!(output.num_steps, 2);
+        let verify = |x, num_steps| {
+            folding_prime_verify_on::<Bls12_381>(&InputDataFoldingPrimeVer {
+                transcript: output.transcript.clone(),
+                x,
+                num_steps,
+                curve: Curve::Bls12_381,
+            })
+            .unwrap()
+            .is_res
+        };
+        assert!(verify(x, 2));
+        assert!(!verify(x, 1));
+        assert!(!verify(x + 1, 2));
+
+        // the third candidate is prime
+        let error = folding_prime::<Bls12_381>(&prime_input(3)).err().unwrap();
+        assert_eq!(error.field(), Some("num_steps"));
+        assert!(prime_input(0).validate().is_err());
+        assert!(prime_input(MAX_PRIME_STEPS + 1).validate().is_err());
+    }
+}
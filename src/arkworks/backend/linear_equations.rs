@@ -1,113 +1,412 @@
+use crate::arkworks::backend::proving::{prove_with_keys, verify_with_keys};
 use crate::arkworks::constraints::linear_equations_constraints::{
-    InputData, LinearEquationCircuit, OutputData,
+    system_public_inputs, Hide, InfeasibilityCircuit, InfeasibleInputData, InfeasibleOutputData,
+    InfeasibleVerifyInputData, InputData, LinearEquationCircuit, OutputData, VerifyInputData,
+    VerifyOutputData,
 };
-use actix_web::{web, HttpResponse, Responder};
-use ark_bls12_381::{Bls12_381, Fr};
-use ark_groth16::Groth16;
-use ark_relations::r1cs::ConstraintSystem;
+use crate::arkworks::curves::{on_curve, SnarkCurve};
+use crate::arkworks::key_store::{key_id, KeyStore};
+use crate::bundle::indexed_fields;
+use crate::error::{check_len, check_shape, parse_field, parse_fields, Validate, ZkError};
+use actix_web::{web, HttpResponse};
+use ark_ff::PrimeField;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
 // groth trace:
 // use ark_relations::r1cs::ConstraintTrace;
 // use ark_relations::r1cs::TracingMode;
-use ark_snark::SNARK;
 // use ark_std::perf_trace;
-use ark_std::rand::SeedableRng;
 // use ark_std::start_timer;
-use ark_std::Zero;
-use rand::rngs::StdRng;
 
-// create a function to solve the linear equations and find a witness x:
-// Assume that the matrix A is a square matrix
-fn solve_linear_equations(a: Vec<Vec<Fr>>, b: Vec<Fr>) -> Vec<Fr> {
-    // create a vector to store the solution
-    let mut x: Vec<Fr> = vec![Fr::zero(); b.len()];
-
-    // iterate over the rows of the matrix A
-    for i in 0..a.len() {
-        // create a variable to store the sum of the products of the elements of the row and the solution vector x
-        let mut sum = Fr::zero();
-
-        // iterate over the elements of the row
-        for j in 0..a[i].len() {
-            // add the product of the element and the corresponding element of the solution vector x to the sum
-            sum += a[i][j] * x[j];
+// the solutions of Ax = b for an m x n matrix A
+#[derive(Clone, Debug, PartialEq)]
+pub enum LinearSystemSolution<F: PrimeField> {
+    // rank(A) < rank([A | b])
+    None { rank: usize },
+    Unique(Vec<F>),
+    // rank(A) < n, x is the solution with all the free variables set to 0
+    Many { x: Vec<F>, rank: usize },
+}
+
+impl<F: PrimeField> LinearSystemSolution<F> {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LinearSystemSolution::None { .. } => "none",
+            LinearSystemSolution::Unique(_) => "unique",
+            LinearSystemSolution::Many { .. } => "many",
         }
+    }
+}
+
+// Gauss-Jordan elimination of the augmented matrix [A | b] over the field. any non zero element
+// is a good pivot in a field, so we take the first one in the column (no rounding errors).
+// the row operations are tracked in E, so E [A | b] is the reduced form.
+struct Elimination<F: PrimeField> {
+    aug: Vec<Vec<F>>,
+    e: Vec<Vec<F>>,
+    pivot_cols: Vec<usize>, // pivot_cols[k] is the column of the pivot in row k
+}
+
+fn gauss_jordan<F: PrimeField>(a: &[Vec<F>], b: &[F]) -> Elimination<F> {
+    let m = a.len();
+    let n = a.first().map_or(0, |row| row.len());
+    let mut aug: Vec<Vec<F>> = a
+        .iter()
+        .zip(b.iter())
+        .map(|(row, b_i)| row.iter().cloned().chain([*b_i]).collect())
+        .collect();
+    let mut e: Vec<Vec<F>> = (0..m)
+        .map(|i| (0..m).map(|j| F::from((i == j) as u64)).collect())
+        .collect();
 
-        // calculate the value of the solution vector x[i] using the corrected formula
-        x[i] = (b[i] - sum) / a[i][i];
+    let mut pivot_cols = vec![];
+    for col in 0..n {
+        let rank = pivot_cols.len();
+        let pivot_row = match (rank..m).find(|&i| !aug[i][col].is_zero()) {
+            Some(row) => row,
+            None => continue,
+        };
+        aug.swap(rank, pivot_row);
+        e.swap(rank, pivot_row);
+        let pivot_inv = aug[rank][col].inverse().unwrap();
+        for value in aug[rank].iter_mut().chain(e[rank].iter_mut()) {
+            *value *= pivot_inv;
+        }
+        for i in 0..m {
+            if i != rank && !aug[i][col].is_zero() {
+                let factor = aug[i][col];
+                for j in col..=n {
+                    let pivot_value = aug[rank][j];
+                    aug[i][j] -= factor * pivot_value;
+                }
+                for j in 0..m {
+                    let pivot_value = e[rank][j];
+                    e[i][j] -= factor * pivot_value;
+                }
+            }
+        }
+        pivot_cols.push(col);
     }
+    Elimination { aug, e, pivot_cols }
+}
+
+pub fn solve_linear_equations<F: PrimeField>(a: &[Vec<F>], b: &[F]) -> LinearSystemSolution<F> {
+    let n = a.first().map_or(0, |row| row.len());
+    let Elimination {
+        aug, pivot_cols, ..
+    } = gauss_jordan(a, b);
 
-    // return the solution vector x
-    x
+    let rank = pivot_cols.len();
+    // a row 0 = b_i with b_i != 0 left after the elimination
+    if aug[rank..].iter().any(|row| !row[n].is_zero()) {
+        return LinearSystemSolution::None { rank };
+    }
+    let mut x = vec![F::zero(); n];
+    for (k, col) in pivot_cols.into_iter().enumerate() {
+        x[col] = aug[k][n];
+    }
+    if rank == n {
+        LinearSystemSolution::Unique(x)
+    } else {
+        LinearSystemSolution::Many { x, rank }
+    }
 }
 
-pub async fn prove_linear_equations(data: web::Json<InputData>) -> impl Responder {
-    // enable tracing using the traceing mode.
+// y with y^T A = 0 and y^T b != 0 when Ax = b has no solution: the row of E that produced a
+// row 0 = b_i with b_i != 0
+pub fn infeasibility_certificate<F: PrimeField>(a: &[Vec<F>], b: &[F]) -> Option<Vec<F>> {
+    let n = a.first().map_or(0, |row| row.len());
+    let Elimination {
+        aug, e, pivot_cols, ..
+    } = gauss_jordan(a, b);
+    (pivot_cols.len()..aug.len())
+        .find(|&i| !aug[i][n].is_zero())
+        .map(|i| e[i].clone())
+}
 
-    // Extract data from the request
-    let a = data.a.clone();
-    let b = data.b.clone();
-    let len_b = b.len();
-    let len_a = a[0].len();
+// the largest systems the endpoints prove, in rows and in columns
+pub const MAX_DIM: usize = 256;
 
-    // Convert the input data to the required format
-    let a: Vec<Vec<Fr>> = a
-        .iter()
-        .map(|row| row.iter().map(|x| Fr::from(*x)).collect())
-        .collect();
-    let b: Vec<Fr> = b.iter().map(|x| Fr::from(*x)).collect();
+// A must be a non empty rectangle of at most MAX_DIM x MAX_DIM
+fn check_matrix(a: &[Vec<u64>]) -> Result<(), ZkError> {
+    check_len("a", a, MAX_DIM)?;
+    check_shape("a", a, a.len(), a[0].len())?;
+    check_len("a", &a[0], MAX_DIM)
+}
 
-    // Solve the linear equations to find the solution vector x
-    let x = solve_linear_equations(a.clone(), b.clone());
-    // Create a constraint system
-    let cs = ConstraintSystem::<Fr>::new_ref();
-
-    // Obtain the public input:
-    // [a[0][0], a[0][1], b[0], a[1][0], a[1][1], b[1]]
-    let mut public_input: Vec<Fr> = vec![];
-    for i in 0..a.clone().len() {
-        for j in 0..a[0].len() {
-            public_input.push(a[i][j]);
+// ... with a row for every element of b
+fn check_system(a: &[Vec<u64>], b: &[u64]) -> Result<(), ZkError> {
+    check_matrix(a)?;
+    if a.len() != b.len() {
+        return Err(ZkError::invalid(
+            "b",
+            "b must have one element per row of A",
+        ));
+    }
+    Ok(())
+}
+
+impl Validate for InputData {
+    fn validate(&self) -> Result<(), ZkError> {
+        check_system(&self.a, &self.b)
+    }
+}
+
+impl Validate for InfeasibleInputData {
+    fn validate(&self) -> Result<(), ZkError> {
+        check_system(&self.a, &self.b)
+    }
+}
+
+impl Validate for VerifyInputData {
+    fn validate(&self) -> Result<(), ZkError> {
+        match (&self.a, &self.b) {
+            (Some(a), Some(b)) => check_system(a, b),
+            (Some(a), None) => check_matrix(a),
+            (None, Some(b)) => check_len("b", b, MAX_DIM),
+            (None, None) => Ok(()),
         }
-        public_input.push(b[i]);
     }
-    // Create a linear equation circuit
-    let circuit = LinearEquationCircuit {
-        a,
-        b,
-        x,
-        len_b,
-        len_a,
-    };
+}
+
+fn to_field_matrix<F: PrimeField>(a: &[Vec<u64>]) -> Vec<Vec<F>> {
+    a.iter()
+        .map(|row| row.iter().map(|x| F::from(*x)).collect())
+        .collect()
+}
+
+fn to_field_vector<F: PrimeField>(b: &[u64]) -> Vec<F> {
+    b.iter().map(|x| F::from(*x)).collect()
+}
+
+// the names of the keys and the bundles, the verify endpoints derive the key ids from them
+const SYSTEM_CIRCUIT: &str = "linear_equations";
+const INFEASIBLE_CIRCUIT: &str = "linear_equations_infeasible";
+
+// the keys of an m x n system with the same hidden parts
+fn system_params(rows: usize, cols: usize, hide: Hide) -> Vec<String> {
+    vec![
+        format!("{}x{}", rows, cols),
+        format!("{:?}", hide).to_lowercase(),
+    ]
+}
+
+fn infeasible_params(rows: usize, cols: usize, commit: bool) -> Vec<String> {
+    vec![
+        format!("{}x{}", rows, cols),
+        if commit { "committed" } else { "public" }.to_string(),
+    ]
+}
+
+// exactly one of the value and its hash (decimal or hex), the hash is 0 when the value is given
+fn value_or_hash<T, F: PrimeField>(
+    name: &str,
+    value: Option<T>,
+    hash: &Option<String>,
+) -> Result<(Option<T>, F), ZkError> {
+    let hash_field = format!("hash_{}", name);
+    match (value, hash) {
+        (Some(value), None) => Ok((Some(value), F::zero())),
+        (None, Some(hash)) => Ok((None, parse_field(&hash_field, hash)?)),
+        _ => Err(ZkError::invalid(
+            name,
+            format!("give either {} or {}", name, hash_field),
+        )),
+    }
+}
+
+pub async fn prove_linear_equations(
+    keys: web::Data<KeyStore>,
+    data: web::Json<InputData>,
+) -> Result<HttpResponse, ZkError> {
+    Ok(HttpResponse::Ok().json(linear_equations_proof(&keys, &data)?))
+}
 
-    // Generate proving and verifying keys
-    let mut rng = StdRng::seed_from_u64(0u64);
+// the proof of /linear_equations/prove, also run by the linear equations jobs
+pub fn linear_equations_proof(keys: &KeyStore, data: &InputData) -> Result<OutputData, ZkError> {
+    on_curve!(data.curve, E => linear_equations_proof_on::<E>(keys, data))
+}
+
+fn linear_equations_proof_on<E: SnarkCurve>(
+    keys: &KeyStore,
+    data: &InputData,
+) -> Result<OutputData, ZkError> {
+    // Convert the input data to the required format
+    data.validate()?;
+    let a = to_field_matrix::<E::ScalarField>(&data.a);
+    let b = to_field_vector(&data.b);
+    let (num_rows, len_a) = (a.len(), a[0].len());
 
-    let (pk, vk) = Groth16::<Bls12_381>::circuit_specific_setup(circuit.clone(), &mut rng).unwrap();
+    // Solve the linear equations to find the solution vector x
+    let solution = solve_linear_equations(&a, &b);
+    let (x, rank) = match &solution {
+        LinearSystemSolution::None { rank } => {
+            return Err(ZkError::Unsatisfiable(format!(
+                "the system has no solution: rank(A) = {} < rank([A | b])",
+                rank
+            )))
+        }
+        LinearSystemSolution::Unique(x) => (x.clone(), len_a),
+        LinearSystemSolution::Many { x, rank } => (x.clone(), *rank),
+    };
 
-    // Create a proof
-    let start_proving = ark_std::time::Instant::now();
-    let proof = Groth16::<Bls12_381>::prove(&pk, circuit.clone(), &mut rng).unwrap();
-    let proving_time = start_proving.elapsed().as_secs_f64();
+    // Create a linear equation circuit, the hidden parts of the system are witnesses
+    let circuit = LinearEquationCircuit::new(a, b, x.clone(), data.hide);
+    let public_input = circuit.public_inputs();
+
+    // Get the proving and verifying keys of an m x n system with the same hidden parts
+    let params = system_params(num_rows, len_a, data.hide);
+    let key_id = key_id(SYSTEM_CIRCUIT, &params);
+    let proved = prove_with_keys::<E, _>(data.backend, keys, &key_id, circuit.clone())?;
+    let verified =
+        verify_with_keys::<E>(data.backend, keys, &key_id, &public_input, &proved.proof)?;
+    if !verified.valid {
+        return Err(ZkError::Internal(
+            "the proof of a solution doesn't verify".to_string(),
+        ));
+    }
 
-    let start_verifying = ark_std::time::Instant::now();
-    let is_correct = Groth16::<Bls12_381>::verify(&vk, &public_input, &proof).unwrap();
-    let verifying_time = start_verifying.elapsed().as_secs_f64();
+    // Create a constraint system to count the constraints
+    let cs = ConstraintSystem::<E::ScalarField>::new_ref();
+    circuit.clone().generate_constraints(cs.clone())?;
+    let bundle = proved.bundle(
+        data.backend,
+        SYSTEM_CIRCUIT,
+        params,
+        indexed_fields("public_input", &public_input),
+    );
 
     // Create the output data
     let result = OutputData {
-        proof: format!("{:?}", proof),
-        public_input: public_input.iter().map(|x| format!("{:?}", x)).collect(),
+        proof: proved.proof,
+        key_id,
+        hide: data.hide,
+        hash_a: data.hide.hides_a().then(|| circuit.hash_of_a.to_string()),
+        hash_b: data.hide.hides_b().then(|| circuit.hash_of_b.to_string()),
+        public_input: public_input.iter().map(|x| x.to_string()).collect(),
+        solution: solution.kind().to_string(),
+        rank,
+        x: x.iter().map(|x| x.to_string()).collect(),
         num_constraints: cs.num_constraints(),
         num_variables: cs.num_instance_variables(),
-        proving_time: 0.0,
-        verifying_time: 0.0,
+        setup_time: proved.setup_time,
+        proving_time: proved.proving_time,
+        verifying_time: verified.verifying_time,
+        bundle,
     };
-    HttpResponse::Ok().json(result)
+    Ok(result)
+}
+
+pub async fn verify_linear_equations(
+    keys: web::Data<KeyStore>,
+    data: web::Json<VerifyInputData>,
+) -> Result<HttpResponse, ZkError> {
+    data.validate()?;
+    let output = on_curve!(data.curve, E => linear_equations_verify::<E>(&keys, &data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn linear_equations_verify<E: SnarkCurve>(
+    keys: &KeyStore,
+    data: &VerifyInputData,
+) -> Result<VerifyOutputData, ZkError> {
+    // the public inputs only need the parts of the system that aren't hidden
+    let a = data.a.as_deref().map(to_field_matrix::<E::ScalarField>);
+    let (a, hash_a) = value_or_hash("a", a, &data.hash_a)?;
+    let b = data.b.as_deref().map(to_field_vector);
+    let (b, hash_b) = value_or_hash("b", b, &data.hash_b)?;
+    let public_input = system_public_inputs(a.as_deref(), b.as_deref(), hash_a, hash_b);
+    // the keys of the shape of the system and of its hidden parts
+    let (rows, cols) = match &data.a {
+        Some(a) => (a.len(), a[0].len()),
+        None => (
+            data.rows
+                .ok_or_else(|| ZkError::invalid("rows", "rows is needed when a is hidden"))?,
+            data.cols
+                .ok_or_else(|| ZkError::invalid("cols", "cols is needed when a is hidden"))?,
+        ),
+    };
+    let hide = Hide::of(data.a.is_none(), data.b.is_none());
+    let key_id = key_id(SYSTEM_CIRCUIT, &system_params(rows, cols, hide));
+    let verified = verify_with_keys::<E>(data.backend, keys, &key_id, &public_input, &data.proof)?;
+
+    Ok(VerifyOutputData {
+        verifying_time: verified.verifying_time,
+        valid: verified.valid,
+    })
+}
+
+pub async fn prove_infeasible(
+    keys: web::Data<KeyStore>,
+    data: web::Json<InfeasibleInputData>,
+) -> Result<HttpResponse, ZkError> {
+    data.validate()?;
+    let output = on_curve!(data.curve, E => infeasible_proof::<E>(&keys, &data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn infeasible_proof<E: SnarkCurve>(
+    keys: &KeyStore,
+    data: &InfeasibleInputData,
+) -> Result<InfeasibleOutputData, ZkError> {
+    let a = to_field_matrix::<E::ScalarField>(&data.a);
+    let b = to_field_vector(&data.b);
+    let y = infeasibility_certificate(&a, &b)
+        .ok_or_else(|| ZkError::Unsatisfiable("the system has a solution".to_string()))?;
+    let params = infeasible_params(a.len(), a[0].len(), data.commit);
+    let key_id = key_id(INFEASIBLE_CIRCUIT, &params);
+    let circuit = InfeasibilityCircuit::new(a, b, y, data.commit);
+    let public_input = circuit.public_inputs();
+
+    let proved = prove_with_keys::<E, _>(data.backend, keys, &key_id, circuit.clone())?;
+
+    let cs = ConstraintSystem::<E::ScalarField>::new_ref();
+    circuit.generate_constraints(cs.clone())?;
+    let bundle = proved.bundle(
+        data.backend,
+        INFEASIBLE_CIRCUIT,
+        params,
+        indexed_fields("public_input", &public_input),
+    );
+
+    Ok(InfeasibleOutputData {
+        proof: proved.proof,
+        key_id,
+        committed: data.commit,
+        public_input: public_input.iter().map(|x| x.to_string()).collect(),
+        num_constraints: cs.num_constraints(),
+        num_variables: cs.num_instance_variables(),
+        setup_time: proved.setup_time,
+        proving_time: proved.proving_time,
+        bundle,
+    })
+}
+
+pub async fn verify_infeasible(
+    keys: web::Data<KeyStore>,
+    data: web::Json<InfeasibleVerifyInputData>,
+) -> Result<HttpResponse, ZkError> {
+    let output = on_curve!(data.curve, E => infeasible_verify::<E>(&keys, &data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn infeasible_verify<E: SnarkCurve>(
+    keys: &KeyStore,
+    data: &InfeasibleVerifyInputData,
+) -> Result<VerifyOutputData, ZkError> {
+    let public_input = parse_fields::<E::ScalarField, _>("public_input", &data.public_input)?;
+    let params = infeasible_params(data.rows, data.cols, data.commit);
+    let key_id = key_id(INFEASIBLE_CIRCUIT, &params);
+    let verified = verify_with_keys::<E>(data.backend, keys, &key_id, &public_input, &data.proof)?;
+
+    Ok(VerifyOutputData {
+        verifying_time: verified.verifying_time,
+        valid: verified.valid,
+    })
 }
 //
 /*
 use ark_bls12_381::{Bls12_381, Fr};
-use ark_ff::{Field, PrimeField};
+use ark_ff::PrimeField;
 use ark_groth16::Groth16;
 use ark_r1cs_std::alloc::AllocVar;
 use ark_r1cs_std::eq::EqGadget;
@@ -221,6 +520,14 @@ pub async fn prime_snark_compute(data: web::Json<InputData>) -> impl Responder {
 mod tests {
 
     use super::*;
+    use crate::arkworks::key_store::TestDir;
+    use crate::arkworks::matrix_proof_of_work::hasher::hasher_native;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ff::Zero;
+    use ark_groth16::Groth16;
+    use ark_snark::SNARK;
+    use ark_std::rand::SeedableRng;
+    use rand::rngs::StdRng;
 
     #[test]
     fn test_linear_equation_circuit() {
@@ -244,19 +551,11 @@ mod tests {
         }
         println!("Public input my {:?}", public_input);
 
-        let len_b = b.len();
-        let len_a = a[0].len();
         // Define the solution vector x
         let x: Vec<Fr> = vec![Fr::from(1u32), Fr::from(1u32)];
 
         // Create a linear equation circuit
-        let circuit = LinearEquationCircuit {
-            a,
-            b: b.clone(),
-            x,
-            len_b,
-            len_a,
-        };
+        let circuit = LinearEquationCircuit::new(a, b.clone(), x, Hide::None);
         println!("Public input real {:?}", public_input);
 
         // Generate proving and verifying keys
@@ -274,4 +573,255 @@ mod tests {
         let is_correct = Groth16::<Bls12_381>::verify(&vk, &public_input, &proof).unwrap();
         assert!(is_correct);
     }
+
+    #[test]
+    fn test_hidden_system() {
+        let (a, b) = field_system(&[&[2, 1], &[1, 3]], &[3, 4]);
+        let x = vec![Fr::from(1u64), Fr::from(1u64)];
+        for hide in [Hide::None, Hide::A, Hide::B, Hide::Both] {
+            let circuit = LinearEquationCircuit::new(a.clone(), b.clone(), x.clone(), hide);
+            let public_input = circuit.public_inputs();
+            // the verifier builds the same public inputs from the visible parts and the hashes
+            assert_eq!(
+                public_input,
+                system_public_inputs(
+                    (!hide.hides_a()).then_some(&a[..]),
+                    (!hide.hides_b()).then_some(&b[..]),
+                    hasher_native(&a),
+                    hasher_native(&[b.clone()]),
+                )
+            );
+            let mut rng = StdRng::seed_from_u64(0u64);
+            let (pk, vk) =
+                Groth16::<Bls12_381>::circuit_specific_setup(circuit.clone(), &mut rng).unwrap();
+            let proof = Groth16::<Bls12_381>::prove(&pk, circuit, &mut rng).unwrap();
+            assert!(Groth16::<Bls12_381>::verify(&vk, &public_input, &proof).unwrap());
+
+            // another A or b doesn't verify
+            let mut wrong = public_input.clone();
+            wrong[0] += Fr::from(1u64);
+            assert!(!Groth16::<Bls12_381>::verify(&vk, &wrong, &proof).unwrap());
+        }
+        assert_eq!(
+            LinearEquationCircuit::new(a.clone(), b.clone(), x.clone(), Hide::Both).public_inputs(),
+            vec![hasher_native(&a), hasher_native(&[b.clone()])]
+        );
+
+        // a wrong x doesn't satisfy the hidden system either
+        let circuit = LinearEquationCircuit::new(a, b, vec![Fr::from(2u64), x[1]], Hide::Both);
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    fn field_system(a: &[&[u64]], b: &[u64]) -> (Vec<Vec<Fr>>, Vec<Fr>) {
+        let a = a
+            .iter()
+            .map(|row| row.iter().map(|x| Fr::from(*x)).collect())
+            .collect();
+        (a, b.iter().map(|x| Fr::from(*x)).collect())
+    }
+
+    fn is_satisfied(a: Vec<Vec<Fr>>, b: Vec<Fr>, x: Vec<Fr>) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let circuit = LinearEquationCircuit::new(a, b, x, Hide::None);
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_solve_needs_pivoting() {
+        // a[0][0] = 0 and A isn't lower triangular
+        let (a, b) = field_system(&[&[0, 1, 2], &[1, 0, 3], &[4, 5, 0]], &[8, 10, 14]);
+        let x = vec![Fr::from(1u64), Fr::from(2u64), Fr::from(3u64)];
+        assert_eq!(
+            solve_linear_equations(&a, &b),
+            LinearSystemSolution::Unique(x.clone())
+        );
+        assert!(is_satisfied(a, b, x));
+    }
+
+    #[test]
+    fn test_solve_singular_systems() {
+        // the second row is twice the first one
+        let (a, b) = field_system(&[&[1, 2], &[2, 4]], &[3, 6]);
+        let solution = solve_linear_equations(&a, &b);
+        assert_eq!(solution.kind(), "many");
+        if let LinearSystemSolution::Many { x, rank } = solution {
+            assert_eq!(rank, 1);
+            assert!(is_satisfied(a.clone(), b, x));
+        }
+        let (_, b) = field_system(&[], &[3, 7]);
+        assert_eq!(
+            solve_linear_equations(&a, &b),
+            LinearSystemSolution::None { rank: 1 }
+        );
+    }
+
+    #[test]
+    fn test_solve_non_square_systems() {
+        // overdetermined and consistent
+        let (a, b) = field_system(&[&[1, 1], &[1, 2], &[2, 3]], &[3, 5, 8]);
+        let x = vec![Fr::from(1u64), Fr::from(2u64)];
+        assert_eq!(
+            solve_linear_equations(&a, &b),
+            LinearSystemSolution::Unique(x.clone())
+        );
+        assert!(is_satisfied(a, b, x));
+        // overdetermined and inconsistent
+        let (a, b) = field_system(&[&[1, 1], &[1, 2], &[2, 3]], &[3, 5, 9]);
+        assert_eq!(solve_linear_equations(&a, &b).kind(), "none");
+        // underdetermined
+        let (a, b) = field_system(&[&[1, 2, 3], &[0, 1, 1]], &[6, 2]);
+        let solution = solve_linear_equations(&a, &b);
+        if let LinearSystemSolution::Many { x, rank } = solution {
+            assert_eq!(rank, 2);
+            assert!(is_satisfied(a, b, x));
+        } else {
+            panic!("expected many solutions");
+        }
+    }
+
+    #[test]
+    fn test_infeasibility_certificate() {
+        let (a, b) = field_system(&[&[1, 1], &[1, 2], &[2, 3]], &[3, 5, 9]);
+        let y = infeasibility_certificate(&a, &b).unwrap();
+        for j in 0..2 {
+            assert!((0..3).map(|i| y[i] * a[i][j]).sum::<Fr>().is_zero());
+        }
+        assert!(!(0..3).map(|i| y[i] * b[i]).sum::<Fr>().is_zero());
+        // feasible systems have no certificate
+        let (a, b) = field_system(&[&[1, 2], &[2, 4]], &[3, 6]);
+        assert_eq!(infeasibility_certificate(&a, &b), None);
+    }
+
+    #[test]
+    fn test_infeasibility_circuit() {
+        let (a, b) = field_system(&[&[1, 2], &[2, 4]], &[3, 7]);
+        let y = infeasibility_certificate(&a, &b).unwrap();
+        for committed in [false, true] {
+            let circuit = InfeasibilityCircuit::new(a.clone(), b.clone(), y.clone(), committed);
+            let public_input = circuit.public_inputs();
+            assert_eq!(public_input.len(), if committed { 2 } else { 6 });
+            let mut rng = StdRng::seed_from_u64(0u64);
+            let (pk, vk) =
+                Groth16::<Bls12_381>::circuit_specific_setup(circuit.clone(), &mut rng).unwrap();
+            let proof = Groth16::<Bls12_381>::prove(&pk, circuit, &mut rng).unwrap();
+            assert!(Groth16::<Bls12_381>::verify(&vk, &public_input, &proof).unwrap());
+        }
+
+        // a certificate for another b doesn't work: y^T b = 0
+        let (_, feasible_b) = field_system(&[], &[3, 6]);
+        let circuit = InfeasibilityCircuit::new(a, feasible_b, y, false);
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        assert!(circuit.generate_constraints(cs.clone()).is_err() || !cs.is_satisfied().unwrap());
+    }
+
+    fn keys(dir: &TestDir) -> web::Data<KeyStore> {
+        web::Data::new(dir.keys())
+    }
+
+    fn input(a: Vec<Vec<u64>>, b: Vec<u64>) -> web::Json<InputData> {
+        web::Json(InputData {
+            a,
+            b,
+            hide: Hide::None,
+            backend: Default::default(),
+            curve: Default::default(),
+        })
+    }
+
+    #[actix_web::test]
+    async fn test_invalid_systems() {
+        let cases = [
+            (vec![], vec![], "a"),
+            (vec![vec![1, 2], vec![3]], vec![1, 2], "a"),
+            (vec![vec![1, 2], vec![3, 4]], vec![1], "b"),
+            (vec![vec![1; MAX_DIM + 1]], vec![1], "a"),
+        ];
+        let dir = TestDir::new("linear_equations_invalid");
+        for (a, b, field) in cases {
+            let error = prove_linear_equations(keys(&dir), input(a, b))
+                .await
+                .unwrap_err();
+            assert_eq!(error.field(), Some(field));
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_false_statements_are_unsatisfiable() {
+        // x + y = 3 and x + y = 4 have no solution, x + y = 3 and 2x + 2y = 6 have many
+        let dir = TestDir::new("linear_equations_false");
+        let error =
+            prove_linear_equations(keys(&dir), input(vec![vec![1, 1], vec![1, 1]], vec![3, 4]))
+                .await
+                .unwrap_err();
+        assert_eq!(error.code(), "unsatisfiable");
+        let data = web::Json(InfeasibleInputData {
+            a: vec![vec![1, 1], vec![2, 2]],
+            b: vec![3, 6],
+            commit: false,
+            backend: Default::default(),
+            curve: Default::default(),
+        });
+        let error = prove_infeasible(keys(&dir), data).await.unwrap_err();
+        assert_eq!(error.code(), "unsatisfiable");
+    }
+
+    #[test]
+    fn test_proofs_of_other_circuits_are_rejected() {
+        let dir = TestDir::new("linear_equations_keys");
+        let keys = dir.keys();
+        let mut system = input(vec![vec![2, 1], vec![1, 3]], vec![3, 4]).into_inner();
+        system.hide = Hide::A;
+        let proved = linear_equations_proof(&keys, &system).unwrap();
+        let mut verify = VerifyInputData {
+            a: None,
+            b: Some(system.b.clone()),
+            hash_a: proved.hash_a.clone(),
+            hash_b: None,
+            rows: Some(2),
+            cols: Some(2),
+            proof: proved.proof.clone(),
+            backend: Default::default(),
+            curve: Default::default(),
+        };
+        assert!(
+            linear_equations_verify::<Bls12_381>(&keys, &verify)
+                .unwrap()
+                .valid
+        );
+        verify.rows = None;
+        let error = linear_equations_verify::<Bls12_381>(&keys, &verify)
+            .err()
+            .unwrap();
+        assert_eq!(error.field(), Some("rows"));
+
+        // a proof of a public system has as many public inputs as a public infeasible system,
+        // but the keys of /prove_infeasible are picked by the request
+        system.hide = Hide::None;
+        let proved = linear_equations_proof(&keys, &system).unwrap();
+        let infeasible = InfeasibleInputData {
+            a: vec![vec![1, 2], vec![2, 4]],
+            b: vec![3, 7],
+            commit: false,
+            backend: Default::default(),
+            curve: Default::default(),
+        };
+        infeasible_proof::<Bls12_381>(&keys, &infeasible).unwrap();
+        let verify = InfeasibleVerifyInputData {
+            proof: proved.proof,
+            rows: 2,
+            cols: 2,
+            commit: false,
+            public_input: proved.public_input,
+            backend: Default::default(),
+            curve: Default::default(),
+        };
+        assert!(
+            !infeasible_verify::<Bls12_381>(&keys, &verify)
+                .unwrap()
+                .valid
+        );
+    }
 }
@@ -0,0 +1,168 @@
+use crate::arkworks::backend::proving::{prove_with_keys, verify_with_keys};
+use crate::arkworks::constraints::linear_recurrence::LinearRecurrenceCircuit;
+use crate::arkworks::curves::{on_curve, SnarkCurve};
+use crate::arkworks::key_store::{key_id, KeyStore};
+use crate::arkworks::matrix_proof_of_work::io::field_to_hex;
+use crate::error::{check_len, parse_field, parse_fields, Validate, ZkError};
+use crate::proof_system::{Backend, Curve};
+use actix_web::{web, HttpResponse};
+use ark_ff::PrimeField;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+
+// f_i = c_1 f_{i-1} + .. + c_k f_{i-k}, e.g. fibonacci is coefficients [1, 1], tribonacci
+// [1, 1, 1] and the lucas sequence U(P, Q) is [P, -Q]. all values are decimal or 0x hex strings
+#[derive(Deserialize)]
+pub struct InputDataRecurrence {
+    pub coefficients: Vec<String>,
+    pub initial: Vec<String>,
+    pub num_of_rounds: usize,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+// the order and num_of_rounds pick the keys, like for the proof
+#[derive(Deserialize)]
+pub struct InputDataRecurrenceVer {
+    pub proof: String,
+    pub coefficients: Vec<String>,
+    pub initial: Vec<String>,
+    pub num_of_rounds: usize,
+    pub result: String,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+// result is the term after num_of_rounds steps mod r, the scalar field of the curve is returned as `modulus`
+#[derive(Serialize)]
+pub struct OutputDataRecurrence {
+    pub proof: String,
+    pub key_id: String,
+    pub result: String,
+    pub result_hex: String,
+    pub modulus: String,
+    pub num_constraints: usize,
+    pub setup_time: f64,
+    pub proving_time: f64,
+}
+
+#[derive(Serialize)]
+pub struct OutputVerifyRecurrence {
+    pub verifying_time: f64,
+    pub is_res: bool,
+}
+
+// the order k of a recurrence is bounded, every coefficient is a public input
+const MAX_ORDER: usize = 64;
+// a round has k constraints, the keys of every order and number of rounds are kept
+const MAX_ROUNDS: usize = 1 << 20;
+
+fn check_recurrence(
+    coefficients: &[String],
+    initial: &[String],
+    num_of_rounds: usize,
+) -> Result<(), ZkError> {
+    check_len("coefficients", coefficients, MAX_ORDER)?;
+    if coefficients.len() != initial.len() {
+        return Err(ZkError::invalid(
+            "initial",
+            "k coefficients need exactly k initial values",
+        ));
+    }
+    if num_of_rounds > MAX_ROUNDS {
+        return Err(ZkError::invalid(
+            "num_of_rounds",
+            format!("num_of_rounds can be at most {}", MAX_ROUNDS),
+        ));
+    }
+    Ok(())
+}
+
+fn recurrence_key_id(order: usize, num_of_rounds: usize) -> String {
+    key_id(
+        "linear_recurrence",
+        &[order.to_string(), num_of_rounds.to_string()],
+    )
+}
+
+impl Validate for InputDataRecurrence {
+    fn validate(&self) -> Result<(), ZkError> {
+        check_recurrence(&self.coefficients, &self.initial, self.num_of_rounds)
+    }
+}
+
+impl Validate for InputDataRecurrenceVer {
+    fn validate(&self) -> Result<(), ZkError> {
+        check_recurrence(&self.coefficients, &self.initial, self.num_of_rounds)
+    }
+}
+
+pub async fn linear_recurrence_proof(
+    keys: web::Data<KeyStore>,
+    data: web::Json<InputDataRecurrence>,
+) -> Result<HttpResponse, ZkError> {
+    data.validate()?;
+    let output = on_curve!(data.curve, E => recurrence_proof::<E>(&keys, &data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn recurrence_proof<E: SnarkCurve>(
+    keys: &KeyStore,
+    data: &InputDataRecurrence,
+) -> Result<OutputDataRecurrence, ZkError> {
+    let coefficients = parse_fields::<E::ScalarField, _>("coefficients", &data.coefficients)?;
+    let initial = parse_fields("initial", &data.initial)?;
+    let circuit = LinearRecurrenceCircuit::new(coefficients, initial, data.num_of_rounds);
+    let result = circuit.result.unwrap();
+
+    // the coefficients and initial values are public inputs, one key serves every recurrence
+    // of the same order and number of rounds
+    let key_id = recurrence_key_id(data.coefficients.len(), data.num_of_rounds);
+    let proved = prove_with_keys::<E, _>(data.backend, keys, &key_id, circuit.clone())?;
+
+    let cs = ConstraintSystem::<E::ScalarField>::new_ref();
+    circuit.generate_constraints(cs.clone())?;
+
+    let modulus: BigUint = E::ScalarField::MODULUS.into();
+    Ok(OutputDataRecurrence {
+        proof: proved.proof,
+        key_id,
+        result: result.to_string(),
+        result_hex: field_to_hex(result),
+        modulus: modulus.to_string(),
+        num_constraints: cs.num_constraints(),
+        setup_time: proved.setup_time,
+        proving_time: proved.proving_time,
+    })
+}
+
+pub async fn linear_recurrence_verify(
+    keys: web::Data<KeyStore>,
+    data: web::Json<InputDataRecurrenceVer>,
+) -> Result<HttpResponse, ZkError> {
+    data.validate()?;
+    let output = on_curve!(data.curve, E => recurrence_verify::<E>(&keys, &data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn recurrence_verify<E: SnarkCurve>(
+    keys: &KeyStore,
+    data: &InputDataRecurrenceVer,
+) -> Result<OutputVerifyRecurrence, ZkError> {
+    // the public inputs are [c_1..c_k, f_0..f_{k-1}, result]
+    let mut inputs = parse_fields::<E::ScalarField, _>("coefficients", &data.coefficients)?;
+    inputs.extend(parse_fields::<E::ScalarField, _>("initial", &data.initial)?);
+    inputs.push(parse_field("result", &data.result)?);
+    let key_id = recurrence_key_id(data.coefficients.len(), data.num_of_rounds);
+    let verified = verify_with_keys::<E>(data.backend, keys, &key_id, &inputs, &data.proof)?;
+
+    Ok(OutputVerifyRecurrence {
+        verifying_time: verified.verifying_time,
+        is_res: verified.valid,
+    })
+}
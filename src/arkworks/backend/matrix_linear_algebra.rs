@@ -0,0 +1,315 @@
+use crate::arkworks::backend::proving::{prove_with_keys, verify_with_keys, BackendQuery};
+use crate::arkworks::curves::{on_curve, SnarkCurve};
+use crate::arkworks::key_store::{key_id, KeyStore};
+use crate::arkworks::matrix_proof_of_work::hasher::hasher_native;
+use crate::arkworks::matrix_proof_of_work::io::{decode_hash, encode_hash};
+use crate::arkworks::matrix_proof_of_work::linear_algebra::{
+    invert_native, to_field_matrix, DeterminantCircuit, MatrixInverseCircuit, RankCircuit,
+};
+use crate::error::{check_shape, Validate, ZkError};
+use crate::proof_system::{Backend, Curve};
+use actix_web::{web, HttpResponse};
+use ark_ff::BigInteger;
+use ark_ff::PrimeField;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+use serde::{Deserialize, Serialize};
+
+// the circuits of the /matrix/{inverse,determinant,rank}/prove endpoints, the keys of each fit
+// every matrix of one size
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinearAlgebraCircuit {
+    Inverse,
+    Determinant,
+    Rank,
+}
+
+impl LinearAlgebraCircuit {
+    fn key_id(self, size: usize) -> String {
+        let circuit = match self {
+            LinearAlgebraCircuit::Inverse => "matrix_inverse",
+            LinearAlgebraCircuit::Determinant => "matrix_determinant",
+            LinearAlgebraCircuit::Rank => "matrix_rank",
+        };
+        key_id(circuit, &[size.to_string()])
+    }
+}
+
+// the circuits have O(size^2) witnesses and constraints, and the keys of every size are kept
+const MAX_SIZE: usize = 64;
+
+fn check_size(size: usize) -> Result<(), ZkError> {
+    if size == 0 || size > MAX_SIZE {
+        return Err(ZkError::invalid(
+            "size",
+            format!("size must be between 1 and {}", MAX_SIZE),
+        ));
+    }
+    Ok(())
+}
+
+// the request for all the /matrix/{inverse,determinant,rank}/prove endpoints
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinearAlgebraInput {
+    size: usize,
+    matrix: Vec<Vec<u64>>,
+    #[serde(default)]
+    backend: Backend,
+    #[serde(default)]
+    curve: Curve,
+}
+
+// the response of all the /matrix/{inverse,determinant,rank}/prove endpoints:
+// public_inputs are the base64 field elements the verifier needs, in circuit order,
+// result is a readable version of what was proven (the determinant, the rank, ..)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinearAlgebraOutput {
+    hash_a: String,
+    public_inputs: Vec<String>,
+    result: String,
+    setup_time: f64,
+    proving_time: f64,
+    num_constraints: usize,
+    num_variables: usize,
+    proof: String,
+    key_id: String,
+}
+
+// circuit and size pick the keys of the proof
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinearAlgebraVerifyInput {
+    circuit: LinearAlgebraCircuit,
+    size: usize,
+    proof: String,
+    public_inputs: Vec<String>,
+    #[serde(default)]
+    backend: Backend,
+    #[serde(default)]
+    curve: Curve,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinearAlgebraVerifyOutput {
+    verifying_time: f64,
+    valid: bool,
+}
+
+impl Validate for LinearAlgebraInput {
+    fn validate(&self) -> Result<(), ZkError> {
+        check_size(self.size)?;
+        check_shape("matrix", &self.matrix, self.size, self.size)
+    }
+}
+
+impl Validate for LinearAlgebraVerifyInput {
+    fn validate(&self) -> Result<(), ZkError> {
+        check_size(self.size)
+    }
+}
+
+fn encode_field<F: PrimeField>(value: F) -> String {
+    encode_hash(&value.into_bigint().to_bytes_le())
+}
+
+// prove with the keys of the size and count the constraints of one of the linear algebra
+// circuits
+fn prove_circuit<E: SnarkCurve, C: ConstraintSynthesizer<E::ScalarField> + Clone>(
+    backend: Backend,
+    keys: &KeyStore,
+    key_id: String,
+    circuit: C,
+    public_inputs: Vec<E::ScalarField>,
+    result: String,
+) -> Result<LinearAlgebraOutput, ZkError> {
+    let proved = prove_with_keys::<E, _>(backend, keys, &key_id, circuit.clone())?;
+
+    let cs = ConstraintSystem::<E::ScalarField>::new_ref();
+    circuit.generate_constraints(cs.clone())?;
+
+    Ok(LinearAlgebraOutput {
+        hash_a: encode_field(public_inputs[0]),
+        public_inputs: public_inputs.into_iter().map(encode_field).collect(),
+        result,
+        setup_time: proved.setup_time,
+        proving_time: proved.proving_time,
+        num_constraints: cs.num_constraints(),
+        num_variables: cs.num_instance_variables(),
+        proof: proved.proof,
+        key_id,
+    })
+}
+
+// prove that B = A^-1, B is returned only by its hash
+pub async fn prove_inverse(
+    keys: web::Data<KeyStore>,
+    query: web::Query<BackendQuery>,
+    data: web::Json<LinearAlgebraInput>,
+) -> Result<HttpResponse, ZkError> {
+    let data = data.into_inner();
+    data.validate()?;
+    let backend = query.or(data.backend);
+    let output = on_curve!(data.curve, E => inverse_proof::<E>(&keys, backend, data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn inverse_proof<E: SnarkCurve>(
+    keys: &KeyStore,
+    backend: Backend,
+    data: LinearAlgebraInput,
+) -> Result<LinearAlgebraOutput, ZkError> {
+    let matrix_a = data.matrix;
+    let matrix_a_field = to_field_matrix::<E::ScalarField>(&matrix_a);
+    let matrix_b = invert_native(&matrix_a_field)
+        .ok_or_else(|| ZkError::invalid("matrix", "the matrix is not invertible"))?;
+    let hash_a = hasher_native(&matrix_a_field);
+    let hash_b = hasher_native(&matrix_b);
+
+    let circuit = MatrixInverseCircuit::new(matrix_a, matrix_b, hash_a, hash_b);
+    let result = format!("inverse of a {}x{} matrix", data.size, data.size);
+    let key_id = LinearAlgebraCircuit::Inverse.key_id(data.size);
+    prove_circuit::<E, _>(backend, keys, key_id, circuit, vec![hash_a, hash_b], result)
+}
+
+// prove that det(A) = d
+pub async fn prove_determinant(
+    keys: web::Data<KeyStore>,
+    query: web::Query<BackendQuery>,
+    data: web::Json<LinearAlgebraInput>,
+) -> Result<HttpResponse, ZkError> {
+    let data = data.into_inner();
+    data.validate()?;
+    let backend = query.or(data.backend);
+    let output = on_curve!(data.curve, E => determinant_proof::<E>(&keys, backend, data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn determinant_proof<E: SnarkCurve>(
+    keys: &KeyStore,
+    backend: Backend,
+    data: LinearAlgebraInput,
+) -> Result<LinearAlgebraOutput, ZkError> {
+    let hash_a = hasher_native(&to_field_matrix::<E::ScalarField>(&data.matrix));
+
+    let circuit = DeterminantCircuit::new(data.matrix, hash_a);
+    let det = circuit.det();
+    let key_id = LinearAlgebraCircuit::Determinant.key_id(data.size);
+    prove_circuit::<E, _>(
+        backend,
+        keys,
+        key_id,
+        circuit,
+        vec![hash_a, det],
+        det.to_string(),
+    )
+}
+
+// prove that rank(A) = r
+pub async fn prove_rank(
+    keys: web::Data<KeyStore>,
+    query: web::Query<BackendQuery>,
+    data: web::Json<LinearAlgebraInput>,
+) -> Result<HttpResponse, ZkError> {
+    let data = data.into_inner();
+    data.validate()?;
+    let backend = query.or(data.backend);
+    let output = on_curve!(data.curve, E => rank_proof::<E>(&keys, backend, data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn rank_proof<E: SnarkCurve>(
+    keys: &KeyStore,
+    backend: Backend,
+    data: LinearAlgebraInput,
+) -> Result<LinearAlgebraOutput, ZkError> {
+    let hash_a = hasher_native(&to_field_matrix::<E::ScalarField>(&data.matrix));
+
+    let circuit = RankCircuit::new(data.matrix, hash_a);
+    let rank = circuit.rank();
+    let public_inputs = vec![hash_a, E::ScalarField::from(rank as u64)];
+    let key_id = LinearAlgebraCircuit::Rank.key_id(data.size);
+    prove_circuit::<E, _>(
+        backend,
+        keys,
+        key_id,
+        circuit,
+        public_inputs,
+        rank.to_string(),
+    )
+}
+
+// verify any of the proofs above with the keys of its circuit and size
+pub async fn verify_linear_algebra(
+    keys: web::Data<KeyStore>,
+    query: web::Query<BackendQuery>,
+    data: web::Json<LinearAlgebraVerifyInput>,
+) -> Result<HttpResponse, ZkError> {
+    data.validate()?;
+    let backend = query.or(data.backend);
+    let output = on_curve!(data.curve, E => linear_algebra_verify::<E>(&keys, backend, &data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn linear_algebra_verify<E: SnarkCurve>(
+    keys: &KeyStore,
+    backend: Backend,
+    data: &LinearAlgebraVerifyInput,
+) -> Result<LinearAlgebraVerifyOutput, ZkError> {
+    let public_inputs = data
+        .public_inputs
+        .iter()
+        .map(|input| {
+            decode_hash(input)
+                .map(|bytes| E::ScalarField::from_le_bytes_mod_order(&bytes))
+                .map_err(|_| ZkError::invalid("public_inputs", "public_inputs must be base64"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let key_id = data.circuit.key_id(data.size);
+    let verified = verify_with_keys::<E>(backend, keys, &key_id, &public_inputs, &data.proof)?;
+
+    Ok(LinearAlgebraVerifyOutput {
+        verifying_time: verified.verifying_time,
+        valid: verified.valid,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::key_store::TestDir;
+    use ark_bls12_381::Bls12_381;
+
+    #[test]
+    fn test_proofs_verify_with_the_keys_of_their_circuit() {
+        let dir = TestDir::new("linear_algebra_keys");
+        let keys = dir.keys();
+        let input = || LinearAlgebraInput {
+            size: 2,
+            matrix: vec![vec![1, 2], vec![3, 4]],
+            backend: Backend::Groth16,
+            curve: Curve::Bls12_381,
+        };
+        let rank = rank_proof::<Bls12_381>(&keys, Backend::Groth16, input()).unwrap();
+        assert_eq!(rank.key_id, "matrix_rank-2");
+        determinant_proof::<Bls12_381>(&keys, Backend::Groth16, input()).unwrap();
+        let verify = |circuit, size| {
+            linear_algebra_verify::<Bls12_381>(
+                &keys,
+                Backend::Groth16,
+                &LinearAlgebraVerifyInput {
+                    circuit,
+                    size,
+                    proof: rank.proof.clone(),
+                    public_inputs: rank.public_inputs.clone(),
+                    backend: Backend::Groth16,
+                    curve: Curve::Bls12_381,
+                },
+            )
+        };
+        assert!(verify(LinearAlgebraCircuit::Rank, 2).unwrap().valid);
+        // the determinant keys have the same number of public inputs but don't verify it
+        assert!(!verify(LinearAlgebraCircuit::Determinant, 2).unwrap().valid);
+        let error = verify(LinearAlgebraCircuit::Rank, 3).err().unwrap();
+        assert_eq!(error.code(), "unknown_key");
+    }
+}
@@ -2,30 +2,32 @@ use std::vec;
 
 // create a sync function that will get two matrices A  oand B of size n x n of u64
 // and return the result of A * B
+use crate::arkworks::backend::proving::{prove_with_keys, verify_with_keys, BackendQuery};
+use crate::arkworks::curves::{on_curve, SnarkCurve};
+use crate::arkworks::key_store::{key_id, KeyStore};
 use crate::arkworks::matrix_proof_of_work::alloc::FpVar2DVec;
 use crate::arkworks::matrix_proof_of_work::constraints::matrix_mul;
-use crate::arkworks::matrix_proof_of_work::constraints::MatrixCircuit;
+use crate::arkworks::matrix_proof_of_work::constraints::{matrix_mul_native, MatrixCircuit};
 use crate::arkworks::matrix_proof_of_work::hasher::{hasher, hasher_var};
 use crate::arkworks::matrix_proof_of_work::io::{
-    decode_hash, decode_proof, decode_pvk, encode_hash, encode_proof, encode_pvk, read_proof,
-    write_proof_to_file,
+    decode_hash, decode_path, encode_hash, encode_path, read_proof, write_proof_to_file,
 };
+use crate::arkworks::matrix_proof_of_work::merkle::{merkle_root_native, RowOpening};
+use crate::arkworks::matrix_proof_of_work::sparse::{SparseMatVecCircuit, SparseMatrix};
+use crate::bundle::{ProofBundle, PublicInput, PublicValue};
+use crate::error::{check_len, check_shape, Validate, ZkError};
+use crate::proof_system::{Backend, Curve};
 use ark_ff::fields::models::fp::Fp;
-use actix_web::{web, HttpResponse, Responder};
-use ark_bls12_381::{Bls12_381, Config, Fr as F};
-use ark_ec::bls12::Bls12;
+use actix_web::{web, HttpResponse};
 use ark_ff::BigInteger;
 use ark_ff::PrimeField;
-use ark_groth16::prepare_verifying_key;
-use ark_groth16::{Groth16, Proof};
 use ark_r1cs_std::alloc::AllocVar;
 use ark_r1cs_std::{ToBitsGadget, ToBytesGadget};
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
-use ark_snark::{CircuitSpecificSetupSNARK, SNARK};
 use ark_std::end_timer;
-use ark_std::test_rng;
-use rand::{RngCore, SeedableRng};
+use ark_std::UniformRand;
+use std::str::FromStr;
 use std::string::String;
 
 use serde::{Deserialize, Serialize};
@@ -35,16 +37,31 @@ use serde_json;
 pub struct InputData {
     size: usize,
     matrix: Vec<Vec<u64>>,
+    #[serde(default)]
+    curve: Curve,
 }
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OutputData {
     hash: Vec<u8>,
 }
 
-pub async fn hash_matrix(data: web::Json<InputData>) -> impl Responder {
-    let cs = ConstraintSystem::<F>::new_ref();
+impl Validate for InputData {
+    fn validate(&self) -> Result<(), ZkError> {
+        check_len("matrix", &self.matrix, MAX_DIM)?;
+        check_shape("matrix", &self.matrix, self.size, self.size)
+    }
+}
+
+pub async fn hash_matrix(data: web::Json<InputData>) -> Result<HttpResponse, ZkError> {
     // exctract the matrix from the data
     let data = data.into_inner();
+    data.validate()?;
+    let output = on_curve!(data.curve, E => matrix_hash::<E>(data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn matrix_hash<E: SnarkCurve>(data: InputData) -> Result<OutputData, ZkError> {
+    let cs = ConstraintSystem::<E::ScalarField>::new_ref();
     let data_matrix = data.matrix;
     // create a [[u64; n]; n] from the matrix
     let len: usize = data.size;
@@ -57,122 +74,453 @@ pub async fn hash_matrix(data: web::Json<InputData>) -> impl Responder {
     // convert the vector to [[u64; n]; n]:
 
     // create Fp2Var2D from the matrix:
-    let matrix_c = FpVar2DVec::new_witness(cs.clone(), || Ok(matrix)).unwrap();
+    let matrix_c = FpVar2DVec::new_witness(cs.clone(), || Ok(matrix))?;
     // hash the matrix using hasher:
-    let hash = hasher(&matrix_c).unwrap();
+    let hash = hasher(&matrix_c)?;
     let hash_value = hash[0];
 
     // convert the hash value to bytes:
     let hash_bytes: Vec<u8> = hash_value.into_bigint().to_bytes_le();
 
-    println!("Hash: {:?}", hash_bytes);
     // return the response data
-    HttpResponse::Ok().json(OutputData { hash: hash_bytes })
+    Ok(OutputData { hash: hash_bytes })
 }
 
-// create a struct of InputData that will be used to get the data from the user
+// a matrix is either dense ([[1, 0], [0, 1]]) or sparse:
+// {"format": "coo", "rows": 2, "cols": 2, "entries": [[0, 0, 1], [1, 1, 1]]}
+// {"format": "csr", "rows": 2, "cols": 2, "row_ptr": [0, 1, 2], "col_indices": [0, 1], "values": [1, 1]}
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MatrixInput {
+    Dense(Vec<Vec<u64>>),
+    Sparse(SparseInput),
+}
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "format", rename_all = "lowercase")]
+pub enum SparseInput {
+    Coo {
+        rows: usize,
+        cols: usize,
+        entries: Vec<(usize, usize, u64)>,
+    },
+    Csr {
+        rows: usize,
+        cols: usize,
+        row_ptr: Vec<usize>,
+        col_indices: Vec<usize>,
+        values: Vec<u64>,
+    },
+}
+
+// the names of the keys and the bundles, the verify endpoints derive the key ids from them
+const MATRIX_CIRCUIT: &str = "matrix";
+const SPARSE_CIRCUIT: &str = "sparse_mat_vec";
+
+// the keys are per shape: n x n for A * B, rows x cols and nnz for a sparse A * x
+pub const MAX_DIM: usize = 64;
+pub const MAX_SPARSE_DIM: usize = 4096;
+pub const MAX_NNZ: usize = 1 << 14;
+
+impl MatrixInput {
+    // the shape and the number of entries as sent, before the conversion to a SparseMatrix
+    fn check_size(&self, field: &str, max_dim: usize) -> Result<(), ZkError> {
+        let (rows, cols, nnz) = match self {
+            MatrixInput::Dense(matrix) => (
+                matrix.len(),
+                matrix.iter().map(Vec::len).max().unwrap_or(0),
+                matrix.iter().flatten().filter(|v| **v != 0).count(),
+            ),
+            MatrixInput::Sparse(SparseInput::Coo {
+                rows,
+                cols,
+                entries,
+            }) => (*rows, *cols, entries.len()),
+            MatrixInput::Sparse(SparseInput::Csr {
+                rows, cols, values, ..
+            }) => (*rows, *cols, values.len()),
+        };
+        if rows > max_dim || cols > max_dim {
+            return Err(ZkError::invalid(
+                field,
+                format!(
+                    "{} is {}x{}, at most {}x{} is supported",
+                    field, rows, cols, max_dim, max_dim
+                ),
+            ));
+        }
+        if nnz > MAX_NNZ {
+            return Err(ZkError::invalid(
+                field,
+                format!(
+                    "{} has {} non-zeros, at most {} are supported",
+                    field, nnz, MAX_NNZ
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    fn into_sparse(self) -> Result<SparseMatrix, String> {
+        match self {
+            MatrixInput::Dense(matrix) => {
+                if matrix.is_empty() {
+                    return Err("a dense matrix needs at least one row".into());
+                }
+                if matrix.iter().any(|row| row.len() != matrix[0].len()) {
+                    return Err("all the rows of a dense matrix must have the same length".into());
+                }
+                Ok(SparseMatrix::from_dense(&matrix))
+            }
+            MatrixInput::Sparse(SparseInput::Coo {
+                rows,
+                cols,
+                entries,
+            }) => SparseMatrix::from_coo(rows, cols, entries),
+            MatrixInput::Sparse(SparseInput::Csr {
+                rows,
+                cols,
+                row_ptr,
+                col_indices,
+                values,
+            }) => SparseMatrix::from_csr(rows, cols, &row_ptr, &col_indices, &values),
+        }
+    }
+}
+
+// create a struct of InputData that will be used to get the data from the user:
+// A * B for two n x n matrices, or A * vector for a sparse rows x cols A
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProveInput {
+    #[serde(default)]
     size: usize,
-    matrix_a: Vec<Vec<u64>>,
-    matrix_b: Vec<Vec<u64>>,
+    matrix_a: MatrixInput,
+    matrix_b: Option<MatrixInput>,
+    vector: Option<Vec<u64>>,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+impl ProveInput {
+    pub fn new(
+        matrix_a: MatrixInput,
+        matrix_b: Option<MatrixInput>,
+        vector: Option<Vec<u64>>,
+        backend: Backend,
+        curve: Curve,
+    ) -> Self {
+        Self {
+            size: 0,
+            matrix_a,
+            matrix_b,
+            vector,
+            backend,
+            curve,
+        }
+    }
 }
+// com_* are the public row merkle roots, opening_* are the seeds of their row salts -
+// the openings (and matrix_c) are for the prover / the client only, never for the verifier
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProveOutPut {
-    hash_a: String,
-    hash_b: String,
-    hash_c: String,
+    com_a: String,
+    com_b: String,
+    com_c: String,
+    opening_a: String,
+    opening_b: String,
+    opening_c: String,
+    matrix_c: Vec<Vec<String>>,
+    density_a: f64,
+    density_b: f64,
     setup_time: f64,
     proving_time: f64,
     num_constraints: usize,
     num_variables: usize,
     proof: String,
-    pvk: String,
+    key_id: String,
+    bundle: ProofBundle,
 }
 
-// function to genrate a proof using groth16, getting 2 matrices A and B
-pub async fn prove_matrix(data: web::Json<ProveInput>) -> impl Responder {
-    let cs = ConstraintSystem::<F>::new_ref();
+fn encode_field<F: PrimeField>(value: F) -> String {
+    encode_hash(&value.into_bigint().to_bytes_le())
+}
 
-    // exctract the matrix from the data
-    let data = data.into_inner();
-    let matrix_a: Vec<Vec<u64>> = data.matrix_a;
-    let matrix_b: Vec<Vec<u64>> = data.matrix_b;
-    // create a [[u64; n]; n] from the matrix
-    let len: usize = data.size;
-    // convert the vector to [[u64; n]; n]:
-    // create Fp2Var2D from the matrix:
-    let matrix_a_var = FpVar2DVec::new_witness(cs.clone(), || Ok(matrix_a.clone())).unwrap();
-    let matrix_b_var = FpVar2DVec::new_witness(cs.clone(), || Ok(matrix_b.clone())).unwrap();
+// the entries of the responses, the Display of the fields prints zero as an empty string
+fn decimal<F: PrimeField>(value: &F) -> String {
+    value.into_bigint().to_string()
+}
 
-    let matrix_c = matrix_mul(cs.clone(), matrix_a_var.clone(), matrix_b_var.clone());
-    // hash the matrix using hasher:
-    let hash_c = hasher(&matrix_c).unwrap()[0];
-    //  hash a :
-    let hash_a = hasher(&matrix_a_var).unwrap()[0];
-    //  hash b :
-    let hash_b = hasher(&matrix_b_var).unwrap()[0];
-    // convert the hash value to bytes:
-    let hash_bytes: Vec<u8> = hash_c.into_bigint().to_bytes_le();
-    // encode the hash value to base64:
-    let encoded_hash_c = encode_hash(&hash_bytes);
-    let encoded_hash_a = encode_hash(&hash_a.into_bigint().to_bytes_le());
-    let encoded_hash_b = encode_hash(&hash_b.into_bigint().to_bytes_le());
+// a commitment of the bundles, the same bytes as encode_field
+fn commitment<F: PrimeField>(name: &str, value: F) -> PublicInput {
+    PublicInput::new(name, PublicValue::Bytes(value.into_bigint().to_bytes_le()))
+}
 
-    // use groth16 to generate the proof:
+fn count(name: &str, value: usize) -> PublicInput {
+    PublicInput::new(name, PublicValue::U64(value as u64))
+}
 
-    // create a circuit using new  function
-    let circuit = MatrixCircuit::new(matrix_a.clone(), matrix_b.clone(), hash_a, hash_b, hash_c);
-    // generate the proof
-    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
-    let setup_time = std::time::Instant::now();
-    let (pk, vk) = Groth16::<Bls12_381>::setup(circuit.clone(), &mut rng).unwrap();
+fn decode_field<F: PrimeField>(field: &str, value: &str) -> Result<F, ZkError> {
+    decode_hash(value)
+        .map(|bytes| F::from_le_bytes_mod_order(&bytes))
+        .map_err(|_| ZkError::invalid(field, format!("{} is not valid base64", field)))
+}
 
-    let setup_time = setup_time.elapsed().as_secs_f64();
+impl Validate for ProveInput {
+    fn validate(&self) -> Result<(), ZkError> {
+        if self.matrix_b.is_some() == self.vector.is_some() {
+            return Err(ZkError::invalid(
+                "matrix_b",
+                "give either matrix_b or vector",
+            ));
+        }
+        match (&self.matrix_b, &self.vector) {
+            (Some(matrix_b), _) => {
+                self.matrix_a.check_size("matrix_a", MAX_DIM)?;
+                matrix_b.check_size("matrix_b", MAX_DIM)
+            }
+            (_, Some(vector)) => {
+                self.matrix_a.check_size("matrix_a", MAX_SPARSE_DIM)?;
+                check_len("vector", vector, MAX_SPARSE_DIM)
+            }
+            (None, None) => Ok(()),
+        }
+    }
+}
 
-    // convert the proof and vk to string:
-    let pvk = prepare_verifying_key::<Bls12_381>(&vk);
-    // encode the pvk to byte using encode_pvk:
-    let pvk_str = encode_pvk::<Bls12_381>(&pvk);
-    // open timer:
-    let proving_time = std::time::Instant::now();
-    let proof: Proof<Bls12<Config>> =
-        Groth16::<Bls12_381>::prove(&pk, circuit.clone(), &mut rng).unwrap();
+// A * B and A * vector have different responses
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum MatrixProveOutput {
+    Product(ProveOutPut),
+    SparseMatVec(SparseProveOutput),
+}
 
-    // use the constraint system to get the number of constraints and variables:
+impl MatrixProveOutput {
+    pub fn bundle(&self) -> &ProofBundle {
+        match self {
+            MatrixProveOutput::Product(output) => &output.bundle,
+            MatrixProveOutput::SparseMatVec(output) => &output.bundle,
+        }
+    }
+}
 
-    // end timer:
-    let proving_time = proving_time.elapsed().as_secs_f64();
+// function to genrate a proof using groth16, getting 2 matrices A and B
+pub async fn prove_matrix(
+    keys: web::Data<KeyStore>,
+    query: web::Query<BackendQuery>,
+    data: web::Json<ProveInput>,
+) -> Result<HttpResponse, ZkError> {
+    let data = data.into_inner();
+    let result = matrix_proof(&keys, query.or(data.backend), data)?;
+    Ok(HttpResponse::Ok().json(result))
+}
 
-    // encode the proof to base64 and the vk to base64:
-    let proof_str = encode_proof::<Bls12_381>(&proof);
+// the proof of /matrix_prove/prove, also run by the matrix jobs
+pub fn matrix_proof(
+    keys: &KeyStore,
+    backend: Backend,
+    data: ProveInput,
+) -> Result<MatrixProveOutput, ZkError> {
+    on_curve!(data.curve, E => matrix_proof_on::<E>(keys, backend, data))
+}
 
-    circuit.generate_constraints(cs.clone()).unwrap();
+fn matrix_proof_on<E: SnarkCurve>(
+    keys: &KeyStore,
+    backend: Backend,
+    data: ProveInput,
+) -> Result<MatrixProveOutput, ZkError> {
+    let cs = ConstraintSystem::<E::ScalarField>::new_ref();
+
+    // exctract the matrix from the data
+    data.validate()?;
+    let sparse_a = data
+        .matrix_a
+        .into_sparse()
+        .map_err(|e| ZkError::invalid("matrix_a", e))?;
+    // a vector instead of matrix_b is a sparse matrix-vector product
+    if let Some(vector) = data.vector {
+        return prove_sparse_mat_vec::<E>(backend, keys, sparse_a, vector)
+            .map(MatrixProveOutput::SparseMatVec);
+    }
+    let sparse_b = data
+        .matrix_b
+        .map(MatrixInput::into_sparse)
+        .transpose()
+        .map_err(|e| ZkError::invalid("matrix_b", e))?
+        .ok_or_else(|| ZkError::invalid("matrix_b", "either matrix_b or vector is required"))?;
+    let n = sparse_a.rows;
+    if [sparse_a.cols, sparse_b.rows, sparse_b.cols] != [n, n, n] {
+        return Err(ZkError::invalid(
+            "matrix_b",
+            "matrix_a and matrix_b must both be n x n",
+        ));
+    }
+    let matrix_a: Vec<Vec<u64>> = sparse_a.to_dense();
+    let matrix_b: Vec<Vec<u64>> = sparse_b.to_dense();
+    let matrix_c = matrix_mul_native::<E::ScalarField>(&matrix_a, &matrix_b);
+
+    // the blindings have to be unpredictable, they are the only secret of the commitments
+    let mut blinding_rng = rand::thread_rng();
+    let blindings = [
+        E::ScalarField::rand(&mut blinding_rng),
+        E::ScalarField::rand(&mut blinding_rng),
+        E::ScalarField::rand(&mut blinding_rng),
+    ];
+
+    // use groth16 to generate the proof:
+
+    // create a circuit that commits to a, b and c
+    let circuit = MatrixCircuit::commit(matrix_a, matrix_b, blindings);
+    let [com_a, com_b, com_c] = circuit.commitments();
+    // get the keys of n x n matrices, the setup only runs for the first request of a size
+    let params = vec![n.to_string()];
+    let key_id = key_id(MATRIX_CIRCUIT, &params);
+    // generate the proof, it comes back base64 encoded
+    let proved = prove_with_keys::<E, _>(backend, keys, &key_id, circuit.clone())?;
+    let public_inputs = vec![
+        commitment("com_a", com_a),
+        commitment("com_b", com_b),
+        commitment("com_c", com_c),
+    ];
+
+    // use the constraint system to get the number of constraints and variables:
+    circuit.generate_constraints(cs.clone())?;
 
     // create a response data:
     let response_data = ProveOutPut {
-        hash_a: encoded_hash_a,
-        hash_b: encoded_hash_b,
-        hash_c: encoded_hash_c,
-
-        setup_time,
-        proving_time,
+        com_a: encode_field(com_a),
+        com_b: encode_field(com_b),
+        com_c: encode_field(com_c),
+        opening_a: encode_field(blindings[0]),
+        opening_b: encode_field(blindings[1]),
+        opening_c: encode_field(blindings[2]),
+        matrix_c: matrix_c
+            .iter()
+            .map(|row| row.iter().map(decimal).collect())
+            .collect(),
+        density_a: sparse_a.density(),
+        density_b: sparse_b.density(),
+        setup_time: proved.setup_time,
+        proving_time: proved.proving_time,
         num_constraints: cs.num_constraints(),
         num_variables: cs.num_instance_variables(),
-        proof: proof_str,
-        pvk: pvk_str,
+        bundle: proved.bundle(backend, MATRIX_CIRCUIT, params, public_inputs),
+        proof: proved.proof,
+        key_id,
     };
     // return the response data
-    HttpResponse::Ok().json(response_data)
+    Ok(MatrixProveOutput::Product(response_data))
+}
+
+// the response for a sparse A * x: the shape and nnz are public inputs next to the commitments,
+// com_a commits to the sparse representation of A (see sparse.rs)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SparseProveOutput {
+    rows: usize,
+    cols: usize,
+    nnz: usize,
+    density: f64,
+    com_a: String,
+    com_x: String,
+    com_y: String,
+    opening_a: String,
+    opening_x: String,
+    opening_y: String,
+    vector_y: Vec<String>,
+    setup_time: f64,
+    proving_time: f64,
+    num_constraints: usize,
+    num_variables: usize,
+    proof: String,
+    key_id: String,
+    bundle: ProofBundle,
+}
+
+// the pattern is a witness of the circuit, so the keys are shared by the matrices with the
+// same shape and number of non-zeros
+fn sparse_params(rows: usize, cols: usize, nnz: usize) -> Vec<String> {
+    vec![format!("{}x{}", rows, cols), nnz.to_string()]
+}
+
+fn prove_sparse_mat_vec<E: SnarkCurve>(
+    backend: Backend,
+    keys: &KeyStore,
+    matrix: SparseMatrix,
+    vector: Vec<u64>,
+) -> Result<SparseProveOutput, ZkError> {
+    if vector.len() != matrix.cols {
+        return Err(ZkError::invalid(
+            "vector",
+            "the vector must have one entry per column",
+        ));
+    }
+    let cs = ConstraintSystem::<E::ScalarField>::new_ref();
+    let (rows, cols, nnz, density) = (matrix.rows, matrix.cols, matrix.nnz(), matrix.density());
+    let params = sparse_params(rows, cols, nnz);
+    let key_id = key_id(SPARSE_CIRCUIT, &params);
+
+    let mut blinding_rng = rand::thread_rng();
+    let blindings = [
+        E::ScalarField::rand(&mut blinding_rng),
+        E::ScalarField::rand(&mut blinding_rng),
+        E::ScalarField::rand(&mut blinding_rng),
+    ];
+    let circuit = SparseMatVecCircuit::commit(matrix, vector, blindings);
+    let public_inputs = circuit.public_inputs();
+
+    let proved = prove_with_keys::<E, _>(backend, keys, &key_id, circuit.clone())?;
+
+    let vector_y = circuit.product();
+    circuit.generate_constraints(cs.clone())?;
+    let bundle = proved.bundle(
+        backend,
+        SPARSE_CIRCUIT,
+        params,
+        vec![
+            count("rows", rows),
+            count("cols", cols),
+            count("nnz", nnz),
+            commitment("com_a", public_inputs[3]),
+            commitment("com_x", public_inputs[4]),
+            commitment("com_y", public_inputs[5]),
+        ],
+    );
+
+    Ok(SparseProveOutput {
+        rows,
+        cols,
+        nnz,
+        density,
+        com_a: encode_field(public_inputs[3]),
+        com_x: encode_field(public_inputs[4]),
+        com_y: encode_field(public_inputs[5]),
+        opening_a: encode_field(blindings[0]),
+        opening_x: encode_field(blindings[1]),
+        opening_y: encode_field(blindings[2]),
+        vector_y: vector_y.iter().map(decimal).collect(),
+        setup_time: proved.setup_time,
+        proving_time: proved.proving_time,
+        num_constraints: cs.num_constraints(),
+        num_variables: cs.num_instance_variables(),
+        proof: proved.proof,
+        key_id,
+        bundle,
+    })
 }
 
+// the verifier sees the commitments only, size picks the keys of n x n matrices
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VerifyInput {
-    pvk: String,
+    size: usize,
     proof: String,
-    hash_a: String,
-    hash_b: String,
-    hash_c: String,
+    com_a: String,
+    com_b: String,
+    com_c: String,
+    #[serde(default)]
+    backend: Backend,
+    #[serde(default)]
+    curve: Curve,
 }
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VerifyOutPut {
@@ -180,36 +528,347 @@ pub struct VerifyOutPut {
     valid: bool,
 }
 
-pub async fn verify_proof(data: web::Json<VerifyInput>) -> impl Responder {
-    let hash_c = data.hash_c.clone();
-    let hash_a = data.hash_a.clone();
-    let hash_b = data.hash_b.clone();
-    let pvk = data.pvk.clone();
-    let proof = data.proof.clone();
+pub async fn verify_proof(
+    keys: web::Data<KeyStore>,
+    query: web::Query<BackendQuery>,
+    data: web::Json<VerifyInput>,
+) -> Result<HttpResponse, ZkError> {
+    let backend = query.or(data.backend);
+    let response_data = on_curve!(data.curve, E => matrix_verify::<E>(&keys, backend, &data))?;
+    // return the response data
+    Ok(HttpResponse::Ok().json(response_data))
+}
 
-    // decode the proof and vk from base64:
-    let pvk = decode_pvk::<Bls12_381>(&pvk).unwrap();
+fn matrix_verify<E: SnarkCurve>(
+    keys: &KeyStore,
+    backend: Backend,
+    data: &VerifyInput,
+) -> Result<VerifyOutPut, ZkError> {
+    // convert the commitments to Fp:
+    let com_a = decode_field::<E::ScalarField>("com_a", &data.com_a)?;
+    let com_b = decode_field("com_b", &data.com_b)?;
+    let com_c = decode_field("com_c", &data.com_c)?;
+    // load the vk of the size, decode the proof from base64 and verify:
+    let key_id = key_id(MATRIX_CIRCUIT, &[data.size.to_string()]);
+    let verified = verify_with_keys::<E>(
+        backend,
+        keys,
+        &key_id,
+        &[com_a, com_b, com_c],
+        &data.proof,
+    )?;
 
-    let proof = decode_proof::<Bls12_381>(&proof).unwrap();
+    // create a response data:
+    Ok(VerifyOutPut {
+        verifying_time: verified.verifying_time,
+        valid: verified.valid,
+    })
+}
 
-    // convert the hash value to Fp:
-    let hash_value_c = Fp::from_le_bytes_mod_order(&decode_hash(&hash_c).unwrap());
-    let hash_value_a = Fp::from_le_bytes_mod_order(&decode_hash(&hash_a).unwrap());
-    let hash_value_b = Fp::from_le_bytes_mod_order(&decode_hash(&hash_b).unwrap());
-    let verfiying_time = std::time::Instant::now();
-    let is_valid = Groth16::<Bls12_381>::verify_with_processed_vk(
-        &pvk,
-        &[hash_value_a, hash_value_b, hash_value_c],
-        &proof,
-    )
-    .unwrap();
-    let verifying_time = verfiying_time.elapsed().as_secs_f64();
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SparseVerifyInput {
+    proof: String,
+    rows: usize,
+    cols: usize,
+    nnz: usize,
+    com_a: String,
+    com_x: String,
+    com_y: String,
+    #[serde(default)]
+    backend: Backend,
+    #[serde(default)]
+    curve: Curve,
+}
 
-    // create a response data:
-    let response_data = VerifyOutPut {
-        verifying_time,
-        valid: is_valid,
+pub async fn verify_sparse_proof(
+    keys: web::Data<KeyStore>,
+    query: web::Query<BackendQuery>,
+    data: web::Json<SparseVerifyInput>,
+) -> Result<HttpResponse, ZkError> {
+    let backend = query.or(data.backend);
+    let output = on_curve!(data.curve, E => sparse_verify::<E>(&keys, backend, &data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn sparse_verify<E: SnarkCurve>(
+    keys: &KeyStore,
+    backend: Backend,
+    data: &SparseVerifyInput,
+) -> Result<VerifyOutPut, ZkError> {
+    let public_inputs = [
+        E::ScalarField::from(data.rows as u64),
+        E::ScalarField::from(data.cols as u64),
+        E::ScalarField::from(data.nnz as u64),
+        decode_field("com_a", &data.com_a)?,
+        decode_field("com_x", &data.com_x)?,
+        decode_field("com_y", &data.com_y)?,
+    ];
+
+    let key_id = key_id(SPARSE_CIRCUIT, &sparse_params(data.rows, data.cols, data.nnz));
+    let verified = verify_with_keys::<E>(
+        backend,
+        keys,
+        &key_id,
+        &public_inputs,
+        &data.proof,
+    )?;
+
+    Ok(VerifyOutPut {
+        verifying_time: verified.verifying_time,
+        valid: verified.valid,
+    })
+}
+
+// opening of a single commitment: the matrix entries as decimal field elements
+// (so that c, which doesn't fit in u64, can be opened too) and the base64 seed
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpenInput {
+    matrix: Vec<Vec<String>>,
+    opening: String,
+    commitment: String,
+    #[serde(default)]
+    curve: Curve,
+}
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpenOutput {
+    valid: bool,
+}
+
+fn parse_row<F: PrimeField>(field: &str, row: &[String]) -> Result<Vec<F>, ZkError> {
+    row.iter()
+        .map(|v| F::from_str(v))
+        .collect::<Result<_, _>>()
+        .map_err(|_| ZkError::invalid(field, format!("{} entries must be decimal numbers", field)))
+}
+
+fn parse_matrix<F: PrimeField>(matrix: &[Vec<String>]) -> Result<Vec<Vec<F>>, ZkError> {
+    if matrix.is_empty() {
+        return Err(ZkError::invalid("matrix", "the matrix needs at least one row"));
+    }
+    matrix.iter().map(|row| parse_row("matrix", row)).collect()
+}
+
+// check that the whole matrix opens the commitment
+pub async fn open_commitment(data: web::Json<OpenInput>) -> Result<HttpResponse, ZkError> {
+    let output = on_curve!(data.curve, E => commitment_opening::<E>(&data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn commitment_opening<E: SnarkCurve>(data: &OpenInput) -> Result<OpenOutput, ZkError> {
+    let matrix = parse_matrix::<E::ScalarField>(&data.matrix)?;
+    let seed = decode_field("opening", &data.opening)?;
+    let commitment = decode_field("commitment", &data.commitment)?;
+
+    Ok(OpenOutput {
+        valid: merkle_root_native(&matrix, seed) == commitment,
+    })
+}
+
+// the prover side of a row audit: the full matrix (usually c) and its opening,
+// and the row to reveal. with a column the response also points at a single entry,
+// note that an entry is proven by revealing its whole row
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpenRowInput {
+    matrix: Vec<Vec<String>>,
+    opening: String,
+    row: usize,
+    column: Option<usize>,
+    #[serde(default)]
+    curve: Curve,
+}
+
+// an inclusion proof of a row under a root, this is all an auditor needs
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RowProof {
+    root: String,
+    row_index: usize,
+    row: Vec<String>,
+    salt: String,
+    path: String,
+    column: Option<usize>,
+    entry: Option<String>,
+    #[serde(default)]
+    curve: Curve,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyRowOutput {
+    valid: bool,
+}
+
+pub async fn open_row(data: web::Json<OpenRowInput>) -> Result<HttpResponse, ZkError> {
+    let output = on_curve!(data.curve, E => row_opening::<E>(&data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn row_opening<E: SnarkCurve>(data: &OpenRowInput) -> Result<RowProof, ZkError> {
+    let matrix = parse_matrix::<E::ScalarField>(&data.matrix)?;
+    let seed = decode_field("opening", &data.opening)?;
+    let opening = RowOpening::open(&matrix, seed, data.row)
+        .ok_or_else(|| ZkError::invalid("row", "row index out of range"))?;
+    let entry = match data.column {
+        Some(column) => match opening.row.get(column) {
+            Some(entry) => Some(entry.to_string()),
+            None => return Err(ZkError::invalid("column", "column index out of range")),
+        },
+        None => None,
     };
-    // return the response data
-    HttpResponse::Ok().json(response_data)
+
+    Ok(RowProof {
+        root: encode_field(merkle_root_native(&matrix, seed)),
+        row_index: opening.row_index,
+        row: opening.row.iter().map(decimal).collect(),
+        salt: encode_field(opening.salt),
+        path: encode_path(&opening.path),
+        column: data.column,
+        entry,
+        curve: data.curve,
+    })
+}
+
+// check a row proof against the root, and the entry against the row if there is one
+pub async fn verify_row(data: web::Json<RowProof>) -> Result<HttpResponse, ZkError> {
+    let output = on_curve!(data.curve, E => row_verify::<E>(&data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn row_verify<E: SnarkCurve>(data: &RowProof) -> Result<VerifyRowOutput, ZkError> {
+    let row = parse_row::<E::ScalarField>("row", &data.row)?;
+    let path = decode_path(&data.path)
+        .map_err(|_| ZkError::invalid("path", "invalid merkle path"))?;
+    let entry_matches = match (data.column, &data.entry) {
+        (Some(column), Some(entry)) => {
+            row.get(column).map(|v| v.to_string()) == Some(entry.clone())
+        }
+        (None, None) => true,
+        _ => false,
+    };
+    let opening = RowOpening {
+        row_index: data.row_index,
+        salt: decode_field("salt", &data.salt)?,
+        row,
+        path,
+    };
+    let root = decode_field("root", &data.root)?;
+
+    Ok(VerifyRowOutput {
+        valid: entry_matches && opening.verify(root),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::key_store::TestDir;
+
+    fn coo(rows: usize, cols: usize, entries: Vec<(usize, usize, u64)>) -> MatrixInput {
+        MatrixInput::Sparse(SparseInput::Coo {
+            rows,
+            cols,
+            entries,
+        })
+    }
+
+    fn mat_vec(matrix_a: MatrixInput, vector: Vec<u64>) -> ProveInput {
+        ProveInput::new(matrix_a, None, Some(vector), Backend::Groth16, Curve::default())
+    }
+
+    #[test]
+    fn test_oversized_matrices_are_rejected() {
+        let dir = TestDir::new("matrix_caps");
+        let keys = dir.keys();
+        let big = MatrixInput::Dense(vec![vec![1; MAX_DIM + 1]; MAX_DIM + 1]);
+        let identity = MatrixInput::Dense(vec![vec![1]]);
+        let too_many = (0..=MAX_NNZ).map(|k| (k / 8, k % 8, 1)).collect();
+        let cases = [
+            (
+                ProveInput::new(big, Some(identity), None, Backend::Groth16, Curve::default()),
+                "matrix_a",
+            ),
+            (mat_vec(coo(MAX_SPARSE_DIM + 1, 1, vec![]), vec![1]), "matrix_a"),
+            (mat_vec(coo(MAX_SPARSE_DIM, 8, too_many), vec![1; 8]), "matrix_a"),
+            (
+                mat_vec(coo(1, 1, vec![(0, 0, 1)]), vec![1; MAX_SPARSE_DIM + 1]),
+                "vector",
+            ),
+        ];
+        for (input, field) in cases {
+            let error = matrix_proof(&keys, Backend::Groth16, input).unwrap_err();
+            assert_eq!(error.field(), Some(field));
+        }
+    }
+
+    #[test]
+    fn test_proofs_of_other_sizes_are_rejected() {
+        let dir = TestDir::new("matrix_sizes");
+        let keys = dir.keys();
+        let prove = |n: usize| {
+            let identity = (0..n).map(|i| (0..n).map(|j| (i == j) as u64).collect()).collect();
+            let input = ProveInput::new(
+                MatrixInput::Dense(identity),
+                Some(MatrixInput::Dense(vec![vec![2; n]; n])),
+                None,
+                Backend::Groth16,
+                Curve::default(),
+            );
+            match matrix_proof(&keys, Backend::Groth16, input).unwrap() {
+                MatrixProveOutput::Product(output) => output,
+                MatrixProveOutput::SparseMatVec(_) => unreachable!(),
+            }
+        };
+        let verify = |size: usize, proved: &ProveOutPut| {
+            let input = VerifyInput {
+                size,
+                proof: proved.proof.clone(),
+                com_a: proved.com_a.clone(),
+                com_b: proved.com_b.clone(),
+                com_c: proved.com_c.clone(),
+                backend: Backend::Groth16,
+                curve: Curve::default(),
+            };
+            on_curve!(input.curve, E => matrix_verify::<E>(&keys, Backend::Groth16, &input))
+        };
+        let two = prove(2);
+        let three = prove(3);
+        assert!(verify(2, &two).unwrap().valid);
+        assert!(verify(3, &three).unwrap().valid);
+        // the key of the size comes from the request, not from the client
+        assert!(!verify(3, &two).unwrap().valid);
+        assert_eq!(
+            verify(4, &two).unwrap_err(),
+            ZkError::UnknownKey("matrix-4".to_string())
+        );
+    }
+
+    #[test]
+    fn test_sparse_patterns_share_the_key_of_their_shape() {
+        let dir = TestDir::new("sparse_keys");
+        let keys = dir.keys();
+        let prove = |entries| {
+            let input = mat_vec(coo(3, 3, entries), vec![1, 2, 3]);
+            match matrix_proof(&keys, Backend::Groth16, input).unwrap() {
+                MatrixProveOutput::SparseMatVec(output) => output,
+                MatrixProveOutput::Product(_) => unreachable!(),
+            }
+        };
+        let first = prove(vec![(0, 0, 1), (1, 1, 2)]);
+        let second = prove(vec![(0, 2, 5), (2, 0, 7)]);
+        assert_eq!(first.key_id, second.key_id);
+        assert_eq!(second.vector_y, vec!["15", "0", "7"]);
+
+        let input = SparseVerifyInput {
+            proof: second.proof,
+            rows: 3,
+            cols: 3,
+            nnz: 2,
+            com_a: second.com_a,
+            com_x: second.com_x,
+            com_y: second.com_y,
+            backend: Backend::Groth16,
+            curve: Curve::default(),
+        };
+        let verified =
+            on_curve!(input.curve, E => sparse_verify::<E>(&keys, Backend::Groth16, &input));
+        assert!(verified.unwrap().valid);
+    }
 }
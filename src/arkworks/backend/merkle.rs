@@ -0,0 +1,277 @@
+use crate::arkworks::backend::proving::{prove_with_keys, verify_with_keys};
+use crate::arkworks::curves::{on_curve, SnarkCurve};
+use crate::arkworks::key_store::{key_id, KeyStore};
+use crate::arkworks::merkle::circuit::MerkleMembershipCircuit;
+use crate::arkworks::merkle::tree::{MerkleHash, MerkleTree, PoseidonMerkle, Sha256Merkle};
+use crate::error::{check_len, Validate, ZkError};
+use crate::proof_system::{Backend, Curve};
+use actix_web::{web, HttpResponse};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+use serde::{Deserialize, Serialize};
+
+// poseidon roots are decimal elements of the scalar field of the curve, sha256 roots and
+// siblings are base64
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MerkleHashKind {
+    #[default]
+    Poseidon,
+    Sha256,
+}
+
+#[derive(Deserialize)]
+pub struct InputDataMerkleTree {
+    pub leaves: Vec<String>,
+    #[serde(default)]
+    pub hash: MerkleHashKind,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+#[derive(Deserialize)]
+pub struct InputDataMerklePath {
+    pub leaves: Vec<String>,
+    pub index: usize,
+    #[serde(default)]
+    pub hash: MerkleHashKind,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+// the index of the leaf is looked up in the tree and stays out of the response
+#[derive(Deserialize)]
+pub struct InputDataMerkleProve {
+    pub leaves: Vec<String>,
+    pub leaf: String,
+    #[serde(default)]
+    pub hash: MerkleHashKind,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+// depth and leaf_len pick the keys, like in the response of the proof
+#[derive(Deserialize)]
+pub struct InputDataMerkleVerify {
+    pub proof: String,
+    pub root: String,
+    pub depth: usize,
+    pub leaf_len: usize,
+    #[serde(default)]
+    pub hash: MerkleHashKind,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+#[derive(Serialize)]
+pub struct OutputDataMerkleTree {
+    pub root: String,
+    pub depth: usize,
+    pub num_leaves: usize,
+    pub hash: MerkleHashKind,
+}
+
+#[derive(Serialize)]
+pub struct OutputDataMerklePath {
+    pub root: String,
+    pub index: usize,
+    pub siblings: Vec<String>,
+    pub hash: MerkleHashKind,
+}
+
+// the keys only fit trees of this depth and leaves of this length
+#[derive(Serialize)]
+pub struct OutputDataMerkleProve {
+    pub proof: String,
+    pub key_id: String,
+    pub root: String,
+    pub depth: usize,
+    pub leaf_len: usize,
+    pub hash: MerkleHashKind,
+    pub num_constraints: usize,
+    pub setup_time: f64,
+    pub proving_time: f64,
+}
+
+#[derive(Serialize)]
+pub struct OutputVerifyMerkle {
+    pub verifying_time: f64,
+    pub valid: bool,
+}
+
+// the trees are built natively for every request, the leaves of the proofs are hashed in the
+// circuit
+const MAX_LEAVES: usize = 1 << 16;
+const MAX_LEAF_LEN: usize = 1024;
+
+fn check_leaves(leaves: &[String]) -> Result<(), ZkError> {
+    check_len("leaves", leaves, MAX_LEAVES)
+}
+
+impl Validate for InputDataMerkleTree {
+    fn validate(&self) -> Result<(), ZkError> {
+        check_leaves(&self.leaves)
+    }
+}
+
+impl Validate for InputDataMerklePath {
+    fn validate(&self) -> Result<(), ZkError> {
+        check_leaves(&self.leaves)
+    }
+}
+
+impl Validate for InputDataMerkleProve {
+    fn validate(&self) -> Result<(), ZkError> {
+        check_leaves(&self.leaves)?;
+        if self.leaf.len() > MAX_LEAF_LEN {
+            return Err(ZkError::invalid(
+                "leaf",
+                format!("the leaf can have at most {} bytes", MAX_LEAF_LEN),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn merkle_key_id(hash: MerkleHashKind, depth: usize, leaf_len: usize) -> String {
+    key_id(
+        "merkle",
+        &[
+            format!("{:?}", hash).to_lowercase(),
+            depth.to_string(),
+            leaf_len.to_string(),
+        ],
+    )
+}
+
+fn build_tree<E: SnarkCurve, H: MerkleHash<E::ScalarField>>(
+    leaves: &[String],
+) -> MerkleTree<E::ScalarField, H> {
+    let leaves: Vec<Vec<u8>> = leaves.iter().map(|leaf| leaf.as_bytes().to_vec()).collect();
+    MerkleTree::new(&leaves)
+}
+
+fn tree<E: SnarkCurve, H: MerkleHash<E::ScalarField>>(
+    data: &InputDataMerkleTree,
+) -> Result<HttpResponse, ZkError> {
+    data.validate()?;
+    let tree = build_tree::<E, H>(&data.leaves);
+    Ok(HttpResponse::Ok().json(OutputDataMerkleTree {
+        root: H::encode_digest(&tree.root()),
+        depth: tree.depth(),
+        num_leaves: tree.num_leaves,
+        hash: data.hash,
+    }))
+}
+
+fn path<E: SnarkCurve, H: MerkleHash<E::ScalarField>>(
+    data: &InputDataMerklePath,
+) -> Result<HttpResponse, ZkError> {
+    data.validate()?;
+    let tree = build_tree::<E, H>(&data.leaves);
+    let path = tree
+        .path(data.index)
+        .ok_or_else(|| ZkError::invalid("index", "index is out of range"))?;
+    Ok(HttpResponse::Ok().json(OutputDataMerklePath {
+        root: H::encode_digest(&tree.root()),
+        index: path.leaf_index,
+        siblings: path.siblings.iter().map(H::encode_digest).collect(),
+        hash: data.hash,
+    }))
+}
+
+fn prove<E: SnarkCurve, H: MerkleHash<E::ScalarField>>(
+    keys: &KeyStore,
+    data: &InputDataMerkleProve,
+) -> Result<HttpResponse, ZkError> {
+    data.validate()?;
+    let tree = build_tree::<E, H>(&data.leaves);
+    let index = data
+        .leaves
+        .iter()
+        .position(|leaf| leaf == &data.leaf)
+        .ok_or_else(|| ZkError::invalid("leaf", "leaf is not in the tree"))?;
+    let circuit = MerkleMembershipCircuit::<E::ScalarField, H>::new(
+        tree.root(),
+        data.leaf.as_bytes().to_vec(),
+        tree.path(index).unwrap(),
+    );
+
+    let key_id = merkle_key_id(data.hash, tree.depth(), data.leaf.len());
+    let proved = prove_with_keys::<E, _>(data.backend, keys, &key_id, circuit.clone())?;
+
+    let cs = ConstraintSystem::<E::ScalarField>::new_ref();
+    circuit.generate_constraints(cs.clone())?;
+
+    Ok(HttpResponse::Ok().json(OutputDataMerkleProve {
+        proof: proved.proof,
+        key_id,
+        root: H::encode_digest(&tree.root()),
+        depth: tree.depth(),
+        leaf_len: data.leaf.len(),
+        hash: data.hash,
+        num_constraints: cs.num_constraints(),
+        setup_time: proved.setup_time,
+        proving_time: proved.proving_time,
+    }))
+}
+
+fn verify<E: SnarkCurve, H: MerkleHash<E::ScalarField>>(
+    keys: &KeyStore,
+    data: &InputDataMerkleVerify,
+) -> Result<HttpResponse, ZkError> {
+    let root = H::decode_digest(&data.root).map_err(|e| ZkError::invalid("root", e))?;
+    let public_input = H::public_inputs(&root);
+    let key_id = merkle_key_id(data.hash, data.depth, data.leaf_len);
+    let verified = verify_with_keys::<E>(data.backend, keys, &key_id, &public_input, &data.proof)?;
+
+    Ok(HttpResponse::Ok().json(OutputVerifyMerkle {
+        verifying_time: verified.verifying_time,
+        valid: verified.valid,
+    }))
+}
+
+pub async fn merkle_tree(data: web::Json<InputDataMerkleTree>) -> Result<HttpResponse, ZkError> {
+    match data.hash {
+        MerkleHashKind::Poseidon => on_curve!(data.curve, E => tree::<E, PoseidonMerkle>(&data)),
+        MerkleHashKind::Sha256 => on_curve!(data.curve, E => tree::<E, Sha256Merkle>(&data)),
+    }
+}
+
+pub async fn merkle_path(data: web::Json<InputDataMerklePath>) -> Result<HttpResponse, ZkError> {
+    match data.hash {
+        MerkleHashKind::Poseidon => on_curve!(data.curve, E => path::<E, PoseidonMerkle>(&data)),
+        MerkleHashKind::Sha256 => on_curve!(data.curve, E => path::<E, Sha256Merkle>(&data)),
+    }
+}
+
+pub async fn merkle_membership_proof(
+    keys: web::Data<KeyStore>,
+    data: web::Json<InputDataMerkleProve>,
+) -> Result<HttpResponse, ZkError> {
+    match data.hash {
+        MerkleHashKind::Poseidon => {
+            on_curve!(data.curve, E => prove::<E, PoseidonMerkle>(&keys, &data))
+        }
+        MerkleHashKind::Sha256 => {
+            on_curve!(data.curve, E => prove::<E, Sha256Merkle>(&keys, &data))
+        }
+    }
+}
+
+pub async fn merkle_membership_verify(
+    keys: web::Data<KeyStore>,
+    data: web::Json<InputDataMerkleVerify>,
+) -> Result<HttpResponse, ZkError> {
+    match data.hash {
+        MerkleHashKind::Poseidon => {
+            on_curve!(data.curve, E => verify::<E, PoseidonMerkle>(&keys, &data))
+        }
+        MerkleHashKind::Sha256 => {
+            on_curve!(data.curve, E => verify::<E, Sha256Merkle>(&keys, &data))
+        }
+    }
+}
@@ -1,4 +1,14 @@
+pub mod aggregate;
+pub mod conv2d;
+pub mod evm;
 pub mod fibbonaci_handler;
+pub mod folding;
 pub mod linear_equations;
+pub mod linear_recurrence;
+pub mod matrix_linear_algebra;
 pub mod matrix_proof;
+pub mod merkle;
+pub mod poseidon_preimage;
 pub mod prime_snark;
+pub mod proving;
+pub mod sha256_preimage;
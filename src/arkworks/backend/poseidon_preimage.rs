@@ -0,0 +1,127 @@
+use crate::arkworks::backend::proving::{prove_with_keys, verify_with_keys};
+use crate::arkworks::curves::{on_curve, SnarkCurve};
+use crate::arkworks::key_store::{key_id, KeyStore};
+use crate::arkworks::matrix_proof_of_work::io::field_to_hex;
+use crate::arkworks::preimage_poseidon::constraints::PoseidonPreimageCircuit;
+use crate::error::{parse_field, Validate, ZkError};
+use crate::proof_system::{Backend, Curve};
+use actix_web::{web, HttpResponse};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+use serde::{Deserialize, Serialize};
+
+// the message is hashed in the scalar field of the curve of the request
+#[derive(Deserialize)]
+pub struct InputDataPreimage {
+    pub message: String,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+// message_len picks the keys, like in the response of the proof
+#[derive(Deserialize)]
+pub struct InputDataPreimageVer {
+    pub proof: String,
+    pub digest: String,
+    pub message_len: usize,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+// the digest is in decimal, the proof is base64
+#[derive(Serialize)]
+pub struct OutputDataPreimage {
+    pub proof: String,
+    pub key_id: String,
+    pub digest: String,
+    pub digest_hex: String,
+    pub message_len: usize,
+    pub num_constraints: usize,
+    pub setup_time: f64,
+    pub proving_time: f64,
+}
+
+#[derive(Serialize)]
+pub struct OutputVerifyPreimage {
+    pub verifying_time: f64,
+    pub valid: bool,
+}
+
+// every byte of the message is a witness and the keys are per length
+const MAX_MESSAGE_LEN: usize = 1024;
+
+fn preimage_key_id(message_len: usize) -> String {
+    key_id("poseidon_preimage", &[message_len.to_string()])
+}
+
+impl Validate for InputDataPreimage {
+    fn validate(&self) -> Result<(), ZkError> {
+        if self.message.len() > MAX_MESSAGE_LEN {
+            return Err(ZkError::invalid(
+                "message",
+                format!("the message can have at most {} bytes", MAX_MESSAGE_LEN),
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub async fn poseidon_preimage_proof(
+    keys: web::Data<KeyStore>,
+    data: web::Json<InputDataPreimage>,
+) -> Result<HttpResponse, ZkError> {
+    data.validate()?;
+    let output = on_curve!(data.curve, E => preimage_proof::<E>(&keys, &data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn preimage_proof<E: SnarkCurve>(
+    keys: &KeyStore,
+    data: &InputDataPreimage,
+) -> Result<OutputDataPreimage, ZkError> {
+    let circuit = PoseidonPreimageCircuit::<E::ScalarField>::new(data.message.as_bytes().to_vec());
+
+    // the message bytes are witnesses, the keys fit every message of the same length
+    let key_id = preimage_key_id(data.message.len());
+    let proved = prove_with_keys::<E, _>(data.backend, keys, &key_id, circuit.clone())?;
+
+    let digest = circuit.digest;
+    let cs = ConstraintSystem::<E::ScalarField>::new_ref();
+    circuit.generate_constraints(cs.clone())?;
+
+    Ok(OutputDataPreimage {
+        proof: proved.proof,
+        key_id,
+        digest: digest.to_string(),
+        digest_hex: field_to_hex(digest),
+        message_len: data.message.len(),
+        num_constraints: cs.num_constraints(),
+        setup_time: proved.setup_time,
+        proving_time: proved.proving_time,
+    })
+}
+
+pub async fn poseidon_preimage_verify(
+    keys: web::Data<KeyStore>,
+    data: web::Json<InputDataPreimageVer>,
+) -> Result<HttpResponse, ZkError> {
+    let output = on_curve!(data.curve, E => preimage_verify::<E>(&keys, &data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn preimage_verify<E: SnarkCurve>(
+    keys: &KeyStore,
+    data: &InputDataPreimageVer,
+) -> Result<OutputVerifyPreimage, ZkError> {
+    let digest = parse_field::<E::ScalarField>("digest", &data.digest)?;
+    let key_id = preimage_key_id(data.message_len);
+    let verified = verify_with_keys::<E>(data.backend, keys, &key_id, &[digest], &data.proof)?;
+
+    Ok(OutputVerifyPreimage {
+        verifying_time: verified.verifying_time,
+        valid: verified.valid,
+    })
+}
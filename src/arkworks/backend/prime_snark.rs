@@ -2,34 +2,45 @@ use crate::arkworks::prime_snark::fermat_circut::fermat_constructor;
 use crate::arkworks::prime_snark::utils::constants::get_max_val;
 use crate::arkworks::prime_snark::utils::hasher::hash_x_plus_i_native;
 
-use crate::arkworks::matrix_proof_of_work::io::{
-    decode_proof, decode_pvk, encode_proof, encode_pvk, read_proof, write_proof_to_file,
-};
+use crate::arkworks::backend::proving::{prove_with_keys, verify_with_keys, BackendQuery};
+use crate::arkworks::curves::{on_curve, SnarkCurve};
+use crate::arkworks::key_store::{key_id, KeyStore};
+use crate::arkworks::matrix_proof_of_work::io::{read_proof, write_proof_to_file};
 use crate::arkworks::prime_snark::prime_circut::{self, init_randomness};
 use crate::arkworks::prime_snark::prime_circut::{check_if_next_is_prime, PrimeCircuit};
 use crate::arkworks::prime_snark::utils::modulo::get_mod_vals;
-use actix_web::{web, HttpResponse, Responder};
-use ark_bls12_381::{Bls12_381, Fr as BlsFr};
+use crate::bundle::{indexed_fields, ProofBundle};
+use crate::error::{Validate, ZkError};
+use crate::proof_system::{Backend, Curve};
+use actix_web::{web, HttpResponse};
 use ark_ff::BigInteger;
 use ark_ff::PrimeField;
-use ark_groth16::{prepare_verifying_key, Groth16};
 use ark_r1cs_std::{ToBitsGadget, ToBytesGadget};
 use ark_relations::r1cs::ConstraintSystem;
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef};
-use ark_snark::SNARK;
-use ark_std::rand::SeedableRng;
-use ark_std::test_rng;
 use num_bigint::BigUint;
-use rand::rngs::StdRng;
-use rand::RngCore as _;
 use serde::Deserialize;
 use serde::Serialize;
-use std::time::Instant;
 // create a struct of ProveInput that will be used to get the data from the user : x- a intial seed number , i - number of rounds
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProveInput {
     x: u64,
     i: u64,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+impl ProveInput {
+    pub fn new(x: u64, i: u64, backend: Backend, curve: Curve) -> Self {
+        Self {
+            x,
+            i,
+            backend,
+            curve,
+        }
+    }
 }
 
 // create a struct of ProveOutput that will be used to send the data to the user : proof - the proof of the computation , public_input - the public input of the computation , num_constraints - the number of constraints in the computation , num_variables - the number of variables in the computation , proving_time - the time it took to prove the computation , verifying_time - the time it took to verify the computation , found_prime - if the number is prime or not
@@ -43,12 +54,63 @@ pub struct ProveOutput {
     found_prime: bool,
     prime_num: String,
     proof: String,
-    pvk: String,
+    key_id: String,
+    // no bundle when there is no proof
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bundle: Option<ProofBundle>,
+}
+
+impl ProveOutput {
+    pub fn bundle(&self) -> Option<&ProofBundle> {
+        self.bundle.as_ref()
+    }
+}
+
+// the circuit and key id of every prime proof
+const PRIME_CIRCUIT: &str = "prime";
+
+// the search hashes x + j for every j up to i and checks each for primality
+pub const MAX_ROUNDS: u64 = 1 << 16;
+
+impl Validate for ProveInput {
+    fn validate(&self) -> Result<(), ZkError> {
+        if self.i > MAX_ROUNDS {
+            return Err(ZkError::invalid(
+                "i",
+                format!("i can be at most {}", MAX_ROUNDS),
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub async fn prove_prime(
+    keys: web::Data<KeyStore>,
+    query: web::Query<BackendQuery>,
+    data: web::Json<ProveInput>,
+) -> Result<HttpResponse, ZkError> {
+    let result = prime_proof(&keys, query.or(data.backend), &data)?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+// the search and proof of /prime_arkworks/prove, also run by the prime jobs
+pub fn prime_proof(
+    keys: &KeyStore,
+    backend: Backend,
+    data: &ProveInput,
+) -> Result<ProveOutput, ZkError> {
+    on_curve!(data.curve, E => prime_proof_on::<E>(keys, backend, data))
 }
 
-pub async fn prove_prime(data: web::Json<ProveInput>) -> impl Responder {
+// x + j is hashed as an element of the scalar field of the curve, so the primes found depend on
+// the curve
+fn prime_proof_on<E: SnarkCurve>(
+    keys: &KeyStore,
+    backend: Backend,
+    data: &ProveInput,
+) -> Result<ProveOutput, ZkError> {
     // extract the data from the user
-    let data = data.into_inner();
+    data.validate()?;
     let x = data.x; // x- a intial seed number
     let i = data.i; // i- number of rounds
     let mut found_prime = None; // To store the first prime found
@@ -59,38 +121,35 @@ pub async fn prove_prime(data: web::Json<ProveInput>) -> impl Responder {
     // is prime after taking mod using get_max_val and check_if_next_is_prime:
     for j in 0..=i {
         // Use check_if_next_is_prime to check each (x + j)
-        check_result = Some(check_if_next_is_prime(BlsFr::from(x), j));
+        let result = check_if_next_is_prime(E::ScalarField::from(x), j);
 
         // If a prime number is found, store it and break the loop
-        if check_result.as_ref().unwrap().1 {
-            found_prime = Some(check_result.as_ref().unwrap().2.remainder.clone());
+        if result.1 {
+            found_prime = Some(result.2.remainder.clone());
             found_j = j; // Save the value of j when a prime is found
+            check_result = Some(result);
             break;
         }
     }
 
     // If no prime was found, skip the rest
-    if found_prime.is_none() {
-        println!("No prime number found in the given range.");
-        return HttpResponse::Ok().json(ProveOutput {
-            proof: "".to_string(),
-            j: 0,
-            num_constraints: 0,
-            num_variables: 0,
-            setup_time: 0.0,
-            proving_time: 0.0,
-            found_prime: false,
-            prime_num: "".to_string(),
-            pvk: "".to_string(),
-        });
-    }
-    // Print the first prime number found
-    // println!("First prime number found: {}", found_prime.unwrap());
-    // unwrap() is safe here since we checked for None
-    println!("j: {}", found_j); // Print the value of j where the prime was found
-
-    // Unwrap check_result
-    let check_result = check_result.unwrap();
+    let (found_prime, check_result) = match (found_prime, check_result) {
+        (Some(found_prime), Some(check_result)) => (found_prime, check_result),
+        _ => {
+            return Ok(ProveOutput {
+                proof: "".to_string(),
+                j: 0,
+                num_constraints: 0,
+                num_variables: 0,
+                setup_time: 0.0,
+                proving_time: 0.0,
+                found_prime: false,
+                prime_num: "".to_string(),
+                key_id: "".to_string(),
+                bundle: None,
+            });
+        }
+    };
 
     //pub struct IsPrimeStruct(Vec<u8>, bool, ModVals, BigUint);
 
@@ -98,58 +157,58 @@ pub async fn prove_prime(data: web::Json<ProveInput>) -> impl Responder {
     let prime_circuit = PrimeCircuit::new(
         check_result.3.clone(),
         check_result.2.remainder.clone(),
-        BlsFr::from(x),
+        E::ScalarField::from(x),
         check_result.0.clone(),
         found_j, // Use the found j from the loop
         check_result.2.clone(),
     );
 
-    // Set up the Groth16 proof system
-    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
-
-    // Setup the Groth16 proving key and verification key
-    let start_setup = Instant::now();
-    let (pk, vk) =
-        Groth16::<Bls12_381>::circuit_specific_setup(prime_circuit.clone(), &mut rng).unwrap();
-    let setup_duration = start_setup.elapsed();
-    println!("Setup time: {:?}", setup_duration);
-
-    // Create the proof
-    let start_proof = Instant::now();
-    let proof = Groth16::<Bls12_381>::prove(&pk, prime_circuit.clone(), &mut rng).unwrap();
-    let proof_duration = start_proof.elapsed();
-    println!("Proof generation time: {:?}", proof_duration);
-
-    let cs = ConstraintSystem::<BlsFr>::new_ref();
-    prime_circuit
-        .clone()
-        .generate_constraints(cs.clone())
-        .unwrap();
-    assert!(cs.is_satisfied().unwrap());
+    // Prove with the keys of the backend. j and the bits of the exponent n - 1 are variables of
+    // the circuit, so one key fits every x and j
+    let params = vec![];
+    let key_id = key_id(PRIME_CIRCUIT, &params);
+    let proved = prove_with_keys::<E, _>(backend, keys, &key_id, prime_circuit.clone())?;
+
+    let cs = ConstraintSystem::<E::ScalarField>::new_ref();
+    prime_circuit.clone().generate_constraints(cs.clone())?;
+    let public_input = ConstraintSystemRef::borrow(&cs)
+        .ok_or_else(|| ZkError::Internal("the constraint system is borrowed".to_string()))?
+        .instance_assignment[1..]
+        .to_vec();
+    let bundle = proved.bundle(
+        backend,
+        PRIME_CIRCUIT,
+        params,
+        indexed_fields("public_input", &public_input),
+    );
 
     // Return the proof and other data
 
-    HttpResponse::Ok().json(ProveOutput {
-        proof: encode_proof::<Bls12_381>(&proof),
+    Ok(ProveOutput {
+        proof: proved.proof,
         j: found_j,
         num_constraints: cs.num_constraints(),
         num_variables: cs.num_instance_variables() + cs.num_witness_variables(),
-        setup_time: setup_duration.as_secs_f64(),
-        proving_time: proof_duration.as_secs_f64(),
+        setup_time: proved.setup_time,
+        proving_time: proved.proving_time,
         found_prime: true,
-        prime_num: found_prime.unwrap().to_string(),
-        pvk: encode_pvk::<Bls12_381>(&prepare_verifying_key::<Bls12_381>(&vk)),
+        prime_num: found_prime.to_string(),
+        key_id,
+        bundle: Some(bundle),
     })
 }
 
 // now for the verification part:
-// create a struct of VerifyInput that will be used to get the data from the user : proof - the proof of the computation , public_input - the public input of the computation , pvk - the verifying key of the computation
+// create a struct of VerifyInput that will be used to get the data from the user : proof - the proof of the computation , j and x - the public input of the computation
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VerifyInput {
     j: u64,
     x: u64,
     proof: String,
-    pvk: String,
+    #[serde(default)]
+    backend: Backend,
+    #[serde(default)]
+    curve: Curve,
 }
 
 // create a struct of VerifyOutput that will be used to send the data to the user : verifying_time - the time it took to verify the computation , valid - if the computation is valid or not
@@ -159,48 +218,106 @@ pub struct VerifyOutput {
     valid: bool,
 }
 
-pub async fn verify_prime(data: web::Json<VerifyInput>) -> impl Responder {
+impl Validate for VerifyInput {
+    fn validate(&self) -> Result<(), ZkError> {
+        if self.j > MAX_ROUNDS {
+            return Err(ZkError::invalid(
+                "j",
+                format!("j can be at most {}", MAX_ROUNDS),
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub async fn verify_prime(
+    keys: web::Data<KeyStore>,
+    query: web::Query<BackendQuery>,
+    data: web::Json<VerifyInput>,
+) -> Result<HttpResponse, ZkError> {
     // extract the data from the user
     let data = data.into_inner();
+    data.validate()?;
+    let backend = query.or(data.backend);
+    let output = on_curve!(data.curve, E => prime_verify::<E>(&keys, backend, &data))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn prime_verify<E: SnarkCurve>(
+    keys: &KeyStore,
+    backend: Backend,
+    data: &VerifyInput,
+) -> Result<VerifyOutput, ZkError> {
     let j = data.j; // j- the value of j where the prime was found
     let x = data.x; // x- a intial seed number
-    let proof = decode_proof::<Bls12_381>(&data.proof); // proof- the proof of the computation
-                                                        // let public_input = decode_proof::<Bls12_381>(&data.public_input); // public_input- the public input of the computation
-    let pvk = decode_pvk::<Bls12_381>(&data.pvk); // pvk- the verifying key of the computation
 
-    let check_result = check_if_next_is_prime(BlsFr::from(x), j);
+    let check_result = check_if_next_is_prime(E::ScalarField::from(x), j);
 
     // Create the prime circuit using the found prime and the j from the loop
     let prime_circuit = PrimeCircuit::new(
         check_result.3.clone(),
         check_result.2.remainder.clone(),
-        BlsFr::from(x),
+        E::ScalarField::from(x),
         check_result.0.clone(),
         j, // Use the found j from the loop
         check_result.2.clone(),
     );
 
-    let cs = ConstraintSystem::<BlsFr>::new_ref();
-    prime_circuit.generate_constraints(cs.clone()).unwrap();
+    let cs = ConstraintSystem::<E::ScalarField>::new_ref();
+    prime_circuit.generate_constraints(cs.clone())?;
     let real_public_input = ConstraintSystemRef::borrow(&cs)
-        .unwrap()
+        .ok_or_else(|| ZkError::Internal("the constraint system is borrowed".to_string()))?
         .instance_assignment
         .clone();
-    // Verify the proof
-    let start_verify = Instant::now();
-    let is_valid = Groth16::<Bls12_381>::verify_with_processed_vk(
-        &pvk.unwrap(),
+    // Verify the proof with the verifying key of the prime circuit
+    let verified = verify_with_keys::<E>(
+        backend,
+        keys,
+        &key_id(PRIME_CIRCUIT, &[]),
         &real_public_input[1..],
-        &proof.unwrap(),
-    )
-    .unwrap();
-
-    let verify_duration = start_verify.elapsed();
-    println!("Verification time: {:?}", verify_duration);
+        &data.proof,
+    )?;
 
     // Return the verification result
-    HttpResponse::Ok().json(VerifyOutput {
-        verifying_time: verify_duration.as_secs_f64(),
-        valid: is_valid,
+    Ok(VerifyOutput {
+        verifying_time: verified.verifying_time,
+        valid: verified.valid,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::key_store::TestDir;
+    use ark_bls12_381::Bls12_381;
+
+    #[test]
+    fn test_one_key_fits_every_x() {
+        let dir = TestDir::new("prime_keys");
+        let keys = dir.keys();
+        let prove = |x| {
+            let input = ProveInput::new(x, 40, Backend::Groth16, Curve::Bls12_381);
+            prime_proof_on::<Bls12_381>(&keys, Backend::Groth16, &input).unwrap()
+        };
+        let verify = |x, j, proof: &str| {
+            let input = VerifyInput {
+                j,
+                x,
+                proof: proof.to_string(),
+                backend: Backend::Groth16,
+                curve: Curve::Bls12_381,
+            };
+            prime_verify::<Bls12_381>(&keys, Backend::Groth16, &input)
+                .unwrap()
+                .valid
+        };
+        let first = prove(5);
+        let second = prove(1000);
+        assert!(first.found_prime && second.found_prime);
+        assert_eq!(first.key_id, "prime");
+        assert_eq!(second.key_id, "prime");
+        assert!(verify(5, first.j, &first.proof));
+        assert!(verify(1000, second.j, &second.proof));
+        assert!(!verify(1000, second.j, &first.proof));
+    }
+}
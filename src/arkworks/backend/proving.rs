@@ -0,0 +1,361 @@
+use crate::arkworks::curves::SnarkCurve;
+use crate::arkworks::key_store::KeyStore;
+use crate::arkworks::matrix_proof_of_work::io::{decode_hash, encode_hash};
+use crate::arkworks::proof_system::Groth16;
+use crate::bundle::{ProofBundle, PublicInput, VerifyingKeyRef};
+use crate::error::ZkError;
+use crate::jobs::{enter_phase, Phase};
+use crate::proof_system::{Backend, Curve, ProofSystem, ProofSystemError, Prover};
+use ark_ff::PrimeField;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+use serde::Deserialize;
+use std::time::Instant;
+
+// the handlers prove their circuits through these, so every circuit is provable by every
+// SNARK backend over every curve E. proofs and verifying keys are base64 of the backend's
+// bytes, for groth16 they are the same strings as encode_proof and encode_pvk.
+
+pub struct BackendProof {
+    pub curve: Curve,
+    pub proof: String,
+    // also set for the keys in the key store, for the handlers that return it
+    pub vk: String,
+    pub setup_time: f64,
+    pub proving_time: f64,
+}
+
+impl BackendProof {
+    // the proof as a bundle of the keys of key_id in the key store, which pins their
+    // verifying key by its hash
+    pub fn bundle(
+        &self,
+        backend: Backend,
+        circuit: &str,
+        params: Vec<String>,
+        public_inputs: Vec<PublicInput>,
+    ) -> ProofBundle {
+        let vk = decode_hash(&self.vk).expect("the verifying keys are base64");
+        ProofBundle::new(
+            circuit,
+            params,
+            self.curve,
+            backend,
+            public_inputs,
+            decode_hash(&self.proof).expect("the proofs are base64"),
+            VerifyingKeyRef::hash_of(&vk),
+        )
+    }
+}
+
+pub struct BackendVerification {
+    pub valid: bool,
+    pub verifying_time: f64,
+}
+
+// `?backend=marlin` on the endpoints that take it, it wins over the backend of the body
+#[derive(Deserialize, Default)]
+pub struct BackendQuery {
+    pub backend: Option<Backend>,
+}
+
+impl BackendQuery {
+    pub fn or(&self, backend: Backend) -> Backend {
+        self.backend.unwrap_or(backend)
+    }
+}
+
+fn sp1_unsupported() -> ZkError {
+    ZkError::invalid(
+        "backend",
+        "the sp1 backend proves RISC-V programs, this circuit needs groth16 or marlin",
+    )
+}
+
+fn encode(bytes: Result<Vec<u8>, ProofSystemError>) -> Result<String, ZkError> {
+    Ok(encode_hash(&bytes?))
+}
+
+// the provers don't check the witness, a proof of a false statement would only fail to verify.
+// the check costs one more synthesis of the circuit
+fn check_satisfied<F: PrimeField, C: ConstraintSynthesizer<F>>(circuit: C) -> Result<(), ZkError> {
+    let cs = ConstraintSystem::<F>::new_ref();
+    circuit.generate_constraints(cs.clone())?;
+    if !cs.is_satisfied()? {
+        let constraint = cs.which_is_unsatisfied()?.unwrap_or_default();
+        return Err(ZkError::Unsatisfiable(format!(
+            "constraint {} doesn't hold",
+            constraint
+        )));
+    }
+    Ok(())
+}
+
+// a proof or a key of a request, the wrong base64 or the bytes of another backend are the
+// client's fault
+fn decode<T>(
+    field: &str,
+    value: &str,
+    from_bytes: fn(&[u8]) -> Result<T, ProofSystemError>,
+) -> Result<T, ZkError> {
+    let bytes = decode_hash(value)
+        .map_err(|_| ZkError::invalid(field, format!("{} is not valid base64", field)))?;
+    from_bytes(&bytes).map_err(|e| ZkError::invalid(field, format!("invalid {}: {}", field, e)))
+}
+
+fn prove<S: Prover<C>, C>(
+    system: &S,
+    pk: &S::ProvingKey,
+    circuit: C,
+) -> Result<(String, f64), ZkError> {
+    enter_phase(Phase::Prove)?;
+    let start = Instant::now();
+    let proof = system.prove(pk, circuit)?;
+    let proving_time = start.elapsed().as_secs_f64();
+    Ok((encode(S::proof_to_bytes(&proof))?, proving_time))
+}
+
+fn verify<F, S: ProofSystem<PublicInput = [F]>>(
+    system: &S,
+    vk: &S::VerifyingKey,
+    public_input: &[F],
+    proof: &str,
+) -> Result<BackendVerification, ZkError> {
+    let proof = decode("proof", proof, S::proof_from_bytes)?;
+    let start = Instant::now();
+    // a wrong number of public inputs is an error for some backends and false for others
+    let valid = system
+        .verify(vk, public_input, &proof)
+        .map_err(|e| ZkError::invalid("proof", format!("verification failed: {}", e)))?;
+    Ok(BackendVerification {
+        valid,
+        verifying_time: start.elapsed().as_secs_f64(),
+    })
+}
+
+fn prove_with_keys_by<S: Prover<C>, C: Clone>(
+    system: &S,
+    curve: Curve,
+    keys: &KeyStore,
+    key_id: &str,
+    circuit: C,
+) -> Result<BackendProof, ZkError> {
+    enter_phase(Phase::Setup)?;
+    let start = Instant::now();
+    let pk = keys.proving_key(system, key_id, circuit.clone())?;
+    let setup_time = start.elapsed().as_secs_f64();
+    let vk = keys.verifying_key::<S>(key_id)?;
+    let (proof, proving_time) = prove(system, &pk, circuit)?;
+    Ok(BackendProof {
+        curve,
+        proof,
+        vk: encode(S::verifying_key_to_bytes(&vk))?,
+        setup_time,
+        proving_time,
+    })
+}
+
+fn prove_with_setup_by<S: Prover<C>, C: Clone>(
+    system: &S,
+    curve: Curve,
+    circuit: C,
+) -> Result<BackendProof, ZkError> {
+    enter_phase(Phase::Setup)?;
+    let start = Instant::now();
+    let (pk, vk) = system.setup(circuit.clone())?;
+    let setup_time = start.elapsed().as_secs_f64();
+    let (proof, proving_time) = prove(system, &pk, circuit)?;
+    Ok(BackendProof {
+        curve,
+        proof,
+        vk: encode(S::verifying_key_to_bytes(&vk))?,
+        setup_time,
+        proving_time,
+    })
+}
+
+fn setup_keys_by<S: Prover<C>, C>(
+    system: &S,
+    keys: &KeyStore,
+    key_id: &str,
+    circuit: C,
+) -> Result<f64, ZkError> {
+    let start = Instant::now();
+    keys.proving_key(system, key_id, circuit)?;
+    Ok(start.elapsed().as_secs_f64())
+}
+
+fn verify_with_keys_by<F, S: ProofSystem<PublicInput = [F]>>(
+    system: &S,
+    keys: &KeyStore,
+    key_id: &str,
+    public_input: &[F],
+    proof: &str,
+) -> Result<BackendVerification, ZkError> {
+    let vk = keys.verifying_key::<S>(key_id)?;
+    verify(system, &vk, public_input, proof)
+}
+
+fn stored_verifying_key_by<S: ProofSystem>(
+    _system: &S,
+    keys: &KeyStore,
+    key_id: &str,
+) -> Result<Vec<u8>, ZkError> {
+    Ok(S::verifying_key_to_bytes(
+        &*keys.verifying_key::<S>(key_id)?,
+    )?)
+}
+
+fn verify_with_vk_by<F, S: ProofSystem<PublicInput = [F]>>(
+    system: &S,
+    vk: &str,
+    public_input: &[F],
+    proof: &str,
+) -> Result<BackendVerification, ZkError> {
+    let vk = decode("pvk", vk, S::verifying_key_from_bytes)?;
+    verify(system, &vk, public_input, proof)
+}
+
+// prove with the keys of key_id, they are set up with this circuit the first time
+pub fn prove_with_keys<E: SnarkCurve, C: ConstraintSynthesizer<E::ScalarField> + Clone>(
+    backend: Backend,
+    keys: &KeyStore,
+    key_id: &str,
+    circuit: C,
+) -> Result<BackendProof, ZkError> {
+    enter_phase(Phase::Witness)?;
+    check_satisfied(circuit.clone())?;
+    let curve = E::CURVE;
+    match backend {
+        Backend::Groth16 => {
+            prove_with_keys_by(&Groth16::<E>::default(), curve, keys, key_id, circuit)
+        }
+        Backend::Marlin => prove_with_keys_by(&E::marlin(keys)?, curve, keys, key_id, circuit),
+        Backend::Sp1 => Err(sp1_unsupported()),
+    }
+}
+
+// set up the keys of key_id before the first proof needs them, the circuit only gives the
+// shape. keys that exist are only loaded
+pub fn setup_keys<E: SnarkCurve, C: ConstraintSynthesizer<E::ScalarField>>(
+    backend: Backend,
+    keys: &KeyStore,
+    key_id: &str,
+    circuit: C,
+) -> Result<f64, ZkError> {
+    match backend {
+        Backend::Groth16 => setup_keys_by(&Groth16::<E>::default(), keys, key_id, circuit),
+        Backend::Marlin => setup_keys_by(&E::marlin(keys)?, keys, key_id, circuit),
+        Backend::Sp1 => Err(sp1_unsupported()),
+    }
+}
+
+// prove with new keys for this circuit, the verifying key is returned with the proof. for
+// marlin the new keys are an index against the universal srs of the key store
+pub fn prove_with_setup<E: SnarkCurve, C: ConstraintSynthesizer<E::ScalarField> + Clone>(
+    backend: Backend,
+    keys: &KeyStore,
+    circuit: C,
+) -> Result<BackendProof, ZkError> {
+    enter_phase(Phase::Witness)?;
+    check_satisfied(circuit.clone())?;
+    match backend {
+        Backend::Groth16 => prove_with_setup_by(&Groth16::<E>::default(), E::CURVE, circuit),
+        Backend::Marlin => prove_with_setup_by(&E::marlin(keys)?, E::CURVE, circuit),
+        Backend::Sp1 => Err(sp1_unsupported()),
+    }
+}
+
+pub fn verify_with_keys<E: SnarkCurve>(
+    backend: Backend,
+    keys: &KeyStore,
+    key_id: &str,
+    public_input: &[E::ScalarField],
+    proof: &str,
+) -> Result<BackendVerification, ZkError> {
+    match backend {
+        Backend::Groth16 => {
+            verify_with_keys_by(&Groth16::<E>::default(), keys, key_id, public_input, proof)
+        }
+        Backend::Marlin => {
+            verify_with_keys_by(&E::marlin_verifier()?, keys, key_id, public_input, proof)
+        }
+        Backend::Sp1 => Err(sp1_unsupported()),
+    }
+}
+
+// the bytes of the verifying key of key_id, the ones BackendProof::vk is the base64 of
+pub fn stored_verifying_key<E: SnarkCurve>(
+    backend: Backend,
+    keys: &KeyStore,
+    key_id: &str,
+) -> Result<Vec<u8>, ZkError> {
+    match backend {
+        Backend::Groth16 => stored_verifying_key_by(&Groth16::<E>::default(), keys, key_id),
+        Backend::Marlin => stored_verifying_key_by(&E::marlin_verifier()?, keys, key_id),
+        Backend::Sp1 => Err(sp1_unsupported()),
+    }
+}
+
+pub fn verify_with_vk<E: SnarkCurve>(
+    backend: Backend,
+    vk: &str,
+    public_input: &[E::ScalarField],
+    proof: &str,
+) -> Result<BackendVerification, ZkError> {
+    match backend {
+        Backend::Groth16 => verify_with_vk_by(&Groth16::<E>::default(), vk, public_input, proof),
+        Backend::Marlin => verify_with_vk_by(&E::marlin_verifier()?, vk, public_input, proof),
+        Backend::Sp1 => Err(sp1_unsupported()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::backend::fibbonaci_handler::fibonacci;
+    use crate::arkworks::constraints::fibbonaci::FibonacciCircuit;
+    use crate::arkworks::key_store::TestDir;
+    use ark_bls12_381::{Bls12_381, Fr};
+
+    #[test]
+    fn test_unsatisfiable_witness() {
+        let (a, b) = (Fr::from(0u64), Fr::from(1u64));
+        let circuit = |result| FibonacciCircuit {
+            a: Some(a),
+            b: Some(b),
+            num_of_steps: 10,
+            result: Some(result),
+        };
+        let dir = TestDir::new("proving_unsatisfiable");
+        let keys = dir.keys();
+        assert!(check_satisfied(circuit(fibonacci(10, a, b))).is_ok());
+        // the prover would make a proof of F(10) = 90 that doesn't verify
+        let error =
+            prove_with_setup::<Bls12_381, _>(Backend::Groth16, &keys, circuit(Fr::from(90u64)))
+                .err()
+                .unwrap();
+        assert_eq!(error.code(), "unsatisfiable");
+    }
+
+    #[test]
+    fn test_marlin_is_only_over_bls12_381() {
+        use ark_bn254::{Bn254, Fr};
+        let (a, b) = (Fr::from(0u64), Fr::from(1u64));
+        let circuit = FibonacciCircuit {
+            a: Some(a),
+            b: Some(b),
+            num_of_steps: 10,
+            result: Some(fibonacci(10, a, b)),
+        };
+        let dir = TestDir::new("proving_marlin_bn254");
+        let keys = dir.keys();
+        let error = prove_with_setup::<Bn254, _>(Backend::Marlin, &keys, circuit)
+            .err()
+            .unwrap();
+        assert_eq!(error.field(), Some("curve"));
+        let error = verify_with_vk::<Bn254>(Backend::Marlin, "AAAA", &[a], "AAAA")
+            .err()
+            .unwrap();
+        assert_eq!(error.field(), Some("curve"));
+    }
+}
@@ -0,0 +1,153 @@
+use crate::arkworks::backend::proving::{prove_with_keys, verify_with_keys};
+use crate::arkworks::constraints::preimage::{max_blocks, Sha256PreimageCircuit};
+use crate::arkworks::curves::{on_curve, SnarkCurve};
+use crate::arkworks::key_store::{key_id, KeyStore};
+use crate::arkworks::matrix_proof_of_work::io::{decode_hash, encode_hash};
+use crate::error::{Validate, ZkError};
+use crate::proof_system::{Backend, Curve};
+use actix_web::{web, HttpResponse};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+use serde::{Deserialize, Serialize};
+
+// every 64 bytes add a SHA-256 compression to the circuit, this bounds the time of a request
+const MAX_PREIMAGE_LEN: usize = 1024;
+
+// the message is either text (a password) or base64 bytes (a file chunk). max_len fixes the
+// circuit, proofs of messages of any length up to max_len share the keys.
+#[derive(Deserialize)]
+pub struct InputDataSha256Preimage {
+    pub message: Option<String>,
+    pub message_base64: Option<String>,
+    pub max_len: usize,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+// max_len picks the keys, like in the response of the proof
+#[derive(Deserialize)]
+pub struct InputDataSha256PreimageVer {
+    pub proof: String,
+    pub digest: String,
+    pub max_len: usize,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+// digest and proof are base64
+#[derive(Serialize)]
+pub struct OutputDataSha256Preimage {
+    pub proof: String,
+    pub key_id: String,
+    pub digest: String,
+    pub max_len: usize,
+    pub num_blocks: usize,
+    pub num_constraints: usize,
+    pub setup_time: f64,
+    pub proving_time: f64,
+}
+
+#[derive(Serialize)]
+pub struct OutputVerifySha256Preimage {
+    pub verifying_time: f64,
+    pub valid: bool,
+}
+
+fn preimage_key_id(max_len: usize) -> String {
+    key_id("sha256_preimage", &[max_len.to_string()])
+}
+
+impl Validate for InputDataSha256Preimage {
+    fn validate(&self) -> Result<(), ZkError> {
+        if self.max_len > MAX_PREIMAGE_LEN {
+            return Err(ZkError::invalid(
+                "max_len",
+                format!("max_len can be at most {}", MAX_PREIMAGE_LEN),
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub async fn sha256_preimage_proof(
+    keys: web::Data<KeyStore>,
+    data: web::Json<InputDataSha256Preimage>,
+) -> Result<HttpResponse, ZkError> {
+    data.validate()?;
+    let message = match (&data.message, &data.message_base64) {
+        (Some(message), None) => message.as_bytes().to_vec(),
+        (None, Some(message)) => decode_hash(message)
+            .map_err(|_| ZkError::invalid("message_base64", "message_base64 must be base64"))?,
+        _ => {
+            return Err(ZkError::invalid(
+                "message",
+                "give either message or message_base64",
+            ))
+        }
+    };
+    if message.len() > data.max_len {
+        return Err(ZkError::invalid(
+            "max_len",
+            "the message is longer than max_len",
+        ));
+    }
+    let output = on_curve!(data.curve, E => preimage_proof::<E>(&keys, &data, message))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn preimage_proof<E: SnarkCurve>(
+    keys: &KeyStore,
+    data: &InputDataSha256Preimage,
+    message: Vec<u8>,
+) -> Result<OutputDataSha256Preimage, ZkError> {
+    let circuit = Sha256PreimageCircuit::<E::ScalarField>::new(message, data.max_len);
+
+    // the keys of max_len are set up once and then shared by all the messages
+    let key_id = preimage_key_id(data.max_len);
+    let proved = prove_with_keys::<E, _>(data.backend, keys, &key_id, circuit.clone())?;
+
+    let digest = encode_hash(&circuit.digest);
+    let cs = ConstraintSystem::<E::ScalarField>::new_ref();
+    circuit.generate_constraints(cs.clone())?;
+
+    Ok(OutputDataSha256Preimage {
+        proof: proved.proof,
+        key_id,
+        digest,
+        max_len: data.max_len,
+        num_blocks: max_blocks(data.max_len),
+        num_constraints: cs.num_constraints(),
+        setup_time: proved.setup_time,
+        proving_time: proved.proving_time,
+    })
+}
+
+pub async fn sha256_preimage_verify(
+    keys: web::Data<KeyStore>,
+    data: web::Json<InputDataSha256PreimageVer>,
+) -> Result<HttpResponse, ZkError> {
+    let digest = decode_hash(&data.digest)
+        .ok()
+        .filter(|digest| digest.len() == 32)
+        .ok_or_else(|| ZkError::invalid("digest", "digest must be 32 base64 bytes"))?;
+    let output = on_curve!(data.curve, E => preimage_verify::<E>(&keys, &data, &digest))?;
+    Ok(HttpResponse::Ok().json(output))
+}
+
+fn preimage_verify<E: SnarkCurve>(
+    keys: &KeyStore,
+    data: &InputDataSha256PreimageVer,
+    digest: &[u8],
+) -> Result<OutputVerifySha256Preimage, ZkError> {
+    let public_input = Sha256PreimageCircuit::<E::ScalarField>::public_inputs(digest);
+    let key_id = preimage_key_id(data.max_len);
+    let verified = verify_with_keys::<E>(data.backend, keys, &key_id, &public_input, &data.proof)?;
+
+    Ok(OutputVerifySha256Preimage {
+        verifying_time: verified.verifying_time,
+        valid: verified.valid,
+    })
+}
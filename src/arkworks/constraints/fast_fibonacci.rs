@@ -0,0 +1,188 @@
+use ark_ff::PrimeField;
+use ark_r1cs_std::boolean::Boolean;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::fields::FieldVar;
+use ark_r1cs_std::prelude::{AllocVar, EqGadget};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+// n < 2^NUM_BITS
+pub const NUM_BITS: usize = 64;
+
+// the powers of M = [[1, 1], [1, 0]] are M^k = [[F(k+1), F(k)], [F(k), F(k-1)]], so a power is
+// kept as the pair (F(k), F(k-1)) and F(k+1) = F(k) + F(k-1). M^a * M^b = M^(a+b):
+fn mul_powers<F: PrimeField>((y_a, x_a): (F, F), (y_b, x_b): (F, F)) -> (F, F) {
+    (y_a * (x_b + y_b) + x_a * y_b, y_a * y_b + x_a * x_b)
+}
+
+// M^(2^i) for i in 0..NUM_BITS, they don't depend on n so the circuit uses them as constants
+fn doublings<F: PrimeField>() -> Vec<(F, F)> {
+    let mut power = (F::one(), F::zero());
+    let mut powers = Vec::with_capacity(NUM_BITS);
+    for _ in 0..NUM_BITS {
+        powers.push(power);
+        power = mul_powers(power, power);
+    }
+    powers
+}
+
+// the same result as fibonacci(n, a, b) for n > 0 (f_0 = a, f_1 = b, the result is f_{n+1}) in
+// O(log n) field operations. for n = 0 the result is b.
+pub fn fast_fibonacci<F: PrimeField>(n: u64, a: F, b: F) -> F {
+    let mut acc = (F::zero(), F::one()); // M^0
+    for (i, power) in doublings::<F>().into_iter().enumerate() {
+        if (n >> i) & 1 == 1 {
+            acc = mul_powers(acc, power);
+        }
+    }
+    // f_{n+1} = F(n) a + F(n+1) b
+    let (y, x) = acc;
+    y * a + (x + y) * b
+}
+
+// O(log n) version of FibonacciCircuit: M^n is multiplied together from the M^(2^i) selected
+// by the bits of n, 4 constraints per bit for any n < 2^64. n is a public input, so a single
+// setup serves every n.
+#[derive(Clone)]
+pub struct FastFibonacciCircuit<F: PrimeField> {
+    pub a: Option<F>,
+    pub b: Option<F>,
+    pub n: Option<u64>,
+    pub result: Option<F>,
+}
+
+impl<F: PrimeField> FastFibonacciCircuit<F> {
+    pub fn new(n: u64, a: F, b: F) -> Self {
+        Self {
+            a: Some(a),
+            b: Some(b),
+            n: Some(n),
+            result: Some(fast_fibonacci(n, a, b)),
+        }
+    }
+
+    // [a, b, n, result]
+    pub fn public_inputs(&self) -> [F; 4] {
+        [
+            self.a.unwrap(),
+            self.b.unwrap(),
+            F::from(self.n.unwrap()),
+            self.result.unwrap(),
+        ]
+    }
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for FastFibonacciCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let a = FpVar::<F>::new_input(cs.clone(), || {
+            self.a.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let b = FpVar::<F>::new_input(cs.clone(), || {
+            self.b.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let n = FpVar::<F>::new_input(cs.clone(), || {
+            self.n.map(F::from).ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let saved_result = FpVar::<F>::new_input(cs.clone(), || {
+            self.result.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // the bits of n are witnesses, 64 bits can't wrap around the field so they are unique
+        let bits = (0..NUM_BITS)
+            .map(|i| {
+                Boolean::new_witness(cs.clone(), || {
+                    self.n
+                        .map(|n| (n >> i) & 1 == 1)
+                        .ok_or(SynthesisError::AssignmentMissing)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Boolean::le_bits_to_fp_var(&bits)?.enforce_equal(&n)?;
+
+        let mut y = FpVar::<F>::zero();
+        let mut x = FpVar::<F>::one();
+        for (bit, (power_y, power_x)) in bits.iter().zip(doublings::<F>()) {
+            // bit ? M^(2^i) : I, a linear combination of the bit
+            let bit = FpVar::from(bit.clone());
+            let select_y = &bit * power_y;
+            let select_x = &bit * (power_x - F::one()) + F::one();
+
+            let next_y = &y * (&select_x + &select_y) + &x * &select_y;
+            let next_x = &y * &select_y + &x * &select_x;
+            y = next_y;
+            x = next_x;
+        }
+
+        (&y * &a + (&x + &y) * &b).enforce_equal(&saved_result)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::backend::fibbonaci_handler::fibonacci;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_groth16::{prepare_verifying_key, Groth16};
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_snark::SNARK;
+    use ark_std::rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn matches_the_linear_fibonacci() {
+        let (a, b) = (Fr::from(3u64), Fr::from(7u64));
+        for n in [1usize, 2, 10, 63, 64, 1000, 100_000] {
+            assert_eq!(fast_fibonacci(n as u64, a, b), fibonacci(n, a, b));
+        }
+        assert_eq!(fast_fibonacci(0, a, b), b);
+    }
+
+    #[test]
+    fn constraints_dont_depend_on_n() {
+        let mut sizes = vec![];
+        for n in [0, 1, 1_000, 10_000_000, u64::MAX] {
+            let circuit = FastFibonacciCircuit::new(n, Fr::from(0u64), Fr::from(1u64));
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            assert!(cs.is_satisfied().unwrap());
+            sizes.push(cs.num_constraints());
+        }
+        assert!(sizes.iter().all(|size| *size == sizes[0]));
+        assert!(sizes[0] < 8 * NUM_BITS);
+    }
+
+    #[test]
+    fn wrong_result_is_not_satisfied() {
+        let mut circuit = FastFibonacciCircuit::new(10, Fr::from(0u64), Fr::from(1u64));
+        assert_eq!(circuit.result.unwrap(), Fr::from(89u64));
+        circuit.result = Some(Fr::from(90u64));
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn groth16_one_setup_for_every_n() {
+        let rng = &mut StdRng::seed_from_u64(42);
+        let setup = FastFibonacciCircuit::<Fr> {
+            a: None,
+            b: None,
+            n: None,
+            result: None,
+        };
+        let (pk, vk) = Groth16::<Bls12_381>::circuit_specific_setup(setup, rng).unwrap();
+        let pvk = prepare_verifying_key(&vk);
+
+        for n in [5, 10_000_000] {
+            let circuit = FastFibonacciCircuit::new(n, Fr::from(0u64), Fr::from(1u64));
+            let proof = Groth16::<Bls12_381>::prove(&pk, circuit.clone(), rng).unwrap();
+            let mut inputs = circuit.public_inputs();
+            assert!(Groth16::<Bls12_381>::verify_with_processed_vk(&pvk, &inputs, &proof).unwrap());
+            inputs[2] += Fr::from(1u64);
+            assert!(
+                !Groth16::<Bls12_381>::verify_with_processed_vk(&pvk, &inputs, &proof).unwrap()
+            );
+        }
+    }
+}
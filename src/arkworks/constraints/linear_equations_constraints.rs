@@ -1,35 +1,225 @@
+use crate::arkworks::matrix_proof_of_work::alloc::FpVar2DVec;
+use crate::arkworks::matrix_proof_of_work::hasher::{hasher_native, hasher_var};
+use crate::bundle::ProofBundle;
+use crate::proof_system::{Backend, Curve};
+use ark_crypto_primitives::sponge::Absorb;
 use ark_ff::PrimeField;
 use ark_r1cs_std::alloc::AllocVar;
 use ark_r1cs_std::eq::EqGadget;
 use ark_r1cs_std::fields::fp::FpVar;
-// import zero
+use ark_r1cs_std::fields::FieldVar;
+use ark_r1cs_std::R1CSVar;
 
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
 use serde::{Deserialize, Serialize};
 
+// which parts of the system are committed by their Poseidon hash instead of being public inputs,
+// b is hashed as a one row matrix. the hashes aren't blinded, so a small A or b can be found
+// by trying all the candidates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Hide {
+    #[default]
+    None,
+    A,
+    B,
+    Both,
+}
+
+impl Hide {
+    pub fn of(hides_a: bool, hides_b: bool) -> Self {
+        match (hides_a, hides_b) {
+            (false, false) => Hide::None,
+            (true, false) => Hide::A,
+            (false, true) => Hide::B,
+            (true, true) => Hide::Both,
+        }
+    }
+
+    pub fn hides_a(self) -> bool {
+        matches!(self, Hide::A | Hide::Both)
+    }
+
+    pub fn hides_b(self) -> bool {
+        matches!(self, Hide::B | Hide::Both)
+    }
+}
+
 #[derive(Clone)]
 pub struct LinearEquationCircuit<ConstraintF: PrimeField> {
     pub a: Vec<Vec<ConstraintF>>, // Matrix A
     pub b: Vec<ConstraintF>,      // Vector b
     pub x: Vec<ConstraintF>,      // Solution vector x
-    pub len_b: usize,             // Length of the vector b
-    pub len_a: usize,             // Length of the matrix A
+    pub len_b: usize,             // Length of the vector b (rows of A)
+    pub len_a: usize,             // Length of the rows of A (length of x)
+    pub hide: Hide,
+    pub hash_of_a: ConstraintF, // public input when A is hidden
+    pub hash_of_b: ConstraintF, // public input when b is hidden
+}
+
+impl<ConstraintF: PrimeField + Absorb> LinearEquationCircuit<ConstraintF> {
+    pub fn new(
+        a: Vec<Vec<ConstraintF>>,
+        b: Vec<ConstraintF>,
+        x: Vec<ConstraintF>,
+        hide: Hide,
+    ) -> Self {
+        Self {
+            len_b: b.len(),
+            len_a: x.len(),
+            hash_of_a: hasher_native(&a),
+            hash_of_b: hasher_native(&[b.clone()]),
+            a,
+            b,
+            x,
+            hide,
+        }
+    }
+}
+
+impl<ConstraintF: PrimeField> LinearEquationCircuit<ConstraintF> {
+    // the public inputs in the order the verifier expects them: the rows of A interleaved with
+    // b when nothing is hidden, else the A part (hash_of_a or the rows) followed by the b part
+    pub fn public_inputs(&self) -> Vec<ConstraintF> {
+        system_public_inputs(
+            (!self.hide.hides_a()).then_some(&self.a[..]),
+            (!self.hide.hides_b()).then_some(&self.b[..]),
+            self.hash_of_a,
+            self.hash_of_b,
+        )
+    }
+}
+
+// the public inputs of a system where A and b are either given or replaced by their hashes
+pub fn system_public_inputs<F: PrimeField>(
+    a: Option<&[Vec<F>]>,
+    b: Option<&[F]>,
+    hash_of_a: F,
+    hash_of_b: F,
+) -> Vec<F> {
+    let mut public_input = vec![];
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            for (row, b_i) in a.iter().zip(b.iter()) {
+                public_input.extend_from_slice(row);
+                public_input.push(*b_i);
+            }
+        }
+        (a, b) => {
+            match a {
+                Some(a) => public_input.extend(a.iter().flatten()),
+                None => public_input.push(hash_of_a),
+            }
+            match b {
+                Some(b) => public_input.extend_from_slice(b),
+                None => public_input.push(hash_of_b),
+            }
+        }
+    }
+    public_input
 }
-// output struct for the web server
+
+// output struct for the web server: solution is "unique" or "many" (x is then the solution
+// with all the free variables set to 0), x and the hashes are in decimal. x is only returned to
+// the prover, it isn't part of the public input
 #[derive(Serialize)]
 pub struct OutputData {
     pub proof: String,
+    pub key_id: String,
+    pub hide: Hide,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash_a: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash_b: Option<String>,
     pub public_input: Vec<String>,
+    pub solution: String,
+    pub rank: usize,
+    pub x: Vec<String>,
     pub num_constraints: usize,
     pub num_variables: usize,
+    pub setup_time: f64,
     pub proving_time: f64,
     pub verifying_time: f64,
+    pub bundle: ProofBundle,
 }
-// input struct for the web server : the matrix A, the vector b.
+// input struct for the web server : the matrix A, the vector b and what to hide
+// ("none", "a", "b" or "both")
 #[derive(Deserialize)]
 pub struct InputData {
     pub a: Vec<Vec<u64>>,
     pub b: Vec<u64>,
+    #[serde(default)]
+    pub hide: Hide,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+// input struct for the verify endpoint: each of A and b is given either in the clear or by its
+// hash from the prove response, rows and cols are the shape of a hidden A
+#[derive(Deserialize)]
+pub struct VerifyInputData {
+    pub a: Option<Vec<Vec<u64>>>,
+    pub b: Option<Vec<u64>>,
+    pub hash_a: Option<String>,
+    pub hash_b: Option<String>,
+    pub rows: Option<usize>,
+    pub cols: Option<usize>,
+    pub proof: String,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+#[derive(Serialize)]
+pub struct VerifyOutputData {
+    pub verifying_time: f64,
+    pub valid: bool,
+}
+
+// input struct for /linear_equations/prove_infeasible, commit hides A and b behind their
+// Poseidon hashes
+#[derive(Deserialize)]
+pub struct InfeasibleInputData {
+    pub a: Vec<Vec<u64>>,
+    pub b: Vec<u64>,
+    #[serde(default)]
+    pub commit: bool,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+// public_input is A and b, or [hash_a, hash_b] when committed, in decimal
+#[derive(Serialize)]
+pub struct InfeasibleOutputData {
+    pub proof: String,
+    pub key_id: String,
+    pub committed: bool,
+    pub public_input: Vec<String>,
+    pub num_constraints: usize,
+    pub num_variables: usize,
+    pub setup_time: f64,
+    pub proving_time: f64,
+    pub bundle: ProofBundle,
+}
+
+// rows, cols and commit are the ones of the proven system, they pick the keys
+#[derive(Deserialize)]
+pub struct InfeasibleVerifyInputData {
+    pub proof: String,
+    pub rows: usize,
+    pub cols: usize,
+    #[serde(default)]
+    pub commit: bool,
+    pub public_input: Vec<String>,
+    #[serde(default)]
+    pub backend: Backend,
+    #[serde(default)]
+    pub curve: Curve,
 }
 
 // Implement ConstraintSynthesizer trait for LinearEquationCircuit
@@ -40,22 +230,173 @@ impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF>
         self,
         cs: ConstraintSystemRef<ConstraintF>,
     ) -> Result<(), SynthesisError> {
-        // Implement constraints to enforce the properties of the linear equations Ax = b
-
-        // Example:
-        // Ensure that Ax = b
+        // Ensure that Ax = b, x is allocated once and shared by all the rows
+        let x = self
+            .x
+            .iter()
+            .map(|x_j| FpVar::<ConstraintF>::new_witness(cs.clone(), || Ok(*x_j)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (a, b) = match self.hide {
+            Hide::None => alloc_public_system(cs.clone(), &self.a, &self.b)?,
+            hide => {
+                let a = if hide.hides_a() {
+                    alloc_committed(cs.clone(), &self.a, self.hash_of_a)?
+                } else {
+                    self.a
+                        .iter()
+                        .map(|row| alloc_inputs(cs.clone(), row))
+                        .collect::<Result<Vec<_>, _>>()?
+                };
+                let b = if hide.hides_b() {
+                    alloc_committed(cs.clone(), &[self.b.clone()], self.hash_of_b)?.swap_remove(0)
+                } else {
+                    alloc_inputs(cs.clone(), &self.b)?
+                };
+                (a, b)
+            }
+        };
         for i in 0..self.len_b {
-            let mut sum =
-                FpVar::<ConstraintF>::new_witness(cs.clone(), || Ok(ConstraintF::zero()))?;
+            let mut sum = FpVar::<ConstraintF>::zero();
             for j in 0..self.len_a {
-                let a_var = FpVar::<ConstraintF>::new_input(cs.clone(), || Ok(self.a[i][j]))?;
-                let x_var = FpVar::<ConstraintF>::new_witness(cs.clone(), || Ok(self.x[j]))?;
-                let product = a_var * &x_var;
-                sum += &product;
+                sum += &a[i][j] * &x[j];
             }
-            let b_var = FpVar::<ConstraintF>::new_input(cs.clone(), || Ok(self.b[i]))?;
-            sum.enforce_equal(&b_var)?;
+            sum.enforce_equal(&b[i])?;
+        }
+
+        Ok(())
+    }
+}
+
+fn alloc_inputs<F: PrimeField>(
+    cs: ConstraintSystemRef<F>,
+    values: &[F],
+) -> Result<Vec<FpVar<F>>, SynthesisError> {
+    values
+        .iter()
+        .map(|v| FpVar::<F>::new_input(cs.clone(), || Ok(*v)))
+        .collect()
+}
+
+// A and b as public inputs, the rows of A interleaved with b:
+// [a[0][0], a[0][1], b[0], a[1][0], a[1][1], b[1]]
+#[allow(clippy::type_complexity)]
+fn alloc_public_system<F: PrimeField>(
+    cs: ConstraintSystemRef<F>,
+    a: &[Vec<F>],
+    b: &[F],
+) -> Result<(Vec<Vec<FpVar<F>>>, Vec<FpVar<F>>), SynthesisError> {
+    let mut a_vars = vec![];
+    let mut b_vars = vec![];
+    for (row, b_i) in a.iter().zip(b.iter()) {
+        a_vars.push(alloc_inputs(cs.clone(), row)?);
+        b_vars.push(FpVar::<F>::new_input(cs.clone(), || Ok(*b_i))?);
+    }
+    Ok((a_vars, b_vars))
+}
+
+// a matrix as witnesses whose Poseidon hash is a public input
+fn alloc_committed<F: PrimeField>(
+    cs: ConstraintSystemRef<F>,
+    rows: &[Vec<F>],
+    hash: F,
+) -> Result<Vec<Vec<FpVar<F>>>, SynthesisError> {
+    let hash = FpVar::<F>::new_input(cs.clone(), || Ok(hash))?;
+    let matrix = FpVar2DVec(
+        rows.iter()
+            .map(|row| {
+                row.iter()
+                    .map(|v| FpVar::<F>::new_witness(cs.clone(), || Ok(*v)))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+    );
+    hasher_var(cs, &matrix)?[0].enforce_equal(&hash)?;
+    Ok(matrix.0)
+}
+
+// proves that Ax = b has no solution: a certificate y with y^T A = 0 and y^T b != 0 (then
+// y^T A x = 0 != y^T b for every x). the non-equality is proven with the inverse of y^T b.
+// A and b are either public inputs (in the same order as in LinearEquationCircuit) or
+// witnesses that are committed by their Poseidon hashes, b hashed as a one row matrix.
+#[derive(Clone)]
+pub struct InfeasibilityCircuit<ConstraintF: PrimeField> {
+    pub a: Vec<Vec<ConstraintF>>, // Matrix A
+    pub b: Vec<ConstraintF>,      // Vector b
+    pub y: Vec<ConstraintF>,      // witness, the certificate
+    pub committed: bool,
+    pub hash_of_a: ConstraintF, // public input when committed
+    pub hash_of_b: ConstraintF, // public input when committed
+}
+
+impl<ConstraintF: PrimeField + Absorb> InfeasibilityCircuit<ConstraintF> {
+    pub fn new(
+        a: Vec<Vec<ConstraintF>>,
+        b: Vec<ConstraintF>,
+        y: Vec<ConstraintF>,
+        committed: bool,
+    ) -> Self {
+        Self {
+            hash_of_a: hasher_native(&a),
+            hash_of_b: hasher_native(&[b.clone()]),
+            a,
+            b,
+            y,
+            committed,
+        }
+    }
+}
+
+impl<ConstraintF: PrimeField> InfeasibilityCircuit<ConstraintF> {
+    // the public inputs in the order the verifier expects them
+    pub fn public_inputs(&self) -> Vec<ConstraintF> {
+        if self.committed {
+            vec![self.hash_of_a, self.hash_of_b]
+        } else {
+            system_public_inputs(Some(&self.a), Some(&self.b), self.hash_of_a, self.hash_of_b)
+        }
+    }
+}
+
+impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF>
+    for InfeasibilityCircuit<ConstraintF>
+{
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let (a, b) = if self.committed {
+            let a = alloc_committed(cs.clone(), &self.a, self.hash_of_a)?;
+            let b = alloc_committed(cs.clone(), &[self.b.clone()], self.hash_of_b)?.swap_remove(0);
+            (a, b)
+        } else {
+            alloc_public_system(cs.clone(), &self.a, &self.b)?
+        };
+
+        let y = self
+            .y
+            .iter()
+            .map(|y_i| FpVar::<ConstraintF>::new_witness(cs.clone(), || Ok(*y_i)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // y^T A = 0, column by column
+        let num_cols = self.a.first().map_or(0, |row| row.len());
+        for j in 0..num_cols {
+            let mut sum = FpVar::<ConstraintF>::zero();
+            for (y_i, row) in y.iter().zip(a.iter()) {
+                sum += y_i * &row[j];
+            }
+            sum.enforce_equal(&FpVar::zero())?;
+        }
+
+        // y^T b * inverse = 1
+        let mut y_b = FpVar::<ConstraintF>::zero();
+        for (y_i, b_i) in y.iter().zip(b.iter()) {
+            y_b += y_i * b_i;
         }
+        let inverse = FpVar::<ConstraintF>::new_witness(cs.clone(), || {
+            y_b.value()?.inverse().ok_or(SynthesisError::Unsatisfiable)
+        })?;
+        y_b.mul_equals(&inverse, &FpVar::one())?;
 
         Ok(())
     }
@@ -0,0 +1,187 @@
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::fields::FieldVar;
+use ark_r1cs_std::prelude::{AllocVar, EqGadget};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+// f_i = c_1 * f_{i-1} + c_2 * f_{i-2} + .. + c_k * f_{i-k}
+// starting from the initial values f_0, .., f_{k-1}, the result is the term after num_of_steps
+// steps (f_{k-1+num_of_steps}), so coefficients [1, 1] and initial values [a, b] give the same
+// result as the fibonacci circuit. the coefficients, initial values and the result are public
+// inputs: one setup serves every recurrence of the same order and number of steps, and the
+// verification doesn't depend on the number of steps.
+#[derive(Clone)]
+pub struct LinearRecurrenceCircuit<F: PrimeField> {
+    pub coefficients: Vec<F>, // public input, c_1..c_k
+    pub initial: Vec<F>,      // public input, f_0..f_{k-1}
+    pub num_of_steps: usize,
+    pub result: Option<F>, // public input
+}
+
+// the recurrence over the field, outside of the circuit
+pub fn linear_recurrence<F: PrimeField>(
+    coefficients: &[F],
+    initial: &[F],
+    num_of_steps: usize,
+) -> F {
+    let mut window = initial.to_vec();
+    for _ in 0..num_of_steps {
+        let next = coefficients
+            .iter()
+            .zip(window.iter().rev())
+            .fold(F::zero(), |acc, (c, f)| acc + *c * f);
+        window.rotate_left(1);
+        *window.last_mut().unwrap() = next;
+    }
+    *window.last().unwrap()
+}
+
+impl<F: PrimeField> LinearRecurrenceCircuit<F> {
+    pub fn new(coefficients: Vec<F>, initial: Vec<F>, num_of_steps: usize) -> Self {
+        assert!(
+            !coefficients.is_empty(),
+            "the recurrence needs at least one coefficient"
+        );
+        assert_eq!(
+            coefficients.len(),
+            initial.len(),
+            "k coefficients need k initial values"
+        );
+        let result = linear_recurrence(&coefficients, &initial, num_of_steps);
+        Self {
+            coefficients,
+            initial,
+            num_of_steps,
+            result: Some(result),
+        }
+    }
+
+    // the public inputs in the order the verifier expects them: [c_1..c_k, f_0..f_{k-1}, result]
+    pub fn public_inputs(&self) -> Vec<F> {
+        let mut inputs = self.coefficients.clone();
+        inputs.extend_from_slice(&self.initial);
+        inputs.push(self.result.unwrap());
+        inputs
+    }
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for LinearRecurrenceCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let coefficients = self
+            .coefficients
+            .iter()
+            .map(|c| FpVar::<F>::new_input(cs.clone(), || Ok(*c)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut window = self
+            .initial
+            .iter()
+            .map(|f| FpVar::<F>::new_input(cs.clone(), || Ok(*f)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let saved_result = FpVar::<F>::new_input(cs.clone(), || {
+            self.result.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // k constraints per step, the coefficients are variables so every product is one
+        for _i in 0..self.num_of_steps {
+            let mut next = FpVar::<F>::zero();
+            for (c, f) in coefficients.iter().zip(window.iter().rev()) {
+                next += c * f;
+            }
+            window.rotate_left(1);
+            *window.last_mut().unwrap() = next;
+        }
+
+        window.last().unwrap().enforce_equal(&saved_result)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::backend::fibbonaci_handler::fibonacci;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_groth16::{prepare_verifying_key, Groth16};
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_snark::SNARK;
+    use ark_std::rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn field(values: &[u64]) -> Vec<Fr> {
+        values.iter().map(|v| Fr::from(*v)).collect()
+    }
+
+    fn is_satisfied(circuit: LinearRecurrenceCircuit<Fr>) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn generalizes_fibonacci() {
+        for steps in [0, 1, 10, 300] {
+            let circuit = LinearRecurrenceCircuit::new(field(&[1, 1]), field(&[3, 4]), steps);
+            if steps > 0 {
+                assert_eq!(
+                    circuit.result.unwrap(),
+                    fibonacci(steps, Fr::from(3u64), Fr::from(4u64))
+                );
+            }
+            assert!(is_satisfied(circuit));
+        }
+    }
+
+    #[test]
+    fn tribonacci_and_lucas() {
+        // tribonacci 0, 0, 1, 1, 2, 4, 7, 13, 24, 44
+        let tribonacci = LinearRecurrenceCircuit::new(field(&[1, 1, 1]), field(&[0, 0, 1]), 7);
+        assert_eq!(tribonacci.result.unwrap(), Fr::from(44u64));
+        assert!(is_satisfied(tribonacci));
+        // the lucas sequence U(P=3, Q=2) = 2^n - 1: u_n = 3 u_{n-1} - 2 u_{n-2}
+        let lucas =
+            LinearRecurrenceCircuit::new(vec![Fr::from(3u64), -Fr::from(2u64)], field(&[0, 1]), 9);
+        assert_eq!(lucas.result.unwrap(), Fr::from(1023u64));
+        assert!(is_satisfied(lucas));
+    }
+
+    #[test]
+    fn wrong_result_is_not_satisfied() {
+        let mut circuit = LinearRecurrenceCircuit::new(field(&[1, 1, 1]), field(&[0, 0, 1]), 7);
+        circuit.result = Some(Fr::from(45u64));
+        assert!(!is_satisfied(circuit));
+    }
+
+    #[test]
+    fn groth16_one_setup_for_every_recurrence_of_the_same_shape() {
+        let rng = &mut StdRng::seed_from_u64(42);
+        let fibonacci = LinearRecurrenceCircuit::new(field(&[1, 1]), field(&[0, 1]), 20);
+        let (pk, vk) =
+            Groth16::<Bls12_381>::circuit_specific_setup(fibonacci.clone(), rng).unwrap();
+        let pvk = prepare_verifying_key(&vk);
+
+        let proof = Groth16::<Bls12_381>::prove(&pk, fibonacci.clone(), rng).unwrap();
+        assert!(Groth16::<Bls12_381>::verify_with_processed_vk(
+            &pvk,
+            &fibonacci.public_inputs(),
+            &proof
+        )
+        .unwrap());
+
+        // pell numbers p_n = 2 p_{n-1} + p_{n-2} with the same key
+        let pell = LinearRecurrenceCircuit::new(field(&[2, 1]), field(&[0, 1]), 20);
+        let proof = Groth16::<Bls12_381>::prove(&pk, pell.clone(), rng).unwrap();
+        assert!(Groth16::<Bls12_381>::verify_with_processed_vk(
+            &pvk,
+            &pell.public_inputs(),
+            &proof
+        )
+        .unwrap());
+        assert!(!Groth16::<Bls12_381>::verify_with_processed_vk(
+            &pvk,
+            &fibonacci.public_inputs(),
+            &proof
+        )
+        .unwrap());
+    }
+}
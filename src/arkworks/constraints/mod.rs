@@ -5,4 +5,6 @@ pub mod preimage;
 // pub mod miller_rabin_v3;
 // pub mod modpow;
 // pub mod matrix;
+pub mod fast_fibonacci;
 pub mod fibbonaci;
+pub mod linear_recurrence;
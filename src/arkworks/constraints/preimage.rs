@@ -10,6 +10,9 @@ use ark_r1cs_std::boolean::Boolean;
 use ark_r1cs_std::eq::EqGadget;
 use ark_r1cs_std::fields::fp::FpVar;
 
+use ark_r1cs_std::fields::FieldVar;
+use ark_r1cs_std::select::CondSelectGadget;
+use ark_r1cs_std::uint32::UInt32;
 use ark_r1cs_std::uint8::UInt8;
 use ark_r1cs_std::{R1CSVar, ToBitsGadget, ToBytesGadget};
 use ark_relations::ns;
@@ -17,14 +20,17 @@ use ark_relations::r1cs::{
     ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, Namespace, SynthesisError,
 };
 use rand::RngCore;
+use sha2::{Digest, Sha256 as sha256_default};
+use std::marker::PhantomData;
 
+// knowledge of a field element x with SHA-256(x) = h, for the little endian bytes of x
 #[derive(Clone)]
 pub struct PreImage<ConstraintF: PrimeField> {
     pub x: Option<ConstraintF>,  // preimage - private input
     pub hash_x: Option<Vec<u8>>, // digest - public input
 }
 
-fn to_byte_vars<ConstraintF: PrimeField>(
+pub fn to_byte_vars<ConstraintF: PrimeField>(
     cs: impl Into<Namespace<ConstraintF>>,
     data: &[u8],
 ) -> Vec<UInt8<ConstraintF>> {
@@ -43,20 +49,12 @@ impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF> for PreImage<Co
         })?;
         // Create parameter unit:
         let unit_var = UnitVar::default();
-        // Convert x to bytes
+        // Hash the bytes of x itself, so the digest is bound to the witness
         let x_bytes = x_var.to_bytes()?;
-
-        let x_bytes_u8 = x_bytes
-            .iter()
-            .map(|byte| byte.value().unwrap())
-            .collect::<Vec<u8>>();
-        // Compute the hash
         let computed_hash =
             <Sha256Gadget<ConstraintF> as CRHSchemeGadget<Sha256, ConstraintF>>::evaluate(
-                &unit_var,
-                &to_byte_vars(ns!(cs, "input"), &x_bytes_u8),
-            )
-            .unwrap();
+                &unit_var, &x_bytes,
+            )?;
         // Create digest variable from hash_x:
         let hash_x_bytes = self
             .hash_x
@@ -70,6 +68,256 @@ impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF> for PreImage<Co
     }
 }
 
+// SHA-256 of messages of any length up to max_len bytes. the circuit hashes max_blocks(max_len)
+// blocks of the padded message, the length is private and the padding is checked in the
+// circuit: a prefix mask m_i = (i < len) marks the message bytes, everything after them must be
+// 0x80, zeros and the bit length in the last 8 bytes of the last block, and the digest is the
+// state after that block. the digest bytes are the public input.
+#[derive(Clone)]
+pub struct Sha256PreimageCircuit<ConstraintF: PrimeField> {
+    pub max_len: usize,
+    pub message: Vec<u8>, // witness, at most max_len bytes
+    pub digest: Vec<u8>,  // public input
+    _field: PhantomData<ConstraintF>,
+}
+
+impl<ConstraintF: PrimeField> Sha256PreimageCircuit<ConstraintF> {
+    pub fn new(message: Vec<u8>, max_len: usize) -> Self {
+        assert!(message.len() <= max_len);
+        Self {
+            max_len,
+            digest: sha256_default::digest(&message).to_vec(),
+            message,
+            _field: PhantomData,
+        }
+    }
+
+    // the digest as the circuit allocates it, 256 bits in little endian order per byte
+    pub fn public_inputs(digest: &[u8]) -> Vec<ConstraintF> {
+        digest
+            .iter()
+            .flat_map(|byte| (0..8).map(move |i| ConstraintF::from((byte >> i) & 1)))
+            .collect()
+    }
+}
+
+// the number of blocks of a padded message of max_len bytes: the message, 0x80 and 8 bytes
+pub fn max_blocks(max_len: usize) -> usize {
+    (max_len + 9).div_ceil(64)
+}
+
+// the message with the SHA-256 padding, zero filled to max_blocks(max_len) blocks
+pub fn sha256_pad(message: &[u8], max_len: usize) -> Vec<u8> {
+    let mut padded = message.to_vec();
+    padded.push(0x80);
+    let last_block_end = (message.len() + 9).div_ceil(64) * 64;
+    padded.resize(last_block_end - 8, 0);
+    padded.extend_from_slice(&(message.len() as u64 * 8).to_be_bytes());
+    padded.resize(max_blocks(max_len) * 64, 0);
+    padded
+}
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const SHA256_H: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+fn u32_shr<F: PrimeField>(x: &UInt32<F>, by: usize) -> UInt32<F> {
+    let mut bits = x.to_bits_le()[by..].to_vec();
+    bits.resize(32, Boolean::constant(false));
+    UInt32::from_bits_le(&bits)
+}
+
+fn u32_not<F: PrimeField>(x: &UInt32<F>) -> UInt32<F> {
+    UInt32::from_bits_le(&x.to_bits_le().iter().map(Boolean::not).collect::<Vec<_>>())
+}
+
+fn u32_and<F: PrimeField>(x: &UInt32<F>, y: &UInt32<F>) -> Result<UInt32<F>, SynthesisError> {
+    let bits = x
+        .to_bits_le()
+        .iter()
+        .zip(y.to_bits_le().iter())
+        .map(|(a, b)| a.and(b))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(UInt32::from_bits_le(&bits))
+}
+
+fn u32_from_bytes_be<F: PrimeField>(bytes: &[UInt8<F>]) -> Result<UInt32<F>, SynthesisError> {
+    let mut bits = vec![];
+    for byte in bytes.iter().rev() {
+        bits.extend(byte.to_bits_le()?);
+    }
+    Ok(UInt32::from_bits_le(&bits))
+}
+
+// the SHA-256 compression function on one 64 byte block. Sha256Gadget keeps it private and
+// only exposes the digest of the whole message, we need the state after every block.
+fn sha256_compress<F: PrimeField>(
+    state: &[UInt32<F>],
+    block: &[UInt8<F>],
+) -> Result<Vec<UInt32<F>>, SynthesisError> {
+    let mut w = block
+        .chunks(4)
+        .map(u32_from_bytes_be)
+        .collect::<Result<Vec<_>, _>>()?;
+    for i in 16..64 {
+        let s0 = w[i - 15]
+            .rotr(7)
+            .xor(&w[i - 15].rotr(18))?
+            .xor(&u32_shr(&w[i - 15], 3))?;
+        let s1 = w[i - 2]
+            .rotr(17)
+            .xor(&w[i - 2].rotr(19))?
+            .xor(&u32_shr(&w[i - 2], 10))?;
+        w.push(UInt32::addmany(&[
+            w[i - 16].clone(),
+            s0,
+            w[i - 7].clone(),
+            s1,
+        ])?);
+    }
+
+    let mut h = state.to_vec();
+    for i in 0..64 {
+        let ch = u32_and(&h[4], &h[5])?.xor(&u32_and(&u32_not(&h[4]), &h[6])?)?;
+        let maj = u32_and(&h[0], &h[1])?
+            .xor(&u32_and(&h[0], &h[2])?)?
+            .xor(&u32_and(&h[1], &h[2])?)?;
+        let s0 = h[0].rotr(2).xor(&h[0].rotr(13))?.xor(&h[0].rotr(22))?;
+        let s1 = h[4].rotr(6).xor(&h[4].rotr(11))?.xor(&h[4].rotr(25))?;
+        let t0 = UInt32::addmany(&[
+            h[7].clone(),
+            s1,
+            ch,
+            UInt32::constant(SHA256_K[i]),
+            w[i].clone(),
+        ])?;
+        let t1 = UInt32::addmany(&[s0, maj])?;
+        h.rotate_right(1);
+        h[4] = UInt32::addmany(&[h[4].clone(), t0.clone()])?;
+        h[0] = UInt32::addmany(&[t0, t1])?;
+    }
+    state
+        .iter()
+        .zip(h.iter())
+        .map(|(s, h_i)| UInt32::addmany(&[s.clone(), h_i.clone()]))
+        .collect()
+}
+
+impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF>
+    for Sha256PreimageCircuit<ConstraintF>
+{
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let num_bytes = max_blocks(self.max_len) * 64;
+        let len = self.message.len();
+        let padded = sha256_pad(&self.message, self.max_len);
+
+        let digest = DigestVar::new_input(ns!(cs, "digest"), || Ok(self.digest.clone()))?;
+        let bytes = UInt8::new_witness_vec(ns!(cs, "padded"), &padded)?;
+
+        // m_i = (i < len): a decreasing 0/1 sequence whose sum is len
+        let len_bits = (0..usize::BITS - self.max_len.leading_zeros())
+            .map(|i| Boolean::new_witness(cs.clone(), || Ok((len >> i) & 1 == 1)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let len_var = Boolean::le_bits_to_fp_var(&len_bits)?;
+        let mask = (0..num_bytes)
+            .map(|i| Boolean::new_witness(cs.clone(), || Ok(i < len)))
+            .collect::<Result<Vec<_>, _>>()?;
+        for i in 1..num_bytes {
+            mask[i].enforce_equal(&mask[i].and(&mask[i - 1])?)?;
+        }
+        let mut sum = FpVar::<ConstraintF>::zero();
+        for m in &mask {
+            sum += FpVar::from(m.clone());
+        }
+        sum.enforce_equal(&len_var)?;
+        // len <= max_len
+        if self.max_len < num_bytes {
+            mask[self.max_len].enforce_equal(&Boolean::FALSE)?;
+        }
+
+        // the last block b has 64b <= len + 8 < 64(b + 1)
+        let last_block = (0..num_bytes / 64)
+            .map(|b| {
+                let ends_here = mask[64 * b + 55].not();
+                if b == 0 {
+                    Ok(ends_here)
+                } else {
+                    ends_here.and(&mask[64 * b - 9])
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // the bit length as 8 big endian bytes
+        let mut bitlen_bits = vec![Boolean::FALSE; 3];
+        bitlen_bits.extend(len_bits.iter().cloned());
+        bitlen_bits.resize(64, Boolean::FALSE);
+        let bitlen_bytes = bitlen_bits
+            .chunks(8)
+            .rev()
+            .map(Boolean::le_bits_to_fp_var)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // after the message: 0x80 at len, the bit length at the end of the last block, else 0
+        for (i, byte) in bytes.iter().enumerate() {
+            let first_pad = if i == 0 {
+                mask[0].not()
+            } else {
+                mask[i - 1].and(&mask[i].not())?
+            };
+            let mut expected = FpVar::from(first_pad) * ConstraintF::from(0x80u64);
+            if i % 64 >= 56 {
+                expected += FpVar::from(last_block[i / 64].clone()) * &bitlen_bytes[i % 64 - 56];
+            }
+            let value = Boolean::le_bits_to_fp_var(&byte.to_bits_le()?)?;
+            let is_padding = FpVar::from(mask[i].not());
+            (value - expected).mul_equals(&is_padding, &FpVar::zero())?;
+        }
+
+        // the digest is the state after the last block
+        let mut state = SHA256_H
+            .iter()
+            .map(|h| UInt32::constant(*h))
+            .collect::<Vec<_>>();
+        let mut computed: Option<DigestVar<ConstraintF>> = None;
+        for (block, is_last) in bytes.chunks(64).zip(last_block.iter()) {
+            state = sha256_compress(&state, block)?;
+            let block_digest = DigestVar(
+                state
+                    .iter()
+                    .flat_map(|word| {
+                        word.to_bits_le()
+                            .chunks(8)
+                            .rev()
+                            .map(UInt8::from_bits_le)
+                            .collect::<Vec<_>>()
+                    })
+                    .collect(),
+            );
+            computed = Some(match computed {
+                None => block_digest,
+                Some(previous) => {
+                    DigestVar::conditionally_select(is_last, &block_digest, &previous)?
+                }
+            });
+        }
+        computed.unwrap().enforce_equal(&digest)
+    }
+}
+
 fn compute_using_evaluate<ConstraintF: PrimeField>(
     cs: ConstraintSystemRef<ConstraintF>,
     x: ConstraintF,
@@ -249,4 +497,55 @@ mod test {
         // assert equal to false
         assert!(!is_satisfied);
     }
+
+    fn sha256_is_satisfied(circuit: Sha256PreimageCircuit<Fr>) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn sha256_padding_matches_the_gadget() {
+        for len in [0, 1, 55, 56, 63, 64, 65, 119, 120] {
+            let message = vec![0x61u8; len];
+            let padded = sha256_pad(&message, 128);
+            assert_eq!(padded.len(), max_blocks(128) * 64);
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let gadget = Sha256Gadget::digest(&to_byte_vars(cs.clone(), &message)).unwrap();
+            let circuit = Sha256PreimageCircuit::<Fr>::new(message, 128);
+            assert_eq!(gadget.value().unwrap().to_vec(), circuit.digest);
+            assert!(sha256_is_satisfied(circuit), "length {}", len);
+        }
+    }
+
+    #[test]
+    fn sha256_wrong_preimage() {
+        let mut circuit = Sha256PreimageCircuit::<Fr>::new(b"password".to_vec(), 64);
+        circuit.digest = sha256_default::digest(b"passwore").to_vec();
+        assert!(!sha256_is_satisfied(circuit));
+        // a prefix of the message has another digest
+        let mut circuit = Sha256PreimageCircuit::<Fr>::new(b"password".to_vec(), 64);
+        circuit.message.pop();
+        assert!(!sha256_is_satisfied(circuit));
+    }
+
+    #[test]
+    fn sha256_preimage_groth16() {
+        use ark_bls12_381::Bls12_381;
+        use ark_groth16::Groth16;
+        use ark_snark::SNARK;
+        use ark_std::rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let mut rng = StdRng::seed_from_u64(0u64);
+        let setup = Sha256PreimageCircuit::<Fr>::new(vec![], 70);
+        let (pk, vk) = Groth16::<Bls12_381>::circuit_specific_setup(setup, &mut rng).unwrap();
+        // the same keys prove messages of any length up to max_len
+        for message in [b"pw".to_vec(), vec![7u8; 70]] {
+            let circuit = Sha256PreimageCircuit::<Fr>::new(message, 70);
+            let public_input = Sha256PreimageCircuit::<Fr>::public_inputs(&circuit.digest);
+            let proof = Groth16::<Bls12_381>::prove(&pk, circuit, &mut rng).unwrap();
+            assert!(Groth16::<Bls12_381>::verify(&vk, &public_input, &proof).unwrap());
+        }
+    }
 }
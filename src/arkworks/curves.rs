@@ -0,0 +1,102 @@
+use crate::arkworks::key_store::KeyStore;
+use crate::arkworks::proof_system::{Groth16, MarlinBackend};
+use crate::error::ZkError;
+use crate::proof_system::{Curve, ProofSystem, Prover};
+use ark_bls12_377::Bls12_377;
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ec::pairing::Pairing;
+use ark_relations::r1cs::ConstraintSynthesizer;
+
+// the pairing engines of Curve. the circuits are written over any prime field, the proving
+// functions of backend::proving are generic over the engine and on_curve! picks the engine of
+// a request. the scalar fields absorb into the poseidon sponges of the hashing circuits
+pub trait SnarkCurve: Pairing<ScalarField: Absorb> {
+    const CURVE: Curve;
+    // the directory of the groth16 keys in the key store
+    const GROTH16: &'static str;
+
+    // marlin with the universal srs of the key store, only provers need it
+    fn marlin<C: ConstraintSynthesizer<Self::ScalarField>>(
+        keys: &KeyStore,
+    ) -> Result<impl Prover<C, PublicInput = [Self::ScalarField]>, ZkError>;
+
+    fn marlin_verifier() -> Result<impl ProofSystem<PublicInput = [Self::ScalarField]>, ZkError>;
+}
+
+// the polynomial commitments of marlin are over bls12_381 only. groth16 only names a type for
+// the other curves, they never return it
+fn marlin_unsupported(curve: Curve) -> ZkError {
+    ZkError::invalid(
+        "curve",
+        format!("marlin is only over bls12_381, use groth16 on {}", curve),
+    )
+}
+
+impl SnarkCurve for Bls12_381 {
+    const CURVE: Curve = Curve::Bls12_381;
+    const GROTH16: &'static str = "groth16";
+
+    fn marlin<C: ConstraintSynthesizer<Self::ScalarField>>(
+        keys: &KeyStore,
+    ) -> Result<impl Prover<C, PublicInput = [Self::ScalarField]>, ZkError> {
+        Ok(keys.marlin()?)
+    }
+
+    fn marlin_verifier() -> Result<impl ProofSystem<PublicInput = [Self::ScalarField]>, ZkError> {
+        Ok(MarlinBackend::default())
+    }
+}
+
+impl SnarkCurve for Bn254 {
+    const CURVE: Curve = Curve::Bn254;
+    const GROTH16: &'static str = "groth16_bn254";
+
+    fn marlin<C: ConstraintSynthesizer<Self::ScalarField>>(
+        _keys: &KeyStore,
+    ) -> Result<impl Prover<C, PublicInput = [Self::ScalarField]>, ZkError> {
+        Err::<Groth16<Self>, _>(marlin_unsupported(Self::CURVE))
+    }
+
+    fn marlin_verifier() -> Result<impl ProofSystem<PublicInput = [Self::ScalarField]>, ZkError> {
+        Err::<Groth16<Self>, _>(marlin_unsupported(Self::CURVE))
+    }
+}
+
+impl SnarkCurve for Bls12_377 {
+    const CURVE: Curve = Curve::Bls12_377;
+    const GROTH16: &'static str = "groth16_bls12_377";
+
+    fn marlin<C: ConstraintSynthesizer<Self::ScalarField>>(
+        _keys: &KeyStore,
+    ) -> Result<impl Prover<C, PublicInput = [Self::ScalarField]>, ZkError> {
+        Err::<Groth16<Self>, _>(marlin_unsupported(Self::CURVE))
+    }
+
+    fn marlin_verifier() -> Result<impl ProofSystem<PublicInput = [Self::ScalarField]>, ZkError> {
+        Err::<Groth16<Self>, _>(marlin_unsupported(Self::CURVE))
+    }
+}
+
+// the body with $engine as the engine of the curve, for the handlers that take a curve:
+// on_curve!(data.curve, E => fibonacci_proof_on::<E>(keys, data))
+macro_rules! on_curve {
+    ($curve:expr, $engine:ident => $body:expr) => {
+        match $curve {
+            $crate::proof_system::Curve::Bls12_381 => {
+                type $engine = ark_bls12_381::Bls12_381;
+                $body
+            }
+            $crate::proof_system::Curve::Bn254 => {
+                type $engine = ark_bn254::Bn254;
+                $body
+            }
+            $crate::proof_system::Curve::Bls12_377 => {
+                type $engine = ark_bls12_377::Bls12_377;
+                $body
+            }
+        }
+    };
+}
+pub(crate) use on_curve;
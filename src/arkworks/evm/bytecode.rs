@@ -0,0 +1,271 @@
+use crate::arkworks::evm::calldata::{
+    base_modulus, g1_words, g2_words, scalar_modulus, verify_proof_selector, Word,
+};
+use ark_bn254::Bn254;
+use ark_groth16::VerifyingKey;
+
+// the verifier contract as EVM bytecode, assembled by hand. it is the contract of
+// solidity::verifier_contract step for step: the same checks of the inputs, the same
+// precompile calls and false for a proof that doesn't verify. nobody audits it, so it is only
+// a reference for the tests of the solidity verifier and isn't handed out.
+
+const SUB: u8 = 0x03;
+const MOD: u8 = 0x06;
+const LT: u8 = 0x10;
+const EQ: u8 = 0x14;
+const ISZERO: u8 = 0x15;
+const AND: u8 = 0x16;
+const SHR: u8 = 0x1c;
+const CALLDATALOAD: u8 = 0x35;
+const CALLDATASIZE: u8 = 0x36;
+const CALLDATACOPY: u8 = 0x37;
+const CODECOPY: u8 = 0x39;
+const MLOAD: u8 = 0x51;
+const MSTORE: u8 = 0x52;
+const JUMPI: u8 = 0x57;
+const GAS: u8 = 0x5a;
+const JUMPDEST: u8 = 0x5b;
+const PUSH1: u8 = 0x60;
+const PUSH2: u8 = 0x61;
+const DUP1: u8 = 0x80;
+const RETURN: u8 = 0xf3;
+const STATICCALL: u8 = 0xfa;
+const REVERT: u8 = 0xfd;
+
+// the precompiles of EIP-196 and EIP-197
+const EC_ADD: usize = 0x06;
+const EC_MUL: usize = 0x07;
+const EC_PAIRING: usize = 0x08;
+
+// the calldata of verifyProof: the selector, a, b, c and the inputs
+const A: usize = 0x04;
+const B: usize = 0x44;
+const C: usize = 0xc4;
+const INPUTS: usize = 0x104;
+
+// the memory: vk_x at 0x00, the point and the scalar of a multiplication at 0x40 and the
+// four pairs of the pairing check at 0x100
+const VK_X: usize = 0x00;
+const SCRATCH: usize = 0x40;
+const PAIRS: usize = 0x100;
+
+#[derive(Clone, Copy)]
+struct Label(usize);
+
+#[derive(Default)]
+struct Assembler {
+    code: Vec<u8>,
+    labels: Vec<Option<usize>>,
+    // the positions of the PUSH2 operands of the jumps
+    jumps: Vec<(usize, Label)>,
+}
+
+impl Assembler {
+    fn op(&mut self, op: u8) {
+        self.code.push(op);
+    }
+
+    // the shortest PUSH of a big endian value
+    fn push(&mut self, value: &[u8]) {
+        let start = value
+            .iter()
+            .position(|byte| *byte != 0)
+            .unwrap_or(value.len() - 1);
+        let value = &value[start..];
+        self.code.push(PUSH1 + value.len() as u8 - 1);
+        self.code.extend_from_slice(value);
+    }
+
+    fn push_usize(&mut self, value: usize) {
+        self.push(&value.to_be_bytes());
+    }
+
+    fn mstore(&mut self, offset: usize, word: &Word) {
+        self.push(word);
+        self.push_usize(offset);
+        self.op(MSTORE);
+    }
+
+    fn calldataload(&mut self, offset: usize) {
+        self.push_usize(offset);
+        self.op(CALLDATALOAD);
+    }
+
+    fn calldatacopy(&mut self, to: usize, offset: usize, len: usize) {
+        self.push_usize(len);
+        self.push_usize(offset);
+        self.push_usize(to);
+        self.op(CALLDATACOPY);
+    }
+
+    // leaves the success flag of the call on the stack
+    fn staticcall(&mut self, address: usize, input: usize, input_len: usize, output: usize) {
+        for value in [0x40, output, input_len, input, address] {
+            self.push_usize(value);
+        }
+        self.op(GAS);
+        self.op(STATICCALL);
+    }
+
+    // returns the word at memory 0x00
+    fn return_word(&mut self) {
+        self.push_usize(0x20);
+        self.push_usize(0x00);
+        self.op(RETURN);
+    }
+
+    fn label(&mut self) -> Label {
+        self.labels.push(None);
+        Label(self.labels.len() - 1)
+    }
+
+    fn mark(&mut self, label: Label) {
+        self.labels[label.0] = Some(self.code.len());
+        self.op(JUMPDEST);
+    }
+
+    // jumps when the top of the stack isn't zero
+    fn jumpi(&mut self, label: Label) {
+        self.op(PUSH2);
+        self.jumps.push((self.code.len(), label));
+        self.code.extend_from_slice(&[0, 0]);
+        self.op(JUMPI);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        for (position, label) in self.jumps {
+            let target = self.labels[label.0].expect("every label is marked") as u16;
+            self.code[position..position + 2].copy_from_slice(&target.to_be_bytes());
+        }
+        self.code
+    }
+}
+
+// the code of the deployed contract
+pub fn verifier_runtime_code(vk: &VerifyingKey<Bn254>) -> Vec<u8> {
+    let num_inputs = vk.gamma_abc_g1.len() - 1;
+    let (q, r) = (base_modulus(), scalar_modulus());
+    let mut asm = Assembler::default();
+    let (fail, revert) = (asm.label(), asm.label());
+
+    // only verifyProof, with exactly its arguments
+    asm.calldataload(0);
+    asm.push_usize(0xe0);
+    asm.op(SHR);
+    asm.push(&verify_proof_selector(num_inputs));
+    asm.op(EQ);
+    asm.op(ISZERO);
+    asm.jumpi(revert);
+    asm.op(CALLDATASIZE);
+    asm.push_usize(INPUTS + 32 * num_inputs);
+    asm.op(EQ);
+    asm.op(ISZERO);
+    asm.jumpi(revert);
+
+    // vk_x = IC[0] + input[0] * IC[1] + ...
+    let [x, y] = g1_words(&vk.gamma_abc_g1[0]);
+    asm.mstore(VK_X, &x);
+    asm.mstore(VK_X + 0x20, &y);
+    for (i, point) in vk.gamma_abc_g1[1..].iter().enumerate() {
+        let input = INPUTS + 32 * i;
+        // input < r
+        asm.push(&r);
+        asm.calldataload(input);
+        asm.op(LT);
+        asm.op(ISZERO);
+        asm.jumpi(fail);
+        let [x, y] = g1_words(point);
+        asm.mstore(SCRATCH, &x);
+        asm.mstore(SCRATCH + 0x20, &y);
+        asm.calldataload(input);
+        asm.push_usize(SCRATCH + 0x40);
+        asm.op(MSTORE);
+        asm.staticcall(EC_MUL, SCRATCH, 0x60, SCRATCH);
+        asm.op(ISZERO);
+        asm.jumpi(fail);
+        asm.staticcall(EC_ADD, VK_X, 0x80, VK_X);
+        asm.op(ISZERO);
+        asm.jumpi(fail);
+    }
+
+    // e(-A, B) * e(alpha, beta) * e(vk_x, gamma) * e(C, delta) == 1
+    asm.calldataload(A);
+    asm.push_usize(PAIRS);
+    asm.op(MSTORE);
+    // A.y < q, and -A.y = (q - A.y) % q
+    asm.push(&q);
+    asm.calldataload(A + 0x20);
+    asm.op(LT);
+    asm.op(ISZERO);
+    asm.jumpi(fail);
+    asm.push(&q);
+    asm.calldataload(A + 0x20);
+    asm.push(&q);
+    asm.op(SUB);
+    asm.op(MOD);
+    asm.push_usize(PAIRS + 0x20);
+    asm.op(MSTORE);
+    asm.calldatacopy(PAIRS + 0x40, B, 0x80);
+    let alpha_beta = g1_words(&vk.alpha_g1)
+        .into_iter()
+        .chain(g2_words(&vk.beta_g2));
+    for (i, word) in alpha_beta.enumerate() {
+        asm.mstore(PAIRS + 0xc0 + 32 * i, &word);
+    }
+    for i in 0..2 {
+        asm.push_usize(VK_X + 32 * i);
+        asm.op(MLOAD);
+        asm.push_usize(PAIRS + 0x180 + 32 * i);
+        asm.op(MSTORE);
+    }
+    for (i, word) in g2_words(&vk.gamma_g2).iter().enumerate() {
+        asm.mstore(PAIRS + 0x1c0 + 32 * i, word);
+    }
+    asm.calldatacopy(PAIRS + 0x240, C, 0x40);
+    for (i, word) in g2_words(&vk.delta_g2).iter().enumerate() {
+        asm.mstore(PAIRS + 0x280 + 32 * i, word);
+    }
+    asm.staticcall(EC_PAIRING, PAIRS, 0x300, 0x00);
+    // a failed call leaves vk_x in the output, the and makes it false
+    asm.push_usize(0x00);
+    asm.op(MLOAD);
+    asm.op(AND);
+    asm.push_usize(0x00);
+    asm.op(MSTORE);
+    asm.return_word();
+
+    asm.mark(fail);
+    asm.push_usize(0);
+    asm.push_usize(0x00);
+    asm.op(MSTORE);
+    asm.return_word();
+
+    asm.mark(revert);
+    asm.push_usize(0);
+    asm.push_usize(0);
+    asm.op(REVERT);
+    asm.finish()
+}
+
+// the code of a contract creation, it returns the runtime code that follows it
+pub fn verifier_deployment_code(vk: &VerifyingKey<Bn254>) -> Vec<u8> {
+    let runtime = verifier_runtime_code(vk);
+    let len = (runtime.len() as u16).to_be_bytes();
+    let mut code = vec![
+        PUSH2, len[0], len[1], DUP1, PUSH2, 0, 13, PUSH1, 0, CODECOPY,
+    ];
+    code.extend_from_slice(&[PUSH1, 0, RETURN]);
+    code.extend(runtime);
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::evm::local_evm::check_verifier;
+
+    #[test]
+    fn test_verifier_in_evm() {
+        check_verifier(verifier_deployment_code);
+    }
+}
@@ -0,0 +1,131 @@
+use ark_bn254::{Bn254, Fq, Fr, G1Affine, G2Affine};
+use ark_ec::AffineRepr;
+use ark_ff::{BigInteger, PrimeField};
+use ark_groth16::Proof;
+use num_bigint::BigUint;
+use sha3::{Digest, Keccak256};
+
+// a big endian 256 bit word of the EVM
+pub type Word = [u8; 32];
+
+pub fn field_word<F: PrimeField>(value: F) -> Word {
+    let bytes = value.into_bigint().to_bytes_be();
+    let mut word = [0u8; 32];
+    word[32 - bytes.len()..].copy_from_slice(&bytes);
+    word
+}
+
+pub fn word_to_decimal(word: &Word) -> String {
+    BigUint::from_bytes_be(word).to_string()
+}
+
+// the moduli are one more than -1, the largest element of the field
+fn add_one(mut word: Word) -> Word {
+    for byte in word.iter_mut().rev() {
+        let (sum, carry) = byte.overflowing_add(1);
+        *byte = sum;
+        if !carry {
+            break;
+        }
+    }
+    word
+}
+
+// the modulus of the scalar field, the public inputs have to be below it
+pub fn scalar_modulus() -> Word {
+    add_one(field_word(-Fr::from(1u64)))
+}
+
+// the modulus of the base field of the coordinates
+pub fn base_modulus() -> Word {
+    add_one(field_word(-Fq::from(1u64)))
+}
+
+// x and y, the point at infinity is (0, 0)
+pub fn g1_words(point: &G1Affine) -> [Word; 2] {
+    match point.xy() {
+        Some((x, y)) => [field_word(*x), field_word(*y)],
+        None => [[0u8; 32]; 2],
+    }
+}
+
+// the precompile takes the coefficients of x = x.c1 * i + x.c0 (and y) in the order
+// x.c1, x.c0, y.c1, y.c0
+pub fn g2_words(point: &G2Affine) -> [Word; 4] {
+    match point.xy() {
+        Some((x, y)) => [
+            field_word(x.c1),
+            field_word(x.c0),
+            field_word(y.c1),
+            field_word(y.c0),
+        ],
+        None => [[0u8; 32]; 4],
+    }
+}
+
+// the signature of the verifier, the public inputs are a fixed size array
+pub fn verify_proof_signature(num_inputs: usize) -> String {
+    format!(
+        "verifyProof(uint256[2],uint256[2][2],uint256[2],uint256[{}])",
+        num_inputs
+    )
+}
+
+pub fn verify_proof_selector(num_inputs: usize) -> [u8; 4] {
+    let hash = Keccak256::digest(verify_proof_signature(num_inputs).as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+// the ABI encoding of the arguments of verifyProof without the selector. every argument is
+// static, so they are the words of a, b, c and the inputs one after the other
+pub fn verify_proof_arguments(proof: &Proof<Bn254>, public_inputs: &[Fr]) -> Vec<u8> {
+    let words = g1_words(&proof.a)
+        .into_iter()
+        .chain(g2_words(&proof.b))
+        .chain(g1_words(&proof.c))
+        .chain(public_inputs.iter().map(|input| field_word(*input)));
+    words.flatten().collect()
+}
+
+pub fn verify_proof_calldata(proof: &Proof<Bn254>, public_inputs: &[Fr]) -> Vec<u8> {
+    let mut calldata = verify_proof_selector(public_inputs.len()).to_vec();
+    calldata.extend(verify_proof_arguments(proof, public_inputs));
+    calldata
+}
+
+// 0x prefixed, as wallets and cast take calldata
+pub fn to_hex(bytes: &[u8]) -> String {
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("0x{}", hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::Fq;
+    use ark_ec::CurveGroup;
+    use ark_ff::Zero;
+
+    #[test]
+    fn test_calldata_layout() {
+        // the selector of the usual verifier with one public input
+        assert_eq!(verify_proof_selector(1), [0x43, 0x75, 0x3b, 0x4d]);
+        let g1 = G1Affine::generator();
+        assert_eq!(g1_words(&g1)[0], field_word(Fq::from(1u64)));
+        assert_eq!(g1_words(&g1)[1], field_word(Fq::from(2u64)));
+        assert_eq!(g1_words(&G1Affine::zero()), [[0u8; 32]; 2]);
+        assert_eq!(field_word(-Fr::from(1u64))[31], 0);
+
+        let proof = Proof::<Bn254> {
+            a: g1,
+            b: G2Affine::generator(),
+            c: (g1 + g1).into_affine(),
+        };
+        let calldata = verify_proof_calldata(&proof, &[Fr::from(7u64), Fr::zero()]);
+        assert_eq!(calldata.len(), 4 + 32 * 10);
+        assert_eq!(calldata[4 + 32 * 8 + 31], 7);
+        let x = G2Affine::generator().x;
+        assert_eq!(calldata[4 + 64..4 + 96], field_word(x.c1));
+        assert_eq!(calldata[4 + 96..4 + 128], field_word(x.c0));
+    }
+}
@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.19;
+
+// a verifier of Groth16 proofs over BN254 for a single verifying key
+contract Groth16Verifier {
+    uint256 constant Q = 21888242871839275222246405745257275088696311157297823662689037894645226208583;
+    uint256 constant R = 21888242871839275222246405745257275088548364400416034343698204186575808495617;
+
+    uint256 constant ALPHA_X = 12482315145605634215648334706638549483275507710751263711046035768487804433381;
+    uint256 constant ALPHA_Y = 232585616273116244141968972397055400365968707694484139778424435636102707697;
+    uint256 constant BETA_X1 = 10050690556184755524369731779316315771339825162592538108842741812853889104290;
+    uint256 constant BETA_X0 = 20506536018984934809364196265542121829923090628662338845191224312266767951134;
+    uint256 constant BETA_Y1 = 2245699774199344401400093923406931940683489803720702752129705800095012052383;
+    uint256 constant BETA_Y0 = 13545582153576464774395402627100348460763153963959447750683600977224007444051;
+    uint256 constant GAMMA_X1 = 15606854251108311624932492540729942072377571241162164974029808788155296745147;
+    uint256 constant GAMMA_X0 = 12037645698608300624859857232711896945628064680938530968830528353051387749399;
+    uint256 constant GAMMA_Y1 = 10688328431330588095108391394620617003386696564554555570650218145941401356965;
+    uint256 constant GAMMA_Y0 = 12195029540569318793893576638245528390757467619377681266812242894838876342754;
+    uint256 constant DELTA_X1 = 13742385414561786092052320351024841237159696142927471384985407793986173414974;
+    uint256 constant DELTA_X0 = 21572750832331394747762895622244011235662410533561193673486770426374412277063;
+    uint256 constant DELTA_Y1 = 2630285547725763610525614752605783915666329478961957201327904340043410387211;
+    uint256 constant DELTA_Y0 = 9635192938797755277021317769355417597822986759504392285121582485653220331047;
+    uint256 constant IC0_X = 17949823290689115564388623883260564371793501414178009106123601015658372623535;
+    uint256 constant IC0_Y = 14489541085826127163892750386653182149202180992418265430184464937853711994947;
+    uint256 constant IC1_X = 18317567268188039061578433639303958067032822592588739252839238036364258871972;
+    uint256 constant IC1_Y = 4091472905889303291923016805090931204173842377580131290338190828228328427841;
+    uint256 constant IC2_X = 20946541541816711340695279735753944185442476492074820012774676004220293411190;
+    uint256 constant IC2_Y = 21046002915214893040076981454122391424768062718280922513217059968799798646079;
+    uint256 constant IC3_X = 10801444398448180076210550239020295289925897046532728256479477430082326505744;
+    uint256 constant IC3_Y = 15565191095346175261785296202472155275836924607328961360420537243800746662487;
+
+    // e(-a, b) * e(alpha, beta) * e(vk_x, gamma) * e(c, delta) == 1 where
+    // vk_x = IC0 + input[0] * IC1 + ...
+    function verifyProof(
+        uint256[2] calldata a,
+        uint256[2][2] calldata b,
+        uint256[2] calldata c,
+        uint256[3] calldata input
+    ) external view returns (bool) {
+        uint256[2] memory vk = [IC0_X, IC0_Y];
+        bool success;
+        if (input[0] >= R) return false;
+        (vk, success) = mulAdd(vk, IC1_X, IC1_Y, input[0]);
+        if (!success) return false;
+        if (input[1] >= R) return false;
+        (vk, success) = mulAdd(vk, IC2_X, IC2_Y, input[1]);
+        if (!success) return false;
+        if (input[2] >= R) return false;
+        (vk, success) = mulAdd(vk, IC3_X, IC3_Y, input[2]);
+        if (!success) return false;
+
+        if (a[1] >= Q) return false;
+        uint256[24] memory pairs;
+        pairs[0] = a[0];
+        pairs[1] = (Q - a[1]) % Q;
+        pairs[2] = b[0][0];
+        pairs[3] = b[0][1];
+        pairs[4] = b[1][0];
+        pairs[5] = b[1][1];
+        pairs[6] = ALPHA_X;
+        pairs[7] = ALPHA_Y;
+        pairs[8] = BETA_X1;
+        pairs[9] = BETA_X0;
+        pairs[10] = BETA_Y1;
+        pairs[11] = BETA_Y0;
+        pairs[12] = vk[0];
+        pairs[13] = vk[1];
+        pairs[14] = GAMMA_X1;
+        pairs[15] = GAMMA_X0;
+        pairs[16] = GAMMA_Y1;
+        pairs[17] = GAMMA_Y0;
+        pairs[18] = c[0];
+        pairs[19] = c[1];
+        pairs[20] = DELTA_X1;
+        pairs[21] = DELTA_X0;
+        pairs[22] = DELTA_Y1;
+        pairs[23] = DELTA_Y0;
+        uint256[1] memory result;
+        assembly {
+            success := staticcall(gas(), 0x08, pairs, 0x300, result, 0x20)
+        }
+        return success && result[0] == 1;
+    }
+
+    // vk + s * (x, y) with the precompiles 0x07 and 0x06
+    function mulAdd(
+        uint256[2] memory vk,
+        uint256 x,
+        uint256 y,
+        uint256 s
+    ) internal view returns (uint256[2] memory, bool) {
+        uint256[3] memory mul = [x, y, s];
+        uint256[4] memory add;
+        bool success;
+        assembly {
+            success := staticcall(gas(), 0x07, mul, 0x60, add, 0x40)
+        }
+        if (!success) return (vk, false);
+        add[2] = vk[0];
+        add[3] = vk[1];
+        assembly {
+            success := staticcall(gas(), 0x06, add, 0x80, add, 0x40)
+        }
+        return ([add[0], add[1]], success);
+    }
+}
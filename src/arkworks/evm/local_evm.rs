@@ -0,0 +1,98 @@
+use crate::arkworks::backend::fibbonaci_handler::fibonacci;
+use crate::arkworks::constraints::fibbonaci::FibonacciCircuit;
+use crate::arkworks::evm::calldata::{scalar_modulus, verify_proof_calldata};
+use crate::arkworks::proof_system::Groth16Bn254Backend;
+use crate::proof_system::{ProofSystem, Prover};
+use ark_bn254::{Bn254, Fr};
+use ark_groth16::{prepare_verifying_key, Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_snark::SNARK;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use revm::db::{CacheDB, EmptyDB};
+use revm::primitives::{Address, ExecutionResult, Output, TxKind};
+use revm::Evm;
+
+// a local EVM for the tests of the verifiers, so they need no chain
+
+type LocalEvm = Evm<'static, (), CacheDB<EmptyDB>>;
+
+fn circuit(a: Fr, b: Fr) -> FibonacciCircuit<Fr> {
+    FibonacciCircuit {
+        a: Some(a),
+        b: Some(b),
+        num_of_steps: 10,
+        result: Some(fibonacci(10, a, b)),
+    }
+}
+
+// the key of a seeded setup, so a verifier compiled from it once stays valid as a fixture
+pub fn fixed_keys() -> (ProvingKey<Bn254>, VerifyingKey<Bn254>) {
+    let (a, b) = (Fr::from(0u64), Fr::from(1u64));
+    Groth16::<Bn254>::circuit_specific_setup(circuit(a, b), &mut StdRng::seed_from_u64(0)).unwrap()
+}
+
+fn transact(evm: &mut LocalEvm, to: TxKind, data: Vec<u8>) -> ExecutionResult {
+    evm.tx_mut().transact_to = to;
+    evm.tx_mut().data = data.into();
+    evm.transact_commit().unwrap()
+}
+
+// the bool the contract returns, none when it reverts
+fn call(evm: &mut LocalEvm, verifier: Address, calldata: Vec<u8>) -> Option<bool> {
+    match transact(evm, TxKind::Call(verifier), calldata) {
+        ExecutionResult::Success { output, .. } => {
+            let output = output.into_data();
+            assert_eq!(output.len(), 32);
+            Some(output[31] == 1)
+        }
+        _ => None,
+    }
+}
+
+// deploys the creation code of a verifier of the fixed fibonacci key and checks a proof of the
+// key, other statements and other proofs against it
+pub fn check_verifier(deployment_code: impl FnOnce(&VerifyingKey<Bn254>) -> Vec<u8>) {
+    let (a, b) = (Fr::from(0u64), Fr::from(1u64));
+    let groth16 = Groth16Bn254Backend::default();
+    let (pk, vk) = fixed_keys();
+    let pvk = prepare_verifying_key(&vk);
+    let proof = groth16.prove(&pk, circuit(a, b)).unwrap();
+    let inputs = [a, b, fibonacci(10, a, b)];
+    assert!(groth16.verify(&pvk, &inputs, &proof).unwrap());
+
+    let mut evm = Evm::builder()
+        .with_db(CacheDB::new(EmptyDB::default()))
+        .build();
+    let deployment = deployment_code(&pvk.vk);
+    let verifier = match transact(&mut evm, TxKind::Create, deployment) {
+        ExecutionResult::Success {
+            output: Output::Create(_, Some(address)),
+            ..
+        } => address,
+        result => panic!("the deployment failed: {:?}", result),
+    };
+    let calldata = verify_proof_calldata(&proof, &inputs);
+    assert_eq!(call(&mut evm, verifier, calldata.clone()), Some(true));
+
+    // another statement, a negated proof and an input that isn't reduced
+    let wrong = [a, b, inputs[2] + Fr::from(1u64)];
+    let wrong = verify_proof_calldata(&proof, &wrong);
+    assert_eq!(call(&mut evm, verifier, wrong), Some(false));
+    let negated = Proof {
+        a: -proof.a,
+        ..proof.clone()
+    };
+    let negated = verify_proof_calldata(&negated, &inputs);
+    assert_eq!(call(&mut evm, verifier, negated), Some(false));
+    let mut unreduced = calldata.clone();
+    let last = unreduced.len() - 32;
+    unreduced[last..].copy_from_slice(&scalar_modulus());
+    assert_eq!(call(&mut evm, verifier, unreduced), Some(false));
+
+    // another number of inputs or another function reverts
+    let fewer = verify_proof_calldata(&proof, &inputs[..2]);
+    assert_eq!(call(&mut evm, verifier, fewer), None);
+    let mut selector = calldata;
+    selector[0] ^= 1;
+    assert_eq!(call(&mut evm, verifier, selector), None);
+}
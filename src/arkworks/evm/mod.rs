@@ -0,0 +1,11 @@
+// Groth16 proofs over BN254 for the EVM: a solidity verifier contract for a verifying key and
+// the calldata of its verifyProof(a, b, c, input). the contract checks the pairing equation
+// with the precompiles of EIP-196 and EIP-197 (0x06, 0x07 and 0x08).
+pub mod calldata;
+pub mod solidity;
+
+// the tests check the solidity verifier against a bytecode one, and both in a local EVM
+#[cfg(test)]
+mod bytecode;
+#[cfg(test)]
+mod local_evm;
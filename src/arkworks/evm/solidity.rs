@@ -0,0 +1,247 @@
+use crate::arkworks::evm::calldata::{
+    base_modulus, g1_words, g2_words, scalar_modulus, word_to_decimal,
+};
+use ark_bn254::{Bn254, G1Affine, G2Affine};
+use ark_groth16::VerifyingKey;
+use std::fmt::Write;
+
+// the solidity verifier of a verifying key. the key is in constants, so there is a contract
+// per circuit, with verifyProof(a, b, c, input) for input of exactly the public inputs of the
+// circuit. it returns false for a proof that doesn't verify and for inputs that aren't
+// reduced.
+pub fn verifier_contract(vk: &VerifyingKey<Bn254>) -> String {
+    let num_inputs = vk.gamma_abc_g1.len() - 1;
+    let mut constants = String::new();
+    constant(&mut constants, "Q", &word_to_decimal(&base_modulus()));
+    constant(&mut constants, "R", &word_to_decimal(&scalar_modulus()));
+    constants.push('\n');
+    g1_constants(&mut constants, "ALPHA", &vk.alpha_g1);
+    g2_constants(&mut constants, "BETA", &vk.beta_g2);
+    g2_constants(&mut constants, "GAMMA", &vk.gamma_g2);
+    g2_constants(&mut constants, "DELTA", &vk.delta_g2);
+    for (i, point) in vk.gamma_abc_g1.iter().enumerate() {
+        g1_constants(&mut constants, &format!("IC{}", i), point);
+    }
+
+    let mut vk_x = String::new();
+    for i in 0..num_inputs {
+        let _ = write!(
+            vk_x,
+            r#"
+        if (input[{i}] >= R) return false;
+        (vk, success) = mulAdd(vk, IC{j}_X, IC{j}_Y, input[{i}]);
+        if (!success) return false;"#,
+            i = i,
+            j = i + 1,
+        );
+    }
+
+    let pairs = [
+        "a[0]",
+        "(Q - a[1]) % Q",
+        "b[0][0]",
+        "b[0][1]",
+        "b[1][0]",
+        "b[1][1]",
+        "ALPHA_X",
+        "ALPHA_Y",
+        "BETA_X1",
+        "BETA_X0",
+        "BETA_Y1",
+        "BETA_Y0",
+        "vk[0]",
+        "vk[1]",
+        "GAMMA_X1",
+        "GAMMA_X0",
+        "GAMMA_Y1",
+        "GAMMA_Y0",
+        "c[0]",
+        "c[1]",
+        "DELTA_X1",
+        "DELTA_X0",
+        "DELTA_Y1",
+        "DELTA_Y0",
+    ];
+    let mut pairing = String::new();
+    for (i, value) in pairs.iter().enumerate() {
+        let _ = write!(pairing, "\n        pairs[{}] = {};", i, value);
+    }
+
+    format!(
+        r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.19;
+
+// a verifier of Groth16 proofs over BN254 for a single verifying key
+contract Groth16Verifier {{
+{constants}
+    // e(-a, b) * e(alpha, beta) * e(vk_x, gamma) * e(c, delta) == 1 where
+    // vk_x = IC0 + input[0] * IC1 + ...
+    function verifyProof(
+        uint256[2] calldata a,
+        uint256[2][2] calldata b,
+        uint256[2] calldata c,
+        uint256[{num_inputs}] calldata input
+    ) external view returns (bool) {{
+        uint256[2] memory vk = [IC0_X, IC0_Y];
+        bool success;{vk_x}
+
+        if (a[1] >= Q) return false;
+        uint256[24] memory pairs;{pairing}
+        uint256[1] memory result;
+        assembly {{
+            success := staticcall(gas(), 0x08, pairs, 0x300, result, 0x20)
+        }}
+        return success && result[0] == 1;
+    }}
+
+    // vk + s * (x, y) with the precompiles 0x07 and 0x06
+    function mulAdd(
+        uint256[2] memory vk,
+        uint256 x,
+        uint256 y,
+        uint256 s
+    ) internal view returns (uint256[2] memory, bool) {{
+        uint256[3] memory mul = [x, y, s];
+        uint256[4] memory add;
+        bool success;
+        assembly {{
+            success := staticcall(gas(), 0x07, mul, 0x60, add, 0x40)
+        }}
+        if (!success) return (vk, false);
+        add[2] = vk[0];
+        add[3] = vk[1];
+        assembly {{
+            success := staticcall(gas(), 0x06, add, 0x80, add, 0x40)
+        }}
+        return ([add[0], add[1]], success);
+    }}
+}}
+"#,
+        constants = constants,
+        num_inputs = num_inputs,
+        vk_x = vk_x,
+        pairing = pairing,
+    )
+}
+
+fn constant(out: &mut String, name: &str, value: &str) {
+    let _ = writeln!(out, "    uint256 constant {} = {};", name, value);
+}
+
+fn g1_constants(out: &mut String, name: &str, point: &G1Affine) {
+    let [x, y] = g1_words(point);
+    constant(out, &format!("{}_X", name), &word_to_decimal(&x));
+    constant(out, &format!("{}_Y", name), &word_to_decimal(&y));
+}
+
+// in the order of the precompile, the coefficient of i first
+fn g2_constants(out: &mut String, name: &str, point: &G2Affine) {
+    let words = g2_words(point);
+    for (suffix, word) in ["X1", "X0", "Y1", "Y0"].iter().zip(words.iter()) {
+        constant(out, &format!("{}_{}", name, suffix), &word_to_decimal(word));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::evm::bytecode::verifier_runtime_code;
+    use crate::arkworks::evm::calldata::verify_proof_signature;
+    use crate::arkworks::evm::local_evm::{check_verifier, fixed_keys};
+    use ark_bn254::Fr;
+    use ark_ec::AffineRepr;
+    use ark_ff::PrimeField;
+    use num_bigint::BigUint;
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    // the creation code of the contract, compiled by the solc on the PATH
+    fn compile(source: &str) -> Vec<u8> {
+        let mut solc = Command::new("solc")
+            .args(["--optimize", "--combined-json", "bin", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("solc isn't on the PATH");
+        solc.stdin
+            .take()
+            .unwrap()
+            .write_all(source.as_bytes())
+            .unwrap();
+        let output = solc.wait_with_output().unwrap();
+        let errors = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "solc failed: {}", errors);
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let contracts = json["contracts"].as_object().unwrap();
+        let (_, contract) = contracts
+            .iter()
+            .find(|(name, _)| name.ends_with(":Groth16Verifier"))
+            .unwrap();
+        decode_hex(contract["bin"].as_str().unwrap())
+    }
+
+    // the contract of the fixed key of the local evm
+    const FIXTURE: &str = include_str!("fixtures/fibonacci_verifier.sol");
+
+    fn decode_hex(hex: &str) -> Vec<u8> {
+        let hex = hex.trim();
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_contract_of_the_fixed_key_matches_the_fixture() {
+        assert_eq!(verifier_contract(&fixed_keys().1), FIXTURE);
+    }
+
+    #[test]
+    #[ignore = "needs solc on the PATH, run with cargo test -- --ignored"]
+    fn test_compiled_contract_in_evm() {
+        check_verifier(|_| compile(FIXTURE));
+    }
+
+    #[test]
+    fn test_contract_matches_the_bytecode() {
+        let g1 = G1Affine::generator();
+        let vk = VerifyingKey::<Bn254> {
+            alpha_g1: g1,
+            beta_g2: G2Affine::generator(),
+            gamma_g2: G2Affine::generator(),
+            delta_g2: -G2Affine::generator(),
+            gamma_abc_g1: vec![g1, -g1, g1],
+        };
+        let contract = verifier_contract(&vk);
+        let code = verifier_runtime_code(&vk);
+        assert!(contract.contains("uint256[2] memory vk = [IC0_X, IC0_Y];"));
+        assert!(contract.contains("uint256[2] calldata input"));
+        assert!(contract.contains("IC2_Y"));
+        assert!(!contract.contains("IC3_X"));
+        assert_eq!(verify_proof_signature(2).matches("uint256").count(), 4);
+
+        // every constant of the contract is pushed by the bytecode
+        let mut constants = 0;
+        for line in contract.lines() {
+            let Some(value) = line.trim().strip_prefix("uint256 constant ") else {
+                continue;
+            };
+            let value = value.split(" = ").nth(1).unwrap().trim_end_matches(';');
+            let bytes = value.parse::<BigUint>().unwrap().to_bytes_be();
+            let push = [vec![0x5f + bytes.len() as u8], bytes].concat();
+            assert!(
+                code.windows(push.len()).any(|window| window == push),
+                "{} isn't in the bytecode",
+                line
+            );
+            constants += 1;
+        }
+        // the moduli, alpha, beta, gamma, delta and the three points of the inputs
+        assert_eq!(constants, 2 + 2 + 3 * 4 + 3 * 2);
+        assert_eq!(
+            word_to_decimal(&scalar_modulus()),
+            BigUint::from(Fr::MODULUS).to_string()
+        );
+    }
+}
@@ -0,0 +1,4 @@
+pub mod nifs;
+pub mod r1cs;
+pub mod steps;
+pub mod transcript;
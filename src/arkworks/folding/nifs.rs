@@ -0,0 +1,148 @@
+use super::r1cs::{CommitmentKey, R1CSShape, RelaxedInstance, RelaxedWitness};
+use ark_ec::CurveGroup;
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+use sha2::{Digest, Sha256};
+
+// Nova's non-interactive folding scheme: a running relaxed instance (u, E) and a fresh step
+// (u = 1, E = 0) are folded into one relaxed instance with the cross term
+// T = Az1 o Bz2 + Az2 o Bz1 - u1 Cz2 - u2 Cz1 and a challenge r, which is a hash of both
+// instances, the commitment to T and the digest of the circuit.
+
+pub fn challenge<G: CurveGroup>(
+    shape_digest: &[u8],
+    running: &RelaxedInstance<G>,
+    fresh: &RelaxedInstance<G>,
+    comm_t: &G,
+) -> G::ScalarField {
+    let mut bytes = shape_digest.to_vec();
+    running.serialize_compressed(&mut bytes).unwrap();
+    fresh.serialize_compressed(&mut bytes).unwrap();
+    comm_t.serialize_compressed(&mut bytes).unwrap();
+    G::ScalarField::from_le_bytes_mod_order(&Sha256::digest(&bytes))
+}
+
+fn cross_term<F: PrimeField>(
+    shape: &R1CSShape<F>,
+    (u1, x1, w1): (F, &[F], &[F]),
+    (u2, x2, w2): (F, &[F], &[F]),
+) -> Vec<F> {
+    let (az1, bz1, cz1) = shape.multiply(u1, x1, w1);
+    let (az2, bz2, cz2) = shape.multiply(u2, x2, w2);
+    (0..shape.num_constraints)
+        .map(|i| az1[i] * bz2[i] + az2[i] * bz1[i] - u1 * cz2[i] - u2 * cz1[i])
+        .collect()
+}
+
+// the verifier side: only the instances and the commitment to T are needed
+pub fn fold_instances<G: CurveGroup>(
+    running: &RelaxedInstance<G>,
+    fresh: &RelaxedInstance<G>,
+    comm_t: &G,
+    r: G::ScalarField,
+) -> RelaxedInstance<G> {
+    RelaxedInstance {
+        comm_w: running.comm_w + fresh.comm_w * r,
+        comm_e: running.comm_e + *comm_t * r + fresh.comm_e * (r * r),
+        u: running.u + r * fresh.u,
+        x: running
+            .x
+            .iter()
+            .zip(fresh.x.iter())
+            .map(|(x1, x2)| *x1 + r * x2)
+            .collect(),
+    }
+}
+
+// the prover side, returns the folded instance and witness and the commitment to T that the
+// verifier needs to fold the instances itself
+pub fn fold<G: CurveGroup>(
+    shape: &R1CSShape<G::ScalarField>,
+    key: &CommitmentKey<G>,
+    shape_digest: &[u8],
+    (running, running_witness): (&RelaxedInstance<G>, &RelaxedWitness<G::ScalarField>),
+    (fresh, fresh_witness): (&RelaxedInstance<G>, &RelaxedWitness<G::ScalarField>),
+) -> (RelaxedInstance<G>, RelaxedWitness<G::ScalarField>, G) {
+    let t = cross_term(
+        shape,
+        (running.u, &running.x, &running_witness.w),
+        (fresh.u, &fresh.x, &fresh_witness.w),
+    );
+    let comm_t = key.commit(&t);
+    let r = challenge(shape_digest, running, fresh, &comm_t);
+
+    let instance = fold_instances(running, fresh, &comm_t, r);
+    let witness = RelaxedWitness {
+        w: running_witness
+            .w
+            .iter()
+            .zip(fresh_witness.w.iter())
+            .map(|(w1, w2)| *w1 + r * w2)
+            .collect(),
+        e: running_witness
+            .e
+            .iter()
+            .zip(t.iter())
+            .zip(fresh_witness.e.iter())
+            .map(|((e1, t), e2)| *e1 + r * t + r * r * e2)
+            .collect(),
+    };
+    (instance, witness, comm_t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::constraints::fast_fibonacci::FastFibonacciCircuit;
+    use crate::arkworks::folding::r1cs::is_relaxed_satisfied;
+    use ark_bls12_381::{Fr, G1Projective};
+
+    #[test]
+    fn folded_instances_stay_satisfied() {
+        // the fast fibonacci circuit multiplies witnesses, so the cross term isn't zero
+        let circuit = |a: u64, n: u64| FastFibonacciCircuit::new(n, Fr::from(a), Fr::from(1u64));
+        let shape = R1CSShape::from_circuit(circuit(2, 7)).unwrap();
+        let key = CommitmentKey::<G1Projective>::setup(
+            shape.num_witness.max(shape.num_constraints),
+            b"test",
+        );
+        let digest = shape.digest();
+
+        let step = |a: u64, n: u64| {
+            let (x, w) = shape.assignment(circuit(a, n)).unwrap();
+            (
+                RelaxedInstance::fresh(key.commit(&w), x),
+                RelaxedWitness::fresh(w, shape.num_constraints),
+            )
+        };
+        let (mut running, mut running_witness) = step(2, 7);
+        for (a, n) in [(3, 13), (5, 101), (2, 65537)] {
+            let (fresh, fresh_witness) = step(a, n);
+            let (instance, witness, comm_t) = fold(
+                &shape,
+                &key,
+                &digest,
+                (&running, &running_witness),
+                (&fresh, &fresh_witness),
+            );
+            // the verifier folds the instances to the same result
+            let r = challenge(&digest, &running, &fresh, &comm_t);
+            assert_eq!(fold_instances(&running, &fresh, &comm_t, r), instance);
+            assert!(is_relaxed_satisfied(&shape, &key, &instance, &witness));
+            running = instance;
+            running_witness = witness;
+        }
+
+        // folding a step with a wrong witness breaks the folded instance
+        let (fresh, mut fresh_witness) = step(3, 13);
+        fresh_witness.w[0] += Fr::from(1u64);
+        let (instance, witness, _) = fold(
+            &shape,
+            &key,
+            &digest,
+            (&running, &running_witness),
+            (&fresh, &fresh_witness),
+        );
+        assert!(!is_relaxed_satisfied(&shape, &key, &instance, &witness));
+    }
+}
@@ -0,0 +1,213 @@
+use ark_ec::CurveGroup;
+use ark_ff::PrimeField;
+use ark_relations::r1cs::{
+    ConstraintMatrices, ConstraintSynthesizer, ConstraintSystem, Matrix, SynthesisError,
+    SynthesisMode,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use sha2::{Digest, Sha256};
+
+// the constraint matrices of a step circuit. rows are sparse (coefficient, column) lists in the
+// arkworks layout: column 0 is the constant one (u for relaxed instances), then the public
+// inputs x, then the witness w, so z = (u, x, w).
+#[derive(Clone)]
+pub struct R1CSShape<F: PrimeField> {
+    pub num_constraints: usize,
+    pub num_io: usize,
+    pub num_witness: usize,
+    pub a: Matrix<F>,
+    pub b: Matrix<F>,
+    pub c: Matrix<F>,
+}
+
+impl<F: PrimeField> R1CSShape<F> {
+    // run the circuit in setup mode and keep the matrices, no values are needed
+    pub fn from_circuit<C: ConstraintSynthesizer<F>>(circuit: C) -> Result<Self, SynthesisError> {
+        let cs = ConstraintSystem::<F>::new_ref();
+        cs.set_mode(SynthesisMode::Setup);
+        circuit.generate_constraints(cs.clone())?;
+        cs.finalize();
+        let ConstraintMatrices {
+            num_instance_variables,
+            num_witness_variables,
+            num_constraints,
+            a,
+            b,
+            c,
+            ..
+        } = cs.to_matrices().ok_or(SynthesisError::MissingCS)?;
+        Ok(Self {
+            num_constraints,
+            num_io: num_instance_variables - 1,
+            num_witness: num_witness_variables,
+            a,
+            b,
+            c,
+        })
+    }
+
+    // the public inputs and the witness of one assignment of the circuit
+    pub fn assignment<C: ConstraintSynthesizer<F>>(
+        &self,
+        circuit: C,
+    ) -> Result<(Vec<F>, Vec<F>), SynthesisError> {
+        let cs = ConstraintSystem::<F>::new_ref();
+        circuit.generate_constraints(cs.clone())?;
+        if !cs.is_satisfied()? {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let cs = cs.borrow().ok_or(SynthesisError::MissingCS)?;
+        let x = cs.instance_assignment[1..].to_vec();
+        let w = cs.witness_assignment.clone();
+        if x.len() != self.num_io || w.len() != self.num_witness {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        Ok((x, w))
+    }
+
+    // (Az, Bz, Cz) for z = (u, x, w)
+    pub fn multiply(&self, u: F, x: &[F], w: &[F]) -> (Vec<F>, Vec<F>, Vec<F>) {
+        let z_at = |column: usize| match column {
+            0 => u,
+            i if i <= self.num_io => x[i - 1],
+            i => w[i - 1 - self.num_io],
+        };
+        let product = |matrix: &Matrix<F>| {
+            matrix
+                .iter()
+                .map(|row| row.iter().map(|(coeff, i)| *coeff * z_at(*i)).sum())
+                .collect::<Vec<F>>()
+        };
+        (product(&self.a), product(&self.b), product(&self.c))
+    }
+
+    // a hash of the matrices that binds the folding challenges to this circuit
+    pub fn digest(&self) -> Vec<u8> {
+        let mut sha256 = Sha256::new();
+        for size in [self.num_constraints, self.num_io, self.num_witness] {
+            sha256.update((size as u64).to_le_bytes());
+        }
+        for matrix in [&self.a, &self.b, &self.c] {
+            for row in matrix {
+                sha256.update((row.len() as u64).to_le_bytes());
+                for (coeff, column) in row {
+                    let mut bytes = vec![];
+                    coeff.serialize_compressed(&mut bytes).unwrap();
+                    sha256.update(&bytes);
+                    sha256.update((*column as u64).to_le_bytes());
+                }
+            }
+        }
+        sha256.finalize().to_vec()
+    }
+}
+
+// pedersen vector commitments without blinding, the generators are sampled from a public seed
+// so nobody knows their discrete logs
+#[derive(Clone)]
+pub struct CommitmentKey<G: CurveGroup> {
+    pub generators: Vec<G::Affine>,
+}
+
+impl<G: CurveGroup> CommitmentKey<G> {
+    pub fn setup(size: usize, label: &[u8]) -> Self {
+        let seed: [u8; 32] = Sha256::digest(label).into();
+        let mut rng = StdRng::from_seed(seed);
+        let generators = (0..size).map(|_| G::rand(&mut rng)).collect::<Vec<_>>();
+        Self {
+            generators: G::normalize_batch(&generators),
+        }
+    }
+
+    pub fn commit(&self, values: &[G::ScalarField]) -> G {
+        assert!(values.len() <= self.generators.len());
+        G::msm_unchecked(&self.generators[..values.len()], values)
+    }
+}
+
+// a fresh step is the relaxed instance with u = 1 and E = 0
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct RelaxedInstance<G: CurveGroup> {
+    pub comm_w: G,
+    pub comm_e: G,
+    pub u: G::ScalarField,
+    pub x: Vec<G::ScalarField>,
+}
+
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct RelaxedWitness<F: PrimeField> {
+    pub w: Vec<F>,
+    pub e: Vec<F>,
+}
+
+impl<G: CurveGroup> RelaxedInstance<G> {
+    pub fn fresh(comm_w: G, x: Vec<G::ScalarField>) -> Self {
+        Self {
+            comm_w,
+            comm_e: G::zero(),
+            u: G::ScalarField::from(1u64),
+            x,
+        }
+    }
+}
+
+impl<F: PrimeField> RelaxedWitness<F> {
+    pub fn fresh(w: Vec<F>, num_constraints: usize) -> Self {
+        Self {
+            w,
+            e: vec![F::zero(); num_constraints],
+        }
+    }
+}
+
+// Az o Bz = u Cz + E, and the commitments open to w and E
+pub fn is_relaxed_satisfied<G: CurveGroup>(
+    shape: &R1CSShape<G::ScalarField>,
+    key: &CommitmentKey<G>,
+    instance: &RelaxedInstance<G>,
+    witness: &RelaxedWitness<G::ScalarField>,
+) -> bool {
+    if instance.x.len() != shape.num_io
+        || witness.w.len() != shape.num_witness
+        || witness.e.len() != shape.num_constraints
+    {
+        return false;
+    }
+    let (az, bz, cz) = shape.multiply(instance.u, &instance.x, &witness.w);
+    let satisfied =
+        (0..shape.num_constraints).all(|i| az[i] * bz[i] == instance.u * cz[i] + witness.e[i]);
+    satisfied
+        && key.commit(&witness.w) == instance.comm_w
+        && key.commit(&witness.e) == instance.comm_e
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::constraints::fibbonaci::FibonacciCircuit;
+    use ark_bls12_381::{Fr, G1Projective};
+
+    #[test]
+    fn fresh_instance_is_relaxed_satisfied() {
+        let circuit = FibonacciCircuit::<Fr> {
+            a: Some(Fr::from(1u64)),
+            b: Some(Fr::from(1u64)),
+            num_of_steps: 10,
+            result: Some(Fr::from(144u64)),
+        };
+        let shape = R1CSShape::from_circuit(circuit.clone()).unwrap();
+        assert_eq!(shape.num_io, 3);
+        let key = CommitmentKey::<G1Projective>::setup(
+            shape.num_witness.max(shape.num_constraints),
+            b"test",
+        );
+        let (x, w) = shape.assignment(circuit).unwrap();
+        let instance = RelaxedInstance::fresh(key.commit(&w), x);
+        let mut witness = RelaxedWitness::fresh(w, shape.num_constraints);
+        assert!(is_relaxed_satisfied(&shape, &key, &instance, &witness));
+        witness.e[0] += Fr::from(1u64);
+        assert!(!is_relaxed_satisfied(&shape, &key, &instance, &witness));
+    }
+}
@@ -0,0 +1,201 @@
+use super::transcript::StepCircuit;
+use crate::arkworks::constraints::fibbonaci::FibonacciCircuit;
+use crate::arkworks::prime_snark::utils::constants::NUM_BITS;
+use crate::arkworks::prime_snark::utils::hasher::hash_to_bytes;
+use ark_ff::{BigInteger, PrimeField};
+use ark_r1cs_std::boolean::Boolean;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::fields::FieldVar;
+use ark_r1cs_std::prelude::AllocVar;
+use ark_r1cs_std::{R1CSVar, ToBitsGadget};
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use sha2::{Digest, Sha256};
+
+// num_of_steps rounds of the fibonacci circuit per folding step, z = (f_{i-2}, f_{i-1}).
+// a and b come from z, the fields a, b and result of the circuit are ignored.
+impl<F: PrimeField> StepCircuit<F> for FibonacciCircuit<F> {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn step_native(&self, z: &[F]) -> Vec<F> {
+        let (mut fi_minus_two, mut fi_minus_one) = (z[0], z[1]);
+        for _ in 0..self.num_of_steps {
+            let fi = fi_minus_one + fi_minus_two;
+            fi_minus_two = fi_minus_one;
+            fi_minus_one = fi;
+        }
+        vec![fi_minus_two, fi_minus_one]
+    }
+
+    fn generate_step_constraints(
+        &self,
+        _cs: ConstraintSystemRef<F>,
+        z: &[FpVar<F>],
+    ) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        let mut fi_minus_two = z[0].clone();
+        let mut fi_minus_one = z[1].clone();
+        for _i in 0..self.num_of_steps {
+            let fi = &fi_minus_one + &fi_minus_two;
+            fi_minus_two = fi_minus_one;
+            fi_minus_one = fi;
+        }
+        Ok(vec![fi_minus_two, fi_minus_one])
+    }
+}
+
+// one round of the hash-to-prime search: z = (x, j), the candidate is hash(x+j) mod 2^NUM_BITS
+// like in the prime circuit. a step proves that the candidate is not a prime (a product p * q
+// with p, q >= 2, or 0 or 1) and moves on to j + 1, so folding the steps from j = 0 proves
+// that no index before j gives a prime.
+#[derive(Clone, Default)]
+pub struct PrimeSearchStep;
+
+// hash(x+j) mod 2^NUM_BITS
+pub fn search_candidate<F: PrimeField>(x_plus_j: F) -> u64 {
+    let digest = Sha256::digest(x_plus_j.into_bigint().to_bytes_le());
+    let low = u64::from_le_bytes(digest[..8].try_into().unwrap());
+    low & ((1 << NUM_BITS) - 1)
+}
+
+// the smallest prime factor of a composite n, None for primes. 0 and 1 use (2, 2)
+pub fn composite_factors(n: u64) -> Option<(u64, u64)> {
+    if n < 2 {
+        return Some((2, 2));
+    }
+    (2..)
+        .take_while(|p| p * p <= n)
+        .find(|p| n % p == 0)
+        .map(|p| (p, n / p))
+}
+
+// the first j in 0..max_j whose candidate is prime
+pub fn first_prime_index<F: PrimeField>(x: F, max_j: u64) -> Option<u64> {
+    (0..max_j).find(|j| composite_factors(search_candidate(x + F::from(*j))).is_none())
+}
+
+impl<F: PrimeField> StepCircuit<F> for PrimeSearchStep {
+    fn arity(&self) -> usize {
+        2
+    }
+
+    fn step_native(&self, z: &[F]) -> Vec<F> {
+        vec![z[0], z[1] + F::one()]
+    }
+
+    fn generate_step_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        z: &[FpVar<F>],
+    ) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        let (x, j) = (&z[0], &z[1]);
+        let digest = hash_to_bytes(x + j);
+        let mut bits = vec![];
+        for byte in &digest.0[..(NUM_BITS + 7) / 8] {
+            bits.extend(byte.to_bits_le()?);
+        }
+        let n = Boolean::le_bits_to_fp_var(&bits[..NUM_BITS])?;
+
+        // p - 2 and q - 2 fit in NUM_BITS bits, so p * q can't wrap around the field
+        let factors = n
+            .value()
+            .ok()
+            .and_then(|n| composite_factors(n.into_bigint().as_ref()[0]));
+        let factor = |pick: fn((u64, u64)) -> u64| -> Result<FpVar<F>, SynthesisError> {
+            let bits = (0..NUM_BITS)
+                .map(|i| {
+                    Boolean::new_witness(cs.clone(), || {
+                        factors
+                            .map(|f| ((pick(f) - 2) >> i) & 1 == 1)
+                            .ok_or(SynthesisError::AssignmentMissing)
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Boolean::le_bits_to_fp_var(&bits)? + F::from(2u64))
+        };
+        let p = factor(|(p, _)| p)?;
+        let q = factor(|(_, q)| q)?;
+
+        // (p * q - n) * n * (n - 1) = 0
+        let small = &n * (&n - F::one());
+        (&p * &q - &n).mul_equals(&small, &FpVar::zero())?;
+
+        Ok(vec![x.clone(), j + F::one()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::backend::fibbonaci_handler::fibonacci;
+    use crate::arkworks::folding::transcript::{verify, FoldingParams, FoldingProver};
+    use crate::arkworks::prime_snark::utils::hasher::hash_x_plus_i_native;
+    use ark_bls12_381::{Fr, G1Projective};
+    use num_bigint::BigUint;
+
+    #[test]
+    fn fibonacci_folding() {
+        let step = FibonacciCircuit::<Fr> {
+            a: None,
+            b: None,
+            num_of_steps: 50,
+            result: None,
+        };
+        let params = FoldingParams::<G1Projective, _>::setup(step).unwrap();
+        let mut prover = FoldingProver::new(&params, vec![Fr::from(0u64), Fr::from(1u64)]);
+        for _ in 0..4 {
+            prover.prove_step().unwrap();
+        }
+        let proof = prover.finish().unwrap();
+        assert_eq!(proof.num_steps(), 4);
+        // 200 rounds from (0, 1) end at F(200)
+        assert_eq!(
+            proof.output()[1],
+            fibonacci(200, Fr::from(0u64), Fr::from(1u64))
+        );
+        assert!(verify(&params, &proof));
+
+        let mut wrong_output = proof.clone();
+        wrong_output.steps[3].z[1] += Fr::from(1u64);
+        assert!(!verify(&params, &wrong_output));
+        let mut wrong_start = proof.clone();
+        wrong_start.z0[0] = Fr::from(1u64);
+        assert!(!verify(&params, &wrong_start));
+        let mut dropped_step = proof;
+        dropped_step.steps.remove(1);
+        assert!(!verify(&params, &dropped_step));
+    }
+
+    #[test]
+    fn candidate_matches_the_prime_circuit() {
+        let x = Fr::from(5u64);
+        let digest = BigUint::from_bytes_le(&hash_x_plus_i_native(x, 3));
+        assert_eq!(
+            BigUint::from(search_candidate(x + Fr::from(3u64))),
+            digest % (BigUint::from(1u64) << NUM_BITS)
+        );
+        assert_eq!(composite_factors(91), Some((7, 13)));
+        assert_eq!(composite_factors(97), None);
+    }
+
+    #[test]
+    fn prime_search_folding() {
+        // a seed whose first two candidates are composite
+        let x = (0u64..)
+            .map(Fr::from)
+            .find(|x| first_prime_index(*x, 2).is_none())
+            .unwrap();
+        let params = FoldingParams::<G1Projective, _>::setup(PrimeSearchStep).unwrap();
+        let mut prover = FoldingProver::new(&params, vec![x, Fr::from(0u64)]);
+        prover.prove_step().unwrap();
+        prover.prove_step().unwrap();
+        let proof = prover.finish().unwrap();
+        assert_eq!(proof.output(), &[x, Fr::from(2u64)]);
+        assert!(verify(&params, &proof));
+
+        // a prime candidate can't be folded
+        let j = first_prime_index(x, 1000).unwrap();
+        let mut prover = FoldingProver::new(&params, vec![x, Fr::from(j)]);
+        assert!(prover.prove_step().is_err());
+    }
+}
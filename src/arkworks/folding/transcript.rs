@@ -0,0 +1,229 @@
+use super::nifs::{challenge, fold, fold_instances};
+use super::r1cs::{
+    is_relaxed_satisfied, CommitmentKey, R1CSShape, RelaxedInstance, RelaxedWitness,
+};
+use ark_ec::CurveGroup;
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::{AllocVar, EqGadget};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+// a computation z_{i+1} = F(z_i) that is repeated many times. the constraints of one step must
+// not depend on the values of z, every step is folded into the same R1CS shape.
+pub trait StepCircuit<F: PrimeField>: Clone {
+    // the number of field elements in z
+    fn arity(&self) -> usize;
+
+    fn step_native(&self, z: &[F]) -> Vec<F>;
+
+    // constraints from z_i to z_{i+1}, z_i are already allocated
+    fn generate_step_constraints(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        z: &[FpVar<F>],
+    ) -> Result<Vec<FpVar<F>>, SynthesisError>;
+}
+
+// one step as a circuit whose public inputs are x = (z_i, z_{i+1})
+#[derive(Clone)]
+pub struct StepInstanceCircuit<F: PrimeField, S: StepCircuit<F>> {
+    pub step: S,
+    pub z: Option<Vec<F>>,
+}
+
+impl<F: PrimeField, S: StepCircuit<F>> ConstraintSynthesizer<F> for StepInstanceCircuit<F, S> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let arity = self.step.arity();
+        let z_next = self.z.as_ref().map(|z| self.step.step_native(z));
+        let z_in = (0..arity)
+            .map(|i| {
+                FpVar::<F>::new_input(cs.clone(), || {
+                    self.z
+                        .as_ref()
+                        .map(|z| z[i])
+                        .ok_or(SynthesisError::AssignmentMissing)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let z_out = self.step.generate_step_constraints(cs.clone(), &z_in)?;
+        for (i, value) in z_out.iter().enumerate() {
+            let saved = FpVar::<F>::new_input(cs.clone(), || {
+                z_next
+                    .as_ref()
+                    .map(|z| z[i])
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            value.enforce_equal(&saved)?;
+        }
+        Ok(())
+    }
+}
+
+// the shape of the step circuit and a commitment key that fits its witness and error vectors,
+// the prover and the verifier derive the same parameters from the step circuit
+pub struct FoldingParams<G: CurveGroup, S: StepCircuit<G::ScalarField>> {
+    pub step: S,
+    pub shape: R1CSShape<G::ScalarField>,
+    pub key: CommitmentKey<G>,
+    pub digest: Vec<u8>,
+}
+
+impl<G: CurveGroup, S: StepCircuit<G::ScalarField>> FoldingParams<G, S> {
+    pub fn setup(step: S) -> Result<Self, SynthesisError> {
+        let shape = R1CSShape::from_circuit(StepInstanceCircuit {
+            step: step.clone(),
+            z: None,
+        })?;
+        let digest = shape.digest();
+        let key = CommitmentKey::setup(shape.num_witness.max(shape.num_constraints), &digest);
+        Ok(Self {
+            step,
+            shape,
+            key,
+            digest,
+        })
+    }
+}
+
+// what the verifier needs from every step: the commitment to its witness, the commitment to
+// the cross term it was folded with (none for the first step) and its output z_{i+1}
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct FoldingStep<G: CurveGroup> {
+    pub comm_w: G,
+    pub comm_t: Option<G>,
+    pub z: Vec<G::ScalarField>,
+}
+
+// the steps and the witness of the final folded instance. there is no augmented circuit that
+// verifies the folding and no final SNARK over it, so the verifier refolds the step instances
+// itself. the transcript is O(steps) in size and verification (a few group operations per
+// step, and one check of the step circuit at the end instead of one per step), and the folded
+// witness is revealed, so it is neither succinct nor zero knowledge.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct FoldingTranscript<G: CurveGroup> {
+    pub z0: Vec<G::ScalarField>,
+    pub steps: Vec<FoldingStep<G>>,
+    pub witness: RelaxedWitness<G::ScalarField>,
+}
+
+impl<G: CurveGroup> FoldingTranscript<G> {
+    pub fn num_steps(&self) -> usize {
+        self.steps.len()
+    }
+
+    // z after the last step
+    pub fn output(&self) -> &[G::ScalarField] {
+        self.steps.last().map_or(&self.z0, |step| &step.z)
+    }
+}
+
+// keeps the running instance and witness only, so the memory of the prover doesn't grow with
+// the number of steps (apart from the short FoldingStep records)
+pub struct FoldingProver<'a, G: CurveGroup, S: StepCircuit<G::ScalarField>> {
+    params: &'a FoldingParams<G, S>,
+    z0: Vec<G::ScalarField>,
+    z: Vec<G::ScalarField>,
+    running: Option<(RelaxedInstance<G>, RelaxedWitness<G::ScalarField>)>,
+    steps: Vec<FoldingStep<G>>,
+}
+
+impl<'a, G: CurveGroup, S: StepCircuit<G::ScalarField>> FoldingProver<'a, G, S> {
+    pub fn new(params: &'a FoldingParams<G, S>, z0: Vec<G::ScalarField>) -> Self {
+        assert_eq!(z0.len(), params.step.arity());
+        Self {
+            params,
+            z: z0.clone(),
+            z0,
+            running: None,
+            steps: vec![],
+        }
+    }
+
+    pub fn z(&self) -> &[G::ScalarField] {
+        &self.z
+    }
+
+    // prove one more step and fold it into the running instance
+    pub fn prove_step(&mut self) -> Result<&FoldingStep<G>, SynthesisError> {
+        let FoldingParams {
+            step,
+            shape,
+            key,
+            digest,
+        } = self.params;
+        let (x, w) = shape.assignment(StepInstanceCircuit {
+            step: step.clone(),
+            z: Some(self.z.clone()),
+        })?;
+        let z_next = x[step.arity()..].to_vec();
+        let fresh = RelaxedInstance::fresh(key.commit(&w), x);
+        let fresh_witness = RelaxedWitness::fresh(w, shape.num_constraints);
+
+        let (running, comm_t) = match self.running.take() {
+            None => ((fresh.clone(), fresh_witness), None),
+            Some((instance, witness)) => {
+                let (instance, witness, comm_t) = fold(
+                    shape,
+                    key,
+                    digest,
+                    (&instance, &witness),
+                    (&fresh, &fresh_witness),
+                );
+                ((instance, witness), Some(comm_t))
+            }
+        };
+        self.running = Some(running);
+        self.steps.push(FoldingStep {
+            comm_w: fresh.comm_w,
+            comm_t,
+            z: z_next.clone(),
+        });
+        self.z = z_next;
+        Ok(self.steps.last().unwrap())
+    }
+
+    pub fn finish(self) -> Option<FoldingTranscript<G>> {
+        let (_, witness) = self.running?;
+        Some(FoldingTranscript {
+            z0: self.z0,
+            steps: self.steps,
+            witness,
+        })
+    }
+}
+
+// refold the step instances from z0 and check the folded instance with the revealed witness
+pub fn verify<G: CurveGroup, S: StepCircuit<G::ScalarField>>(
+    params: &FoldingParams<G, S>,
+    transcript: &FoldingTranscript<G>,
+) -> bool {
+    let arity = params.step.arity();
+    if transcript.z0.len() != arity || transcript.steps.is_empty() {
+        return false;
+    }
+    let mut z = transcript.z0.clone();
+    let mut running: Option<RelaxedInstance<G>> = None;
+    for step in &transcript.steps {
+        if step.z.len() != arity {
+            return false;
+        }
+        let x = z.iter().chain(step.z.iter()).cloned().collect();
+        let fresh = RelaxedInstance::fresh(step.comm_w, x);
+        running = match (running, step.comm_t) {
+            (None, None) => Some(fresh),
+            (Some(instance), Some(comm_t)) => {
+                let r = challenge(&params.digest, &instance, &fresh, &comm_t);
+                Some(fold_instances(&instance, &fresh, &comm_t, r))
+            }
+            _ => return false,
+        };
+        z = step.z.clone();
+    }
+    is_relaxed_satisfied(
+        &params.shape,
+        &params.key,
+        &running.unwrap(),
+        &transcript.witness,
+    )
+}
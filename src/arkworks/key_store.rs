@@ -0,0 +1,371 @@
+use crate::arkworks::aggregation::srs::AggregationSrs;
+use crate::arkworks::curves::SnarkCurve;
+use crate::arkworks::marlin::proof_system::{MarlinBackend, SrsBounds, DEFAULT_SRS_LOG_SIZE};
+use crate::proof_system::{ProofSystem, ProofSystemError, Prover};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand::rngs::OsRng;
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+
+// the directory of the keys when ZK_KEY_DIR isn't set
+pub const DEFAULT_KEY_DIR: &str = "keys";
+
+// the id of the keys of a circuit: its name and the parameters that fix its shape, e.g.
+// fibonacci-10 or linear_equations-3x2-none. the id is also the file name on disk.
+pub fn key_id(circuit: &str, shape: &[String]) -> String {
+    std::iter::once(circuit.to_string())
+        .chain(shape.iter().cloned())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[derive(Debug)]
+pub enum KeyStoreError {
+    InvalidKeyId(String),
+    UnknownKey(String),
+    Io(io::Error),
+    ProofSystem(ProofSystemError),
+}
+
+impl fmt::Display for KeyStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyStoreError::InvalidKeyId(id) => write!(f, "'{}' is not a valid key id", id),
+            KeyStoreError::UnknownKey(id) => write!(f, "no keys with the id '{}'", id),
+            KeyStoreError::Io(e) => write!(f, "key store io error: {}", e),
+            KeyStoreError::ProofSystem(e) => write!(f, "key setup or loading failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for KeyStoreError {}
+
+impl From<io::Error> for KeyStoreError {
+    fn from(e: io::Error) -> Self {
+        KeyStoreError::Io(e)
+    }
+}
+
+impl From<ProofSystemError> for KeyStoreError {
+    fn from(e: ProofSystemError) -> Self {
+        KeyStoreError::ProofSystem(e)
+    }
+}
+
+// the keys of any proof system by id. the setup runs once per proof system and id, and the
+// keys are written to <dir>/<proof system>/<id>.pk and .vk. both are loaded lazily, a
+// verifier only ever reads the small vk file. the universal srs of marlin is kept next to
+// its index keys, as <dir>/marlin/<SrsBounds::id>.srs, and the srs of the aggregation of n
+// groth16 proofs as <dir>/aggregation/<curve>-<n>.srs.
+pub struct KeyStore {
+    dir: PathBuf,
+    srs_bounds: SrsBounds,
+    // marlin with the universal srs, once it is loaded or set up
+    marlin: Mutex<Option<MarlinBackend>>,
+    // by file path, a proving and a verifying key never share one. the values are the
+    // S::ProvingKey and S::VerifyingKey of the proof system in the path
+    keys: RwLock<HashMap<PathBuf, Arc<dyn Any + Send + Sync>>>,
+    // one setup at a time, so two requests for new keys don't both run it
+    setup_lock: Mutex<()>,
+}
+
+impl KeyStore {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            srs_bounds: SrsBounds::with_log_size(DEFAULT_SRS_LOG_SIZE),
+            marlin: Mutex::new(None),
+            keys: RwLock::new(HashMap::new()),
+            setup_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn with_srs_bounds(mut self, srs_bounds: SrsBounds) -> Self {
+        self.srs_bounds = srs_bounds;
+        self
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("ZK_KEY_DIR").unwrap_or_else(|_| DEFAULT_KEY_DIR.to_string()))
+            .with_srs_bounds(SrsBounds::from_env())
+    }
+
+    // marlin with the universal srs of the store, set up by the first request that proves
+    // with marlin and read from disk after a restart
+    pub fn marlin(&self) -> Result<MarlinBackend, KeyStoreError> {
+        let mut marlin = self.marlin.lock().unwrap();
+        if let Some(marlin) = marlin.as_ref() {
+            return Ok(marlin.clone());
+        }
+        let path = self.path::<MarlinBackend>(&self.srs_bounds.id(), "srs")?;
+        let loaded = if path.exists() {
+            MarlinBackend::srs_from_bytes(&fs::read(&path)?)?
+        } else {
+            let created = MarlinBackend::universal_setup(self.srs_bounds)?;
+            self.write(&path, &created.srs_to_bytes()?)?;
+            created
+        };
+        *marlin = Some(loaded.clone());
+        Ok(loaded)
+    }
+
+    // the srs of the aggregation of n proofs over E, n a power of two. it is set up by the
+    // first aggregation of that many proofs and read from disk after a restart
+    pub fn aggregation_srs<E: SnarkCurve>(
+        &self,
+        n: usize,
+    ) -> Result<Arc<AggregationSrs<E>>, KeyStoreError> {
+        self.load_aggregation_srs(n, true)
+    }
+
+    // the srs of an earlier aggregation, a verifier never sets one up
+    pub fn stored_aggregation_srs<E: SnarkCurve>(
+        &self,
+        n: usize,
+    ) -> Result<Arc<AggregationSrs<E>>, KeyStoreError> {
+        self.load_aggregation_srs(n, false)
+    }
+
+    fn load_aggregation_srs<E: SnarkCurve>(
+        &self,
+        n: usize,
+        setup: bool,
+    ) -> Result<Arc<AggregationSrs<E>>, KeyStoreError> {
+        let id = format!("{}-{}", E::CURVE, n);
+        let path = self.dir.join("aggregation").join(format!("{}.srs", id));
+        if let Some(srs) = self.cached(&path) {
+            return Ok(srs);
+        }
+        let _setup = self.setup_lock.lock().unwrap();
+        if let Some(srs) = self.cached(&path) {
+            return Ok(srs);
+        }
+        let srs = if path.exists() {
+            AggregationSrs::deserialize_compressed(&fs::read(&path)?[..])
+                .map_err(ProofSystemError::from)?
+        } else if setup {
+            let srs = AggregationSrs::setup(n, &mut OsRng);
+            let mut bytes = vec![];
+            srs.serialize_compressed(&mut bytes)
+                .map_err(ProofSystemError::from)?;
+            self.write(&path, &bytes)?;
+            srs
+        } else {
+            return Err(KeyStoreError::UnknownKey(format!("aggregation-{}", id)));
+        };
+        Ok(self.cache(path, srs))
+    }
+
+    // ids come from requests, so they may only name files inside the directory
+    fn path<S: ProofSystem>(&self, id: &str, extension: &str) -> Result<PathBuf, KeyStoreError> {
+        let valid = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(KeyStoreError::InvalidKeyId(id.to_string()));
+        }
+        Ok(self.dir.join(S::NAME).join(format!("{}.{}", id, extension)))
+    }
+
+    fn cached<T: Send + Sync + 'static>(&self, path: &Path) -> Option<Arc<T>> {
+        let key = self.keys.read().unwrap().get(path)?.clone();
+        key.downcast::<T>().ok()
+    }
+
+    fn cache<T: Send + Sync + 'static>(&self, path: PathBuf, key: T) -> Arc<T> {
+        let key = Arc::new(key);
+        self.keys.write().unwrap().insert(path, key.clone());
+        key
+    }
+
+    // the proving key of the circuit, from memory, from disk or from a new setup
+    pub fn proving_key<S: Prover<C>, C>(
+        &self,
+        system: &S,
+        id: &str,
+        circuit: C,
+    ) -> Result<Arc<S::ProvingKey>, KeyStoreError> {
+        let pk_path = self.path::<S>(id, "pk")?;
+        if let Some(pk) = self.cached(&pk_path) {
+            return Ok(pk);
+        }
+        let _setup = self.setup_lock.lock().unwrap();
+        if let Some(pk) = self.cached(&pk_path) {
+            return Ok(pk);
+        }
+        let pk = if pk_path.exists() {
+            S::proving_key_from_bytes(&fs::read(&pk_path)?)?
+        } else {
+            let (pk, vk) = system.setup(circuit)?;
+            self.write(&self.path::<S>(id, "vk")?, &S::verifying_key_to_bytes(&vk)?)?;
+            self.write(&pk_path, &S::proving_key_to_bytes(&pk)?)?;
+            pk
+        };
+        Ok(self.cache(pk_path, pk))
+    }
+
+    // the verifying key of keys that were set up before, by this process or an earlier one
+    pub fn verifying_key<S: ProofSystem>(
+        &self,
+        id: &str,
+    ) -> Result<Arc<S::VerifyingKey>, KeyStoreError> {
+        let vk_path = self.path::<S>(id, "vk")?;
+        if let Some(vk) = self.cached(&vk_path) {
+            return Ok(vk);
+        }
+        if !vk_path.exists() {
+            return Err(KeyStoreError::UnknownKey(id.to_string()));
+        }
+        let vk = S::verifying_key_from_bytes(&fs::read(&vk_path)?)?;
+        Ok(self.cache(vk_path, vk))
+    }
+
+    // write to a temporary file first, a crash never leaves half a key behind
+    fn write(&self, path: &Path, bytes: &[u8]) -> Result<(), KeyStoreError> {
+        fs::create_dir_all(path.parent().unwrap_or(&self.dir))?;
+        let tmp = path.with_extension("tmp");
+        let mut file = File::create(&tmp)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+}
+
+// a directory of its own under the temp dir for a test, so concurrent test runs and the keys
+// left by earlier runs aren't shared. it is removed when dropped, also when the test fails
+#[cfg(test)]
+pub(crate) struct TestDir(pub PathBuf);
+
+#[cfg(test)]
+impl TestDir {
+    pub(crate) fn new(name: &str) -> Self {
+        let suffix = format!("{}_{:016x}", std::process::id(), rand::random::<u64>());
+        TestDir(std::env::temp_dir().join(format!("{}_{}", name, suffix)))
+    }
+
+    pub(crate) fn keys(&self) -> KeyStore {
+        KeyStore::new(&self.0)
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::backend::fibbonaci_handler::fibonacci;
+    use crate::arkworks::constraints::fibbonaci::FibonacciCircuit;
+    use crate::arkworks::proof_system::Groth16Backend;
+    use ark_bls12_381::Fr;
+
+    fn circuit(a: u64, b: u64, steps: usize) -> FibonacciCircuit<Fr> {
+        let (a, b) = (Fr::from(a), Fr::from(b));
+        FibonacciCircuit {
+            a: Some(a),
+            b: Some(b),
+            num_of_steps: steps,
+            result: Some(fibonacci(steps, a, b)),
+        }
+    }
+
+    fn store(name: &str) -> (TestDir, KeyStore) {
+        let dir = TestDir::new(&format!("key_store_{}", name));
+        let store = dir.keys();
+        (dir, store)
+    }
+
+    #[test]
+    fn test_keys_are_reused() {
+        let (test_dir, store) = store("reuse");
+        let dir = &test_dir.0;
+        let id = key_id("fibonacci", &["5".to_string()]);
+        assert_eq!(id, "fibonacci-5");
+        let groth16 = Groth16Backend::default();
+        let pk = store.proving_key(&groth16, &id, circuit(0, 1, 5)).unwrap();
+        assert!(Arc::ptr_eq(
+            &pk,
+            &store.proving_key(&groth16, &id, circuit(0, 1, 5)).unwrap()
+        ));
+        assert!(dir.join("groth16").join("fibonacci-5.pk").exists());
+
+        // a new store (a restarted server) loads the same keys from disk
+        let restarted = KeyStore::new(&dir);
+        let pvk = restarted.verifying_key::<Groth16Backend>(&id).unwrap();
+        let loaded = restarted
+            .proving_key(&groth16, &id, circuit(0, 1, 5))
+            .unwrap();
+        assert_eq!(loaded.vk, pk.vk);
+
+        // the keys of the shape prove other inputs too
+        let proof = groth16.prove(&loaded, circuit(2, 3, 5)).unwrap();
+        let public_input = [
+            Fr::from(2u64),
+            Fr::from(3u64),
+            fibonacci(5, Fr::from(2u64), Fr::from(3u64)),
+        ];
+        assert!(groth16.verify(&pvk, &public_input, &proof).unwrap());
+    }
+
+    #[test]
+    fn test_marlin_srs_is_persisted() {
+        let (test_dir, store) = store("srs");
+        let dir = &test_dir.0;
+        let bounds = SrsBounds::with_log_size(8);
+        let store = store.with_srs_bounds(bounds);
+        let id = key_id("fibonacci", &["5".to_string()]);
+        let marlin = store.marlin().unwrap();
+        let pk = store.proving_key(&marlin, &id, circuit(0, 1, 5)).unwrap();
+        assert!(dir
+            .join("marlin")
+            .join(format!("{}.srs", bounds.id()))
+            .exists());
+        assert!(dir.join("marlin").join("fibonacci-5.pk").exists());
+
+        // a restarted server reads the srs back and indexes new circuits against it
+        let restarted = KeyStore::new(&dir).with_srs_bounds(bounds);
+        let marlin = restarted.marlin().unwrap();
+        let vk = restarted.verifying_key::<MarlinBackend>(&id).unwrap();
+        let proof = marlin.prove(&pk, circuit(2, 3, 5)).unwrap();
+        let public_input = [
+            Fr::from(2u64),
+            Fr::from(3u64),
+            fibonacci(5, Fr::from(2u64), Fr::from(3u64)),
+        ];
+        assert!(MarlinBackend::default()
+            .verify(&vk, &public_input, &proof)
+            .unwrap());
+        let id = key_id("fibonacci", &["9".to_string()]);
+        assert!(restarted
+            .proving_key(&marlin, &id, circuit(0, 1, 9))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_unknown_and_invalid_ids() {
+        let (_, store) = store("ids");
+        assert!(matches!(
+            store.verifying_key::<Groth16Backend>("fibonacci-7"),
+            Err(KeyStoreError::UnknownKey(_))
+        ));
+        assert!(matches!(
+            store.verifying_key::<Groth16Backend>("../fibonacci-7"),
+            Err(KeyStoreError::InvalidKeyId(_))
+        ));
+        assert!(matches!(
+            store.verifying_key::<Groth16Backend>(""),
+            Err(KeyStoreError::InvalidKeyId(_))
+        ));
+    }
+}
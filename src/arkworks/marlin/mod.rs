@@ -1,2 +1,3 @@
+pub mod proof_system;
 pub mod rng;
 pub mod test;
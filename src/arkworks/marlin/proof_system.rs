@@ -0,0 +1,220 @@
+use crate::arkworks::marlin::rng::SimplePoseidonRng;
+use crate::proof_system::{ProofSystem, ProofSystemError, Prover};
+use ark_bls12_381::{Bls12_381, Fr};
+use ark_crypto_primitives::sponge::CryptographicSponge;
+use ark_marlin::{Error, IndexProverKey, IndexVerifierKey, Marlin, Proof};
+use ark_poly::polynomial::univariate::DensePolynomial;
+use ark_poly_commit::marlin_pc::MarlinKZG10;
+use ark_poly_commit::PolynomialCommitment;
+use ark_relations::r1cs::ConstraintSynthesizer;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand::{rngs::OsRng, RngCore};
+use std::sync::Arc;
+
+type S = SimplePoseidonRng<Fr>;
+type MultiPC = MarlinKZG10<Bls12_381, DensePolynomial<Fr>, S>;
+type MarlinInst = Marlin<Fr, MultiPC, S>;
+type UniversalSrs = <MultiPC as PolynomialCommitment<Fr, DensePolynomial<Fr>, S>>::UniversalParams;
+
+// the size of the universal srs when ZK_MARLIN_SRS_LOG_SIZE isn't set
+pub const DEFAULT_SRS_LOG_SIZE: u32 = 16;
+
+// a sponge rng that starts from 32 bytes of fresh entropy, for the setup, the zero knowledge of
+// the prover and the batching challenges of the verifier. marlin takes the sponge as its rng, so
+// OsRng can't be passed directly
+fn os_rng() -> S {
+    let mut seed = [0u8; 32];
+    OsRng.fill_bytes(&mut seed);
+    let mut rng = S::default();
+    rng.absorb(&seed.as_slice());
+    rng
+}
+
+fn backend_error<E: std::fmt::Debug>(e: E) -> ProofSystemError {
+    ProofSystemError::Backend(format!("marlin: {:?}", e))
+}
+
+// the largest circuits the universal srs can index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SrsBounds {
+    pub num_constraints: usize,
+    pub num_variables: usize,
+    pub num_non_zero: usize,
+}
+
+impl SrsBounds {
+    // 2^log_size constraints and variables. the matrices of the circuits here have about two
+    // non-zero entries per constraint
+    pub fn with_log_size(log_size: u32) -> Self {
+        Self {
+            num_constraints: 1 << log_size,
+            num_variables: 1 << log_size,
+            num_non_zero: 1 << (log_size + 1),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let log_size = std::env::var("ZK_MARLIN_SRS_LOG_SIZE")
+            .ok()
+            .and_then(|size| size.parse().ok())
+            .unwrap_or(DEFAULT_SRS_LOG_SIZE);
+        Self::with_log_size(log_size)
+    }
+
+    // the name of the srs file, a store keeps one srs per bounds
+    pub fn id(&self) -> String {
+        format!(
+            "universal-{}-{}-{}",
+            self.num_constraints, self.num_variables, self.num_non_zero
+        )
+    }
+}
+
+// Marlin over BLS12-381 with the KZG10 commitments of ark_poly_commit and the Poseidon sponge
+// of marlin::rng for Fiat-Shamir. the universal srs is set up once, every circuit is then
+// indexed against it without a setup of its own. the default backend has no srs, it can
+// only verify.
+#[derive(Clone, Default)]
+pub struct MarlinBackend {
+    srs: Option<Arc<UniversalSrs>>,
+}
+
+impl MarlinBackend {
+    pub fn universal_setup(bounds: SrsBounds) -> Result<Self, ProofSystemError> {
+        let srs = MarlinInst::universal_setup(
+            bounds.num_constraints,
+            bounds.num_variables,
+            bounds.num_non_zero,
+            &mut os_rng(),
+        )
+        .map_err(backend_error)?;
+        Ok(Self {
+            srs: Some(Arc::new(srs)),
+        })
+    }
+
+    // the srs is big and only ever read back by the key store, like the proving keys
+    pub fn srs_to_bytes(&self) -> Result<Vec<u8>, ProofSystemError> {
+        let srs = self.srs()?;
+        let mut bytes = Vec::new();
+        srs.serialize_uncompressed(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    pub fn srs_from_bytes(bytes: &[u8]) -> Result<Self, ProofSystemError> {
+        let srs = UniversalSrs::deserialize_uncompressed_unchecked(bytes)?;
+        Ok(Self {
+            srs: Some(Arc::new(srs)),
+        })
+    }
+
+    fn srs(&self) -> Result<&UniversalSrs, ProofSystemError> {
+        self.srs.as_deref().ok_or_else(|| {
+            ProofSystemError::Backend("marlin: indexing needs the universal srs".to_string())
+        })
+    }
+}
+
+impl ProofSystem for MarlinBackend {
+    const NAME: &'static str = "marlin";
+
+    type ProvingKey = IndexProverKey<Fr, MultiPC, S>;
+    type VerifyingKey = IndexVerifierKey<Fr, MultiPC, S>;
+    type Proof = Proof<Fr, MultiPC, S>;
+    type PublicInput = [Fr];
+
+    fn verify(
+        &self,
+        vk: &Self::VerifyingKey,
+        public_input: &[Fr],
+        proof: &Self::Proof,
+    ) -> Result<bool, ProofSystemError> {
+        MarlinInst::verify(vk, public_input, proof, &mut os_rng()).map_err(backend_error)
+    }
+
+    fn proving_key_to_bytes(pk: &Self::ProvingKey) -> Result<Vec<u8>, ProofSystemError> {
+        let mut bytes = Vec::new();
+        pk.serialize_uncompressed(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn proving_key_from_bytes(bytes: &[u8]) -> Result<Self::ProvingKey, ProofSystemError> {
+        Ok(IndexProverKey::deserialize_uncompressed_unchecked(bytes)?)
+    }
+
+    fn verifying_key_to_bytes(vk: &Self::VerifyingKey) -> Result<Vec<u8>, ProofSystemError> {
+        let mut bytes = Vec::new();
+        vk.serialize_compressed(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn verifying_key_from_bytes(bytes: &[u8]) -> Result<Self::VerifyingKey, ProofSystemError> {
+        Ok(IndexVerifierKey::deserialize_compressed(bytes)?)
+    }
+
+    fn proof_to_bytes(proof: &Self::Proof) -> Result<Vec<u8>, ProofSystemError> {
+        let mut bytes = Vec::new();
+        proof.serialize_compressed(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn proof_from_bytes(bytes: &[u8]) -> Result<Self::Proof, ProofSystemError> {
+        Ok(Proof::deserialize_compressed(bytes)?)
+    }
+}
+
+// the setup of a circuit is its index, the srs stays the same
+impl<C: ConstraintSynthesizer<Fr>> Prover<C> for MarlinBackend {
+    fn setup(
+        &self,
+        circuit: C,
+    ) -> Result<(Self::ProvingKey, Self::VerifyingKey), ProofSystemError> {
+        MarlinInst::index(self.srs()?, circuit).map_err(|e| match e {
+            Error::IndexTooLarge => ProofSystemError::Backend(
+                "marlin: the circuit is larger than the universal srs, \
+                 raise ZK_MARLIN_SRS_LOG_SIZE"
+                    .to_string(),
+            ),
+            e => backend_error(e),
+        })
+    }
+
+    fn prove(&self, pk: &Self::ProvingKey, circuit: C) -> Result<Self::Proof, ProofSystemError> {
+        MarlinInst::prove(pk, circuit, &mut os_rng()).map_err(backend_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::backend::fibbonaci_handler::fibonacci;
+    use crate::arkworks::constraints::fibbonaci::FibonacciCircuit;
+
+    fn circuit(steps: usize) -> FibonacciCircuit<Fr> {
+        let (a, b) = (Fr::from(1u64), Fr::from(1u64));
+        FibonacciCircuit {
+            a: Some(a),
+            b: Some(b),
+            num_of_steps: steps,
+            result: Some(fibonacci(steps, a, b)),
+        }
+    }
+
+    #[test]
+    fn test_one_srs_indexes_many_circuits() {
+        let marlin = MarlinBackend::universal_setup(SrsBounds::with_log_size(8)).unwrap();
+        let marlin = MarlinBackend::srs_from_bytes(&marlin.srs_to_bytes().unwrap()).unwrap();
+        for steps in [4, 16, 64] {
+            let (pk, vk) = marlin.setup(circuit(steps)).unwrap();
+            let proof = marlin.prove(&pk, circuit(steps)).unwrap();
+            let (a, b) = (Fr::from(1u64), Fr::from(1u64));
+            assert!(marlin
+                .verify(&vk, &[a, b, fibonacci(steps, a, b)], &proof)
+                .unwrap());
+        }
+
+        // a circuit over the bounds and a backend without an srs can't be indexed
+        assert!(marlin.setup(circuit(1 << 9)).is_err());
+        assert!(MarlinBackend::default().setup(circuit(4)).is_err());
+    }
+}
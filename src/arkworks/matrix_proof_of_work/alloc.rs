@@ -50,3 +50,21 @@ impl<F: PrimeField> AllocVar<Vec<Vec<u64>>, F> for FpVar2DVec<F> {
         Ok(FpVar2DVec(vec2d))
     }
 }
+
+// allocates a matrix of field elements (e.g. an inverse or an LU factor) in our constrains system:
+pub fn new_field_matrix<F: PrimeField>(
+    cs: impl Into<Namespace<F>>,
+    value: &[Vec<F>],
+    mode: AllocationMode,
+) -> Result<FpVar2DVec<F>, SynthesisError> {
+    let cs = cs.into();
+    let mut vec2d = Vec::with_capacity(value.len());
+    for row in value {
+        let mut vec = Vec::with_capacity(row.len());
+        for cell in row {
+            vec.push(FpVar::new_variable(cs.clone(), || Ok(*cell), mode)?);
+        }
+        vec2d.push(vec);
+    }
+    Ok(FpVar2DVec(vec2d))
+}
@@ -1,6 +1,9 @@
-use crate::arkworks::matrix_proof_of_work::hasher::{hasher, hasher_var};
+use crate::arkworks::matrix_proof_of_work::hasher::{hasher, hasher_native, hasher_var};
 use crate::arkworks::matrix_proof_of_work::io::{read_proof, write_proof_to_file};
+use crate::arkworks::matrix_proof_of_work::linear_algebra::to_field_matrix;
+use crate::arkworks::matrix_proof_of_work::merkle::{merkle_root_native, merkle_root_var};
 use ark_bls12_381::Fr;
+use ark_crypto_primitives::sponge::Absorb;
 use ark_ff::PrimeField;
 use ark_r1cs_std::fields::fp::FpVar;
 use ark_r1cs_std::fields::FieldVar;
@@ -16,9 +19,12 @@ use super::alloc::FpVarVec;
 pub struct MatrixCircuit<F: PrimeField> {
     matrix_a: Vec<Vec<u64>>, // witness
     matrix_b: Vec<Vec<u64>>, // witness
-    hash_of_a: F,            // public input
-    hash_of_b: F,            // public input
-    hash_of_c: F,            // public input
+    blinding_a: F,           // witness, the seed of the row salts of a
+    blinding_b: F,           // witness
+    blinding_c: F,           // witness
+    com_a: F,                // public input, the row merkle root of a
+    com_b: F,                // public input
+    com_c: F,                // public input
 }
 #[derive(Clone, Debug)]
 pub struct MatrixCircuit2<F: PrimeField> {
@@ -29,36 +35,67 @@ pub struct MatrixCircuit2<F: PrimeField> {
     num_variables: usize,
 }
 
-// implement new for MatrixCircuit:
-impl<F: PrimeField> MatrixCircuit<F> {
-    pub fn new(
-        matrix_a: Vec<Vec<u64>>,
-        matrix_b: Vec<Vec<u64>>,
-        hash_of_a: F,
-        hash_of_b: F,
-        hash_of_c: F,
-    ) -> Self {
+// implement commit for MatrixCircuit, the commitments are the row merkle roots (see merkle.rs):
+impl<F: PrimeField + Absorb> MatrixCircuit<F> {
+    pub fn commit(matrix_a: Vec<Vec<u64>>, matrix_b: Vec<Vec<u64>>, blindings: [F; 3]) -> Self {
+        let matrix_c = matrix_mul_native(&matrix_a, &matrix_b);
+        let com_a = merkle_root_native(&to_field_matrix::<F>(&matrix_a), blindings[0]);
+        let com_b = merkle_root_native(&to_field_matrix::<F>(&matrix_b), blindings[1]);
+        let com_c = merkle_root_native(&matrix_c, blindings[2]);
         Self {
             matrix_a,
             matrix_b,
-            hash_of_a,
-            hash_of_b,
-            hash_of_c,
+            blinding_a: blindings[0],
+            blinding_b: blindings[1],
+            blinding_c: blindings[2],
+            com_a,
+            com_b,
+            com_c,
         }
     }
 }
+
+impl<F: PrimeField> MatrixCircuit<F> {
+    // the public inputs in the order the verifier expects them: [com_a, com_b, com_c]
+    pub fn commitments(&self) -> [F; 3] {
+        [self.com_a, self.com_b, self.com_c]
+    }
+}
 // implement clone for MatrixCircuit
 impl<ConstraintF: PrimeField> Clone for MatrixCircuit<ConstraintF> {
     fn clone(&self) -> Self {
         Self {
             matrix_a: self.matrix_a.clone(),
             matrix_b: self.matrix_b.clone(),
-            hash_of_a: self.hash_of_a.clone(),
-            hash_of_b: self.hash_of_b.clone(),
-            hash_of_c: self.hash_of_c.clone(),
+            blinding_a: self.blinding_a,
+            blinding_b: self.blinding_b,
+            blinding_c: self.blinding_c,
+            com_a: self.com_a,
+            com_b: self.com_b,
+            com_c: self.com_c,
         }
     }
 }
+
+// multiply two u64 matrices over the field, outside of the circuit
+pub fn matrix_mul_native<F: PrimeField>(
+    matrix_a: &[Vec<u64>],
+    matrix_b: &[Vec<u64>],
+) -> Vec<Vec<F>> {
+    let a = to_field_matrix::<F>(matrix_a);
+    let b = to_field_matrix::<F>(matrix_b);
+    let n = a.len();
+    let mut c = vec![vec![F::zero(); n]; n];
+    for i in 0..n {
+        for j in 0..n {
+            for k in 0..n {
+                c[i][j] += a[i][k] * b[k][j];
+            }
+        }
+    }
+    c
+}
+
 // create a function that thake 2 matrix and multiply them
 // Matrixa is  2DFpVar
 // Matrixb is  2DFpVar
@@ -84,7 +121,8 @@ pub fn matrix_mul<F: PrimeField>(
     let mut matrix_c = FpVar2DVec::new_witness(cs.clone(), || Ok(vec![vec![0u64; n]; n])).unwrap();
     for i in 0..n {
         for j in 0..n {
-            let mut sum = FpVar::<F>::new_witness(cs.clone(), || Ok(F::zero())).unwrap();
+            // start from the constant zero - a free witness here would let the prover shift c_ij
+            let mut sum = FpVar::<F>::zero();
             for k in 0..n {
                 let ij = matrix_a.get_element(i, k).unwrap();
                 let jk = matrix_b.get_element(k, j).unwrap();
@@ -98,30 +136,33 @@ pub fn matrix_mul<F: PrimeField>(
     matrix_c
 }
 
-impl<F: PrimeField> ConstraintSynthesizer<F> for MatrixCircuit<F> {
+impl<F: PrimeField + Absorb> ConstraintSynthesizer<F> for MatrixCircuit<F> {
     fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
-        let hash_public_input_a = FpVar::<F>::new_input(cs.clone(), || Ok(self.hash_of_a)).unwrap();
-        let hash_public_input_b = FpVar::<F>::new_input(cs.clone(), || Ok(self.hash_of_b)).unwrap();
+        let com_public_input_a = FpVar::<F>::new_input(cs.clone(), || Ok(self.com_a)).unwrap();
+        let com_public_input_b = FpVar::<F>::new_input(cs.clone(), || Ok(self.com_b)).unwrap();
 
         let matrix_a_var: FpVar2DVec<F> =
             FpVar2DVec::new_witness(cs.clone(), || Ok(self.matrix_a)).unwrap();
         let matrix_b_var: FpVar2DVec<F> =
             FpVar2DVec::new_witness(cs.clone(), || Ok(self.matrix_b)).unwrap();
-        // hash matrix a:
-        let hash_a = &hasher_var::<F>(cs.clone(), &matrix_a_var).unwrap()[0];
-        // hash matrix b:
-        let hash_b = &hasher_var::<F>(cs.clone(), &matrix_b_var).unwrap()[0];
-        hash_a.enforce_equal(&hash_public_input_a).unwrap();
-        hash_b.enforce_equal(&hash_public_input_b).unwrap();
+        let blinding_a = FpVar::<F>::new_witness(cs.clone(), || Ok(self.blinding_a)).unwrap();
+        let blinding_b = FpVar::<F>::new_witness(cs.clone(), || Ok(self.blinding_b)).unwrap();
+        let blinding_c = FpVar::<F>::new_witness(cs.clone(), || Ok(self.blinding_c)).unwrap();
+        // commit to matrix a:
+        let com_a = merkle_root_var::<F>(cs.clone(), &matrix_a_var, &blinding_a).unwrap();
+        // commit to matrix b:
+        let com_b = merkle_root_var::<F>(cs.clone(), &matrix_b_var, &blinding_b).unwrap();
+        com_a.enforce_equal(&com_public_input_a).unwrap();
+        com_b.enforce_equal(&com_public_input_b).unwrap();
 
         let matrix_c_var = matrix_mul(cs.clone(), matrix_a_var.clone(), matrix_b_var.clone());
 
-        // hash the matrix c
-        let hash_c = &hasher_var::<F>(cs.clone(), &matrix_c_var).unwrap()[0];
+        // commit to the matrix c
+        let com_c = merkle_root_var::<F>(cs.clone(), &matrix_c_var, &blinding_c).unwrap();
 
-        let hash_public_input_c = FpVar::<F>::new_input(cs.clone(), || Ok(self.hash_of_c)).unwrap();
+        let com_public_input_c = FpVar::<F>::new_input(cs.clone(), || Ok(self.com_c)).unwrap();
 
-        hash_c.enforce_equal(&hash_public_input_c).unwrap();
+        com_c.enforce_equal(&com_public_input_c).unwrap();
 
         Ok(())
     }
@@ -164,6 +205,7 @@ mod tests {
     use ark_snark::{CircuitSpecificSetupSNARK, SNARK};
     use ark_std::ops::MulAssign;
     use ark_std::test_rng;
+    use ark_std::UniformRand;
     use itertools::Itertools;
     use rand::{RngCore, SeedableRng};
     use tracing_subscriber::layer::SubscriberExt;
@@ -193,63 +235,43 @@ mod tests {
         assert_eq!(matrix_c.0[1][0].value().unwrap(), Fp::from(2u64));
         assert_eq!(matrix_c.0[1][1].value().unwrap(), Fp::from(2u64));
     }
+    // random blindings for the three commitments
+    fn random_blindings() -> [Fp; 3] {
+        let rng = &mut test_rng();
+        [Fp::rand(rng), Fp::rand(rng), Fp::rand(rng)]
+    }
+
     #[test]
     fn is_satisfied_constraints() {
-        let rng = &mut test_rng();
         let cs = ConstraintSystem::<Fp>::new_ref();
 
-        // matrix A :
-        //  (1 0
-        //   0 1)
-        // create matrix A:
         let matrix_a = vec![vec![1u64, 2], vec![3, 4]];
         let matrix_b = vec![vec![4u64, 3], vec![2, 1]];
-        let matrix_a_var = FpVar2DVec::new_witness(cs.clone(), || Ok(matrix_a.clone())).unwrap();
-        let matrix_b_var = FpVar2DVec::new_witness(cs.clone(), || Ok(matrix_b.clone())).unwrap();
         println!("matrix_a: {:?}", matrix_a);
         println!("matrix_b: {:?}", matrix_b);
 
-        let matrix_c = matrix_mul(cs.clone(), matrix_a_var.clone(), matrix_b_var.clone());
-        // create hash_a :
-        let hash_a = hasher(&matrix_a_var).unwrap()[0];
-        // create hash_b :
-        let hash_b = hasher(&matrix_b_var).unwrap()[0];
         // create a new instance of the MatrixCircuit
-        let hash = hasher(&matrix_c).unwrap();
-        // take the first element of the hash
-        let hash_value = hash[0];
-        let circuit = MatrixCircuit {
-            matrix_a,
-            matrix_b,
-            hash_of_c: hash_value,
-            hash_of_a: hash_a,
-            hash_of_b: hash_b,
-        };
+        let circuit = MatrixCircuit::commit(matrix_a, matrix_b, random_blindings());
         circuit.generate_constraints(cs.clone()).unwrap();
         assert!(cs.is_satisfied().unwrap());
     }
     #[test]
-    fn groth16_correctness_and_soundness() {
+    fn wrong_blinding_is_not_satisfied() {
         let cs = ConstraintSystem::<Fp>::new_ref();
+        let matrix_a = vec![vec![1u64, 2], vec![3, 4]];
+        let matrix_b = vec![vec![4u64, 3], vec![2, 1]];
+        let mut circuit = MatrixCircuit::commit(matrix_a, matrix_b, random_blindings());
+        // the commitment to c doesn't open with another blinding
+        circuit.blinding_c += Fp::from(1u64);
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+    #[test]
+    fn groth16_correctness_and_soundness() {
         let matrix_a = vec![vec![1u64, 2], vec![3, 4]]; // witness
         let matrix_b = vec![vec![4u64, 3], vec![2, 1]]; // witness
-                                                        // multiply the matrices
-        let matrix_a_var = FpVar2DVec::new_witness(cs.clone(), || Ok(matrix_a.clone())).unwrap();
-        let matrix_b_var = FpVar2DVec::new_witness(cs.clone(), || Ok(matrix_b.clone())).unwrap();
-        let matrix_c = matrix_mul(cs.clone(), matrix_a_var.clone(), matrix_b_var.clone());
-        // create hash_a :
-        let hash_a = hasher(&matrix_a_var).unwrap()[0];
-        // create hash_b :
-        let hash_b = hasher(&matrix_b_var).unwrap()[0];
-        // create a new instance of the MatrixCircuit
-        let hash_c = hasher(&matrix_c).unwrap()[0];
-        let circuit = MatrixCircuit {
-            matrix_a,
-            matrix_b,
-            hash_of_c: hash_c,
-            hash_of_a: hash_a,
-            hash_of_b: hash_b,
-        };
+        let circuit = MatrixCircuit::commit(matrix_a, matrix_b, random_blindings());
+        let [com_a, com_b, com_c] = circuit.commitments();
 
         // generate the proof
         let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
@@ -265,14 +287,22 @@ mod tests {
         // test some verification checks
         assert!(Groth16::<Bls12_381>::verify_with_processed_vk(
             &pvk,
-            &[hash_a, hash_b, hash_c],
-            &proof
+            &[com_a, com_b, com_c],
+            &read_proof
+        )
+        .unwrap());
+        // the proof doesn't verify against the plain hash of c
+        let matrix_c =
+            matrix_mul_native::<Fp>(&[vec![1u64, 2], vec![3, 4]], &[vec![4u64, 3], vec![2, 1]]);
+        assert!(!Groth16::<Bls12_381>::verify_with_processed_vk(
+            &pvk,
+            &[com_a, com_b, hasher_native(&matrix_c)],
+            &read_proof
         )
         .unwrap());
     }
     #[test]
     fn big_matrix_20x20() {
-        let cs = ConstraintSystem::<Fp>::new_ref();
         // create vector of vectors of size 20x20:
         let mut matrix_a = vec![vec![0u64; 20]; 20];
         let mut matrix_b = vec![vec![0u64; 20]; 20];
@@ -283,42 +313,23 @@ mod tests {
                 matrix_b[i][j] = j as u64;
             }
         }
-        // multiply the matrices
-        let matrix_a_var = FpVar2DVec::new_witness(cs.clone(), || Ok(matrix_a.clone())).unwrap();
-        let matrix_b_var = FpVar2DVec::new_witness(cs.clone(), || Ok(matrix_b.clone())).unwrap();
-        let matrix_c = matrix_mul(cs.clone(), matrix_a_var.clone(), matrix_b_var.clone());
-        // create hash_a :
-        let hash_a = hasher(&matrix_a_var).unwrap()[0];
-        // create hash_b :
-        let hash_b = hasher(&matrix_b_var).unwrap()[0];
-        // create a new instance of the MatrixCircuit
-        let hash_c = hasher(&matrix_c).unwrap()[0];
-        let circuit = MatrixCircuit {
-            matrix_a,
-            matrix_b,
-            hash_of_c: hash_c,
-            hash_of_a: hash_a,
-            hash_of_b: hash_b,
-        };
+        let circuit = MatrixCircuit::commit(matrix_a, matrix_b, random_blindings());
 
         // generate the proof
         let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
         let (pk, vk) = Groth16::<Bls12_381>::setup(circuit.clone(), &mut rng).unwrap();
         let pvk = Groth16::<Bls12_381>::process_vk(&vk).unwrap();
+        let commitments = circuit.commitments();
         let proof: Proof<Bls12<Config>> =
             Groth16::<Bls12_381>::prove(&pk, circuit, &mut rng).unwrap();
 
         // test some verification checks
-        assert!(Groth16::<Bls12_381>::verify_with_processed_vk(
-            &pvk,
-            &[hash_a, hash_b, hash_c],
-            &proof
-        )
-        .unwrap());
+        assert!(
+            Groth16::<Bls12_381>::verify_with_processed_vk(&pvk, &commitments, &proof).unwrap()
+        );
     }
     #[test]
     fn big_matrix_10x10() {
-        let cs = ConstraintSystem::<Fp>::new_ref();
         // generate random matrix of size 10x10
         let rng = &mut test_rng();
         let mut matrix_a = vec![vec![0u64; 10]; 10];
@@ -329,38 +340,20 @@ mod tests {
                 matrix_b[i][j] = rng.next_u64();
             }
         }
-        // multiply the matrices
-        let matrix_a_var = FpVar2DVec::new_witness(cs.clone(), || Ok(matrix_a.clone())).unwrap();
-        let matrix_b_var = FpVar2DVec::new_witness(cs.clone(), || Ok(matrix_b.clone())).unwrap();
-        let matrix_c = matrix_mul(cs.clone(), matrix_a_var.clone(), matrix_b_var.clone());
-        // create hash_a :
-        let hash_a = hasher(&matrix_a_var).unwrap()[0];
-        // create hash_b :
-        let hash_b = hasher(&matrix_b_var).unwrap()[0];
-        // create a new instance of the MatrixCircuit
-        let hash_c = hasher(&matrix_c).unwrap()[0];
-        let circuit = MatrixCircuit {
-            matrix_a,
-            matrix_b,
-            hash_of_c: hash_c,
-            hash_of_a: hash_a,
-            hash_of_b: hash_b,
-        };
+        let circuit = MatrixCircuit::commit(matrix_a, matrix_b, random_blindings());
 
         // generate the proof
         let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
         let (pk, vk) = Groth16::<Bls12_381>::setup(circuit.clone(), &mut rng).unwrap();
         let pvk = Groth16::<Bls12_381>::process_vk(&vk).unwrap();
+        let commitments = circuit.commitments();
         let proof: Proof<Bls12<Config>> =
             Groth16::<Bls12_381>::prove(&pk, circuit, &mut rng).unwrap();
 
         // test some verification checks
-        assert!(Groth16::<Bls12_381>::verify_with_processed_vk(
-            &pvk,
-            &[hash_a, hash_b, hash_c],
-            &proof
-        )
-        .unwrap());
+        assert!(
+            Groth16::<Bls12_381>::verify_with_processed_vk(&pvk, &commitments, &proof).unwrap()
+        );
     }
     #[test]
     fn marlin_proof_system() {
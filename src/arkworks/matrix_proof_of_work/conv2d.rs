@@ -0,0 +1,343 @@
+use crate::arkworks::matrix_proof_of_work::alloc::{new_field_matrix, FpVar2DVec};
+use crate::arkworks::matrix_proof_of_work::hasher::{hasher_native, hasher_var};
+use crate::arkworks::matrix_proof_of_work::linear_algebra::to_field_matrix;
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::fields::FieldVar;
+use ark_r1cs_std::prelude::{AllocVar, AllocationMode, EqGadget};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use serde::{Deserialize, Serialize};
+
+// 2D convolution Y = X * K of an h x w image X with a kh x kw kernel K.
+// like the conv layers of image processing / ML libraries this is a cross-correlation
+// (the kernel isn't flipped):
+//   Y[i][j] = sum_{a,b} K[a][b] * X[i * stride + a - top][j * stride + b - left]
+// where out of range pixels are zero.
+// the pixels are integers, the kernel is fixed-point (see fixed_point.rs), so Y has the
+// fractional bits of the kernel.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Padding {
+    // only the positions where the kernel fits inside the image
+    Valid,
+    // ceil(h / stride) x ceil(w / stride) outputs, the image is padded with zeros evenly
+    // (the extra zero goes to the bottom / right, like tensorflow)
+    Same,
+}
+
+// the output size and the zero padding before the first pixel, for one axis
+fn output_len(input: usize, kernel: usize, stride: usize, padding: Padding) -> (usize, usize) {
+    match padding {
+        Padding::Valid => ((input - kernel) / stride + 1, 0),
+        Padding::Same => {
+            let out = (input + stride - 1) / stride;
+            let pad = ((out - 1) * stride + kernel).saturating_sub(input);
+            (out, pad / 2)
+        }
+    }
+}
+
+// the shape of Y, None if the kernel doesn't fit
+pub fn conv2d_shape(
+    image: (usize, usize),
+    kernel: (usize, usize),
+    stride: usize,
+    padding: Padding,
+) -> Option<(usize, usize)> {
+    if stride == 0 || image.0 == 0 || image.1 == 0 || kernel.0 == 0 || kernel.1 == 0 {
+        return None;
+    }
+    if padding == Padding::Valid && (kernel.0 > image.0 || kernel.1 > image.1) {
+        return None;
+    }
+    let (out_h, _) = output_len(image.0, kernel.0, stride, padding);
+    let (out_w, _) = output_len(image.1, kernel.1, stride, padding);
+    Some((out_h, out_w))
+}
+
+// visits every (output, kernel, pixel) triple of the convolution that hits the image,
+// shared by the native reference and the circuit so they index the same way
+fn for_each_tap(
+    image: (usize, usize),
+    kernel: (usize, usize),
+    stride: usize,
+    padding: Padding,
+    mut f: impl FnMut((usize, usize), (usize, usize), (usize, usize)),
+) {
+    let (out_h, top) = output_len(image.0, kernel.0, stride, padding);
+    let (out_w, left) = output_len(image.1, kernel.1, stride, padding);
+    for i in 0..out_h {
+        for j in 0..out_w {
+            for a in 0..kernel.0 {
+                for b in 0..kernel.1 {
+                    let x = (i * stride + a).checked_sub(top);
+                    let y = (j * stride + b).checked_sub(left);
+                    if let (Some(x), Some(y)) = (x, y) {
+                        if x < image.0 && y < image.1 {
+                            f((i, j), (a, b), (x, y));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn shape<T>(matrix: &[Vec<T>]) -> (usize, usize) {
+    (matrix.len(), matrix.first().map_or(0, |row| row.len()))
+}
+
+// the convolution over the field, outside of the circuit
+pub fn conv2d_native<F: PrimeField>(
+    image: &[Vec<F>],
+    kernel: &[Vec<F>],
+    stride: usize,
+    padding: Padding,
+) -> Vec<Vec<F>> {
+    let (out_h, out_w) = conv2d_shape(shape(image), shape(kernel), stride, padding).unwrap();
+    let mut output = vec![vec![F::zero(); out_w]; out_h];
+    for_each_tap(
+        shape(image),
+        shape(kernel),
+        stride,
+        padding,
+        |(i, j), (a, b), (x, y)| output[i][j] += kernel[a][b] * image[x][y],
+    );
+    output
+}
+
+#[derive(Clone)]
+pub struct Conv2dCircuit<F: PrimeField> {
+    image: Vec<Vec<u64>>, // witness
+    kernel: Vec<Vec<F>>,  // witness, fixed-point
+    stride: usize,
+    padding: Padding,
+    hash_of_x: F, // public input
+    hash_of_k: F, // public input
+    hash_of_y: F, // public input
+}
+
+impl<F: PrimeField + Absorb> Conv2dCircuit<F> {
+    // None if the kernel doesn't fit the image or the stride is 0
+    pub fn new(
+        image: Vec<Vec<u64>>,
+        kernel: Vec<Vec<F>>,
+        stride: usize,
+        padding: Padding,
+    ) -> Option<Self> {
+        conv2d_shape(shape(&image), shape(&kernel), stride, padding)?;
+        let image_field = to_field_matrix::<F>(&image);
+        let output = conv2d_native(&image_field, &kernel, stride, padding);
+        Some(Self {
+            hash_of_x: hasher_native(&image_field),
+            hash_of_k: hasher_native(&kernel),
+            hash_of_y: hasher_native(&output),
+            image,
+            kernel,
+            stride,
+            padding,
+        })
+    }
+}
+
+impl<F: PrimeField> Conv2dCircuit<F> {
+    // the public inputs in the order the verifier expects them: [hash_x, hash_k, hash_y]
+    pub fn public_inputs(&self) -> [F; 3] {
+        [self.hash_of_x, self.hash_of_k, self.hash_of_y]
+    }
+
+    pub fn output(&self) -> Vec<Vec<F>> {
+        conv2d_native(
+            &to_field_matrix::<F>(&self.image),
+            &self.kernel,
+            self.stride,
+            self.padding,
+        )
+    }
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for Conv2dCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let hash_public_input_x = FpVar::<F>::new_input(cs.clone(), || Ok(self.hash_of_x))?;
+        let hash_public_input_k = FpVar::<F>::new_input(cs.clone(), || Ok(self.hash_of_k))?;
+        let hash_public_input_y = FpVar::<F>::new_input(cs.clone(), || Ok(self.hash_of_y))?;
+
+        let (image, kernel) = (shape(&self.image), shape(&self.kernel));
+        let image_var: FpVar2DVec<F> = FpVar2DVec::new_witness(cs.clone(), || Ok(self.image))?;
+        let kernel_var = new_field_matrix(cs.clone(), &self.kernel, AllocationMode::Witness)?;
+        let hash_x = &hasher_var::<F>(cs.clone(), &image_var)?[0];
+        hash_x.enforce_equal(&hash_public_input_x)?;
+        let hash_k = &hasher_var::<F>(cs.clone(), &kernel_var)?[0];
+        hash_k.enforce_equal(&hash_public_input_k)?;
+
+        // one multiplication per kernel tap that hits the image, the zero padding is free
+        let (out_h, out_w) = conv2d_shape(image, kernel, self.stride, self.padding)
+            .ok_or(SynthesisError::Unsatisfiable)?;
+        let mut output = FpVar2DVec(vec![vec![FpVar::<F>::zero(); out_w]; out_h]);
+        for_each_tap(
+            image,
+            kernel,
+            self.stride,
+            self.padding,
+            |(i, j), (a, b), (x, y)| {
+                output.0[i][j] += &kernel_var.0[a][b] * &image_var.0[x][y];
+            },
+        );
+
+        let hash_y = &hasher_var::<F>(cs.clone(), &output)?[0];
+        hash_y.enforce_equal(&hash_public_input_y)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::matrix_proof_of_work::fixed_point::{from_fixed_matrix, to_fixed_matrix};
+    use ark_bls12_381::{Bls12_381, Fr as F};
+    use ark_groth16::Groth16;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_snark::{CircuitSpecificSetupSNARK, SNARK};
+    use ark_std::test_rng;
+    use rand::{RngCore, SeedableRng};
+
+    const FRAC_BITS: u32 = 8;
+
+    // a plain floating point convolution with explicit zero padding, written independently
+    // of for_each_tap
+    fn conv2d_reference(
+        image: &[Vec<u64>],
+        kernel: &[Vec<f64>],
+        stride: usize,
+        padding: Padding,
+    ) -> Vec<Vec<f64>> {
+        let (h, w) = (image.len(), image[0].len());
+        let (kh, kw) = (kernel.len(), kernel[0].len());
+        let (out_h, out_w, pad_h, pad_w) = match padding {
+            Padding::Valid => ((h - kh) / stride + 1, (w - kw) / stride + 1, 0, 0),
+            Padding::Same => {
+                let (out_h, out_w) = ((h + stride - 1) / stride, (w + stride - 1) / stride);
+                let pad_h = ((out_h - 1) * stride + kh).saturating_sub(h);
+                let pad_w = ((out_w - 1) * stride + kw).saturating_sub(w);
+                (out_h, out_w, pad_h, pad_w)
+            }
+        };
+        let mut padded = vec![vec![0.0; w + pad_w]; h + pad_h];
+        for x in 0..h {
+            for y in 0..w {
+                padded[x + pad_h / 2][y + pad_w / 2] = image[x][y] as f64;
+            }
+        }
+        let mut output = vec![vec![0.0; out_w]; out_h];
+        for i in 0..out_h {
+            for j in 0..out_w {
+                for a in 0..kh {
+                    for b in 0..kw {
+                        output[i][j] += kernel[a][b] * padded[i * stride + a][j * stride + b];
+                    }
+                }
+            }
+        }
+        output
+    }
+
+    fn image(h: usize, w: usize) -> Vec<Vec<u64>> {
+        (0..h)
+            .map(|i| (0..w).map(|j| ((i * 7 + j * 13) % 256) as u64).collect())
+            .collect()
+    }
+
+    fn blur() -> Vec<Vec<f64>> {
+        vec![
+            vec![0.0625, 0.125, 0.0625],
+            vec![0.125, 0.25, 0.125],
+            vec![0.0625, 0.125, 0.0625],
+        ]
+    }
+
+    fn edge_detect() -> Vec<Vec<f64>> {
+        vec![
+            vec![-1.0, -1.0, -1.0],
+            vec![-1.0, 8.0, -1.0],
+            vec![-1.0, -1.0, -1.0],
+        ]
+    }
+
+    #[test]
+    fn matches_reference_implementation() {
+        for kernel in [blur(), edge_detect(), vec![vec![0.5, -0.5]]] {
+            for padding in [Padding::Valid, Padding::Same] {
+                for stride in [1, 2, 3] {
+                    let image = image(7, 6);
+                    let circuit = Conv2dCircuit::<F>::new(
+                        image.clone(),
+                        to_fixed_matrix(&kernel, FRAC_BITS),
+                        stride,
+                        padding,
+                    )
+                    .unwrap();
+                    // the kernels above are exact in 8 fractional bits, so the outputs are too
+                    assert_eq!(
+                        from_fixed_matrix(&circuit.output(), FRAC_BITS),
+                        conv2d_reference(&image, &kernel, stride, padding)
+                    );
+
+                    let cs = ConstraintSystem::<F>::new_ref();
+                    circuit.generate_constraints(cs.clone()).unwrap();
+                    assert!(cs.is_satisfied().unwrap());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn kernel_larger_than_image() {
+        let kernel = to_fixed_matrix::<F>(&blur(), FRAC_BITS);
+        assert!(Conv2dCircuit::new(image(2, 2), kernel.clone(), 1, Padding::Valid).is_none());
+        assert!(Conv2dCircuit::new(image(2, 2), kernel.clone(), 0, Padding::Same).is_none());
+        assert!(Conv2dCircuit::new(image(2, 2), kernel, 1, Padding::Same).is_some());
+    }
+
+    #[test]
+    fn wrong_output_is_not_satisfied() {
+        let kernel = to_fixed_matrix::<F>(&edge_detect(), FRAC_BITS);
+        let mut circuit =
+            Conv2dCircuit::new(image(5, 5), kernel.clone(), 1, Padding::Same).unwrap();
+        // claim the output of another image
+        circuit.hash_of_y = Conv2dCircuit::new(
+            image(5, 5).into_iter().rev().collect(),
+            kernel,
+            1,
+            Padding::Same,
+        )
+        .unwrap()
+        .hash_of_y;
+        let cs = ConstraintSystem::<F>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn groth16_blur() {
+        let kernel = to_fixed_matrix::<F>(&blur(), FRAC_BITS);
+        let circuit = Conv2dCircuit::new(image(6, 6), kernel, 2, Padding::Same).unwrap();
+        let public_inputs = circuit.public_inputs();
+
+        let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+        let (pk, vk) = Groth16::<Bls12_381>::setup(circuit.clone(), &mut rng).unwrap();
+        let pvk = Groth16::<Bls12_381>::process_vk(&vk).unwrap();
+        let proof = Groth16::<Bls12_381>::prove(&pk, circuit, &mut rng).unwrap();
+        assert!(
+            Groth16::<Bls12_381>::verify_with_processed_vk(&pvk, &public_inputs, &proof).unwrap()
+        );
+        let [hash_x, hash_k, _] = public_inputs;
+        assert!(!Groth16::<Bls12_381>::verify_with_processed_vk(
+            &pvk,
+            &[hash_x, hash_k, hash_x],
+            &proof
+        )
+        .unwrap());
+    }
+}
@@ -0,0 +1,68 @@
+use ark_ff::PrimeField;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+
+// Signed fixed-point numbers in the field: x is encoded as round(x * 2^frac_bits), negative
+// values as p - |v|. sums and products of encodings are exact in the field, a product of two
+// encodings carries 2 * frac_bits fractional bits, so decode it with the summed scale.
+
+// encode a real number with frac_bits fractional bits
+pub fn to_fixed<F: PrimeField>(value: f64, frac_bits: u32) -> F {
+    let scaled = (value * (1u64 << frac_bits) as f64).round();
+    let magnitude = F::from(scaled.abs() as u128);
+    if scaled < 0.0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+// decode a field element with frac_bits fractional bits, the upper half of the field is negative
+pub fn from_fixed<F: PrimeField>(value: F, frac_bits: u32) -> f64 {
+    let modulus: BigUint = F::MODULUS.into();
+    let value: BigUint = value.into_bigint().into();
+    let scale = (1u64 << frac_bits) as f64;
+    if value > &modulus >> 1 {
+        -(modulus - value).to_f64().unwrap() / scale
+    } else {
+        value.to_f64().unwrap() / scale
+    }
+}
+
+pub fn to_fixed_matrix<F: PrimeField>(matrix: &[Vec<f64>], frac_bits: u32) -> Vec<Vec<F>> {
+    matrix
+        .iter()
+        .map(|row| row.iter().map(|v| to_fixed(*v, frac_bits)).collect())
+        .collect()
+}
+
+pub fn from_fixed_matrix<F: PrimeField>(matrix: &[Vec<F>], frac_bits: u32) -> Vec<Vec<f64>> {
+    matrix
+        .iter()
+        .map(|row| row.iter().map(|v| from_fixed(*v, frac_bits)).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr as F;
+
+    #[test]
+    fn fixed_point_round_trip() {
+        for value in [0.0, 1.0, -1.0, 0.25, -2.75, 1234.5] {
+            assert_eq!(from_fixed(to_fixed::<F>(value, 8), 8), value);
+        }
+        // values are rounded to the nearest multiple of 2^-frac_bits
+        assert_eq!(from_fixed(to_fixed::<F>(1.0 / 3.0, 4), 4), 0.3125);
+    }
+
+    #[test]
+    fn fixed_point_arithmetic_is_exact() {
+        let a = to_fixed::<F>(-1.5, 8);
+        let b = to_fixed::<F>(2.25, 8);
+        assert_eq!(from_fixed(a + b, 8), 0.75);
+        // the product has 16 fractional bits
+        assert_eq!(from_fixed(a * b, 16), -3.375);
+    }
+}
@@ -1,6 +1,5 @@
 use crate::arkworks::matrix_proof_of_work::alloc::FpVar2DVec;
-pub use crate::arkworks::matrix_proof_of_work::hashing::hashing_utils::poseidon_parameters_for_test;
-use ark_bls12_381::fr::Fr;
+pub use crate::arkworks::matrix_proof_of_work::hashing::hashing_utils::poseidon_parameters;
 use ark_crypto_primitives::sponge::constraints::CryptographicSpongeVar as CryptographicSpongeVarTrait;
 use ark_crypto_primitives::sponge::poseidon::constraints::PoseidonSpongeVar;
 use ark_crypto_primitives::sponge::Absorb;
@@ -13,25 +12,64 @@ use ark_r1cs_std::alloc::AllocVar;
 use ark_r1cs_std::{fields::fp::FpVar, R1CSVar};
 use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
 use std::ops::ShrAssign;
-// // calculates the hash
+// // calculates the hash, with the poseidon parameters of the field
 pub fn hasher<ConstraintF: PrimeField + Absorb>(
     c: &FpVar2DVec<ConstraintF>,
-) -> Result<Vec<Fr>, SynthesisError> {
+) -> Result<Vec<ConstraintF>, SynthesisError> {
     let sponge_param: ark_crypto_primitives::sponge::poseidon::PoseidonConfig<_> =
-        poseidon_parameters_for_test();
-    let mut sponge: PoseidonSponge<Fr> = PoseidonSponge::<Fr>::new(&sponge_param);
+        poseidon_parameters();
+    let mut sponge = PoseidonSponge::<ConstraintF>::new(&sponge_param);
     let flattened_matrix = flatten_fpvar(c).unwrap();
     sponge.absorb(&flattened_matrix);
     let hash = sponge.squeeze_native_field_elements(1).to_vec();
     Ok(hash)
 }
 
+// calculates the same hash as `hasher` directly from field elements, without a constraint system
+pub fn hasher_native<F: PrimeField + Absorb>(matrix: &[Vec<F>]) -> F {
+    let sponge_param: ark_crypto_primitives::sponge::poseidon::PoseidonConfig<F> =
+        poseidon_parameters();
+    let mut sponge: PoseidonSponge<F> = PoseidonSponge::<F>::new(&sponge_param);
+    let flattened_matrix: Vec<F> = matrix.iter().flatten().cloned().collect();
+    sponge.absorb(&flattened_matrix);
+    sponge.squeeze_native_field_elements(1)[0]
+}
+
+// hiding commitment Com(M, r) = Poseidon(r || M), the blinding r is absorbed before the matrix
+// so that low entropy matrices can't be brute forced from the public value. MatrixCircuit
+// commits by the row merkle roots of merkle.rs, which open one row at a time; this is the
+// commitment to a whole matrix in a single sponge, for matrices that are only opened whole
+pub fn commit_native<F: PrimeField + Absorb>(matrix: &[Vec<F>], blinding: F) -> F {
+    let sponge_param: ark_crypto_primitives::sponge::poseidon::PoseidonConfig<F> =
+        poseidon_parameters();
+    let mut sponge: PoseidonSponge<F> = PoseidonSponge::<F>::new(&sponge_param);
+    let flattened_matrix: Vec<F> = matrix.iter().flatten().cloned().collect();
+    sponge.absorb(&blinding);
+    sponge.absorb(&flattened_matrix);
+    sponge.squeeze_native_field_elements(1)[0]
+}
+
+// Calculate the commitment using FpVar2D, the blinding is a witness
+pub fn commit_var<ConstraintF: PrimeField>(
+    cs: ConstraintSystemRef<ConstraintF>,
+    c: &FpVar2DVec<ConstraintF>,
+    blinding: &FpVar<ConstraintF>,
+) -> Result<FpVar<ConstraintF>, SynthesisError> {
+    let sponge_param = poseidon_parameters();
+    let mut sponge = PoseidonSpongeVar::<ConstraintF>::new(cs, &sponge_param);
+    let flattened_matrix = flatten_fpvar2d_var(c)?;
+    sponge.absorb(blinding)?;
+    sponge.absorb(&flattened_matrix)?;
+    let mut commitment = sponge.squeeze_field_elements(1)?;
+    Ok(commitment.remove(0))
+}
+
 // Calculate the hash using FpVar2D
 pub fn hasher_var<ConstraintF: PrimeField>(
     cs: ConstraintSystemRef<ConstraintF>,
     c: &FpVar2DVec<ConstraintF>,
 ) -> Result<Vec<FpVar<ConstraintF>>, SynthesisError> {
-    let sponge_param = poseidon_parameters_for_test();
+    let sponge_param = poseidon_parameters();
     let mut sponge = PoseidonSpongeVar::<ConstraintF>::new(cs, &sponge_param);
     let flattened_matrix = flatten_fpvar2d_var(c)?;
     sponge.absorb(&flattened_matrix)?;
@@ -42,10 +80,10 @@ pub fn hasher_var<ConstraintF: PrimeField>(
 // do hasher that get a string and return a hash:
 pub fn hasher_string_native<ConstraintF: PrimeField + Absorb>(
     c: &str,
-) -> Result<Vec<Fr>, SynthesisError> {
+) -> Result<Vec<ConstraintF>, SynthesisError> {
     let sponge_param: ark_crypto_primitives::sponge::poseidon::PoseidonConfig<_> =
-        poseidon_parameters_for_test();
-    let mut sponge: PoseidonSponge<Fr> = PoseidonSponge::<Fr>::new(&sponge_param);
+        poseidon_parameters();
+    let mut sponge = PoseidonSponge::<ConstraintF>::new(&sponge_param);
     let c_bytes = c.as_bytes();
     let vec_fe: Vec<ConstraintF> = c_bytes.iter().map(|v| ConstraintF::from(*v)).collect();
     sponge.absorb(&vec_fe);
@@ -57,7 +95,7 @@ pub fn hasher_string_var<ConstraintF: PrimeField>(
     cs: ConstraintSystemRef<ConstraintF>,
     c: &str,
 ) -> Result<Vec<FpVar<ConstraintF>>, SynthesisError> {
-    let sponge_param = poseidon_parameters_for_test();
+    let sponge_param = poseidon_parameters();
     let mut sponge = PoseidonSpongeVar::<ConstraintF>::new(cs.clone(), &sponge_param);
     let c_bytes = c.as_bytes().to_vec();
     let c_bytes_var: Vec<FpVar<ConstraintF>> = c_bytes
@@ -69,15 +107,14 @@ pub fn hasher_string_var<ConstraintF: PrimeField>(
     Ok(hash)
 }
 
-// Flatten FpVar2D into a vector of FpVar
+// Flatten FpVar2D into a vector of FpVar, row by row (the matrix doesn't have to be square)
 pub fn flatten_fpvar2d_var<ConstraintF: PrimeField>(
     c: &FpVar2DVec<ConstraintF>,
 ) -> Result<Vec<&FpVar<ConstraintF>>, SynthesisError> {
     let mut flattened_matrix = Vec::new();
-    let len = c.0.len();
-    for i in 0..len {
-        for j in 0..len {
-            flattened_matrix.push(&c.0[i][j]);
+    for row in c.0.iter() {
+        for element in row.iter() {
+            flattened_matrix.push(element);
         }
     }
     Ok(flattened_matrix)
@@ -86,10 +123,8 @@ pub fn flatten_fpvar<ConstraintF: PrimeField>(
     c: &FpVar2DVec<ConstraintF>,
 ) -> Result<Vec<ConstraintF>, SynthesisError> {
     let mut flattened_matrix = Vec::new();
-    let len = c.0.len();
-    for i in 0..len {
-        for j in 0..len {
-            let element = &c.0[i][j];
+    for row in c.0.iter() {
+        for element in row.iter() {
             let element_value = element.value()?;
             flattened_matrix.push(element_value);
         }
@@ -172,6 +207,57 @@ mod tests {
         assert!(!hash.is_empty());
     }
 
+    #[test]
+    fn test_hasher_native_matches_hasher() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let c = vec![vec![1u64, 2, 3], vec![4, 5, 6], vec![7, 8, 9]];
+        let c_field: Vec<Vec<F>> = c
+            .iter()
+            .map(|row| row.iter().map(|v| F::from(*v)).collect())
+            .collect();
+        let c_var = FpVar2DVec::new_witness(cs.clone(), || Ok(c)).unwrap();
+        assert_eq!(hasher(&c_var).unwrap()[0], hasher_native(&c_field));
+    }
+
+    #[test]
+    fn test_commitment_native_vs_var() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let c = vec![vec![1u64, 1], vec![1, 1]];
+        let blinding = F::from(123456789u64);
+        let c_var = FpVar2DVec::new_witness(cs.clone(), || Ok(c.clone())).unwrap();
+        let blinding_var = FpVar::new_witness(cs.clone(), || Ok(blinding)).unwrap();
+        let commitment = commit_var(cs.clone(), &c_var, &blinding_var).unwrap();
+        let c_field: Vec<Vec<F>> = c
+            .iter()
+            .map(|row| row.iter().map(|v| F::from(*v)).collect())
+            .collect();
+        assert_eq!(commitment.value().unwrap(), commit_native(&c_field, blinding));
+    }
+
+    #[test]
+    fn test_commitment_hides_low_entropy_matrix() {
+        let c = vec![vec![F::from(1u64); 2]; 2];
+        // the same matrix under different blindings gives unrelated commitments,
+        // and neither of them is the plain hash of the matrix
+        let com1 = commit_native(&c, F::from(1u64));
+        let com2 = commit_native(&c, F::from(2u64));
+        assert_ne!(com1, com2);
+        assert_ne!(com1, hasher_native(&c));
+    }
+
+    #[test]
+    fn test_hashing_rectangular_matrix() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let c = vec![vec![1u64, 2, 3], vec![4, 5, 6]];
+        let c_field: Vec<Vec<F>> = c
+            .iter()
+            .map(|row| row.iter().map(|v| F::from(*v)).collect())
+            .collect();
+        let c_var = FpVar2DVec::new_witness(cs.clone(), || Ok(c)).unwrap();
+        let hash = hasher_var(cs.clone(), &c_var).unwrap();
+        assert_eq!(hash.value().unwrap()[0], hasher_native(&c_field));
+    }
+
     #[test]
     fn test_hashing_string() {
         let cs = ConstraintSystem::<F>::new_ref();
@@ -183,4 +269,36 @@ mod tests {
 
         assert_eq!(c_var.value().unwrap(), hash);
     }
+
+    #[test]
+    fn test_poseidon_parameters_of_every_curve() {
+        use crate::arkworks::matrix_proof_of_work::hashing::hashing_utils as utils;
+        // the parameters of bls12_381 are the ones the hashes always had
+        let bls = poseidon_parameters::<F>();
+        assert_eq!(bls.ark, utils::poseidon_parameters_for_test::<F>().ark);
+        let bn254 = poseidon_parameters::<ark_bn254::Fr>();
+        assert_eq!((bn254.alpha, bn254.partial_rounds), (5, 57));
+        assert_eq!(bn254.ark.len(), 8 + 57);
+        let bls12_377 = poseidon_parameters::<ark_bls12_377::Fr>();
+        assert_eq!((bls12_377.alpha, bls12_377.partial_rounds), (17, 31));
+        assert_eq!(poseidon_parameters::<ark_bn254::Fr>().mds, bn254.mds);
+
+        // the hash in the circuit is the native one on every curve
+        fn matches<G: PrimeField + Absorb>() -> G {
+            let cs = ConstraintSystem::<G>::new_ref();
+            let c = vec![vec![1u64, 2, 3], vec![4, 5, 6]];
+            let c_field: Vec<Vec<G>> = c
+                .iter()
+                .map(|row| row.iter().map(|v| G::from(*v)).collect())
+                .collect();
+            let c_var = FpVar2DVec::new_witness(cs.clone(), || Ok(c)).unwrap();
+            let hash = hasher_var(cs.clone(), &c_var).unwrap().value().unwrap()[0];
+            assert_eq!(hash, hasher_native(&c_field));
+            assert!(cs.is_satisfied().unwrap());
+            hash
+        }
+        matches::<ark_bn254::Fr>();
+        matches::<ark_bls12_377::Fr>();
+        matches::<F>();
+    }
 }
@@ -1,5 +1,6 @@
+use crate::arkworks::preimage_poseidon::hash_parm::poseidon_alpha;
 use ark_crypto_primitives::sponge::{
-    poseidon::{PoseidonConfig, PoseidonSponge},
+    poseidon::{find_poseidon_ark_and_mds, PoseidonConfig, PoseidonSponge},
     CryptographicSponge, DuplexSpongeMode,
 };
 use ark_ff::PrimeField;
@@ -10,6 +11,9 @@ use ark_r1cs_std::{
     ToBitsGadget, ToBytesGadget, ToConstraintFieldGadget,
 };
 use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
 
 /// Generate default parameters (bls381-fr-only) for alpha = 17, state-size = 8
 pub fn poseidon_parameters_for_test<F: PrimeField>() -> PoseidonConfig<F> {
@@ -714,6 +718,57 @@ pub fn poseidon_parameters_for_test<F: PrimeField>() -> PoseidonConfig<F> {
     }
 }
 
+// the poseidon parameters of the scalar field of a curve, rate 2 and 8 full rounds: the
+// constants above over bls12_381, so its hashes and commitments stay the same, and the grain
+// lfsr constants of the poseidon paper over the others. alpha and the partial rounds are the
+// usual ones of each field, 5 and 57 on bn254 (as circomlib) and 17 and 31 on bls12_377 (as
+// the arkworks defaults). other fields get the smallest alpha and 57 partial rounds
+pub fn poseidon_parameters<F: PrimeField>() -> PoseidonConfig<F> {
+    let is = |modulus: &[u64]| F::MODULUS.as_ref() == modulus;
+    if is(ark_bls12_381::Fr::MODULUS.as_ref()) {
+        return poseidon_parameters_for_test();
+    }
+    let (alpha, partial_rounds) = if is(ark_bn254::Fr::MODULUS.as_ref()) {
+        (5, 57)
+    } else if is(ark_bls12_377::Fr::MODULUS.as_ref()) {
+        (17, 31)
+    } else {
+        (poseidon_alpha::<F>(), 57)
+    };
+    cached(|| {
+        let (full_rounds, rate) = (8, 2);
+        let (ark, mds) = find_poseidon_ark_and_mds::<F>(
+            F::MODULUS_BIT_SIZE as u64,
+            rate,
+            full_rounds as u64,
+            partial_rounds as u64,
+            0,
+        );
+        PoseidonConfig {
+            full_rounds,
+            partial_rounds,
+            alpha,
+            ark,
+            mds,
+            rate,
+            capacity: 1,
+        }
+    })
+}
+
+// the lfsr runs once per field, the hashers ask for the parameters on every hash
+fn cached<F: PrimeField>(generate: impl FnOnce() -> PoseidonConfig<F>) -> PoseidonConfig<F> {
+    static CONFIGS: OnceLock<Mutex<HashMap<TypeId, Box<dyn Any + Send + Sync>>>> =
+        OnceLock::new();
+    let mut configs = CONFIGS.get_or_init(Default::default).lock().unwrap();
+    configs
+        .entry(TypeId::of::<F>())
+        .or_insert_with(|| Box::new(generate()))
+        .downcast_ref::<PoseidonConfig<F>>()
+        .expect("the configs are by the type of their field")
+        .clone()
+}
+
 #[derive(Clone)]
 pub struct PoseidonSpongeVar<F: PrimeField> {
     /// Constraint system
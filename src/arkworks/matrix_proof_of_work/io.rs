@@ -1,21 +1,23 @@
-use ark_bls12_381::Config;
-use ark_ec::bls12::Bls12;
+use crate::bundle::ProofBundle;
+use ark_crypto_primitives::merkle_tree::{self, Path};
 use ark_ec::pairing::Pairing;
+use ark_ff::PrimeField;
 use ark_groth16::{PreparedVerifyingKey, Proof};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use base64::prelude::*;
+use num_bigint::BigUint;
 use std::error::Error;
 use std::fs::File;
 use std::io::{self, Read, Write};
 
 //Generate proof and write to file
-pub fn write_proof_to_file(proof: &Proof<Bls12<Config>>, file_path: &str) -> Result<(), io::Error> {
+pub fn write_proof_to_file<E: Pairing>(
+    proof: &Proof<E>,
+    file_path: &str,
+) -> Result<(), io::Error> {
     let mut compressed_bytes = Vec::new();
     proof.serialize_compressed(&mut compressed_bytes).unwrap();
 
-    println!("Writing proof to file: {}", file_path);
-    // print the bytes
-    println!("Bytes: {:?}", compressed_bytes);
     let mut file: File = std::fs::OpenOptions::new()
         .create(true)
         .write(true)
@@ -42,7 +44,7 @@ pub fn read_proof<E: Pairing>(file_path: &str) -> Result<Proof<E>, Box<dyn Error
 }
 
 // encode the proof to base64:
-pub fn encode_proof<E: Pairing>(proof: &Proof<Bls12<Config>>) -> String {
+pub fn encode_proof<E: Pairing>(proof: &Proof<E>) -> String {
     // serialize the pvk:
     let mut proof_bytes = Vec::new();
     proof.serialize_compressed(&mut proof_bytes).unwrap();
@@ -50,12 +52,11 @@ pub fn encode_proof<E: Pairing>(proof: &Proof<Bls12<Config>>) -> String {
     proof_str
 }
 // decode the proof from base64:
-pub fn decode_proof<E: Pairing>(proof_str: &str) -> Result<Proof<Bls12<Config>>, Box<dyn Error>> {
+pub fn decode_proof<E: Pairing>(proof_str: &str) -> Result<Proof<E>, Box<dyn Error>> {
     // decode the proof from base64:
     let proof_bytes = BASE64_STANDARD.decode(proof_str.as_bytes()).unwrap();
-    let proof: Proof<Bls12<Config>> =
-        Proof::<Bls12<Config>>::deserialize_compressed(&mut proof_bytes.as_slice())
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let proof: Proof<E> = Proof::<E>::deserialize_compressed(&mut proof_bytes.as_slice())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
     Ok(proof)
 }
 
@@ -83,6 +84,73 @@ pub fn encode_hash(hash: &Vec<u8>) -> String {
 }
 pub fn decode_hash(hash_str: &str) -> Result<Vec<u8>, Box<dyn Error>> {
     // decode the hash from base64:
-    let hash_bytes = BASE64_STANDARD.decode(hash_str.as_bytes()).unwrap();
+    let hash_bytes = BASE64_STANDARD.decode(hash_str.as_bytes())?;
     Ok(hash_bytes)
 }
+
+// write a bundle in its binary form
+pub fn write_bundle_to_file(bundle: &ProofBundle, file_path: &str) -> Result<(), io::Error> {
+    let mut file = File::create(file_path)?;
+    file.write_all(&bundle.to_bytes())?;
+    file.flush()?;
+    Ok(())
+}
+
+// read a bundle from a file in the binary or the json form
+pub fn read_bundle(file_path: &str) -> Result<ProofBundle, Box<dyn Error>> {
+    let mut file = File::open(file_path)?;
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer)?;
+    if buffer.starts_with(b"{") {
+        return Ok(ProofBundle::from_json(&buffer)?);
+    }
+    Ok(ProofBundle::from_bytes(&buffer)?)
+}
+
+// encode the binary form of a bundle to base64:
+pub fn encode_bundle(bundle: &ProofBundle) -> String {
+    BASE64_STANDARD.encode(bundle.to_bytes())
+}
+
+pub fn decode_bundle(bundle_str: &str) -> Result<ProofBundle, Box<dyn Error>> {
+    let bundle_bytes = BASE64_STANDARD.decode(bundle_str.as_bytes())?;
+    Ok(ProofBundle::from_bytes(&bundle_bytes)?)
+}
+
+// encode a merkle path to base64:
+pub fn encode_path<P: merkle_tree::Config>(path: &Path<P>) -> String {
+    let mut path_bytes = Vec::new();
+    path.serialize_compressed(&mut path_bytes).unwrap();
+    BASE64_STANDARD.encode(&path_bytes)
+}
+
+pub fn decode_path<P: merkle_tree::Config>(path_str: &str) -> Result<Path<P>, Box<dyn Error>> {
+    let path_bytes = BASE64_STANDARD.decode(path_str.as_bytes())?;
+    let path = Path::<P>::deserialize_compressed(&mut path_bytes.as_slice())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(path)
+}
+
+// parse a field element from a decimal or a 0x prefixed hex string, values that are not below
+// the modulus are rejected instead of being silently reduced
+pub fn parse_field_element<F: PrimeField>(value: &str) -> Result<F, String> {
+    let value = value.trim();
+    let parsed = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => BigUint::parse_bytes(hex.as_bytes(), 16),
+        None => BigUint::parse_bytes(value.as_bytes(), 10),
+    };
+    let parsed = parsed.ok_or(format!("'{}' is not a decimal or 0x hex number", value))?;
+    if parsed >= F::MODULUS.into() {
+        return Err(format!("'{}' is not below the field modulus", value));
+    }
+    Ok(F::from(parsed))
+}
+
+// the 0x prefixed big endian hex representation of a field element
+pub fn field_to_hex<F: PrimeField>(value: F) -> String {
+    let value: BigUint = value.into();
+    format!("0x{}", value.to_str_radix(16))
+}
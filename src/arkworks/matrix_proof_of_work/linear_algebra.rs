@@ -0,0 +1,543 @@
+use crate::arkworks::matrix_proof_of_work::alloc::{new_field_matrix, FpVar2DVec};
+use crate::arkworks::matrix_proof_of_work::constraints::matrix_mul;
+use crate::arkworks::matrix_proof_of_work::hasher::hasher_var;
+use ark_ff::PrimeField;
+use ark_r1cs_std::boolean::Boolean;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::fields::FieldVar;
+use ark_r1cs_std::prelude::{AllocVar, AllocationMode, EqGadget};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+// Circuits for general linear algebra over Fr on an n x n matrix A that is committed by the
+// Poseidon matrix hash (the same one MatrixCircuit uses):
+// 1. MatrixInverseCircuit - B = A^-1, checked by A * B = I
+// 2. DeterminantCircuit   - det(A) = d, checked by a witnessed decomposition PA = LU
+// 3. RankCircuit          - rank(A) = r, checked by a witnessed echelon form E * A * F = D
+
+// converts a matrix of u64 into a matrix of field elements:
+pub fn to_field_matrix<F: PrimeField>(matrix: &[Vec<u64>]) -> Vec<Vec<F>> {
+    matrix
+        .iter()
+        .map(|row| row.iter().map(|v| F::from(*v)).collect())
+        .collect()
+}
+
+pub fn identity_native<F: PrimeField>(n: usize) -> Vec<Vec<F>> {
+    let mut matrix = vec![vec![F::zero(); n]; n];
+    for i in 0..n {
+        matrix[i][i] = F::one();
+    }
+    matrix
+}
+
+// invert a square matrix using Gauss-Jordan elimination, None if the matrix is singular
+pub fn invert_native<F: PrimeField>(a: &[Vec<F>]) -> Option<Vec<Vec<F>>> {
+    let n = a.len();
+    let mut m = a.to_vec();
+    let mut inv = identity_native::<F>(n);
+    for k in 0..n {
+        let pivot = (k..n).find(|&i| !m[i][k].is_zero())?;
+        m.swap(k, pivot);
+        inv.swap(k, pivot);
+        let pivot_inv = m[k][k].inverse()?;
+        for j in 0..n {
+            m[k][j] *= pivot_inv;
+            inv[k][j] *= pivot_inv;
+        }
+        for i in 0..n {
+            if i != k && !m[i][k].is_zero() {
+                let factor = m[i][k];
+                for j in 0..n {
+                    let (m_kj, inv_kj) = (m[k][j], inv[k][j]);
+                    m[i][j] -= factor * m_kj;
+                    inv[i][j] -= factor * inv_kj;
+                }
+            }
+        }
+    }
+    Some(inv)
+}
+
+// witness for the determinant: PA = LU where P[i][perm[i]] = 1,
+// L is unit lower triangular and U is upper triangular
+#[derive(Clone, Debug)]
+pub struct LuWitness<F: PrimeField> {
+    pub perm: Vec<usize>,
+    pub l: Vec<Vec<F>>,
+    pub u: Vec<Vec<F>>,
+    pub det: F,
+}
+
+// LU decomposition with partial pivoting, works for singular matrices as well
+pub fn lu_decompose<F: PrimeField>(a: &[Vec<F>]) -> LuWitness<F> {
+    let n = a.len();
+    let mut u = a.to_vec();
+    let mut l = identity_native::<F>(n);
+    let mut perm: Vec<usize> = (0..n).collect();
+    let mut sign = F::one();
+    for k in 0..n {
+        // if the column is already zero below the diagonal there is nothing to eliminate
+        let pivot = match (k..n).find(|&i| !u[i][k].is_zero()) {
+            Some(pivot) => pivot,
+            None => continue,
+        };
+        if pivot != k {
+            u.swap(k, pivot);
+            perm.swap(k, pivot);
+            // the multipliers we already found move together with their rows
+            for j in 0..k {
+                let tmp = l[k][j];
+                l[k][j] = l[pivot][j];
+                l[pivot][j] = tmp;
+            }
+            sign = -sign;
+        }
+        let pivot_inv = u[k][k].inverse().unwrap();
+        for i in k + 1..n {
+            let factor = u[i][k] * pivot_inv;
+            l[i][k] = factor;
+            for j in k..n {
+                let u_kj = u[k][j];
+                u[i][j] -= factor * u_kj;
+            }
+        }
+    }
+    let det = (0..n).fold(sign, |acc, i| acc * u[i][i]);
+    LuWitness { perm, l, u, det }
+}
+
+// witness for the rank: E * A * F = D where D = diag(1, .., 1, 0, .., 0) has rank ones,
+// E and F are invertible (their inverses are part of the witness)
+#[derive(Clone, Debug)]
+pub struct EchelonWitness<F: PrimeField> {
+    pub e: Vec<Vec<F>>,
+    pub e_inv: Vec<Vec<F>>,
+    pub f: Vec<Vec<F>>,
+    pub f_inv: Vec<Vec<F>>,
+    pub rank: usize,
+}
+
+// reduce A to its echelon (rank normal) form with full pivoting, tracking the row operations
+// in E and the column operations in F
+pub fn echelon_form<F: PrimeField>(a: &[Vec<F>]) -> EchelonWitness<F> {
+    let n = a.len();
+    let mut m = a.to_vec();
+    let mut e = identity_native::<F>(n);
+    let mut f = identity_native::<F>(n);
+    let mut rank = 0;
+    for k in 0..n {
+        // find a non zero pivot in the remaining bottom right block
+        let pivot = (k..n)
+            .flat_map(|i| (k..n).map(move |j| (i, j)))
+            .find(|&(i, j)| !m[i][j].is_zero());
+        let (pivot_row, pivot_col) = match pivot {
+            Some(pivot) => pivot,
+            None => break,
+        };
+        m.swap(k, pivot_row);
+        e.swap(k, pivot_row);
+        for row in m.iter_mut() {
+            row.swap(k, pivot_col);
+        }
+        for row in f.iter_mut() {
+            row.swap(k, pivot_col);
+        }
+        let pivot_inv = m[k][k].inverse().unwrap();
+        for j in 0..n {
+            m[k][j] *= pivot_inv;
+            e[k][j] *= pivot_inv;
+        }
+        // clear column k using row operations:
+        for i in 0..n {
+            if i != k && !m[i][k].is_zero() {
+                let factor = m[i][k];
+                for j in 0..n {
+                    let (m_kj, e_kj) = (m[k][j], e[k][j]);
+                    m[i][j] -= factor * m_kj;
+                    e[i][j] -= factor * e_kj;
+                }
+            }
+        }
+        // clear row k using column operations:
+        for j in 0..n {
+            if j != k && !m[k][j].is_zero() {
+                let factor = m[k][j];
+                for i in 0..n {
+                    let (m_ik, f_ik) = (m[i][k], f[i][k]);
+                    m[i][j] -= factor * m_ik;
+                    f[i][j] -= factor * f_ik;
+                }
+            }
+        }
+        rank += 1;
+    }
+    // row and column operations are always invertible
+    let e_inv = invert_native(&e).unwrap();
+    let f_inv = invert_native(&f).unwrap();
+    EchelonWitness {
+        e,
+        e_inv,
+        f,
+        f_inv,
+        rank,
+    }
+}
+
+// enforce that the (square) matrix is the identity
+fn enforce_identity<F: PrimeField>(matrix: &FpVar2DVec<F>) -> Result<(), SynthesisError> {
+    let n = matrix.0.len();
+    for i in 0..n {
+        for j in 0..n {
+            let expected = if i == j { F::one() } else { F::zero() };
+            matrix.0[i][j].enforce_equal(&FpVar::<F>::constant(expected))?;
+        }
+    }
+    Ok(())
+}
+
+// allocate the committed matrix A and enforce hash(A) = hash_of_a
+fn committed_matrix<F: PrimeField>(
+    cs: ConstraintSystemRef<F>,
+    matrix: Vec<Vec<u64>>,
+    hash_of_a: F,
+) -> Result<FpVar2DVec<F>, SynthesisError> {
+    let hash_public_input_a = FpVar::<F>::new_input(cs.clone(), || Ok(hash_of_a))?;
+    let matrix_a_var: FpVar2DVec<F> = FpVar2DVec::new_witness(cs.clone(), || Ok(matrix))?;
+    let hash_a = &hasher_var::<F>(cs.clone(), &matrix_a_var)?[0];
+    hash_a.enforce_equal(&hash_public_input_a)?;
+    Ok(matrix_a_var)
+}
+
+#[derive(Clone)]
+pub struct MatrixInverseCircuit<F: PrimeField> {
+    matrix_a: Vec<Vec<u64>>, // witness
+    matrix_b: Vec<Vec<F>>,   // witness - the inverse
+    hash_of_a: F,            // public input
+    hash_of_b: F,            // public input
+}
+
+impl<F: PrimeField> MatrixInverseCircuit<F> {
+    pub fn new(matrix_a: Vec<Vec<u64>>, matrix_b: Vec<Vec<F>>, hash_of_a: F, hash_of_b: F) -> Self {
+        Self {
+            matrix_a,
+            matrix_b,
+            hash_of_a,
+            hash_of_b,
+        }
+    }
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for MatrixInverseCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let matrix_a_var = committed_matrix(cs.clone(), self.matrix_a, self.hash_of_a)?;
+
+        let hash_public_input_b = FpVar::<F>::new_input(cs.clone(), || Ok(self.hash_of_b))?;
+        let matrix_b_var = new_field_matrix(cs.clone(), &self.matrix_b, AllocationMode::Witness)?;
+        let hash_b = &hasher_var::<F>(cs.clone(), &matrix_b_var)?[0];
+        hash_b.enforce_equal(&hash_public_input_b)?;
+
+        // A * B = I
+        let product = matrix_mul(cs.clone(), matrix_a_var, matrix_b_var);
+        enforce_identity(&product)
+    }
+}
+
+#[derive(Clone)]
+pub struct DeterminantCircuit<F: PrimeField> {
+    matrix_a: Vec<Vec<u64>>, // witness
+    lu: LuWitness<F>,        // witness
+    hash_of_a: F,            // public input
+    det: F,                  // public input
+}
+
+impl<F: PrimeField> DeterminantCircuit<F> {
+    pub fn new(matrix_a: Vec<Vec<u64>>, hash_of_a: F) -> Self {
+        let lu = lu_decompose(&to_field_matrix::<F>(&matrix_a));
+        let det = lu.det;
+        Self {
+            matrix_a,
+            lu,
+            hash_of_a,
+            det,
+        }
+    }
+
+    pub fn det(&self) -> F {
+        self.det
+    }
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for DeterminantCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let n = self.matrix_a.len();
+        let matrix_a_var = committed_matrix(cs.clone(), self.matrix_a, self.hash_of_a)?;
+        let det_public_input = FpVar::<F>::new_input(cs.clone(), || Ok(self.det))?;
+
+        // P is a permutation matrix: boolean entries, every row and every column sums to one
+        let mut p = Vec::with_capacity(n);
+        for i in 0..n {
+            let mut row = Vec::with_capacity(n);
+            for j in 0..n {
+                let bit = Boolean::new_witness(cs.clone(), || Ok(self.lu.perm[i] == j))?;
+                row.push(FpVar::from(bit));
+            }
+            p.push(row);
+        }
+        for i in 0..n {
+            let mut row_sum = FpVar::<F>::zero();
+            let mut col_sum = FpVar::<F>::zero();
+            for j in 0..n {
+                row_sum += &p[i][j];
+                col_sum += &p[j][i];
+            }
+            row_sum.enforce_equal(&FpVar::one())?;
+            col_sum.enforce_equal(&FpVar::one())?;
+        }
+
+        // sign(P) = prod_{i<j} (perm(j) - perm(i)) / (j - i)
+        let perm: Vec<FpVar<F>> = p
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .enumerate()
+                    .fold(FpVar::<F>::zero(), |acc, (j, bit)| {
+                        acc + bit * F::from(j as u64)
+                    })
+            })
+            .collect();
+        let mut sign = FpVar::<F>::one();
+        let mut denominator = F::one();
+        for i in 0..n {
+            for j in i + 1..n {
+                sign *= &perm[j] - &perm[i];
+                denominator *= F::from((j - i) as u64);
+            }
+        }
+        sign *= denominator.inverse().unwrap();
+
+        // L is unit lower triangular and U is upper triangular, the zeros are constants
+        let mut l = Vec::with_capacity(n);
+        let mut u = Vec::with_capacity(n);
+        for i in 0..n {
+            let mut l_row = Vec::with_capacity(n);
+            let mut u_row = Vec::with_capacity(n);
+            for j in 0..n {
+                if i > j {
+                    l_row.push(FpVar::new_witness(cs.clone(), || Ok(self.lu.l[i][j]))?);
+                    u_row.push(FpVar::zero());
+                } else {
+                    l_row.push(if i == j { FpVar::one() } else { FpVar::zero() });
+                    u_row.push(FpVar::new_witness(cs.clone(), || Ok(self.lu.u[i][j]))?);
+                }
+            }
+            l.push(l_row);
+            u.push(u_row);
+        }
+
+        // PA = LU
+        let pa = matrix_mul(cs.clone(), FpVar2DVec(p), matrix_a_var);
+        let lu = matrix_mul(cs.clone(), FpVar2DVec(l), FpVar2DVec(u.clone()));
+        for i in 0..n {
+            for j in 0..n {
+                pa.0[i][j].enforce_equal(&lu.0[i][j])?;
+            }
+        }
+
+        // det(A) = sign(P) * prod(U_ii)
+        let mut det = sign;
+        for i in 0..n {
+            det *= &u[i][i];
+        }
+        det.enforce_equal(&det_public_input)
+    }
+}
+
+#[derive(Clone)]
+pub struct RankCircuit<F: PrimeField> {
+    matrix_a: Vec<Vec<u64>>,    // witness
+    echelon: EchelonWitness<F>, // witness
+    hash_of_a: F,               // public input
+    rank: F,                    // public input
+}
+
+impl<F: PrimeField> RankCircuit<F> {
+    pub fn new(matrix_a: Vec<Vec<u64>>, hash_of_a: F) -> Self {
+        let echelon = echelon_form(&to_field_matrix::<F>(&matrix_a));
+        let rank = F::from(echelon.rank as u64);
+        Self {
+            matrix_a,
+            echelon,
+            hash_of_a,
+            rank,
+        }
+    }
+
+    pub fn rank(&self) -> usize {
+        self.echelon.rank
+    }
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for RankCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let n = self.matrix_a.len();
+        let matrix_a_var = committed_matrix(cs.clone(), self.matrix_a, self.hash_of_a)?;
+        let rank_public_input = FpVar::<F>::new_input(cs.clone(), || Ok(self.rank))?;
+
+        let e = new_field_matrix(cs.clone(), &self.echelon.e, AllocationMode::Witness)?;
+        let e_inv = new_field_matrix(cs.clone(), &self.echelon.e_inv, AllocationMode::Witness)?;
+        let f = new_field_matrix(cs.clone(), &self.echelon.f, AllocationMode::Witness)?;
+        let f_inv = new_field_matrix(cs.clone(), &self.echelon.f_inv, AllocationMode::Witness)?;
+
+        // E and F are invertible, so rank(A) = rank(E * A * F)
+        enforce_identity(&matrix_mul(cs.clone(), e.clone(), e_inv))?;
+        enforce_identity(&matrix_mul(cs.clone(), f.clone(), f_inv))?;
+
+        // the diagonal of the echelon form is 1, .., 1, 0, .., 0 and has `rank` ones
+        let mut diagonal = Vec::with_capacity(n);
+        for i in 0..n {
+            let bit = Boolean::new_witness(cs.clone(), || Ok(i < self.echelon.rank))?;
+            diagonal.push(FpVar::from(bit));
+        }
+        for i in 1..n {
+            // diagonal[i] = 1 => diagonal[i - 1] = 1
+            diagonal[i].mul_equals(&(FpVar::one() - &diagonal[i - 1]), &FpVar::zero())?;
+        }
+        let mut ones = FpVar::<F>::zero();
+        for bit in diagonal.iter() {
+            ones += bit;
+        }
+        ones.enforce_equal(&rank_public_input)?;
+
+        let echelon = matrix_mul(cs.clone(), matrix_mul(cs.clone(), e, matrix_a_var), f);
+        for i in 0..n {
+            for j in 0..n {
+                if i == j {
+                    echelon.0[i][j].enforce_equal(&diagonal[i])?;
+                } else {
+                    echelon.0[i][j].enforce_equal(&FpVar::zero())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::matrix_proof_of_work::hasher::hasher_native;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_groth16::Groth16;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_snark::{CircuitSpecificSetupSNARK, SNARK};
+    use ark_std::test_rng;
+    use rand::{RngCore, SeedableRng};
+
+    fn hash_of(matrix: &[Vec<u64>]) -> Fr {
+        hasher_native(&to_field_matrix::<Fr>(matrix))
+    }
+
+    #[test]
+    fn inverse_is_satisfied() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let matrix_a = vec![vec![2u64, 1], vec![7, 4]];
+        let matrix_b = invert_native(&to_field_matrix::<Fr>(&matrix_a)).unwrap();
+        let circuit = MatrixInverseCircuit::new(
+            matrix_a.clone(),
+            matrix_b.clone(),
+            hash_of(&matrix_a),
+            hasher_native(&matrix_b),
+        );
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn wrong_inverse_is_not_satisfied() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let matrix_a = vec![vec![2u64, 1], vec![7, 4]];
+        let matrix_b = to_field_matrix::<Fr>(&[vec![4u64, 1], vec![7, 2]]);
+        let circuit = MatrixInverseCircuit::new(
+            matrix_a.clone(),
+            matrix_b.clone(),
+            hash_of(&matrix_a),
+            hasher_native(&matrix_b),
+        );
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn determinant_with_pivoting() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        // the top left entry is zero so the rows have to be swapped: det = 0*4 - 2*3 = -6
+        let matrix_a = vec![vec![0u64, 2, 1], vec![3, 4, 0], vec![0, 0, 1]];
+        let circuit = DeterminantCircuit::new(matrix_a.clone(), hash_of(&matrix_a));
+        assert_eq!(circuit.det(), -Fr::from(6u64));
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn determinant_of_singular_matrix() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let matrix_a = vec![vec![1u64, 2, 3], vec![2, 4, 6], vec![1, 1, 1]];
+        let circuit = DeterminantCircuit::new(matrix_a.clone(), hash_of(&matrix_a));
+        assert_eq!(circuit.det(), Fr::from(0u64));
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn wrong_determinant_is_not_satisfied() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let matrix_a = vec![vec![1u64, 2], vec![3, 4]];
+        let mut circuit = DeterminantCircuit::new(matrix_a.clone(), hash_of(&matrix_a));
+        circuit.det = Fr::from(2u64);
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn rank_of_singular_matrix() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let matrix_a = vec![vec![1u64, 2, 3], vec![2, 4, 6], vec![0, 1, 1]];
+        let circuit = RankCircuit::new(matrix_a.clone(), hash_of(&matrix_a));
+        assert_eq!(circuit.rank(), 2);
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn wrong_rank_is_not_satisfied() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let matrix_a = vec![vec![1u64, 2], vec![2, 4]];
+        let mut circuit = RankCircuit::new(matrix_a.clone(), hash_of(&matrix_a));
+        circuit.rank = Fr::from(2u64);
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn groth16_determinant() {
+        let matrix_a = vec![vec![3u64, 1, 4], vec![1, 5, 9], vec![2, 6, 5]];
+        let hash_a = hash_of(&matrix_a);
+        let circuit = DeterminantCircuit::new(matrix_a, hash_a);
+        let det = circuit.det();
+
+        let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+        let (pk, vk) = Groth16::<Bls12_381>::setup(circuit.clone(), &mut rng).unwrap();
+        let pvk = Groth16::<Bls12_381>::process_vk(&vk).unwrap();
+        let proof = Groth16::<Bls12_381>::prove(&pk, circuit, &mut rng).unwrap();
+
+        assert!(
+            Groth16::<Bls12_381>::verify_with_processed_vk(&pvk, &[hash_a, det], &proof).unwrap()
+        );
+        assert!(!Groth16::<Bls12_381>::verify_with_processed_vk(
+            &pvk,
+            &[hash_a, det + Fr::from(1u64)],
+            &proof
+        )
+        .unwrap());
+    }
+}
@@ -0,0 +1,199 @@
+use crate::arkworks::matrix_proof_of_work::alloc::FpVar2DVec;
+use crate::arkworks::matrix_proof_of_work::hasher::poseidon_parameters;
+use ark_crypto_primitives::crh::poseidon::constraints::{
+    CRHGadget, CRHParametersVar, TwoToOneCRHGadget,
+};
+use ark_crypto_primitives::crh::poseidon::{TwoToOneCRH, CRH};
+use ark_crypto_primitives::crh::{CRHScheme, CRHSchemeGadget, TwoToOneCRHSchemeGadget};
+use ark_crypto_primitives::merkle_tree::{Config, IdentityDigestConverter, MerkleTree, Path};
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_r1cs_std::alloc::AllocVar;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::fields::FieldVar;
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use std::marker::PhantomData;
+
+// a Poseidon Merkle tree over the rows of a matrix:
+// leaf i is [salt_i, row_i[0], .., row_i[n-1]] where salt_i = Poseidon(seed, i).
+// the seed is the blinding of the whole commitment and stays with the prover,
+// opening a row reveals only its own salt so the other rows stay hidden.
+// the leaves are padded with zero digests up to a power of two (at least 2).
+pub struct RowMerkleConfig<F: PrimeField + Absorb>(PhantomData<F>);
+
+impl<F: PrimeField + Absorb> Config for RowMerkleConfig<F> {
+    type Leaf = [F];
+    type LeafDigest = F;
+    type LeafInnerDigestConverter = IdentityDigestConverter<F>;
+    type InnerDigest = F;
+    type LeafHash = CRH<F>;
+    type TwoToOneHash = TwoToOneCRH<F>;
+}
+
+pub type RowPath<F> = Path<RowMerkleConfig<F>>;
+
+// the number of leaves of the tree for a matrix with `rows` rows
+fn num_leaves(rows: usize) -> usize {
+    rows.next_power_of_two().max(2)
+}
+
+// salt_i = Poseidon(seed, i)
+pub fn row_salt<F: PrimeField + Absorb>(seed: F, row_index: usize) -> F {
+    let params = poseidon_parameters();
+    CRH::<F>::evaluate(&params, vec![seed, F::from(row_index as u64)]).unwrap()
+}
+
+// the leaf of a row: the salt followed by the row entries
+pub fn row_leaf<T: Clone>(salt: T, row: &[T]) -> Vec<T> {
+    let mut leaf = vec![salt];
+    leaf.extend_from_slice(row);
+    leaf
+}
+
+// build the tree over the rows of the matrix
+pub fn row_tree<F: PrimeField + Absorb>(
+    matrix: &[Vec<F>],
+    seed: F,
+) -> MerkleTree<RowMerkleConfig<F>> {
+    let params = poseidon_parameters();
+    let mut digests: Vec<F> = matrix
+        .iter()
+        .enumerate()
+        .map(|(i, row)| CRH::<F>::evaluate(&params, row_leaf(row_salt(seed, i), row)).unwrap())
+        .collect();
+    digests.resize(num_leaves(matrix.len()), F::zero());
+    MerkleTree::new_with_leaf_digest(&params, &params, digests).unwrap()
+}
+
+// the root of the tree, this is the public commitment to the matrix
+pub fn merkle_root_native<F: PrimeField + Absorb>(matrix: &[Vec<F>], seed: F) -> F {
+    row_tree(matrix, seed).root()
+}
+
+// Calculate the same root using FpVar2D, the seed is a witness
+pub fn merkle_root_var<F: PrimeField + Absorb>(
+    cs: ConstraintSystemRef<F>,
+    matrix: &FpVar2DVec<F>,
+    seed: &FpVar<F>,
+) -> Result<FpVar<F>, SynthesisError> {
+    let params = CRHParametersVar::new_constant(cs, poseidon_parameters())?;
+    let mut layer = Vec::with_capacity(num_leaves(matrix.0.len()));
+    for (i, row) in matrix.0.iter().enumerate() {
+        let index = FpVar::constant(F::from(i as u64));
+        let salt = CRHGadget::<F>::evaluate(&params, &[seed.clone(), index])?;
+        let leaf = row_leaf(salt, row);
+        layer.push(CRHGadget::<F>::evaluate(&params, &leaf)?);
+    }
+    layer.resize(num_leaves(matrix.0.len()), FpVar::constant(F::zero()));
+    // hash the layers pairwise up to the root, the same way MerkleTree::new does
+    while layer.len() > 1 {
+        layer = layer
+            .chunks(2)
+            .map(|pair| TwoToOneCRHGadget::<F>::compress(&params, &pair[0], &pair[1]))
+            .collect::<Result<Vec<_>, _>>()?;
+    }
+    Ok(layer.remove(0))
+}
+
+// an inclusion proof for one row of a committed matrix
+pub struct RowOpening<F: PrimeField + Absorb> {
+    pub row_index: usize,
+    pub salt: F,
+    pub row: Vec<F>,
+    pub path: RowPath<F>,
+}
+
+impl<F: PrimeField + Absorb> RowOpening<F> {
+    // open row `row_index` of a matrix committed with `seed`
+    pub fn open(matrix: &[Vec<F>], seed: F, row_index: usize) -> Option<Self> {
+        let row = matrix.get(row_index)?.clone();
+        let path = row_tree(matrix, seed).generate_proof(row_index).ok()?;
+        Some(Self {
+            row_index,
+            salt: row_salt(seed, row_index),
+            row,
+            path,
+        })
+    }
+
+    // check that the row sits at `row_index` in the tree with the given root
+    pub fn verify(&self, root: F) -> bool {
+        let params = poseidon_parameters();
+        self.path.leaf_index == self.row_index
+            && self
+                .path
+                .verify(&params, &params, &root, row_leaf(self.salt, &self.row))
+                .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::matrix_proof_of_work::hasher::hasher_native;
+    use crate::arkworks::matrix_proof_of_work::linear_algebra::to_field_matrix;
+    use ark_bls12_381::Fr as F;
+    use ark_r1cs_std::R1CSVar;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    fn matrix() -> Vec<Vec<F>> {
+        to_field_matrix(&[vec![1u64, 2, 3], vec![4, 5, 6], vec![7, 8, 9]])
+    }
+
+    #[test]
+    fn test_merkle_root_native_vs_var() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let seed = F::from(123456789u64);
+        let matrix_var = FpVar2DVec::new_witness(cs.clone(), || {
+            Ok(vec![vec![1u64, 2, 3], vec![4, 5, 6], vec![7, 8, 9]])
+        })
+        .unwrap();
+        let seed_var = FpVar::new_witness(cs.clone(), || Ok(seed)).unwrap();
+        let root = merkle_root_var(cs.clone(), &matrix_var, &seed_var).unwrap();
+        assert_eq!(root.value().unwrap(), merkle_root_native(&matrix(), seed));
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_merkle_root_hides_low_entropy_matrix() {
+        let c = vec![vec![F::from(1u64); 2]; 2];
+        // the same matrix under different seeds gives unrelated roots,
+        // and neither of them is the plain hash of the matrix
+        let root1 = merkle_root_native(&c, F::from(1u64));
+        let root2 = merkle_root_native(&c, F::from(2u64));
+        assert_ne!(root1, root2);
+        assert_ne!(root1, hasher_native(&c));
+    }
+
+    #[test]
+    fn test_row_opening() {
+        let seed = F::from(42u64);
+        let root = merkle_root_native(&matrix(), seed);
+        for i in 0..3 {
+            let opening = RowOpening::open(&matrix(), seed, i).unwrap();
+            assert_eq!(opening.row, matrix()[i]);
+            assert!(opening.verify(root));
+        }
+        // the padding leaf can't be opened as a row
+        assert!(RowOpening::open(&matrix(), seed, 3).is_none());
+    }
+
+    #[test]
+    fn test_row_opening_rejects_tampering() {
+        let seed = F::from(42u64);
+        let root = merkle_root_native(&matrix(), seed);
+
+        let mut opening = RowOpening::open(&matrix(), seed, 1).unwrap();
+        opening.row[2] += F::from(1u64);
+        assert!(!opening.verify(root));
+
+        // a valid row claimed at another position
+        let mut opening = RowOpening::open(&matrix(), seed, 1).unwrap();
+        opening.row_index = 0;
+        assert!(!opening.verify(root));
+
+        // a valid opening against the root of another seed
+        let opening = RowOpening::open(&matrix(), seed, 1).unwrap();
+        assert!(!opening.verify(merkle_root_native(&matrix(), F::from(43u64))));
+    }
+}
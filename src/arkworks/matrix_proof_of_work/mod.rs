@@ -1,6 +1,11 @@
 pub mod alloc;
 pub mod cmp;
 pub mod constraints;
+pub mod conv2d;
+pub mod fixed_point;
 pub mod hasher;
 pub mod hashing;
 pub mod io;
+pub mod linear_algebra;
+pub mod merkle;
+pub mod sparse;
@@ -0,0 +1,504 @@
+use crate::arkworks::matrix_proof_of_work::alloc::{FpVar2DVec, FpVarVec};
+use crate::arkworks::matrix_proof_of_work::hasher::poseidon_parameters;
+use crate::arkworks::matrix_proof_of_work::merkle::{merkle_root_native, merkle_root_var};
+use ark_crypto_primitives::crh::poseidon::constraints::{CRHGadget, CRHParametersVar};
+use ark_crypto_primitives::crh::CRHSchemeGadget;
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::fields::FieldVar;
+use ark_r1cs_std::prelude::{AllocVar, Boolean, EqGadget};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+
+// A sparse rows x cols matrix of u64 in COO form: (row, column, value) triples sorted
+// row-major, without zeros and without duplicates. CSR input is converted to the same form.
+//
+// The committed representation is the table
+//   [rows, cols, nnz]
+//   [r_0, c_0, v_0]
+//   ..
+//   [r_{nnz-1}, c_{nnz-1}, v_{nnz-1}]
+// under the row Merkle commitment of merkle.rs, so the shape and nnz are bound by the root.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SparseMatrix {
+    pub rows: usize,
+    pub cols: usize,
+    pub entries: Vec<(usize, usize, u64)>,
+}
+
+impl SparseMatrix {
+    pub fn from_coo(
+        rows: usize,
+        cols: usize,
+        mut entries: Vec<(usize, usize, u64)>,
+    ) -> Result<Self, String> {
+        if let Some(&(r, c, _)) = entries.iter().find(|(r, c, _)| *r >= rows || *c >= cols) {
+            return Err(format!(
+                "entry ({}, {}) is outside a {}x{} matrix",
+                r, c, rows, cols
+            ));
+        }
+        entries.retain(|(_, _, v)| *v != 0);
+        entries.sort();
+        if let Some(pair) = entries
+            .windows(2)
+            .find(|w| (w[0].0, w[0].1) == (w[1].0, w[1].1))
+        {
+            return Err(format!(
+                "entry ({}, {}) appears twice",
+                pair[0].0, pair[0].1
+            ));
+        }
+        Ok(Self {
+            rows,
+            cols,
+            entries,
+        })
+    }
+
+    pub fn from_csr(
+        rows: usize,
+        cols: usize,
+        row_ptr: &[usize],
+        col_indices: &[usize],
+        values: &[u64],
+    ) -> Result<Self, String> {
+        let len = rows.checked_add(1).ok_or("rows is too large")?;
+        if row_ptr.len() != len || row_ptr.first() != Some(&0) {
+            return Err(format!("row_ptr must have {} entries starting at 0", len));
+        }
+        if row_ptr.windows(2).any(|w| w[0] > w[1]) || row_ptr.last() != Some(&col_indices.len()) {
+            return Err("row_ptr must be non-decreasing and end at nnz".to_string());
+        }
+        if col_indices.len() != values.len() {
+            return Err("col_indices and values must have the same length".to_string());
+        }
+        let mut entries = Vec::with_capacity(values.len());
+        for i in 0..rows {
+            for k in row_ptr[i]..row_ptr[i + 1] {
+                entries.push((i, col_indices[k], values[k]));
+            }
+        }
+        Self::from_coo(rows, cols, entries)
+    }
+
+    pub fn from_dense(matrix: &[Vec<u64>]) -> Self {
+        let mut entries = Vec::new();
+        for (i, row) in matrix.iter().enumerate() {
+            for (j, v) in row.iter().enumerate() {
+                if *v != 0 {
+                    entries.push((i, j, *v));
+                }
+            }
+        }
+        Self {
+            rows: matrix.len(),
+            cols: matrix.first().map_or(0, |row| row.len()),
+            entries,
+        }
+    }
+
+    pub fn to_dense(&self) -> Vec<Vec<u64>> {
+        let mut matrix = vec![vec![0u64; self.cols]; self.rows];
+        for &(i, j, v) in &self.entries {
+            matrix[i][j] = v;
+        }
+        matrix
+    }
+
+    pub fn nnz(&self) -> usize {
+        self.entries.len()
+    }
+
+    // the fraction of non-zero entries
+    pub fn density(&self) -> f64 {
+        if self.rows * self.cols == 0 {
+            return 0.0;
+        }
+        self.nnz() as f64 / (self.rows * self.cols) as f64
+    }
+
+    // y = A * x over the field, outside of the circuit
+    pub fn mul_vec_native<F: PrimeField>(&self, x: &[u64]) -> Vec<F> {
+        let mut y = vec![F::zero(); self.rows];
+        for &(i, j, v) in &self.entries {
+            y[i] += F::from(v) * F::from(x[j]);
+        }
+        y
+    }
+
+    // the committed table, see above
+    pub fn table<F: PrimeField>(&self) -> Vec<Vec<F>> {
+        let mut table = vec![vec![
+            F::from(self.rows as u64),
+            F::from(self.cols as u64),
+            F::from(self.nnz() as u64),
+        ]];
+        for &(i, j, v) in &self.entries {
+            table.push(vec![F::from(i as u64), F::from(j as u64), F::from(v)]);
+        }
+        table
+    }
+}
+
+// proves y = A * x for a sparse A:
+// only rows, cols and nnz are fixed by the circuit (so they are the verifying key), the pattern
+// and the values of A, the vector x and y are witnesses. a witness pattern can't index x and y
+// directly, so the circuit checks the entries with two sums of fractions at challenges hashed
+// from everything the prover committed to (commitments, multiplicities and looked up values):
+//   sum_k 1 / (alpha - j_k - beta w_k) = sum_c m_c / (alpha - c - beta x_c)
+// makes w_k = x_{j_k} with j_k < cols (m_c is the number of entries in column c), and
+//   sum_k v_k w_k / (gamma - i_k) = sum_r y_r / (gamma - r)
+// makes y_r the sum of the products of row r. the entries are range checked and strictly
+// increasing in row-major order below rows * cols, the same form SparseMatrix keeps them in.
+// every non-zero costs a leaf of the commitment, two inversions and the range checks, so the
+// constraints grow with nnz (plus the length of x and y) and not with rows * cols.
+pub struct SparseMatVecCircuit<F: PrimeField> {
+    matrix: SparseMatrix, // witness, the shape is constant
+    vector: Vec<u64>,     // witness
+    blinding_a: F,        // witness
+    blinding_x: F,        // witness
+    blinding_y: F,        // witness
+    com_a: F,             // public input
+    com_x: F,             // public input
+    com_y: F,             // public input
+}
+
+impl<F: PrimeField + Absorb> SparseMatVecCircuit<F> {
+    pub fn commit(matrix: SparseMatrix, vector: Vec<u64>, blindings: [F; 3]) -> Self {
+        assert_eq!(
+            vector.len(),
+            matrix.cols,
+            "the vector must have cols entries"
+        );
+        let vector_field: Vec<F> = vector.iter().map(|v| F::from(*v)).collect();
+        let com_a = merkle_root_native(&matrix.table::<F>(), blindings[0]);
+        let com_x = merkle_root_native(&[vector_field], blindings[1]);
+        let com_y = merkle_root_native(&[matrix.mul_vec_native::<F>(&vector)], blindings[2]);
+        Self {
+            matrix,
+            vector,
+            blinding_a: blindings[0],
+            blinding_x: blindings[1],
+            blinding_y: blindings[2],
+            com_a,
+            com_x,
+            com_y,
+        }
+    }
+}
+
+impl<F: PrimeField> SparseMatVecCircuit<F> {
+    // the public inputs in the order the verifier expects them:
+    // [rows, cols, nnz, com_a, com_x, com_y]
+    pub fn public_inputs(&self) -> Vec<F> {
+        vec![
+            F::from(self.matrix.rows as u64),
+            F::from(self.matrix.cols as u64),
+            F::from(self.matrix.nnz() as u64),
+            self.com_a,
+            self.com_x,
+            self.com_y,
+        ]
+    }
+
+    pub fn product(&self) -> Vec<F> {
+        self.matrix.mul_vec_native(&self.vector)
+    }
+}
+
+impl<F: PrimeField> Clone for SparseMatVecCircuit<F> {
+    fn clone(&self) -> Self {
+        Self {
+            matrix: self.matrix.clone(),
+            vector: self.vector.clone(),
+            blinding_a: self.blinding_a,
+            blinding_x: self.blinding_x,
+            blinding_y: self.blinding_y,
+            com_a: self.com_a,
+            com_x: self.com_x,
+            com_y: self.com_y,
+        }
+    }
+}
+
+// enforces that var is an integer below 2^bits, value is its native value
+fn enforce_bits<F: PrimeField>(
+    cs: ConstraintSystemRef<F>,
+    var: &FpVar<F>,
+    value: u64,
+    bits: usize,
+) -> Result<(), SynthesisError> {
+    let bits = (0..bits)
+        .map(|b| Boolean::new_witness(cs.clone(), || Ok((value >> b) & 1 == 1)))
+        .collect::<Result<Vec<_>, _>>()?;
+    Boolean::le_bits_to_fp_var(&bits)?.enforce_equal(var)
+}
+
+// sum_k numerator_k / (challenge - point_k), one inversion per term
+fn sum_of_fractions<F: PrimeField>(
+    challenge: &FpVar<F>,
+    terms: &[(FpVar<F>, FpVar<F>)],
+) -> Result<FpVar<F>, SynthesisError> {
+    let mut sum = FpVar::<F>::zero();
+    for (numerator, point) in terms {
+        sum += numerator * (challenge - point).inverse()?;
+    }
+    Ok(sum)
+}
+
+impl<F: PrimeField + Absorb> ConstraintSynthesizer<F> for SparseMatVecCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        // the shape is public and fixed by the circuit
+        let (rows, cols) = (self.matrix.rows, self.matrix.cols);
+        let shape = [rows, cols, self.matrix.nnz()];
+        let mut header = Vec::with_capacity(3);
+        for value in shape {
+            let value = F::from(value as u64);
+            let input = FpVar::<F>::new_input(cs.clone(), || Ok(value))?;
+            input.enforce_equal(&FpVar::constant(value))?;
+            header.push(input);
+        }
+        let com_public_input_a = FpVar::<F>::new_input(cs.clone(), || Ok(self.com_a))?;
+        let com_public_input_x = FpVar::<F>::new_input(cs.clone(), || Ok(self.com_x))?;
+        let com_public_input_y = FpVar::<F>::new_input(cs.clone(), || Ok(self.com_y))?;
+
+        let vector_var = FpVarVec::new_witness(cs.clone(), || Ok(self.vector.clone()))?;
+        let product_var = self
+            .product()
+            .into_iter()
+            .map(|y| FpVar::<F>::new_witness(cs.clone(), || Ok(y)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let blinding_a = FpVar::<F>::new_witness(cs.clone(), || Ok(self.blinding_a))?;
+        let blinding_x = FpVar::<F>::new_witness(cs.clone(), || Ok(self.blinding_x))?;
+        let blinding_y = FpVar::<F>::new_witness(cs.clone(), || Ok(self.blinding_y))?;
+
+        // one row of the table per non-zero, with the range checks of its position
+        let mut table = vec![header];
+        let mut looked_up = Vec::with_capacity(self.matrix.nnz());
+        let mut multiplicities = vec![0u64; cols];
+        let mut previous: Option<(FpVar<F>, u64)> = None;
+        for &(i, j, v) in &self.matrix.entries {
+            let row = FpVar::<F>::new_witness(cs.clone(), || Ok(F::from(i as u64)))?;
+            let col = FpVar::<F>::new_witness(cs.clone(), || Ok(F::from(j as u64)))?;
+            let value = FpVar::<F>::new_witness(cs.clone(), || Ok(F::from(v)))?;
+            let x_j = self.vector.get(j).copied().unwrap_or_default();
+            looked_up.push(FpVar::<F>::new_witness(cs.clone(), || Ok(F::from(x_j)))?);
+            if let Some(count) = multiplicities.get_mut(j) {
+                *count += 1;
+            }
+
+            enforce_bits(cs.clone(), &row, i as u64, 32)?;
+            enforce_bits(cs.clone(), &col, j as u64, 32)?;
+            let position = &row * F::from(cols as u64) + &col;
+            let index = (i * cols + j) as u64;
+            if let Some((previous_var, previous_index)) = previous {
+                let gap = index.wrapping_sub(previous_index).wrapping_sub(1);
+                enforce_bits(cs.clone(), &(&position - previous_var - F::one()), gap, 64)?;
+            }
+            previous = Some((position, index));
+            table.push(vec![row, col, value]);
+        }
+        if let Some((last_var, last_index)) = previous {
+            let end = (rows * cols) as u64;
+            let gap = end.wrapping_sub(last_index).wrapping_sub(1);
+            let gap_var = FpVar::constant(F::from(end)) - last_var - F::one();
+            enforce_bits(cs.clone(), &gap_var, gap, 64)?;
+        }
+        let multiplicities = multiplicities
+            .into_iter()
+            .map(|m| FpVar::<F>::new_witness(cs.clone(), || Ok(F::from(m))))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let com_a = merkle_root_var(cs.clone(), &FpVar2DVec(table.clone()), &blinding_a)?;
+        com_a.enforce_equal(&com_public_input_a)?;
+        let x_table = FpVar2DVec(vec![vector_var.0.clone()]);
+        let com_x = merkle_root_var(cs.clone(), &x_table, &blinding_x)?;
+        com_x.enforce_equal(&com_public_input_x)?;
+        let y_table = FpVar2DVec(vec![product_var.clone()]);
+        let com_y = merkle_root_var(cs.clone(), &y_table, &blinding_y)?;
+        com_y.enforce_equal(&com_public_input_y)?;
+
+        // the challenges come after everything the sums depend on
+        let params = CRHParametersVar::new_constant(cs.clone(), poseidon_parameters())?;
+        let mut transcript = vec![com_a, com_x, com_y];
+        transcript.extend(multiplicities.iter().cloned());
+        transcript.extend(looked_up.iter().cloned());
+        let seed = CRHGadget::<F>::evaluate(&params, &transcript)?;
+        let challenge = |k: u64| {
+            CRHGadget::<F>::evaluate(&params, &[seed.clone(), FpVar::constant(F::from(k))])
+        };
+        let (alpha, beta, gamma) = (challenge(0)?, challenge(1)?, challenge(2)?);
+
+        // x_{j_k} is looked up in the table (c, x_c) of the vector
+        let lookups = table[1..]
+            .iter()
+            .zip(&looked_up)
+            .map(|(entry, w)| (FpVar::one(), &entry[1] + &beta * w))
+            .collect::<Vec<_>>();
+        let columns = vector_var
+            .0
+            .iter()
+            .zip(multiplicities)
+            .enumerate()
+            .map(|(c, (x, m))| (m, &beta * x + F::from(c as u64)))
+            .collect::<Vec<_>>();
+        sum_of_fractions(&alpha, &lookups)?.enforce_equal(&sum_of_fractions(&alpha, &columns)?)?;
+
+        // the products of the entries add up to y in their rows
+        let products = table[1..]
+            .iter()
+            .zip(&looked_up)
+            .map(|(entry, w)| (&entry[2] * w, entry[0].clone()))
+            .collect::<Vec<_>>();
+        let sums = product_var
+            .into_iter()
+            .enumerate()
+            .map(|(r, y)| (y, FpVar::constant(F::from(r as u64))))
+            .collect::<Vec<_>>();
+        sum_of_fractions(&gamma, &products)?.enforce_equal(&sum_of_fractions(&gamma, &sums)?)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::{Bls12_381, Fr as F};
+    use ark_groth16::Groth16;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_snark::{CircuitSpecificSetupSNARK, SNARK};
+    use ark_std::test_rng;
+    use ark_std::UniformRand;
+    use rand::{RngCore, SeedableRng};
+
+    fn random_blindings() -> [F; 3] {
+        let rng = &mut test_rng();
+        [F::rand(rng), F::rand(rng), F::rand(rng)]
+    }
+
+    fn diagonal(n: usize) -> SparseMatrix {
+        SparseMatrix::from_coo(n, n, (0..n).map(|i| (i, i, i as u64 + 1)).collect()).unwrap()
+    }
+
+    fn num_constraints(circuit: SparseMatVecCircuit<F>) -> usize {
+        let cs = ConstraintSystem::<F>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+        cs.num_constraints()
+    }
+
+    #[test]
+    fn csr_and_coo_agree() {
+        // [[0, 5, 0],
+        //  [0, 0, 0],
+        //  [7, 0, 9]]
+        let dense = vec![vec![0u64, 5, 0], vec![0, 0, 0], vec![7, 0, 9]];
+        let csr = SparseMatrix::from_csr(3, 3, &[0, 1, 1, 3], &[1, 0, 2], &[5, 7, 9]).unwrap();
+        let coo = SparseMatrix::from_coo(3, 3, vec![(2, 2, 9), (0, 1, 5), (2, 0, 7)]).unwrap();
+        assert_eq!(csr, coo);
+        assert_eq!(csr, SparseMatrix::from_dense(&dense));
+        assert_eq!(csr.to_dense(), dense);
+        assert_eq!(csr.nnz(), 3);
+        assert!((csr.density() - 3.0 / 9.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        assert!(SparseMatrix::from_coo(2, 2, vec![(2, 0, 1)]).is_err());
+        assert!(SparseMatrix::from_coo(2, 2, vec![(0, 0, 1), (0, 0, 2)]).is_err());
+        assert!(SparseMatrix::from_csr(2, 2, &[0, 1], &[0], &[1]).is_err());
+        assert!(SparseMatrix::from_csr(2, 2, &[0, 2, 1], &[0, 1], &[1, 1]).is_err());
+        assert!(SparseMatrix::from_csr(2, 2, &[0, 1, 2], &[0, 1], &[1]).is_err());
+        assert!(SparseMatrix::from_csr(0, 2, &[], &[], &[]).is_err());
+        assert!(SparseMatrix::from_csr(usize::MAX, 2, &[0], &[], &[]).is_err());
+    }
+
+    #[test]
+    fn is_satisfied_constraints() {
+        let matrix = SparseMatrix::from_coo(3, 3, vec![(0, 1, 5), (2, 0, 7), (2, 2, 9)]).unwrap();
+        let circuit = SparseMatVecCircuit::<F>::commit(matrix, vec![1, 2, 3], random_blindings());
+        assert_eq!(
+            circuit.product(),
+            vec![F::from(10u64), F::from(0u64), F::from(34u64)]
+        );
+        num_constraints(circuit);
+    }
+
+    #[test]
+    fn wrong_product_is_not_satisfied() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let mut circuit =
+            SparseMatVecCircuit::<F>::commit(diagonal(3), vec![1, 2, 3], random_blindings());
+        // commit to y for another x
+        circuit.com_y =
+            SparseMatVecCircuit::<F>::commit(diagonal(3), vec![1, 2, 4], random_blindings()).com_y;
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn unordered_entries_are_not_satisfied() {
+        // the same entry twice, and two entries out of row-major order
+        for entries in [vec![(0, 0, 1), (0, 0, 2)], vec![(1, 0, 1), (0, 1, 2)]] {
+            let matrix = SparseMatrix {
+                rows: 2,
+                cols: 2,
+                entries,
+            };
+            let circuit = SparseMatVecCircuit::<F>::commit(matrix, vec![1, 2], random_blindings());
+            let cs = ConstraintSystem::<F>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            assert!(!cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn constraints_scale_with_nnz() {
+        // 4 times the rows and the non-zeros, a dense circuit would be 16 times bigger
+        let small = num_constraints(SparseMatVecCircuit::commit(
+            diagonal(4),
+            vec![1; 4],
+            random_blindings(),
+        ));
+        let big = num_constraints(SparseMatVecCircuit::commit(
+            diagonal(16),
+            vec![1; 16],
+            random_blindings(),
+        ));
+        assert!(big < 8 * small);
+    }
+
+    #[test]
+    fn groth16_correctness_and_soundness() {
+        let matrix = SparseMatrix::from_coo(4, 3, vec![(0, 0, 1), (1, 2, 2), (3, 1, 3)]).unwrap();
+        let circuit = SparseMatVecCircuit::<F>::commit(matrix, vec![4, 5, 6], random_blindings());
+        let public_inputs = circuit.public_inputs();
+
+        let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+        let (pk, vk) = Groth16::<Bls12_381>::setup(circuit.clone(), &mut rng).unwrap();
+        let pvk = Groth16::<Bls12_381>::process_vk(&vk).unwrap();
+        let proof = Groth16::<Bls12_381>::prove(&pk, circuit, &mut rng).unwrap();
+        assert!(
+            Groth16::<Bls12_381>::verify_with_processed_vk(&pvk, &public_inputs, &proof).unwrap()
+        );
+
+        // the key of the shape proves another pattern with as many non-zeros
+        let other = SparseMatrix::from_coo(4, 3, vec![(0, 2, 7), (2, 0, 8), (2, 1, 9)]).unwrap();
+        let other = SparseMatVecCircuit::<F>::commit(other, vec![1, 2, 3], random_blindings());
+        let other_inputs = other.public_inputs();
+        let other_proof = Groth16::<Bls12_381>::prove(&pk, other, &mut rng).unwrap();
+        assert!(
+            Groth16::<Bls12_381>::verify_with_processed_vk(&pvk, &other_inputs, &other_proof)
+                .unwrap()
+        );
+
+        // the proof doesn't verify for another nnz
+        let mut wrong_inputs = public_inputs.clone();
+        wrong_inputs[2] = F::from(4u64);
+        assert!(
+            !Groth16::<Bls12_381>::verify_with_processed_vk(&pvk, &wrong_inputs, &proof).unwrap()
+        );
+    }
+}
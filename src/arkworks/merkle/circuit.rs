@@ -0,0 +1,161 @@
+use crate::arkworks::merkle::tree::{MerkleHash, MerklePath};
+use ark_ff::PrimeField;
+use ark_r1cs_std::alloc::AllocVar;
+use ark_r1cs_std::boolean::Boolean;
+use ark_r1cs_std::eq::EqGadget;
+use ark_r1cs_std::select::CondSelectGadget;
+use ark_r1cs_std::uint8::UInt8;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use std::marker::PhantomData;
+
+// proves that a private leaf is in the tree with the public root. the siblings and the sides
+// (the bits of the leaf index) are witnesses, so the proof doesn't tell which leaf it is.
+// the leaf length and the depth of the tree fix the circuit.
+pub struct MerkleMembershipCircuit<F: PrimeField, H: MerkleHash<F>> {
+    pub root: H::Digest,
+    pub leaf: Vec<u8>,
+    pub path: MerklePath<H::Digest>,
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField, H: MerkleHash<F>> Clone for MerkleMembershipCircuit<F, H> {
+    fn clone(&self) -> Self {
+        Self::new(self.root.clone(), self.leaf.clone(), self.path.clone())
+    }
+}
+
+impl<F: PrimeField, H: MerkleHash<F>> MerkleMembershipCircuit<F, H> {
+    pub fn new(root: H::Digest, leaf: Vec<u8>, path: MerklePath<H::Digest>) -> Self {
+        Self {
+            root,
+            leaf,
+            path,
+            _field: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, H: MerkleHash<F>> ConstraintSynthesizer<F> for MerkleMembershipCircuit<F, H> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let root = H::DigestVar::new_input(cs.clone(), || Ok(self.root.clone()))?;
+        let leaf = UInt8::new_witness_vec(cs.clone(), &self.leaf)?;
+
+        let mut current = H::leaf_var(cs.clone(), &leaf)?;
+        for (level, sibling) in self.path.siblings.iter().enumerate() {
+            let sibling = H::DigestVar::new_witness(cs.clone(), || Ok(sibling.clone()))?;
+            // true when the current node is the right child
+            let is_right =
+                Boolean::new_witness(cs.clone(), || Ok((self.path.leaf_index >> level) & 1 == 1))?;
+            let left = H::DigestVar::conditionally_select(&is_right, &sibling, &current)?;
+            let right = H::DigestVar::conditionally_select(&is_right, &current, &sibling)?;
+            current = H::node_var(cs.clone(), &left, &right)?;
+        }
+        current.enforce_equal(&root)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::merkle::tree::{MerkleTree, PoseidonMerkle, Sha256Merkle};
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_groth16::{prepare_verifying_key, Groth16};
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_snark::SNARK;
+    use ark_std::rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn leaves() -> Vec<Vec<u8>> {
+        (0..5)
+            .map(|i| format!("member {}", i).into_bytes())
+            .collect()
+    }
+
+    fn is_satisfied<H: MerkleHash<Fr>>(circuit: MerkleMembershipCircuit<Fr, H>) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_membership_at_every_position() {
+        let tree = MerkleTree::<Fr, PoseidonMerkle>::new(&leaves());
+        for (i, leaf) in leaves().into_iter().enumerate() {
+            let circuit = MerkleMembershipCircuit::<Fr, PoseidonMerkle>::new(
+                tree.root(),
+                leaf,
+                tree.path(i).unwrap(),
+            );
+            assert!(is_satisfied(circuit));
+        }
+    }
+
+    #[test]
+    fn test_membership_rejects_outsiders() {
+        let tree = MerkleTree::<Fr, PoseidonMerkle>::new(&leaves());
+        let path = tree.path(2).unwrap();
+        let wrong_leaf = MerkleMembershipCircuit::<Fr, PoseidonMerkle>::new(
+            tree.root(),
+            b"member 9".to_vec(),
+            path.clone(),
+        );
+        assert!(!is_satisfied(wrong_leaf));
+        let wrong_root = MerkleMembershipCircuit::<Fr, PoseidonMerkle>::new(
+            tree.root() + Fr::from(1u64),
+            leaves()[2].clone(),
+            path,
+        );
+        assert!(!is_satisfied(wrong_root));
+    }
+
+    #[test]
+    fn test_sha256_membership() {
+        let tree = MerkleTree::<Fr, Sha256Merkle>::new(&leaves()[..2]);
+        let circuit = MerkleMembershipCircuit::<Fr, Sha256Merkle>::new(
+            tree.root(),
+            leaves()[1].clone(),
+            tree.path(1).unwrap(),
+        );
+        assert!(is_satisfied(circuit));
+        let wrong_leaf = MerkleMembershipCircuit::<Fr, Sha256Merkle>::new(
+            tree.root(),
+            leaves()[0].clone(),
+            tree.path(1).unwrap(),
+        );
+        assert!(!is_satisfied(wrong_leaf));
+    }
+
+    #[test]
+    fn test_membership_groth16() {
+        let tree = MerkleTree::<Fr, PoseidonMerkle>::new(&leaves());
+        let circuit = MerkleMembershipCircuit::<Fr, PoseidonMerkle>::new(
+            tree.root(),
+            leaves()[3].clone(),
+            tree.path(3).unwrap(),
+        );
+        let mut rng = StdRng::seed_from_u64(0u64);
+        let (pk, vk) =
+            Groth16::<Bls12_381>::circuit_specific_setup(circuit.clone(), &mut rng).unwrap();
+        let pvk = prepare_verifying_key::<Bls12_381>(&vk);
+        let proof = Groth16::<Bls12_381>::prove(&pk, circuit, &mut rng).unwrap();
+        let public_input = <PoseidonMerkle as MerkleHash<Fr>>::public_inputs(&tree.root());
+        assert!(
+            Groth16::<Bls12_381>::verify_with_processed_vk(&pvk, &public_input, &proof).unwrap()
+        );
+        // the same keys prove any other leaf of the same length
+        let other = MerkleMembershipCircuit::<Fr, PoseidonMerkle>::new(
+            tree.root(),
+            leaves()[0].clone(),
+            tree.path(0).unwrap(),
+        );
+        let proof = Groth16::<Bls12_381>::prove(&pk, other, &mut rng).unwrap();
+        assert!(
+            Groth16::<Bls12_381>::verify_with_processed_vk(&pvk, &public_input, &proof).unwrap()
+        );
+        let other_root = vec![tree.root() + Fr::from(1u64)];
+        assert!(
+            !Groth16::<Bls12_381>::verify_with_processed_vk(&pvk, &other_root, &proof).unwrap()
+        );
+    }
+}
@@ -0,0 +1,348 @@
+use crate::arkworks::matrix_proof_of_work::hashing::hashing_utils::{
+    poseidon_parameters, CryptographicSpongeVar, PoseidonSpongeVar,
+};
+use crate::arkworks::matrix_proof_of_work::io::{decode_hash, encode_hash, parse_field_element};
+use ark_crypto_primitives::crh::sha256::constraints::{DigestVar, Sha256Gadget};
+use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ff::PrimeField;
+use ark_r1cs_std::alloc::AllocVar;
+use ark_r1cs_std::eq::EqGadget;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::select::CondSelectGadget;
+use ark_r1cs_std::uint8::UInt8;
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use sha2::{Digest, Sha256};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+// the hash of a Merkle tree, natively and in the circuit. leaves and inner nodes are domain
+// separated (a leading 0 or 1), so an inner node can't be passed off as a leaf.
+pub trait MerkleHash<F: PrimeField> {
+    type Digest: Clone + Debug + PartialEq;
+    type DigestVar: AllocVar<Self::Digest, F> + EqGadget<F> + CondSelectGadget<F>;
+
+    fn leaf(data: &[u8]) -> Self::Digest;
+    fn node(left: &Self::Digest, right: &Self::Digest) -> Self::Digest;
+    // the digest of the padding leaves, nobody knows a leaf that hashes to it
+    fn empty() -> Self::Digest;
+
+    fn leaf_var(
+        cs: ConstraintSystemRef<F>,
+        data: &[UInt8<F>],
+    ) -> Result<Self::DigestVar, SynthesisError>;
+    fn node_var(
+        cs: ConstraintSystemRef<F>,
+        left: &Self::DigestVar,
+        right: &Self::DigestVar,
+    ) -> Result<Self::DigestVar, SynthesisError>;
+
+    // the root as the circuit allocates it
+    fn public_inputs(root: &Self::Digest) -> Vec<F>;
+    fn encode_digest(digest: &Self::Digest) -> String;
+    fn decode_digest(digest: &str) -> Result<Self::Digest, String>;
+}
+
+// Poseidon with the project parameters (matrix_proof_of_work::hasher), one field element digests.
+// the sponge packs several bytes into a field element, so a leaf absorbs its length before its
+// bytes, or "a" and "a\0" would hash alike
+pub struct PoseidonMerkle;
+
+impl<F: PrimeField + Absorb> MerkleHash<F> for PoseidonMerkle {
+    type Digest = F;
+    type DigestVar = FpVar<F>;
+
+    fn leaf(data: &[u8]) -> F {
+        let mut sponge = PoseidonSponge::<F>::new(&poseidon_parameters());
+        sponge.absorb(&vec![F::zero(), F::from(data.len() as u64)]);
+        sponge.absorb(&data.to_vec());
+        sponge.squeeze_field_elements(1)[0]
+    }
+
+    fn node(left: &F, right: &F) -> F {
+        let mut sponge = PoseidonSponge::<F>::new(&poseidon_parameters());
+        sponge.absorb(&vec![F::one(), *left, *right]);
+        sponge.squeeze_field_elements(1)[0]
+    }
+
+    fn empty() -> F {
+        F::zero()
+    }
+
+    fn leaf_var(cs: ConstraintSystemRef<F>, data: &[UInt8<F>]) -> Result<FpVar<F>, SynthesisError> {
+        let mut sponge = PoseidonSpongeVar::<F>::new(cs, &poseidon_parameters());
+        // the length is fixed by the circuit, as the leaf length is part of the key id
+        let len = F::from(data.len() as u64);
+        sponge.absorb(&vec![FpVar::Constant(F::zero()), FpVar::Constant(len)])?;
+        sponge.absorb(&data.to_vec())?;
+        Ok(sponge.squeeze_field_elements(1)?.remove(0))
+    }
+
+    fn node_var(
+        cs: ConstraintSystemRef<F>,
+        left: &FpVar<F>,
+        right: &FpVar<F>,
+    ) -> Result<FpVar<F>, SynthesisError> {
+        let mut sponge = PoseidonSpongeVar::<F>::new(cs, &poseidon_parameters());
+        sponge.absorb(&vec![
+            FpVar::Constant(F::one()),
+            left.clone(),
+            right.clone(),
+        ])?;
+        Ok(sponge.squeeze_field_elements(1)?.remove(0))
+    }
+
+    fn public_inputs(root: &F) -> Vec<F> {
+        vec![*root]
+    }
+
+    fn encode_digest(digest: &F) -> String {
+        digest.to_string()
+    }
+
+    fn decode_digest(digest: &str) -> Result<F, String> {
+        parse_field_element(digest)
+    }
+}
+
+// SHA-256 with 0x00 || leaf and 0x01 || left || right, 32 byte digests. the root is 256 public
+// input bits, the circuit is much larger than with Poseidon.
+pub struct Sha256Merkle;
+
+impl<F: PrimeField> MerkleHash<F> for Sha256Merkle {
+    type Digest = Vec<u8>;
+    type DigestVar = DigestVar<F>;
+
+    fn leaf(data: &[u8]) -> Vec<u8> {
+        Sha256::new()
+            .chain_update([0u8])
+            .chain_update(data)
+            .finalize()
+            .to_vec()
+    }
+
+    fn node(left: &Vec<u8>, right: &Vec<u8>) -> Vec<u8> {
+        Sha256::new()
+            .chain_update([1u8])
+            .chain_update(left)
+            .chain_update(right)
+            .finalize()
+            .to_vec()
+    }
+
+    fn empty() -> Vec<u8> {
+        vec![0u8; 32]
+    }
+
+    fn leaf_var(
+        _cs: ConstraintSystemRef<F>,
+        data: &[UInt8<F>],
+    ) -> Result<DigestVar<F>, SynthesisError> {
+        let mut sha256 = Sha256Gadget::default();
+        sha256.update(&[UInt8::constant(0)])?;
+        sha256.update(data)?;
+        sha256.finalize()
+    }
+
+    fn node_var(
+        _cs: ConstraintSystemRef<F>,
+        left: &DigestVar<F>,
+        right: &DigestVar<F>,
+    ) -> Result<DigestVar<F>, SynthesisError> {
+        let mut sha256 = Sha256Gadget::default();
+        sha256.update(&[UInt8::constant(1)])?;
+        sha256.update(&left.0)?;
+        sha256.update(&right.0)?;
+        sha256.finalize()
+    }
+
+    // every byte is allocated as 8 little endian bits
+    fn public_inputs(root: &Vec<u8>) -> Vec<F> {
+        root.iter()
+            .flat_map(|byte| (0..8).map(move |i| F::from((byte >> i) & 1)))
+            .collect()
+    }
+
+    fn encode_digest(digest: &Vec<u8>) -> String {
+        encode_hash(digest)
+    }
+
+    fn decode_digest(digest: &str) -> Result<Vec<u8>, String> {
+        match decode_hash(digest) {
+            Ok(digest) if digest.len() == 32 => Ok(digest),
+            _ => Err("a SHA-256 digest is 32 base64 bytes".to_string()),
+        }
+    }
+}
+
+// the siblings from the leaf up to the root, the leaf index gives the sides
+#[derive(Clone, Debug, PartialEq)]
+pub struct MerklePath<D> {
+    pub leaf_index: usize,
+    pub siblings: Vec<D>,
+}
+
+// a tree over the hashes of the leaves, padded with empty() up to a power of two (at least 2)
+pub struct MerkleTree<F: PrimeField, H: MerkleHash<F>> {
+    pub num_leaves: usize,
+    layers: Vec<Vec<H::Digest>>, // layers[0] are the leaves, the last layer is the root
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField, H: MerkleHash<F>> MerkleTree<F, H> {
+    pub fn new(leaves: &[Vec<u8>]) -> Self {
+        let mut layer: Vec<H::Digest> = leaves.iter().map(|leaf| H::leaf(leaf)).collect();
+        layer.resize(leaves.len().next_power_of_two().max(2), H::empty());
+        let mut layers = vec![layer];
+        while layers.last().unwrap().len() > 1 {
+            let next = layers
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| H::node(&pair[0], &pair[1]))
+                .collect();
+            layers.push(next);
+        }
+        Self {
+            num_leaves: leaves.len(),
+            layers,
+            _field: PhantomData,
+        }
+    }
+
+    pub fn root(&self) -> H::Digest {
+        self.layers.last().unwrap()[0].clone()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.layers.len() - 1
+    }
+
+    pub fn path(&self, leaf_index: usize) -> Option<MerklePath<H::Digest>> {
+        if leaf_index >= self.num_leaves {
+            return None;
+        }
+        let siblings = self.layers[..self.depth()]
+            .iter()
+            .enumerate()
+            .map(|(level, layer)| layer[(leaf_index >> level) ^ 1].clone())
+            .collect();
+        Some(MerklePath {
+            leaf_index,
+            siblings,
+        })
+    }
+}
+
+// hash the leaf up the path and compare with the root
+pub fn verify_path<F: PrimeField, H: MerkleHash<F>>(
+    root: &H::Digest,
+    leaf: &[u8],
+    path: &MerklePath<H::Digest>,
+) -> bool {
+    let mut current = H::leaf(leaf);
+    for (level, sibling) in path.siblings.iter().enumerate() {
+        current = if (path.leaf_index >> level) & 1 == 0 {
+            H::node(&current, sibling)
+        } else {
+            H::node(sibling, &current)
+        };
+    }
+    path.leaf_index >> path.siblings.len() == 0 && &current == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr;
+    use ark_r1cs_std::R1CSVar;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    fn leaves(n: usize) -> Vec<Vec<u8>> {
+        (0..n)
+            .map(|i| format!("member {}", i).into_bytes())
+            .collect()
+    }
+
+    fn paths_verify<H: MerkleHash<Fr>>() {
+        for n in [1, 2, 3, 5, 8] {
+            let tree = MerkleTree::<Fr, H>::new(&leaves(n));
+            assert_eq!(
+                tree.depth(),
+                n.next_power_of_two().max(2).trailing_zeros() as usize
+            );
+            for (i, leaf) in leaves(n).iter().enumerate() {
+                let path = tree.path(i).unwrap();
+                assert!(verify_path::<Fr, H>(&tree.root(), leaf, &path));
+                assert!(!verify_path::<Fr, H>(&tree.root(), b"outsider", &path));
+            }
+            assert!(tree.path(n).is_none());
+        }
+    }
+
+    #[test]
+    fn test_poseidon_paths() {
+        paths_verify::<PoseidonMerkle>();
+    }
+
+    #[test]
+    fn test_sha256_paths() {
+        paths_verify::<Sha256Merkle>();
+        // the leaves are hashed with the 0x00 prefix
+        let tree = MerkleTree::<Fr, Sha256Merkle>::new(&[b"a".to_vec(), b"b".to_vec()]);
+        assert_eq!(
+            tree.root(),
+            <Sha256Merkle as MerkleHash<Fr>>::node(
+                &Sha256::digest(b"\x00a").to_vec(),
+                &Sha256::digest(b"\x00b").to_vec()
+            )
+        );
+    }
+
+    #[test]
+    fn test_poseidon_leaves_of_another_length() {
+        let leaf = <PoseidonMerkle as MerkleHash<Fr>>::leaf;
+        assert_ne!(leaf(b"a"), leaf(b"a\0"));
+        assert_ne!(leaf(b""), leaf(b"\0"));
+    }
+
+    #[test]
+    fn test_path_at_another_position() {
+        let tree = MerkleTree::<Fr, PoseidonMerkle>::new(&leaves(4));
+        let mut path = tree.path(1).unwrap();
+        path.leaf_index = 3;
+        assert!(!verify_path::<Fr, PoseidonMerkle>(
+            &tree.root(),
+            &leaves(4)[1],
+            &path
+        ));
+    }
+
+    #[test]
+    fn test_native_matches_var() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let data = UInt8::new_witness_vec(cs.clone(), b"hello merkle").unwrap();
+        let leaf = <PoseidonMerkle as MerkleHash<Fr>>::leaf_var(cs.clone(), &data).unwrap();
+        let node = <PoseidonMerkle as MerkleHash<Fr>>::node_var(cs.clone(), &leaf, &leaf).unwrap();
+        let native = <PoseidonMerkle as MerkleHash<Fr>>::leaf(b"hello merkle");
+        assert_eq!(leaf.value().unwrap(), native);
+        assert_eq!(
+            node.value().unwrap(),
+            <PoseidonMerkle as MerkleHash<Fr>>::node(&native, &native)
+        );
+    }
+
+    #[test]
+    fn test_sha256_native_matches_var() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let data = UInt8::new_witness_vec(cs.clone(), b"hello merkle").unwrap();
+        let leaf = <Sha256Merkle as MerkleHash<Fr>>::leaf_var(cs.clone(), &data).unwrap();
+        let node = <Sha256Merkle as MerkleHash<Fr>>::node_var(cs.clone(), &leaf, &leaf).unwrap();
+        let native = <Sha256Merkle as MerkleHash<Fr>>::leaf(b"hello merkle");
+        assert_eq!(leaf.value().unwrap().to_vec(), native);
+        assert_eq!(
+            node.value().unwrap().to_vec(),
+            <Sha256Merkle as MerkleHash<Fr>>::node(&native, &native)
+        );
+    }
+}
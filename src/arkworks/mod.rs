@@ -1,6 +1,13 @@
+pub mod aggregation;
 pub mod backend;
 pub mod constraints;
+pub mod curves;
+pub mod evm;
+pub mod folding;
+pub mod key_store;
 pub mod marlin;
 pub mod matrix_proof_of_work;
+pub mod merkle;
 pub mod preimage_poseidon;
 pub mod prime_snark;
+pub mod proof_system;
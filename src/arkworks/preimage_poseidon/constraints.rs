@@ -1,4 +1,4 @@
-use super::hash_parm::{poseidon_parameters_for_test, poseidon_parameters_for_test_field64};
+use crate::arkworks::matrix_proof_of_work::hashing::hashing_utils::poseidon_parameters;
 use ark_bls12_381::Fr;
 use ark_crypto_primitives::sponge::constraints::CryptographicSpongeVar;
 use ark_crypto_primitives::sponge::poseidon::constraints::PoseidonSpongeVar;
@@ -41,8 +41,7 @@ pub fn hasher_string_var<ConstraintF: PrimeField>(
     cs: ConstraintSystemRef<ConstraintF>,
     message: Vec<UInt8<ConstraintF>>,
 ) -> Result<Vec<FpVar<ConstraintF>>, SynthesisError> {
-    // let sponge_param = poseidon_parameters_for_test();
-    let sponge_param: PoseidonConfig<ConstraintF> = poseidon_parameters_for_test_field64();
+    let sponge_param: PoseidonConfig<ConstraintF> = poseidon_parameters();
     let mut sponge = PoseidonSpongeVar::<ConstraintF>::new(cs.clone(), &sponge_param);
     // Absorb the message:
     sponge.absorb(&message)?;
@@ -65,12 +64,39 @@ impl<F: PrimeField> ConstraintSynthesizer<F> for HashCircuit<F> {
         hash[0].enforce_equal(&expected_hash)
     }
 }
+// the message is a witness and its Poseidon digest the only public input. the length of the
+// message is fixed by the circuit, so the keys are set up per message length.
+#[derive(Clone)]
+pub struct PoseidonPreimageCircuit<F: PrimeField> {
+    pub message: Vec<u8>,
+    pub digest: F,
+}
+
+impl<F: PrimeField + Absorb> PoseidonPreimageCircuit<F> {
+    pub fn new(message: Vec<u8>) -> Self {
+        let digest = hasher_string_native::<F>(&message).unwrap()[0];
+        Self { message, digest }
+    }
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for PoseidonPreimageCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let digest = FpVar::<F>::new_input(cs.clone(), || Ok(self.digest))?;
+        let message = self
+            .message
+            .iter()
+            .map(|byte| UInt8::new_witness(cs.clone(), || Ok(*byte)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let hash = hasher_string_var(cs.clone(), message)?;
+        hash[0].enforce_equal(&digest)
+    }
+}
+
 // do hasher that get a string and return a hash in the native field:
 pub fn hasher_string_native<ConstraintF: PrimeField + Absorb>(
     c: &Vec<u8>,
 ) -> Result<Vec<ConstraintF>, SynthesisError> {
-    // let sponge_param: PoseidonConfig<_> = poseidon_parameters_for_test();
-    let sponge_param: PoseidonConfig<ConstraintF> = poseidon_parameters_for_test_field64();
+    let sponge_param: PoseidonConfig<ConstraintF> = poseidon_parameters();
     let mut sponge: PoseidonSponge<ConstraintF> = PoseidonSponge::<ConstraintF>::new(&sponge_param);
     sponge.absorb(&c);
     let hash = sponge.squeeze_field_elements(1).to_vec();
@@ -137,4 +163,52 @@ mod test {
     }
     #[test]
     fn smaller_field() {}
+
+    fn preimage_is_satisfied<F: PrimeField + Absorb>(circuit: PoseidonPreimageCircuit<F>) -> bool {
+        let cs = ConstraintSystem::<F>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn test_poseidon_preimage_circuit() {
+        assert!(preimage_is_satisfied(PoseidonPreimageCircuit::<F>::new(
+            b"hello world".to_vec()
+        )));
+        assert!(preimage_is_satisfied(
+            PoseidonPreimageCircuit::<Field64>::new(b"hello world".to_vec())
+        ));
+
+        // another message doesn't hash to the digest
+        let mut circuit = PoseidonPreimageCircuit::<Field64>::new(b"hello world".to_vec());
+        circuit.message[0] = b'j';
+        assert!(!preimage_is_satisfied(circuit));
+        let mut circuit = PoseidonPreimageCircuit::<F>::new(b"hello world".to_vec());
+        circuit.digest += F::from(1u64);
+        assert!(!preimage_is_satisfied(circuit));
+    }
+
+    #[test]
+    fn test_poseidon_preimage_groth16() {
+        use ark_bls12_381::Bls12_381;
+        use ark_groth16::Groth16;
+        use ark_snark::SNARK;
+        use ark_std::rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let rng = &mut StdRng::seed_from_u64(0u64);
+        let circuit = PoseidonPreimageCircuit::<F>::new(b"hello world".to_vec());
+        let (pk, vk) = Groth16::<Bls12_381>::circuit_specific_setup(circuit.clone(), rng).unwrap();
+        let proof = Groth16::<Bls12_381>::prove(&pk, circuit.clone(), rng).unwrap();
+        assert!(Groth16::<Bls12_381>::verify(&vk, &[circuit.digest], &proof).unwrap());
+        let other = hasher_string_native::<F>(&b"hello there".to_vec()).unwrap();
+        assert!(!Groth16::<Bls12_381>::verify(&vk, &other, &proof).unwrap());
+    }
+
+    #[test]
+    fn test_poseidon_alpha() {
+        use crate::arkworks::preimage_poseidon::hash_parm::poseidon_alpha;
+        assert_eq!(poseidon_alpha::<F>(), 5);
+        assert_eq!(poseidon_alpha::<Field64>(), 7);
+    }
 }
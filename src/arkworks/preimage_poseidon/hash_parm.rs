@@ -741,11 +741,28 @@ pub fn poseidon_parameters_for_test<F: PrimeField>() -> PoseidonConfig<F> {
     }
 }
 
-// generate the parameters for Field64 prime field and alpha = 17
+// the smallest alpha for which x^alpha is a permutation of F, i.e. gcd(alpha, p - 1) = 1:
+// 5 for the BLS12-381 scalar field, 7 for Goldilocks where 5 divides p - 1
+pub fn poseidon_alpha<F: PrimeField>() -> u64 {
+    let modulus_rem = |alpha: u64| {
+        F::MODULUS
+            .as_ref()
+            .iter()
+            .rev()
+            .fold(0u128, |rem, limb| ((rem << 64) + *limb as u128) % alpha as u128)
+    };
+    [5, 7, 11, 13, 17]
+        .into_iter()
+        .find(|alpha| modulus_rem(*alpha) != 1)
+        .unwrap()
+}
+
+// generate the parameters for the Field64 prime field (and any other field), alpha from
+// poseidon_alpha
 pub fn poseidon_parameters_for_test_field64<F: PrimeField>() -> PoseidonConfig<F> {
     let full_rounds = 8;
     let partial_rounds = 57;
-    let alpha = 5; // Example for Poseidon
+    let alpha = poseidon_alpha::<F>();
 
     let rate = 2;
     let capacity = 1;
@@ -35,22 +35,18 @@ pub struct FermatCircuit<ConstraintF: PrimeField> {
     mod_base_ver: Vec<ModWitnesses<ConstraintF>>,
 }
 
+// the bits are witnesses, so the constraints (and the keys) don't depend on the exponent
 fn check_bits_is_exp<ConstraintF: PrimeField>(
-    cs: ConstraintSystemRef<ConstraintF>,
-    bits: Vec<ConstraintF>,
+    bits: &[FpVar<ConstraintF>],
     exp: FpVar<ConstraintF>,
-) {
-    let mut res =
-        FpVar::<ConstraintF>::new_witness(cs.clone(), || Ok(ConstraintF::zero())).unwrap();
-    let two = FpVar::<ConstraintF>::constant(ConstraintF::one() + ConstraintF::one());
-    let mut cur_pow = FpVar::<ConstraintF>::constant(ConstraintF::one());
-    for i in 0..NUM_BITS {
-        res.add_assign(&cur_pow * bits[i]);
-        cur_pow.mul_assign(&two);
+) -> Result<(), SynthesisError> {
+    let mut res = FpVar::<ConstraintF>::zero();
+    let mut cur_pow = ConstraintF::one();
+    for bit in bits {
+        res.add_assign(bit * cur_pow);
+        cur_pow.double_in_place();
     }
-    //println!("{:?}",res.value().unwrap());
-    //println!("{:?}",exp.value().unwrap());
-    res.enforce_equal(&exp).unwrap();
+    res.enforce_equal(&exp)
 }
 // function that get modpow_ver_circuit and create the constraints for  modpow
 fn modpow<ConstraintF: PrimeField>(
@@ -61,16 +57,19 @@ fn modpow<ConstraintF: PrimeField>(
     exp: FpVar<ConstraintF>,
 ) -> Result<(), SynthesisError> {
     let mut cur_pow = base.clone();
-    let bits = &modpow_ver_circuit.bits;
+    let bits = (0..NUM_BITS)
+        .map(|i| {
+            Boolean::new_witness(cs.clone(), || Ok(modpow_ver_circuit.bits[i].is_one()))
+                .map(FpVar::from)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
     let result: FpVar<ConstraintF> =
         FpVar::<ConstraintF>::new_witness(cs.clone(), || Ok(modpow_ver_circuit.result))?;
     let one = base * &base.inverse().unwrap();
     let mut calculated_res = one.clone();
-    check_bits_is_exp(cs.clone(), bits.clone(), exp);
+    check_bits_is_exp(&bits, exp)?;
     for i in 0..NUM_BITS {
-        let elem_val = &bits[i];
-        let elem = FpVar::<ConstraintF>::new_witness(cs.clone(), || Ok(elem_val))?;
-        calculated_res.mul_assign(elem * (&cur_pow - &one) + &one);
+        calculated_res.mul_assign(&bits[i] * (&cur_pow - &one) + &one);
         let cur_q: FpVar<ConstraintF> = FpVar::<ConstraintF>::new_witness(cs.clone(), || {
             Ok(modpow_ver_circuit.modulo_witnesses[i].q)
         })?;
@@ -128,9 +127,10 @@ impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF> for FermatCircu
         Ok(())
     }
 }
-pub fn fermat_test(a: &BigUint, p: &BigUint) -> bool {
+// the bases hash a as an element of F, so the test depends on the field of the circuit
+pub fn fermat_test<F: PrimeField>(a: &BigUint, p: &BigUint) -> bool {
     let one_val = BigUint::from(1u32);
-    let bases = generate_bases_native(a,p).0;
+    let bases = generate_bases_native::<F>(a,p).0;
     for i in 0..K {
         if bases[i].modpow(&(p - &one_val), p) == one_val {
             return true;
@@ -146,7 +146,7 @@ pub fn fermat_constructor<ConstraintF: PrimeField>(
     let modpow_circuit = struct_initializer::<ConstraintF>(a.clone(), n.clone() - 1u32, n.clone());
     let mut circuits = vec![modpow_circuit; K];
     let mut results = vec![ConstraintF::from(0u8); K];
-    let base_outputs = generate_bases_native(&a,&n);
+    let base_outputs = generate_bases_native::<ConstraintF>(&a,&n);
     let bases = base_outputs.0;
     let mod_vals = base_outputs.1;
     let witnesses = vector_convertor::<ConstraintF>(mod_vals);
@@ -156,7 +156,7 @@ pub fn fermat_constructor<ConstraintF: PrimeField>(
         results[i] = circuits[i].result.clone();
     }
     return FermatCircuit {
-        is_prime: fermat_test(&a, &n),
+        is_prime: fermat_test::<ConstraintF>(&a, &n),
         n: ConstraintF::from(n),
         a: ConstraintF::from(a),
         results,
@@ -5,7 +5,7 @@ use super::utils::hasher::{finalize, hash_to_bytes};
 use super::utils::modulo::{self, get_mod_vals};
 use crate::arkworks::prime_snark::modpow_circut::{ModWitnesses, ModpowVerCircuit};
 use alloy_sol_types::sol_data::Bool;
-use ark_bls12_381::{Bls12_381, Fr};
+use ark_bls12_381::Bls12_381;
 use ark_crypto_primitives::crh::sha256::constraints::DigestVar;
 use ark_ff::BigInteger;
 use ark_ff::{Field, PrimeField};
@@ -41,12 +41,12 @@ pub struct PrimeCircuit<ConstraintF: PrimeField> {
     pub fermat_circuit: FermatCircuit<ConstraintF>, // The randomness is inside this struct
 }
 // create constructor for the circuit:
-impl PrimeCircuit<Fr> {
-    /// Creates a new [`PrimeCircut<Fr>`].
+impl<ConstraintF: PrimeField> PrimeCircuit<ConstraintF> {
+    /// Creates a new [`PrimeCircut`].
     pub fn new(
         a: BigUint,
         num_to_prove: BigUint,
-        x: Fr,
+        x: ConstraintF,
         a_i: Vec<u8>,
         i: u64,
         vals: ModVals,
@@ -73,7 +73,7 @@ impl PrimeCircuit<Fr> {
 
         // let vals: ModVals = modulo::get_mod_vals(&a_i_biguint, &get_max_val());
 
-        let fermat_circuit = fermat_circut::fermat_constructor::<Fr>(a, num_to_prove);
+        let fermat_circuit = fermat_circut::fermat_constructor::<ConstraintF>(a, num_to_prove);
 
         //TODO: hash x+1 ... x+i-1
         let a_j_s = vec![];
@@ -96,8 +96,12 @@ impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF> for PrimeCircui
     ) -> Result<(), SynthesisError> {
         // create the public inputs:
         let x_var = FpVar::<ConstraintF>::new_input(ark_relations::ns!(cs, "x"), || Ok(self.x))?;
+        // i is a public input rather than a constant, so one key fits every i:
+        let i_var = FpVar::<ConstraintF>::new_input(ark_relations::ns!(cs, "i"), || {
+            Ok(ConstraintF::from(self.i))
+        })?;
         // compute x+i:
-        let x_plus_i = x_var + FpVar::<ConstraintF>::constant(ConstraintF::from(self.i));
+        let x_plus_i = x_var + i_var;
         // calculate the hash(x+i):
         let calculated_a_i: DigestVar<ConstraintF> = hash_to_bytes(x_plus_i);
         // enforce that a_i = hash(x+i):
@@ -162,10 +166,10 @@ fn vec_u8_to_vec_bigint(vec: Vec<u8>) -> Vec<BigInt> {
         .collect()
 }
 pub struct IsPrimeStruct(pub Vec<u8>, pub bool, pub ModVals, pub BigUint);
-pub fn check_if_next_is_prime(x: Fr, j: u64) -> IsPrimeStruct {
+pub fn check_if_next_is_prime<F: PrimeField>(x: F, j: u64) -> IsPrimeStruct {
     // hash(x+j):
     let mut sha256 = Sha256::default();
-    let x_plus_j = x + Fr::from(j);
+    let x_plus_j = x + F::from(j);
     let x_plus_j_bytes = x_plus_j.into_bigint().to_bytes_le();
     // do the hash for x+j:
     sha256.update(&x_plus_j_bytes);
@@ -179,16 +183,16 @@ pub fn check_if_next_is_prime(x: Fr, j: u64) -> IsPrimeStruct {
     // create the randomnes:
     init_randomness(&mut r_bytes, x_plus_j_bytes.clone(), a_j.clone(), j);
 
-    // convert r to Fr:
-    let r = Fr::from_le_bytes_mod_order(&r_bytes);
+    // convert r to F:
+    let r = F::from_le_bytes_mod_order(&r_bytes);
     // take mod MAX_VAL:
     let max_val = get_max_val();
     let vals = get_mod_vals(&a_j_biguint, &max_val);
 
     let num_to_check = &vals.remainder;
-    let a = &BigUint::from(r);
+    let a: &BigUint = &r.into();
     // check if num_to_check is prime using the Fermat primality test
-    let is_prime = fermat_test(a, num_to_check);
+    let is_prime = fermat_test::<F>(a, num_to_check);
 
     // return the IsPrimeStruct containing the hashed value, primality result, and mod results
     IsPrimeStruct(a_j, is_prime, vals, a.clone())
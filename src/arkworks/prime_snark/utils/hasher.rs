@@ -1,4 +1,3 @@
-use ark_bls12_381::Fr;
 use ark_crypto_primitives::crh::sha256::constraints::{DigestVar, Sha256Gadget};
 use ark_ff::BigInteger;
 use ark_ff::PrimeField;
@@ -48,7 +47,7 @@ pub fn hash_x_plus_i_native<F: PrimeField>(x: F, i: u64) -> Vec<u8> {
     // Finalize and return the resulting hash as a byte vector
     sha256.finalize().to_vec()
 }
-pub fn generate_bases_native(
+pub fn generate_bases_native<F: PrimeField>(
     x: &BigUint,
     n_value: &BigUint,
 ) -> (Vec<num_bigint::BigUint>, Vec<ModVals>) {
@@ -57,9 +56,8 @@ pub fn generate_bases_native(
     let divisor = n_value; // + BigUint::from(1u8);
     for j in 0..K {
         let mut sha256 = Sha256::default();
-        let x_fr = Fr::from(x.clone());
-        let j_fr: ark_ff::Fp<ark_ff::MontBackend<ark_bls12_381::FrConfig, 4>, 4> =
-            Fr::from(j as u64);
+        let x_fr = F::from(x.clone());
+        let j_fr = F::from(j as u64);
         let x_bytes = x_fr.into_bigint().to_bytes_le();
         let j_bytes = j_fr.into_bigint().to_bytes_le();
         // do the hash for x || j
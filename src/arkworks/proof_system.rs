@@ -0,0 +1,168 @@
+use crate::arkworks::curves::SnarkCurve;
+use crate::proof_system::{ProofSystem, ProofSystemError, Prover};
+use ark_bls12_377::Bls12_377;
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use ark_groth16::{prepare_verifying_key, PreparedVerifyingKey, Proof, ProvingKey};
+use ark_relations::r1cs::ConstraintSynthesizer;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_snark::{CircuitSpecificSetupSNARK, SNARK};
+use rand::rngs::OsRng;
+use std::fmt;
+use std::marker::PhantomData;
+
+pub use crate::arkworks::marlin::proof_system::MarlinBackend;
+
+// Groth16 with a setup per circuit over one of the curves, the keys of every curve are in
+// their own directory of the key store. the setup uses OsRng, so nobody knows the toxic
+// waste. the verifying key is kept prepared, its bytes are the same as encode_pvk's.
+pub struct Groth16<E>(PhantomData<E>);
+
+// the SNARKs of the endpoints
+pub type Groth16Backend = Groth16<Bls12_381>;
+// the curve with EVM precompiles, for the proofs of the solidity verifiers (see arkworks::evm)
+pub type Groth16Bn254Backend = Groth16<Bn254>;
+pub type Groth16Bls12_377Backend = Groth16<Bls12_377>;
+
+// not derived, they would need E: Default, Clone..
+impl<E> Default for Groth16<E> {
+    fn default() -> Self {
+        Groth16(PhantomData)
+    }
+}
+
+impl<E> Clone for Groth16<E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E> Copy for Groth16<E> {}
+
+impl<E: SnarkCurve> fmt::Debug for Groth16<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Groth16<{}>", E::CURVE)
+    }
+}
+
+impl<E: SnarkCurve> ProofSystem for Groth16<E> {
+    const NAME: &'static str = E::GROTH16;
+
+    type ProvingKey = ProvingKey<E>;
+    type VerifyingKey = PreparedVerifyingKey<E>;
+    type Proof = Proof<E>;
+    type PublicInput = [E::ScalarField];
+
+    fn verify(
+        &self,
+        vk: &Self::VerifyingKey,
+        public_input: &Self::PublicInput,
+        proof: &Self::Proof,
+    ) -> Result<bool, ProofSystemError> {
+        Ok(ark_groth16::Groth16::<E>::verify_with_processed_vk(
+            vk,
+            public_input,
+            proof,
+        )?)
+    }
+
+    // proving keys are big and only ever read back by the key store, so they are stored
+    // uncompressed and loaded without the curve checks
+    fn proving_key_to_bytes(pk: &Self::ProvingKey) -> Result<Vec<u8>, ProofSystemError> {
+        let mut bytes = Vec::new();
+        pk.serialize_uncompressed(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn proving_key_from_bytes(bytes: &[u8]) -> Result<Self::ProvingKey, ProofSystemError> {
+        Ok(ProvingKey::deserialize_uncompressed_unchecked(bytes)?)
+    }
+
+    fn verifying_key_to_bytes(vk: &Self::VerifyingKey) -> Result<Vec<u8>, ProofSystemError> {
+        let mut bytes = Vec::new();
+        vk.serialize_compressed(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn verifying_key_from_bytes(bytes: &[u8]) -> Result<Self::VerifyingKey, ProofSystemError> {
+        Ok(PreparedVerifyingKey::deserialize_compressed(bytes)?)
+    }
+
+    fn proof_to_bytes(proof: &Self::Proof) -> Result<Vec<u8>, ProofSystemError> {
+        let mut bytes = Vec::new();
+        proof.serialize_compressed(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn proof_from_bytes(bytes: &[u8]) -> Result<Self::Proof, ProofSystemError> {
+        Ok(Proof::deserialize_compressed(bytes)?)
+    }
+}
+
+impl<E: SnarkCurve, C: ConstraintSynthesizer<E::ScalarField>> Prover<C> for Groth16<E> {
+    fn setup(
+        &self,
+        circuit: C,
+    ) -> Result<(Self::ProvingKey, Self::VerifyingKey), ProofSystemError> {
+        let (pk, vk) = ark_groth16::Groth16::<E>::setup(circuit, &mut OsRng)?;
+        Ok((pk, prepare_verifying_key(&vk)))
+    }
+
+    fn prove(&self, pk: &Self::ProvingKey, circuit: C) -> Result<Self::Proof, ProofSystemError> {
+        Ok(ark_groth16::Groth16::<E>::prove(pk, circuit, &mut OsRng)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::backend::fibbonaci_handler::fibonacci;
+    use crate::arkworks::constraints::fibbonaci::FibonacciCircuit;
+    use crate::arkworks::marlin::proof_system::SrsBounds;
+    use ark_bls12_381::Fr;
+    use ark_ff::PrimeField;
+
+    fn circuit<F: PrimeField>(a: u64, b: u64, steps: usize) -> FibonacciCircuit<F> {
+        let (a, b) = (F::from(a), F::from(b));
+        FibonacciCircuit {
+            a: Some(a),
+            b: Some(b),
+            num_of_steps: steps,
+            result: Some(fibonacci(steps, a, b)),
+        }
+    }
+
+    // the same circuit, proven and verified through the trait only
+    fn round_trip<F: PrimeField, S: Prover<FibonacciCircuit<F>, PublicInput = [F]>>(system: &S) {
+        let (pk, vk) = system.setup(circuit(1, 1, 8)).unwrap();
+        let pk = S::proving_key_from_bytes(&S::proving_key_to_bytes(&pk).unwrap()).unwrap();
+        let vk = S::verifying_key_from_bytes(&S::verifying_key_to_bytes(&vk).unwrap()).unwrap();
+
+        let proof = system.prove(&pk, circuit(2, 5, 8)).unwrap();
+        let proof = S::proof_from_bytes(&S::proof_to_bytes(&proof).unwrap()).unwrap();
+        let (a, b) = (F::from(2u64), F::from(5u64));
+        assert!(system
+            .verify(&vk, &[a, b, fibonacci(8, a, b)], &proof)
+            .unwrap());
+        assert!(!system
+            .verify(&vk, &[a, b, fibonacci(8, a, b) + F::from(1u64)], &proof)
+            .unwrap());
+    }
+
+    #[test]
+    fn groth16_proof_system() {
+        round_trip(&Groth16Backend::default());
+    }
+
+    #[test]
+    fn groth16_on_every_curve() {
+        round_trip(&Groth16Bn254Backend::default());
+        round_trip(&Groth16Bls12_377Backend::default());
+        assert_eq!(Groth16Bls12_377Backend::NAME, "groth16_bls12_377");
+    }
+
+    #[test]
+    fn marlin_proof_system() {
+        round_trip::<Fr, _>(&MarlinBackend::universal_setup(SrsBounds::with_log_size(8)).unwrap());
+    }
+}
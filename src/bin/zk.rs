@@ -0,0 +1,433 @@
+//! The circuits of the server on the command line, for scripts and CI. Proofs are written as
+//! bundles (see prime_snarks::bundle) and use the same key store as the server.
+//!
+//! ```shell
+//! cargo run --release --bin zk -- fib prove --rounds 100 --out fib.json
+//! cargo run --release --bin zk -- verify fib.json
+//! ```
+
+use ark_bls12_377::Bls12_377;
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use ark_ff::Zero;
+use clap::{Args, Parser, Subcommand};
+use prime_snarks::arkworks::backend::fibbonaci_handler::{
+    fast_fibonacci_proof, fibonacci, fibonacci_proof, InputDataFastFib, InputDataFib, MAX_ROUNDS,
+};
+use prime_snarks::arkworks::backend::matrix_proof::{self, MatrixInput};
+use prime_snarks::arkworks::backend::prime_snark;
+use prime_snarks::arkworks::backend::proving::setup_keys;
+use prime_snarks::arkworks::constraints::fast_fibonacci::FastFibonacciCircuit;
+use prime_snarks::arkworks::constraints::fibbonaci::FibonacciCircuit;
+use prime_snarks::arkworks::curves::SnarkCurve;
+use prime_snarks::arkworks::key_store::{key_id, KeyStore, DEFAULT_KEY_DIR};
+use prime_snarks::arkworks::marlin::proof_system::SrsBounds;
+use prime_snarks::arkworks::matrix_proof_of_work::constraints::MatrixCircuit;
+use prime_snarks::arkworks::matrix_proof_of_work::io::{
+    read_bundle, read_proof, write_bundle_to_file, write_proof_to_file,
+};
+use prime_snarks::arkworks::proof_system::Groth16;
+use prime_snarks::bundle::{verify_bundle, ProofBundle};
+use prime_snarks::proof_system::{Backend, Curve, ProofSystem};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::error::Error;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "zk",
+    version,
+    about = "Prove and verify the circuits without the server"
+)]
+struct Cli {
+    /// The directory of the key store
+    #[arg(long, global = true, env = "ZK_KEY_DIR", default_value = DEFAULT_KEY_DIR)]
+    keys: PathBuf,
+    /// The proof system: groth16 or marlin
+    #[arg(long, global = true, default_value = "groth16")]
+    backend: Backend,
+    /// The pairing engine: bls12_381, bn254 or bls12_377
+    #[arg(long, global = true, default_value = "bls12_381")]
+    curve: Curve,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// F(n) for the starting values a and b
+    Fib {
+        #[command(subcommand)]
+        command: FibCommand,
+    },
+    /// A * B for n x n matrices, or A * x for a sparse A
+    Matrix {
+        #[command(subcommand)]
+        command: MatrixCommand,
+    },
+    /// The first prime among the hashes of x + j for j up to rounds
+    Prime {
+        #[command(subcommand)]
+        command: PrimeCommand,
+    },
+    /// Set up the keys of a circuit before its first proof
+    Setup {
+        #[command(subcommand)]
+        circuit: SetupCircuit,
+    },
+    /// Verify a bundle written by a prove command or returned by the server
+    Verify {
+        bundle: PathBuf,
+        /// A groth16 proof file for the statement of the bundle, instead of its proof
+        #[arg(long)]
+        proof: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum FibCommand {
+    Prove {
+        #[arg(long, default_value = "0")]
+        a: String,
+        #[arg(long, default_value = "1")]
+        b: String,
+        #[arg(long, default_value_t = 10)]
+        rounds: u64,
+        /// The circuit with O(log n) constraints, one set of keys for every n
+        #[arg(long)]
+        fast: bool,
+        #[command(flatten)]
+        output: Output,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum MatrixCommand {
+    /// The matrices are json files in the formats of /matrix_prove/prove
+    Prove {
+        #[arg(long)]
+        a: PathBuf,
+        #[arg(long, required_unless_present = "vector")]
+        b: Option<PathBuf>,
+        #[arg(long, conflicts_with = "b")]
+        vector: Option<PathBuf>,
+        #[command(flatten)]
+        output: Output,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum PrimeCommand {
+    Prove {
+        #[arg(long)]
+        x: u64,
+        #[arg(long)]
+        rounds: u64,
+        #[command(flatten)]
+        output: Output,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum SetupCircuit {
+    Fibonacci {
+        #[arg(long)]
+        rounds: usize,
+    },
+    #[command(name = "fast_fibonacci")]
+    FastFibonacci,
+    Matrix {
+        #[arg(long)]
+        n: usize,
+    },
+}
+
+#[derive(Args, Debug)]
+struct Output {
+    /// Where to write the bundle, as json for a .json file and in the binary form otherwise
+    #[arg(long, default_value = "bundle.json")]
+    out: PathBuf,
+    /// Also write the groth16 proof alone to this file
+    #[arg(long)]
+    proof: Option<PathBuf>,
+}
+
+fn path_str(path: &Path) -> Result<&str, Box<dyn Error>> {
+    path.to_str()
+        .ok_or_else(|| format!("{} is not a utf-8 path", path.display()).into())
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, Box<dyn Error>> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    serde_json::from_reader(file).map_err(|e| format!("{}: {}", path.display(), e).into())
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<(), Box<dyn Error>> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+fn check_groth16(bundle: &ProofBundle) -> Result<(), Box<dyn Error>> {
+    if bundle.backend != Backend::Groth16 {
+        return Err(format!(
+            "proof files are groth16 proofs, the bundle is {}",
+            bundle.backend
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn write_output(bundle: &ProofBundle, output: &Output) -> Result<(), Box<dyn Error>> {
+    let json = output
+        .out
+        .extension()
+        .is_some_and(|extension| extension == "json");
+    if json {
+        fs::write(&output.out, bundle.to_json())?;
+    } else {
+        write_bundle_to_file(bundle, path_str(&output.out)?)?;
+    }
+    if let Some(path) = &output.proof {
+        check_groth16(bundle)?;
+        let path = path_str(path)?;
+        match bundle.curve {
+            Curve::Bls12_381 => write_groth16_proof::<Bls12_381>(&bundle.proof, path)?,
+            Curve::Bn254 => write_groth16_proof::<Bn254>(&bundle.proof, path)?,
+            Curve::Bls12_377 => write_groth16_proof::<Bls12_377>(&bundle.proof, path)?,
+        }
+    }
+    eprintln!("wrote the bundle to {}", output.out.display());
+    Ok(())
+}
+
+// the proof files are the compressed arkworks proofs of the curve of the bundle
+fn write_groth16_proof<E: SnarkCurve>(proof: &[u8], path: &str) -> Result<(), Box<dyn Error>> {
+    write_proof_to_file(&Groth16::<E>::proof_from_bytes(proof)?, path)?;
+    Ok(())
+}
+
+fn read_groth16_proof<E: SnarkCurve>(path: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    Ok(Groth16::<E>::proof_to_bytes(&read_proof::<E>(path)?)?)
+}
+
+fn fib(
+    keys: &KeyStore,
+    backend: Backend,
+    curve: Curve,
+    command: FibCommand,
+) -> Result<(), Box<dyn Error>> {
+    let FibCommand::Prove {
+        a,
+        b,
+        rounds,
+        fast,
+        output,
+    } = command;
+    let proved = if fast {
+        let input = InputDataFastFib {
+            a,
+            b,
+            n: rounds,
+            backend,
+            curve,
+        };
+        fast_fibonacci_proof(keys, backend, &input)?
+    } else {
+        let input = InputDataFib {
+            a,
+            b,
+            num_of_rounds: rounds.try_into()?,
+            backend,
+            curve,
+        };
+        fibonacci_proof(keys, backend, &input)?
+    };
+    write_output(&proved.bundle, &output)?;
+    print_json(&proved)
+}
+
+fn matrix(
+    keys: &KeyStore,
+    backend: Backend,
+    curve: Curve,
+    command: MatrixCommand,
+) -> Result<(), Box<dyn Error>> {
+    let MatrixCommand::Prove {
+        a,
+        b,
+        vector,
+        output,
+    } = command;
+    let input = matrix_proof::ProveInput::new(
+        read_json::<MatrixInput>(&a)?,
+        b.as_deref().map(read_json).transpose()?,
+        vector.as_deref().map(read_json).transpose()?,
+        backend,
+        curve,
+    );
+    let proved = matrix_proof::matrix_proof(keys, backend, input)?;
+    write_output(proved.bundle(), &output)?;
+    print_json(&proved)
+}
+
+fn prime(
+    keys: &KeyStore,
+    backend: Backend,
+    curve: Curve,
+    command: PrimeCommand,
+) -> Result<(), Box<dyn Error>> {
+    let PrimeCommand::Prove { x, rounds, output } = command;
+    let input = prime_snark::ProveInput::new(x, rounds, backend, curve);
+    let proved = prime_snark::prime_proof(keys, backend, &input)?;
+    let bundle = proved
+        .bundle()
+        .ok_or_else(|| format!("no prime among x + j for j up to {}", rounds))?;
+    write_output(bundle, &output)?;
+    print_json(&proved)
+}
+
+fn setup(
+    keys: &KeyStore,
+    backend: Backend,
+    curve: Curve,
+    circuit: SetupCircuit,
+) -> Result<(), Box<dyn Error>> {
+    let (key_id, setup_time) = match curve {
+        Curve::Bls12_381 => setup_on::<Bls12_381>(keys, backend, circuit)?,
+        Curve::Bn254 => setup_on::<Bn254>(keys, backend, circuit)?,
+        Curve::Bls12_377 => setup_on::<Bls12_377>(keys, backend, circuit)?,
+    };
+    print_json(&serde_json::json!({
+        "key_id": key_id,
+        "backend": backend,
+        "curve": curve,
+        "setup_time": setup_time,
+    }))
+}
+
+// the keys only depend on the shape, the witnesses of the circuits are placeholders
+fn setup_on<E: SnarkCurve>(
+    keys: &KeyStore,
+    backend: Backend,
+    circuit: SetupCircuit,
+) -> Result<(String, f64), Box<dyn Error>> {
+    let (a, b) = (E::ScalarField::zero(), E::ScalarField::from(1u64));
+    Ok(match circuit {
+        SetupCircuit::Fibonacci { rounds } => {
+            if rounds > MAX_ROUNDS {
+                return Err(format!(
+                    "fibonacci takes at most {} rounds, more are proven with fast_fibonacci",
+                    MAX_ROUNDS
+                )
+                .into());
+            }
+            let key_id = key_id("fibonacci", &[rounds.to_string()]);
+            let circuit = FibonacciCircuit {
+                a: Some(a),
+                b: Some(b),
+                num_of_steps: rounds,
+                result: Some(fibonacci(rounds, a, b)),
+            };
+            let setup_time = setup_keys::<E, _>(backend, keys, &key_id, circuit)?;
+            (key_id, setup_time)
+        }
+        SetupCircuit::FastFibonacci => {
+            let key_id = key_id("fast_fibonacci", &[]);
+            let circuit = FastFibonacciCircuit::new(0, a, b);
+            let setup_time = setup_keys::<E, _>(backend, keys, &key_id, circuit)?;
+            (key_id, setup_time)
+        }
+        SetupCircuit::Matrix { n } => {
+            let key_id = key_id("matrix", &[n.to_string()]);
+            let zeros = vec![vec![0u64; n]; n];
+            let circuit = MatrixCircuit::commit(zeros.clone(), zeros, [E::ScalarField::zero(); 3]);
+            let setup_time = setup_keys::<E, _>(backend, keys, &key_id, circuit)?;
+            (key_id, setup_time)
+        }
+    })
+}
+
+// true when the bundle verifies
+fn verify(keys: &KeyStore, path: &Path, proof: Option<&Path>) -> Result<bool, Box<dyn Error>> {
+    let mut bundle = read_bundle(path_str(path)?)?;
+    if let Some(proof) = proof {
+        check_groth16(&bundle)?;
+        let proof = path_str(proof)?;
+        bundle.proof = match bundle.curve {
+            Curve::Bls12_381 => read_groth16_proof::<Bls12_381>(proof)?,
+            Curve::Bn254 => read_groth16_proof::<Bn254>(proof)?,
+            Curve::Bls12_377 => read_groth16_proof::<Bls12_377>(proof)?,
+        };
+    }
+    let verified = verify_bundle(keys, &bundle)?;
+    print_json(&serde_json::json!({
+        "circuit": bundle.circuit,
+        "backend": bundle.backend,
+        "curve": bundle.curve,
+        "valid": verified.valid,
+        "verifying_time": verified.verifying_time,
+    }))?;
+    Ok(verified.valid)
+}
+
+fn run(cli: Cli) -> Result<bool, Box<dyn Error>> {
+    let keys = KeyStore::new(&cli.keys).with_srs_bounds(SrsBounds::from_env());
+    match cli.command {
+        Command::Fib { command } => fib(&keys, cli.backend, cli.curve, command)?,
+        Command::Matrix { command } => matrix(&keys, cli.backend, cli.curve, command)?,
+        Command::Prime { command } => prime(&keys, cli.backend, cli.curve, command)?,
+        Command::Setup { circuit } => setup(&keys, cli.backend, cli.curve, circuit)?,
+        Command::Verify { bundle, proof } => return verify(&keys, &bundle, proof.as_deref()),
+    }
+    Ok(true)
+}
+
+// 0 for success, 1 for a proof that doesn't verify and 2 for errors
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_commands() {
+        Cli::command().debug_assert();
+        let cli =
+            Cli::try_parse_from(["zk", "prime", "prove", "--x", "5", "--rounds", "10"]).unwrap();
+        assert_eq!(cli.backend, Backend::Groth16);
+        assert_eq!(cli.curve, Curve::Bls12_381);
+        assert!(matches!(
+            cli.command,
+            Command::Prime {
+                command: PrimeCommand::Prove {
+                    x: 5,
+                    rounds: 10,
+                    ..
+                }
+            }
+        ));
+        let cli =
+            Cli::try_parse_from(["zk", "setup", "fast_fibonacci", "--backend", "marlin"]).unwrap();
+        assert_eq!(cli.backend, Backend::Marlin);
+        let cli = Cli::try_parse_from(["zk", "fib", "prove", "--curve", "bn254"]).unwrap();
+        assert_eq!(cli.curve, Curve::Bn254);
+        assert!(Cli::try_parse_from(["zk", "fib", "prove", "--curve", "secp256k1"]).is_err());
+        assert!(Cli::try_parse_from(["zk", "verify", "b.json", "--backend", "plonk"]).is_err());
+        // either b or a vector
+        assert!(Cli::try_parse_from(["zk", "matrix", "prove", "--a", "a.json"]).is_err());
+    }
+}
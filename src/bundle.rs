@@ -0,0 +1,652 @@
+use crate::arkworks::backend::proving::{
+    stored_verifying_key, verify_with_vk, BackendVerification,
+};
+use crate::arkworks::curves::{on_curve, SnarkCurve};
+use crate::arkworks::key_store::{key_id, KeyStore};
+use crate::arkworks::matrix_proof_of_work::io::{encode_hash, parse_field_element};
+use crate::error::ZkError;
+use crate::proof_system::{Backend, Curve};
+use crate::sp1::miller_rabin::script::src::main as miller_rabin;
+use actix_web::http::header::CONTENT_TYPE;
+use actix_web::{web, HttpRequest, HttpResponse};
+use ark_ff::PrimeField;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+// the version of the bundles this build writes. it reads every version up to this one
+pub const BUNDLE_VERSION: u32 = 1;
+
+// the first bytes of the binary form
+const MAGIC: &[u8; 4] = b"ZKPB";
+
+#[derive(Debug)]
+pub enum BundleError {
+    Json(String),
+    Binary(String),
+    Version(u32),
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BundleError::Json(e) => write!(f, "invalid bundle json: {}", e),
+            BundleError::Binary(e) => write!(f, "invalid binary bundle: {}", e),
+            BundleError::Version(version) => write!(
+                f,
+                "bundle version {} is newer than the supported version {}",
+                version, BUNDLE_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BundleError {}
+
+impl From<BundleError> for ZkError {
+    fn from(e: BundleError) -> Self {
+        ZkError::invalid("bundle", e.to_string())
+    }
+}
+
+// bytes are base64 in the json form, like every proof and key of the endpoints
+pub(crate) mod base64_bytes {
+    use crate::arkworks::matrix_proof_of_work::io::{decode_hash, encode_hash};
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &Vec<u8>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&encode_hash(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        decode_hash(&value).map_err(|_| de::Error::custom("invalid base64"))
+    }
+}
+
+// a public input and how it becomes a field element: decimal field elements, integers,
+// booleans, and bytes that are read as a little endian number (like the matrix commitments)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum PublicValue {
+    Field(String),
+    U64(u64),
+    Bool(bool),
+    Bytes(#[serde(with = "base64_bytes")] Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicInput {
+    pub name: String,
+    #[serde(flatten)]
+    pub value: PublicValue,
+}
+
+impl PublicInput {
+    pub fn field<F: PrimeField>(name: impl Into<String>, value: F) -> Self {
+        Self {
+            name: name.into(),
+            value: PublicValue::Field(value.into_bigint().to_string()),
+        }
+    }
+
+    pub fn new(name: impl Into<String>, value: PublicValue) -> Self {
+        Self {
+            name: name.into(),
+            value,
+        }
+    }
+
+    pub fn to_field<F: PrimeField>(&self) -> Result<F, ZkError> {
+        Ok(match &self.value {
+            PublicValue::Field(value) => parse_field_element(value)
+                .map_err(|e| ZkError::invalid("public_inputs", format!("{}: {}", self.name, e)))?,
+            PublicValue::U64(value) => F::from(*value),
+            PublicValue::Bool(value) => F::from(*value),
+            PublicValue::Bytes(bytes) => F::from_le_bytes_mod_order(bytes),
+        })
+    }
+}
+
+// the public inputs of the circuits that only return them as a list, named name[0], name[1]..
+pub fn indexed_fields<F: PrimeField>(name: &str, values: &[F]) -> Vec<PublicInput> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| PublicInput::field(format!("{}[{}]", name, i), *value))
+        .collect()
+}
+
+// the verifying key travels with the proof, or the verifier looks it up by circuit and params
+// and the bundle pins it with the hex sha256 of its bytes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerifyingKeyRef {
+    Embedded(#[serde(with = "base64_bytes")] Vec<u8>),
+    Hash(String),
+}
+
+impl VerifyingKeyRef {
+    pub fn hash_of(vk: &[u8]) -> Self {
+        VerifyingKeyRef::Hash(vk_hash(vk))
+    }
+
+    // whether this is vk itself or its hash
+    pub fn references(&self, vk: &[u8]) -> bool {
+        match self {
+            VerifyingKeyRef::Embedded(embedded) => embedded[..] == *vk,
+            VerifyingKeyRef::Hash(hash) => vk_hash(vk) == *hash,
+        }
+    }
+}
+
+pub fn vk_hash(vk: &[u8]) -> String {
+    Sha256::digest(vk)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+// a proof with everything a verifier needs to check it: what was proven (the circuit and the
+// params that fix its shape, as in the key ids), with which proof system, the named public
+// inputs and the verifying key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofBundle {
+    pub version: u32,
+    pub circuit: String,
+    pub params: Vec<String>,
+    pub curve: Curve,
+    pub backend: Backend,
+    pub public_inputs: Vec<PublicInput>,
+    #[serde(with = "base64_bytes")]
+    pub proof: Vec<u8>,
+    pub verifying_key: VerifyingKeyRef,
+}
+
+impl ProofBundle {
+    pub fn new(
+        circuit: &str,
+        params: Vec<String>,
+        curve: Curve,
+        backend: Backend,
+        public_inputs: Vec<PublicInput>,
+        proof: Vec<u8>,
+        verifying_key: VerifyingKeyRef,
+    ) -> Self {
+        Self {
+            version: BUNDLE_VERSION,
+            circuit: circuit.to_string(),
+            params,
+            curve,
+            backend,
+            public_inputs,
+            proof,
+            verifying_key,
+        }
+    }
+
+    // the id of the keys of the circuit in the key store
+    pub fn key_id(&self) -> String {
+        key_id(&self.circuit, &self.params)
+    }
+
+    pub fn field_inputs<F: PrimeField>(&self) -> Result<Vec<F>, ZkError> {
+        self.public_inputs
+            .iter()
+            .map(PublicInput::to_field)
+            .collect()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("a bundle always serializes")
+    }
+
+    pub fn from_json(json: &[u8]) -> Result<Self, BundleError> {
+        // the version first, a newer bundle may not have the fields of this one
+        #[derive(Deserialize)]
+        struct Version {
+            version: u32,
+        }
+        let Version { version } =
+            serde_json::from_slice(json).map_err(|e| BundleError::Json(e.to_string()))?;
+        if version > BUNDLE_VERSION {
+            return Err(BundleError::Version(version));
+        }
+        serde_json::from_slice(json).map_err(|e| BundleError::Json(e.to_string()))
+    }
+
+    // MAGIC, then the fields in order: integers little endian, strings and bytes with a u32
+    // length, lists with a u32 count and enums as a u8 tag
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Writer(MAGIC.to_vec());
+        out.u32(self.version);
+        out.string(&self.circuit);
+        out.u32(self.params.len() as u32);
+        for param in &self.params {
+            out.string(param);
+        }
+        out.u8(match self.curve {
+            Curve::Bls12_381 => 0,
+            Curve::Bn254 => 1,
+            Curve::Bls12_377 => 2,
+        });
+        out.u8(match self.backend {
+            Backend::Groth16 => 0,
+            Backend::Marlin => 1,
+            Backend::Sp1 => 2,
+        });
+        out.u32(self.public_inputs.len() as u32);
+        for input in &self.public_inputs {
+            out.string(&input.name);
+            match &input.value {
+                PublicValue::Field(value) => {
+                    out.u8(0);
+                    out.string(value);
+                }
+                PublicValue::U64(value) => {
+                    out.u8(1);
+                    out.0.extend_from_slice(&value.to_le_bytes());
+                }
+                PublicValue::Bool(value) => {
+                    out.u8(2);
+                    out.u8(*value as u8);
+                }
+                PublicValue::Bytes(bytes) => {
+                    out.u8(3);
+                    out.bytes(bytes);
+                }
+            }
+        }
+        out.bytes(&self.proof);
+        match &self.verifying_key {
+            VerifyingKeyRef::Embedded(vk) => {
+                out.u8(0);
+                out.bytes(vk);
+            }
+            VerifyingKeyRef::Hash(hash) => {
+                out.u8(1);
+                out.string(hash);
+            }
+        }
+        out.0
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BundleError> {
+        let mut input = Reader(bytes);
+        if input.take(MAGIC.len())? != MAGIC {
+            return Err(BundleError::Binary("not a proof bundle".to_string()));
+        }
+        let version = input.u32()?;
+        if version > BUNDLE_VERSION {
+            return Err(BundleError::Version(version));
+        }
+        let circuit = input.string()?;
+        let params = (0..input.u32()?)
+            .map(|_| input.string())
+            .collect::<Result<_, _>>()?;
+        let curve = match input.u8()? {
+            0 => Curve::Bls12_381,
+            1 => Curve::Bn254,
+            2 => Curve::Bls12_377,
+            tag => return Err(BundleError::Binary(format!("unknown curve {}", tag))),
+        };
+        let backend = match input.u8()? {
+            0 => Backend::Groth16,
+            1 => Backend::Marlin,
+            2 => Backend::Sp1,
+            tag => return Err(BundleError::Binary(format!("unknown backend {}", tag))),
+        };
+        let mut public_inputs = vec![];
+        for _ in 0..input.u32()? {
+            let name = input.string()?;
+            let value = match input.u8()? {
+                0 => PublicValue::Field(input.string()?),
+                1 => PublicValue::U64(u64::from_le_bytes(input.take(8)?.try_into().unwrap())),
+                2 => PublicValue::Bool(input.u8()? != 0),
+                3 => PublicValue::Bytes(input.bytes()?),
+                tag => return Err(BundleError::Binary(format!("unknown value type {}", tag))),
+            };
+            public_inputs.push(PublicInput { name, value });
+        }
+        let proof = input.bytes()?;
+        let verifying_key = match input.u8()? {
+            0 => VerifyingKeyRef::Embedded(input.bytes()?),
+            1 => VerifyingKeyRef::Hash(input.string()?),
+            tag => return Err(BundleError::Binary(format!("unknown key type {}", tag))),
+        };
+        if !input.0.is_empty() {
+            return Err(BundleError::Binary("trailing bytes".to_string()));
+        }
+        Ok(Self {
+            version,
+            circuit,
+            params,
+            curve,
+            backend,
+            public_inputs,
+            proof,
+            verifying_key,
+        })
+    }
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.u32(bytes.len() as u32);
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn string(&mut self, value: &str) {
+        self.bytes(value.as_bytes());
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], BundleError> {
+        if self.0.len() < len {
+            return Err(BundleError::Binary(
+                "unexpected end of the bundle".to_string(),
+            ));
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, BundleError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, BundleError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, BundleError> {
+        let len = self.u32()? as usize;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    fn string(&mut self) -> Result<String, BundleError> {
+        String::from_utf8(self.bytes()?)
+            .map_err(|_| BundleError::Binary("a string isn't utf-8".to_string()))
+    }
+}
+
+// the bytes of the verifying key of key_id in the key store, after checking that the bundle
+// references it: an embedded key must be the stored one byte for byte and a hash must be its
+// hash. a key set up by the client proves nothing about the circuit, so it is never trusted
+pub fn verifying_key_bytes<E: SnarkCurve>(
+    keys: &KeyStore,
+    backend: Backend,
+    key_id: &str,
+    verifying_key: &VerifyingKeyRef,
+) -> Result<Vec<u8>, ZkError> {
+    let vk = stored_verifying_key::<E>(backend, keys, key_id)?;
+    if !verifying_key.references(&vk) {
+        return Err(ZkError::invalid(
+            "verifying_key",
+            format!(
+                "the bundle doesn't reference the verifying key of {}",
+                key_id
+            ),
+        ));
+    }
+    Ok(vk)
+}
+
+fn verify_circuit<E: SnarkCurve>(
+    keys: &KeyStore,
+    backend: Backend,
+    bundle: &ProofBundle,
+) -> Result<BackendVerification, ZkError> {
+    let public_input = bundle.field_inputs::<E::ScalarField>()?;
+    let vk = verifying_key_bytes::<E>(keys, backend, &bundle.key_id(), &bundle.verifying_key)?;
+    verify_with_vk::<E>(
+        backend,
+        &encode_hash(&vk),
+        &public_input,
+        &encode_hash(&bundle.proof),
+    )
+}
+
+// verifies a bundle of any circuit, curve and backend
+pub fn verify_bundle(
+    keys: &KeyStore,
+    bundle: &ProofBundle,
+) -> Result<BackendVerification, ZkError> {
+    match bundle.backend {
+        Backend::Sp1 => miller_rabin::verify_bundle(bundle),
+        backend => on_curve!(bundle.curve, E => verify_circuit::<E>(keys, backend, bundle)),
+    }
+}
+
+#[derive(Serialize)]
+pub struct VerifyBundleOutput {
+    pub circuit: String,
+    pub backend: Backend,
+    pub valid: bool,
+    pub verifying_time: f64,
+}
+
+// POST /verify with a bundle, as json or as the binary form with the content type
+// application/octet-stream
+pub async fn verify(
+    keys: web::Data<KeyStore>,
+    request: HttpRequest,
+    body: web::Bytes,
+) -> Result<HttpResponse, ZkError> {
+    let binary = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|content_type| content_type == "application/octet-stream");
+    let bundle = if binary {
+        ProofBundle::from_bytes(&body)?
+    } else {
+        ProofBundle::from_json(&body)?
+    };
+    let verified = verify_bundle(&keys, &bundle)?;
+    Ok(HttpResponse::Ok().json(VerifyBundleOutput {
+        circuit: bundle.circuit,
+        backend: bundle.backend,
+        valid: verified.valid,
+        verifying_time: verified.verifying_time,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::backend::fibbonaci_handler::{fibonacci, fibonacci_proof, InputDataFib};
+    use crate::arkworks::backend::proving::prove_with_setup;
+    use crate::arkworks::constraints::fibbonaci::FibonacciCircuit;
+    use crate::arkworks::key_store::TestDir;
+    use crate::arkworks::matrix_proof_of_work::io::{
+        decode_bundle, decode_hash, encode_bundle, read_bundle, write_bundle_to_file,
+    };
+    use actix_web::body::to_bytes;
+    use actix_web::test::TestRequest;
+    use ark_bls12_381::Fr;
+
+    fn bundle() -> ProofBundle {
+        ProofBundle::new(
+            "fibonacci",
+            vec!["10".to_string()],
+            Curve::Bls12_377,
+            Backend::Groth16,
+            vec![
+                PublicInput::field("a", Fr::from(0u64)),
+                PublicInput::new("n", PublicValue::U64(10)),
+                PublicInput::new("found", PublicValue::Bool(true)),
+                PublicInput::new("com", PublicValue::Bytes(vec![1, 2, 3])),
+            ],
+            vec![7; 16],
+            VerifyingKeyRef::hash_of(b"vk"),
+        )
+    }
+
+    #[test]
+    fn test_bundle_round_trips() {
+        let bundle = bundle();
+        assert_eq!(
+            ProofBundle::from_json(bundle.to_json().as_bytes()).unwrap(),
+            bundle
+        );
+        assert_eq!(ProofBundle::from_bytes(&bundle.to_bytes()).unwrap(), bundle);
+        assert_eq!(decode_bundle(&encode_bundle(&bundle)).unwrap(), bundle);
+        assert_eq!(bundle.key_id(), "fibonacci-10");
+        assert_eq!(
+            bundle.field_inputs::<Fr>().unwrap(),
+            vec![
+                Fr::from(0u64),
+                Fr::from(10u64),
+                Fr::from(1u64),
+                Fr::from(0x030201u64)
+            ]
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&bundle.to_json()).unwrap();
+        assert_eq!(
+            json["public_inputs"][1],
+            serde_json::json!({"name": "n", "type": "u64", "value": 10})
+        );
+        assert_eq!(json["verifying_key"]["hash"], vk_hash(b"vk"));
+        assert_eq!(json["curve"], "bls12_377");
+    }
+
+    #[test]
+    fn test_invalid_bundles() {
+        let mut newer = bundle();
+        newer.version = BUNDLE_VERSION + 1;
+        assert!(matches!(
+            ProofBundle::from_json(newer.to_json().as_bytes()),
+            Err(BundleError::Version(_))
+        ));
+        assert!(matches!(
+            ProofBundle::from_bytes(&newer.to_bytes()),
+            Err(BundleError::Version(_))
+        ));
+        let bytes = bundle().to_bytes();
+        for len in [0, 3, bytes.len() - 1] {
+            assert!(ProofBundle::from_bytes(&bytes[..len]).is_err());
+        }
+        assert!(ProofBundle::from_bytes(&[bytes.clone(), vec![0]].concat()).is_err());
+    }
+
+    fn fibonacci_bundle(keys: &KeyStore, curve: Curve) -> ProofBundle {
+        let input = InputDataFib {
+            a: "0".to_string(),
+            b: "1".to_string(),
+            num_of_rounds: 10,
+            backend: Backend::Groth16,
+            curve,
+        };
+        fibonacci_proof(keys, Backend::Groth16, &input)
+            .unwrap()
+            .bundle
+    }
+
+    async fn verify_request(keys: &web::Data<KeyStore>, content_type: &str, body: Vec<u8>) -> bool {
+        let request = TestRequest::default()
+            .insert_header((CONTENT_TYPE, content_type))
+            .to_http_request();
+        let response = verify(keys.clone(), request, body.into()).await.unwrap();
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let output: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(output["circuit"], "fibonacci");
+        output["valid"].as_bool().unwrap()
+    }
+
+    #[actix_web::test]
+    async fn test_verify_dispatches_on_the_bundle() {
+        let dir = TestDir::new("bundle_verify");
+        let keys = web::Data::new(dir.keys());
+        let bundle = fibonacci_bundle(&keys, Curve::Bls12_381);
+        assert_eq!(
+            bundle.public_inputs[2].value,
+            PublicValue::Field("89".to_string())
+        );
+        let json = bundle.to_json().into_bytes();
+        assert!(verify_request(&keys, "application/json", json).await);
+        assert!(verify_request(&keys, "application/octet-stream", bundle.to_bytes()).await);
+
+        // through a file, and with the verifying key in the bundle instead of its hash
+        let path = dir.0.join("bundle.zkpb");
+        let path = path.to_str().unwrap();
+        write_bundle_to_file(&bundle, path).unwrap();
+        let mut embedded = read_bundle(path).unwrap();
+        assert_eq!(embedded, bundle);
+        embedded.verifying_key = VerifyingKeyRef::Embedded(
+            stored_verifying_key::<ark_bls12_381::Bls12_381>(
+                Backend::Groth16,
+                &keys,
+                &bundle.key_id(),
+            )
+            .unwrap(),
+        );
+        assert!(verify_bundle(&keys, &embedded).unwrap().valid);
+
+        // another statement, and keys that aren't the ones of the hash
+        let mut wrong = bundle.clone();
+        wrong.public_inputs[2] = PublicInput::field("fib_number", Fr::from(90u64));
+        assert!(!verify_request(&keys, "application/json", wrong.to_json().into_bytes()).await);
+        let mut other_keys = bundle.clone();
+        other_keys.verifying_key = VerifyingKeyRef::hash_of(b"other keys");
+        let error = verify_bundle(&keys, &other_keys).err().unwrap();
+        assert_eq!(error.field(), Some("verifying_key"));
+        let mut unknown = bundle;
+        unknown.params = vec!["11".to_string()];
+        let error = verify_bundle(&keys, &unknown).err().unwrap();
+        assert_eq!(error.code(), "unknown_key");
+
+        // the same statement over bn254 has its own keys, the curve of the bundle picks them
+        let bn254 = fibonacci_bundle(&keys, Curve::Bn254);
+        assert_eq!(bn254.curve, Curve::Bn254);
+        assert!(verify_request(&keys, "application/octet-stream", bn254.to_bytes()).await);
+        let mut other_curve = bn254;
+        other_curve.curve = Curve::Bls12_381;
+        let error = verify_bundle(&keys, &other_curve).err().unwrap();
+        assert_eq!(error.field(), Some("verifying_key"));
+    }
+
+    #[test]
+    fn test_embedded_keys_of_another_setup_are_rejected() {
+        let dir = TestDir::new("bundle_foreign_vk");
+        let keys = dir.keys();
+        let bundle = fibonacci_bundle(&keys, Curve::Bls12_381);
+        // a fresh setup of 11 rounds proves F(11) = 144, with its verifying key embedded the
+        // bundle would claim that 10 rounds give 144
+        let (a, b) = (Fr::from(0u64), Fr::from(1u64));
+        let circuit = FibonacciCircuit {
+            a: Some(a),
+            b: Some(b),
+            num_of_steps: 11,
+            result: Some(fibonacci(11, a, b)),
+        };
+        let proved =
+            prove_with_setup::<ark_bls12_381::Bls12_381, _>(Backend::Groth16, &keys, circuit)
+                .unwrap();
+        let mut forged = bundle.clone();
+        forged.public_inputs[2] = PublicInput::field("fib_number", fibonacci(11, a, b));
+        forged.proof = decode_hash(&proved.proof).unwrap();
+        forged.verifying_key = VerifyingKeyRef::Embedded(decode_hash(&proved.vk).unwrap());
+        let error = verify_bundle(&keys, &forged).err().unwrap();
+        assert_eq!(error.field(), Some("verifying_key"));
+
+        // the key of the other setup under its own hash isn't the stored one either
+        forged.verifying_key = VerifyingKeyRef::hash_of(&decode_hash(&proved.vk).unwrap());
+        let error = verify_bundle(&keys, &forged).err().unwrap();
+        assert_eq!(error.field(), Some("verifying_key"));
+    }
+}
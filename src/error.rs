@@ -0,0 +1,280 @@
+use crate::arkworks::key_store::KeyStoreError;
+use crate::arkworks::matrix_proof_of_work::io::parse_field_element;
+use crate::proof_system::ProofSystemError;
+use actix_web::error::{JsonPayloadError, QueryPayloadError};
+use actix_web::http::StatusCode;
+use actix_web::{HttpRequest, HttpResponse, ResponseError};
+use ark_ff::PrimeField;
+use ark_relations::r1cs::SynthesisError;
+use serde::Serialize;
+use std::fmt;
+
+// the errors of the HTTP handlers. every one is answered with a JSON body
+// {"code": ..., "message": ..., "field": ...}, where field names the request field at fault
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZkError {
+    // a field of the request is malformed or doesn't fit the other fields
+    InvalidInput {
+        field: Option<String>,
+        message: String,
+    },
+    // the inputs are well formed but the statement is false, e.g. a result that isn't the
+    // result of the computation, so there is no witness to prove
+    Unsatisfiable(String),
+    UnknownKey(String),
+    UnknownJob(String),
+    // the job of the request was cancelled before it finished
+    Cancelled,
+    // the job queue already has as many waiting jobs as it takes
+    JobQueueFull,
+    Internal(String),
+}
+
+impl ZkError {
+    pub fn invalid(field: &str, message: impl Into<String>) -> Self {
+        ZkError::InvalidInput {
+            field: Some(field.to_string()),
+            message: message.into(),
+        }
+    }
+
+    // a request that is wrong as a whole, like a body that isn't JSON
+    pub fn malformed(message: impl Into<String>) -> Self {
+        ZkError::InvalidInput {
+            field: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            ZkError::InvalidInput { .. } => "invalid_input",
+            ZkError::Unsatisfiable(_) => "unsatisfiable",
+            ZkError::UnknownKey(_) => "unknown_key",
+            ZkError::UnknownJob(_) => "unknown_job",
+            ZkError::Cancelled => "cancelled",
+            ZkError::JobQueueFull => "queue_full",
+            ZkError::Internal(_) => "internal",
+        }
+    }
+
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            ZkError::InvalidInput { field, .. } => field.as_deref(),
+            ZkError::UnknownKey(_) => Some("key_id"),
+            ZkError::UnknownJob(_) => Some("id"),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ZkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZkError::InvalidInput { message, .. } => f.write_str(message),
+            ZkError::Unsatisfiable(message) => {
+                write!(f, "the inputs don't satisfy the circuit: {}", message)
+            }
+            ZkError::UnknownKey(id) => write!(f, "no keys with the id '{}'", id),
+            ZkError::UnknownJob(id) => write!(f, "no job with the id '{}'", id),
+            ZkError::Cancelled => f.write_str("the job was cancelled"),
+            ZkError::JobQueueFull => f.write_str("the job queue is full, try again later"),
+            ZkError::Internal(message) => write!(f, "internal error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ZkError {}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    code: &'static str,
+    message: String,
+    field: Option<&'a str>,
+}
+
+impl ResponseError for ZkError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ZkError::InvalidInput { .. } => StatusCode::BAD_REQUEST,
+            ZkError::Unsatisfiable(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ZkError::UnknownKey(_) | ZkError::UnknownJob(_) => StatusCode::NOT_FOUND,
+            ZkError::Cancelled => StatusCode::CONFLICT,
+            ZkError::JobQueueFull => StatusCode::SERVICE_UNAVAILABLE,
+            ZkError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(ErrorBody {
+            code: self.code(),
+            message: self.to_string(),
+            field: self.field(),
+        })
+    }
+}
+
+// a bad or unknown id is the client's fault, anything else is ours
+impl From<KeyStoreError> for ZkError {
+    fn from(e: KeyStoreError) -> Self {
+        match e {
+            KeyStoreError::InvalidKeyId(_) => ZkError::invalid("key_id", e.to_string()),
+            KeyStoreError::UnknownKey(id) => ZkError::UnknownKey(id),
+            e => ZkError::Internal(e.to_string()),
+        }
+    }
+}
+
+impl From<ProofSystemError> for ZkError {
+    fn from(e: ProofSystemError) -> Self {
+        match e {
+            ProofSystemError::Synthesis(SynthesisError::Unsatisfiable) => {
+                ZkError::Unsatisfiable(e.to_string())
+            }
+            e => ZkError::Internal(e.to_string()),
+        }
+    }
+}
+
+impl From<SynthesisError> for ZkError {
+    fn from(e: SynthesisError) -> Self {
+        ProofSystemError::Synthesis(e).into()
+    }
+}
+
+// the checks of a request payload that serde can't do: lengths, shapes and ranges. handlers
+// call it before they parse field elements or build circuits, so the errors name the field
+pub trait Validate {
+    fn validate(&self) -> Result<(), ZkError>;
+}
+
+// at most max elements, and at least one
+pub fn check_len<T>(field: &str, values: &[T], max: usize) -> Result<(), ZkError> {
+    if values.is_empty() {
+        return Err(ZkError::invalid(field, format!("{} can't be empty", field)));
+    }
+    if values.len() > max {
+        return Err(ZkError::invalid(
+            field,
+            format!(
+                "{} has {} elements, at most {} are supported",
+                field,
+                values.len(),
+                max
+            ),
+        ));
+    }
+    Ok(())
+}
+
+// a matrix with rows x cols entries
+pub fn check_shape<T>(
+    field: &str,
+    matrix: &[Vec<T>],
+    rows: usize,
+    cols: usize,
+) -> Result<(), ZkError> {
+    if matrix.len() != rows || matrix.iter().any(|row| row.len() != cols) {
+        return Err(ZkError::invalid(
+            field,
+            format!("{} must be a {}x{} matrix", field, rows, cols),
+        ));
+    }
+    Ok(())
+}
+
+// a decimal or 0x hex field element of the request
+pub fn parse_field<F: PrimeField>(field: &str, value: &str) -> Result<F, ZkError> {
+    parse_field_element(value).map_err(|e| ZkError::invalid(field, e))
+}
+
+pub fn parse_fields<F: PrimeField, S: AsRef<str>>(
+    field: &str,
+    values: &[S],
+) -> Result<Vec<F>, ZkError> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            parse_field_element(value.as_ref())
+                .map_err(|e| ZkError::invalid(field, format!("{}[{}]: {}", field, i, e)))
+        })
+        .collect()
+}
+
+// the error handlers of the Json and Query extractors, so a body that doesn't parse gets the
+// same JSON error as the rest
+pub fn json_error_handler(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    ZkError::malformed(format!("invalid JSON body: {}", err)).into()
+}
+
+pub fn query_error_handler(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    ZkError::malformed(format!("invalid query: {}", err)).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use actix_web::{test, web, App};
+    use serde::Deserialize;
+    use serde_json::Value;
+
+    async fn body(error: &ZkError) -> Value {
+        let bytes = to_bytes(error.error_response().into_body()).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[actix_web::test]
+    async fn test_error_bodies() {
+        let error = ZkError::invalid("proof", "invalid proof");
+        assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body(&error).await,
+            serde_json::json!({
+                "code": "invalid_input",
+                "message": "invalid proof",
+                "field": "proof",
+            })
+        );
+
+        let error = ZkError::from(KeyStoreError::UnknownKey("fibonacci-7".to_string()));
+        assert_eq!(error.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(body(&error).await["field"], "key_id");
+
+        let error = ZkError::from(SynthesisError::Unsatisfiable);
+        assert_eq!(error.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body(&error).await["field"], Value::Null);
+    }
+
+    #[derive(Deserialize)]
+    struct Payload {
+        #[allow(dead_code)]
+        n: usize,
+    }
+
+    async fn handler(_: web::Json<Payload>) -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_web::test]
+    async fn test_malformed_json_is_a_json_error() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::JsonConfig::default().error_handler(json_error_handler))
+                .route("/", web::post().to(handler)),
+        )
+        .await;
+        for payload in [r#"{"n": "#, r#"{"n": -1}"#, r#"{"m": 1}"#] {
+            let request = test::TestRequest::post()
+                .uri("/")
+                .insert_header(("content-type", "application/json"))
+                .set_payload(payload)
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            let body: Value = test::read_body_json(response).await;
+            assert_eq!(body["code"], "invalid_input");
+        }
+    }
+}
@@ -0,0 +1,662 @@
+use crate::arkworks::backend::fibbonaci_handler::{
+    fast_fibonacci_proof, fibonacci_proof, InputDataFastFib, InputDataFib,
+};
+use crate::arkworks::backend::linear_equations::linear_equations_proof;
+use crate::arkworks::backend::matrix_proof::{self, matrix_proof};
+use crate::arkworks::backend::prime_snark::{self, prime_proof};
+use crate::arkworks::constraints::linear_equations_constraints::InputData as LinearEquationsInput;
+use crate::arkworks::key_store::KeyStore;
+use crate::error::{Validate, ZkError};
+use crate::sp1::miller_rabin::script::src::main as miller_rabin;
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// the directory of the jobs when ZK_JOB_DIR isn't set
+pub const DEFAULT_JOB_DIR: &str = "jobs";
+// the proofs that run at the same time when ZK_JOB_WORKERS isn't set
+pub const DEFAULT_JOB_WORKERS: usize = 2;
+// the jobs that wait for a worker when ZK_JOB_QUEUE isn't set, more are answered with 503
+pub const DEFAULT_MAX_QUEUED_JOBS: usize = 64;
+// the finished jobs that are kept when ZK_JOB_KEEP isn't set, the oldest are deleted first
+pub const DEFAULT_MAX_FINISHED_JOBS: usize = 1024;
+// how long a finished job is kept when ZK_JOB_TTL (in seconds) isn't set
+pub const DEFAULT_JOB_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+// the limits of a job queue, so neither the waiting jobs nor the results grow without bound
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JobLimits {
+    pub max_queued: usize,
+    pub max_finished: usize,
+    pub finished_ttl: Duration,
+}
+
+impl Default for JobLimits {
+    fn default() -> Self {
+        JobLimits {
+            max_queued: DEFAULT_MAX_QUEUED_JOBS,
+            max_finished: DEFAULT_MAX_FINISHED_JOBS,
+            finished_ttl: DEFAULT_JOB_TTL,
+        }
+    }
+}
+
+// milliseconds since the unix epoch
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis() as u64)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Done | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+}
+
+// what a running job is doing: checking the witness, loading or setting up the keys, or
+// proving. the provers report it through enter_phase
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Witness,
+    Setup,
+    Prove,
+}
+
+// the body of a failed request, for a job that failed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobError {
+    pub code: String,
+    pub message: String,
+    pub field: Option<String>,
+}
+
+impl From<&ZkError> for JobError {
+    fn from(e: &ZkError) -> Self {
+        JobError {
+            code: e.code().to_string(),
+            message: e.to_string(),
+            field: e.field().map(str::to_string),
+        }
+    }
+}
+
+// a job as GET /jobs/{id} returns it and as it is kept in <dir>/<id>.json. result is the
+// response of the prove endpoint of the circuit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: String,
+    pub circuit: String,
+    pub status: JobStatus,
+    pub phase: Option<Phase>,
+    // set by DELETE on a running job, it stops at its next phase
+    #[serde(default)]
+    pub cancel_requested: bool,
+    pub result: Option<Value>,
+    pub error: Option<JobError>,
+    // when the job finished, in milliseconds since the unix epoch
+    #[serde(default)]
+    pub finished_at: Option<u64>,
+}
+
+impl Job {
+    fn finish(&mut self, status: JobStatus) {
+        self.status = status;
+        self.phase = None;
+        self.finished_at = Some(now_millis());
+    }
+}
+
+// POST /jobs, e.g. {"circuit": "prime", "input": {"x": 10, "i": 50}}. the input is the body
+// of the prove endpoint of the circuit
+#[derive(Deserialize)]
+#[serde(tag = "circuit", content = "input", rename_all = "snake_case")]
+pub enum JobRequest {
+    Fibonacci(InputDataFib),
+    FastFibonacci(InputDataFastFib),
+    Matrix(matrix_proof::ProveInput),
+    Prime(prime_snark::ProveInput),
+    LinearEquations(LinearEquationsInput),
+    PrimeSp1(miller_rabin::ProvePayload),
+}
+
+fn to_value<T: Serialize>(result: Result<T, ZkError>) -> Result<Value, ZkError> {
+    serde_json::to_value(result?).map_err(|e| ZkError::Internal(e.to_string()))
+}
+
+impl JobRequest {
+    pub fn circuit(&self) -> &'static str {
+        match self {
+            JobRequest::Fibonacci(_) => "fibonacci",
+            JobRequest::FastFibonacci(_) => "fast_fibonacci",
+            JobRequest::Matrix(_) => "matrix",
+            JobRequest::Prime(_) => "prime",
+            JobRequest::LinearEquations(_) => "linear_equations",
+            JobRequest::PrimeSp1(_) => "prime_sp1",
+        }
+    }
+
+    fn run(self, keys: &KeyStore) -> Result<Value, ZkError> {
+        match self {
+            JobRequest::Fibonacci(data) => to_value(fibonacci_proof(keys, data.backend, &data)),
+            JobRequest::FastFibonacci(data) => {
+                to_value(fast_fibonacci_proof(keys, data.backend, &data))
+            }
+            JobRequest::Matrix(data) => to_value(matrix_proof(keys, data.backend, data)),
+            JobRequest::Prime(data) => to_value(prime_proof(keys, data.backend, &data)),
+            JobRequest::LinearEquations(data) => to_value(linear_equations_proof(keys, &data)),
+            JobRequest::PrimeSp1(data) => to_value(miller_rabin::miller_rabin_proof(&data)),
+        }
+    }
+}
+
+// a request that fails its checks is answered right away instead of becoming a failed job
+impl Validate for JobRequest {
+    fn validate(&self) -> Result<(), ZkError> {
+        match self {
+            JobRequest::Fibonacci(data) => data.validate(),
+            JobRequest::FastFibonacci(_) => Ok(()),
+            JobRequest::Matrix(data) => data.validate(),
+            JobRequest::Prime(data) => data.validate(),
+            JobRequest::LinearEquations(data) => data.validate(),
+            JobRequest::PrimeSp1(data) => data.validate(),
+        }
+    }
+}
+
+struct Jobs {
+    dir: PathBuf,
+    limits: JobLimits,
+    jobs: Mutex<HashMap<String, Job>>,
+}
+
+impl Jobs {
+    // the jobs of an earlier process. the ones that hadn't finished died with it
+    fn load(dir: &Path) -> HashMap<String, Job> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return HashMap::new(),
+        };
+        let mut jobs = HashMap::new();
+        for path in entries.filter_map(|entry| Some(entry.ok()?.path())) {
+            if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+                continue;
+            }
+            let job = fs::read(&path)
+                .ok()
+                .and_then(|bytes| serde_json::from_slice::<Job>(&bytes).ok());
+            let mut job = match job {
+                Some(job) => job,
+                None => {
+                    eprintln!("skipping the unreadable job file {}", path.display());
+                    continue;
+                }
+            };
+            if !job.status.is_finished() {
+                job.finish(JobStatus::Failed);
+                job.error = Some(JobError::from(&ZkError::Internal(
+                    "the server restarted before the job finished".to_string(),
+                )));
+            } else if job.finished_at.is_none() {
+                // a job of a version without finished_at, its ttl starts now
+                job.finished_at = Some(now_millis());
+            }
+            jobs.insert(job.id.clone(), job);
+        }
+        jobs
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    // deletes the finished jobs that are older than the ttl, then the oldest ones over the
+    // limit, with their files. it runs when a job is submitted or finishes
+    fn prune(&self, jobs: &mut HashMap<String, Job>) {
+        let now = now_millis();
+        let ttl = self.limits.finished_ttl.as_millis() as u64;
+        let mut finished: Vec<(u64, String)> = jobs
+            .values()
+            .filter_map(|job| Some((job.finished_at?, job.id.clone())))
+            .collect();
+        finished.sort();
+        let over = finished.len().saturating_sub(self.limits.max_finished);
+        for (i, (finished_at, id)) in finished.into_iter().enumerate() {
+            if i < over || now.saturating_sub(finished_at) > ttl {
+                jobs.remove(&id);
+                let _ = fs::remove_file(self.path(&id));
+            }
+        }
+    }
+
+    // like the key store, through a temporary file so a crash never leaves half a job behind.
+    // a job that can't be written is still served from memory
+    fn persist(&self, job: &Job) {
+        let write = || -> std::io::Result<()> {
+            fs::create_dir_all(&self.dir)?;
+            let path = self.path(&job.id);
+            let tmp = path.with_extension("tmp");
+            let mut file = File::create(&tmp)?;
+            file.write_all(&serde_json::to_vec(job)?)?;
+            file.sync_all()?;
+            fs::rename(tmp, path)
+        };
+        if let Err(e) = write() {
+            eprintln!("failed to write the job {}: {}", job.id, e);
+        }
+    }
+
+    fn update<T>(&self, id: &str, f: impl FnOnce(&mut Job) -> T) -> Option<T> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs.get_mut(id)?;
+        let result = f(job);
+        self.persist(job);
+        Some(result)
+    }
+
+    fn enter_phase(&self, id: &str, phase: Phase) -> Result<(), ZkError> {
+        self.update(id, |job| {
+            if job.cancel_requested {
+                return Err(ZkError::Cancelled);
+            }
+            job.phase = Some(phase);
+            Ok(())
+        })
+        .unwrap_or(Ok(()))
+    }
+
+    fn run(jobs: &Arc<Jobs>, keys: &KeyStore, id: String, request: JobRequest) {
+        // a job that was cancelled while it was queued never starts
+        let started = jobs.update(&id, |job| {
+            if job.status != JobStatus::Queued {
+                return false;
+            }
+            job.status = JobStatus::Running;
+            job.phase = Some(Phase::Witness);
+            true
+        });
+        if started != Some(true) {
+            return;
+        }
+
+        CURRENT_JOB.with(|current| *current.borrow_mut() = Some((jobs.clone(), id.clone())));
+        // a prover that panics fails its job, not the worker
+        let result = panic::catch_unwind(AssertUnwindSafe(|| request.run(keys)))
+            .unwrap_or_else(|_| Err(ZkError::Internal("the prover panicked".to_string())));
+        CURRENT_JOB.with(|current| current.borrow_mut().take());
+
+        // pruned under the same lock, so the job is never seen next to the ones it pushes out
+        let mut all = jobs.jobs.lock().unwrap();
+        if let Some(job) = all.get_mut(&id) {
+            match result {
+                Ok(result) => {
+                    job.finish(JobStatus::Done);
+                    job.result = Some(result);
+                }
+                Err(ZkError::Cancelled) => job.finish(JobStatus::Cancelled),
+                Err(e) => {
+                    job.finish(JobStatus::Failed);
+                    job.error = Some(JobError::from(&e));
+                }
+            }
+            jobs.persist(job);
+        }
+        jobs.prune(&mut all);
+    }
+}
+
+thread_local! {
+    // the job of the worker thread, so the provers can report their phases without knowing
+    // whether they run in a job or in a request
+    static CURRENT_JOB: RefCell<Option<(Arc<Jobs>, String)>> = const { RefCell::new(None) };
+}
+
+// called by the provers between their phases. it records the phase of the job of the thread,
+// and stops the proof with ZkError::Cancelled when the job was cancelled. outside of a job it
+// does nothing
+pub fn enter_phase(phase: Phase) -> Result<(), ZkError> {
+    CURRENT_JOB.with(|current| match &*current.borrow() {
+        Some((jobs, id)) => jobs.enter_phase(id, phase),
+        None => Ok(()),
+    })
+}
+
+// the proofs of the jobs run on a fixed number of worker threads, off the actix workers. jobs
+// wait in submission order for a free worker, up to limits.max_queued of them
+pub struct JobQueue {
+    jobs: Arc<Jobs>,
+    sender: Mutex<mpsc::SyncSender<(String, JobRequest)>>,
+    // shared by the workers. the queue keeps it too, so a queue without workers still takes
+    // jobs and keeps them queued
+    _receiver: Arc<Mutex<mpsc::Receiver<(String, JobRequest)>>>,
+}
+
+impl JobQueue {
+    pub fn new(dir: impl AsRef<Path>, keys: Arc<KeyStore>, workers: usize) -> Self {
+        Self::with_limits(dir, keys, workers, JobLimits::default())
+    }
+
+    pub fn with_limits(
+        dir: impl AsRef<Path>,
+        keys: Arc<KeyStore>,
+        workers: usize,
+        limits: JobLimits,
+    ) -> Self {
+        let dir = dir.as_ref().to_path_buf();
+        let jobs = Arc::new(Jobs {
+            jobs: Mutex::new(Jobs::load(&dir)),
+            limits,
+            dir,
+        });
+        {
+            let mut loaded = jobs.jobs.lock().unwrap();
+            jobs.prune(&mut loaded);
+            for job in loaded.values() {
+                jobs.persist(job);
+            }
+        }
+
+        let (sender, receiver) =
+            mpsc::sync_channel::<(String, JobRequest)>(limits.max_queued.max(1));
+        let receiver = Arc::new(Mutex::new(receiver));
+        for worker in 0..workers {
+            let (jobs, keys, receiver) = (jobs.clone(), keys.clone(), receiver.clone());
+            thread::Builder::new()
+                .name(format!("job-worker-{}", worker))
+                .spawn(move || loop {
+                    // the lock is only held while waiting, the next worker waits for it
+                    let next = receiver.lock().unwrap().recv();
+                    match next {
+                        Ok((id, request)) => Jobs::run(&jobs, &keys, id, request),
+                        Err(_) => return,
+                    }
+                })
+                .expect("failed to start a job worker");
+        }
+        Self {
+            jobs,
+            sender: Mutex::new(sender),
+            _receiver: receiver,
+        }
+    }
+
+    pub fn from_env(keys: Arc<KeyStore>) -> Self {
+        let dir = std::env::var("ZK_JOB_DIR").unwrap_or_else(|_| DEFAULT_JOB_DIR.to_string());
+        let number = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+        };
+        let workers = number("ZK_JOB_WORKERS").map_or(DEFAULT_JOB_WORKERS, |n| n as usize);
+        let limits = JobLimits {
+            max_queued: number("ZK_JOB_QUEUE").map_or(DEFAULT_MAX_QUEUED_JOBS, |n| n as usize),
+            max_finished: number("ZK_JOB_KEEP").map_or(DEFAULT_MAX_FINISHED_JOBS, |n| n as usize),
+            finished_ttl: number("ZK_JOB_TTL").map_or(DEFAULT_JOB_TTL, Duration::from_secs),
+        };
+        Self::with_limits(dir, keys, workers.max(1), limits)
+    }
+
+    pub fn submit(&self, request: JobRequest) -> Result<Job, ZkError> {
+        request.validate()?;
+        let job = Job {
+            id: format!("{:016x}", rand::random::<u64>()),
+            circuit: request.circuit().to_string(),
+            status: JobStatus::Queued,
+            phase: None,
+            cancel_requested: false,
+            result: None,
+            error: None,
+            finished_at: None,
+        };
+        self.jobs.persist(&job);
+        {
+            let mut jobs = self.jobs.jobs.lock().unwrap();
+            self.jobs.prune(&mut jobs);
+            jobs.insert(job.id.clone(), job.clone());
+        }
+        let sent = self
+            .sender
+            .lock()
+            .unwrap()
+            .try_send((job.id.clone(), request));
+        if let Err(e) = sent {
+            // the job never existed
+            self.jobs.jobs.lock().unwrap().remove(&job.id);
+            let _ = fs::remove_file(self.jobs.path(&job.id));
+            return Err(match e {
+                TrySendError::Full(_) => ZkError::JobQueueFull,
+                TrySendError::Disconnected(_) => {
+                    ZkError::Internal("the job workers stopped".to_string())
+                }
+            });
+        }
+        Ok(job)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Job, ZkError> {
+        let jobs = self.jobs.jobs.lock().unwrap();
+        jobs.get(id)
+            .cloned()
+            .ok_or_else(|| ZkError::UnknownJob(id.to_string()))
+    }
+
+    // a queued job is cancelled right away and a running one at its next phase. a finished
+    // job is deleted, with its result
+    pub fn cancel(&self, id: &str) -> Result<Job, ZkError> {
+        let mut jobs = self.jobs.jobs.lock().unwrap();
+        let job = jobs
+            .get_mut(id)
+            .ok_or_else(|| ZkError::UnknownJob(id.to_string()))?;
+        match job.status {
+            JobStatus::Queued => job.finish(JobStatus::Cancelled),
+            JobStatus::Running => job.cancel_requested = true,
+            _ => {
+                let job = jobs.remove(id).unwrap();
+                let _ = fs::remove_file(self.jobs.path(id));
+                return Ok(job);
+            }
+        }
+        self.jobs.persist(job);
+        Ok(job.clone())
+    }
+}
+
+pub async fn submit_job(
+    jobs: web::Data<JobQueue>,
+    data: web::Json<JobRequest>,
+) -> Result<HttpResponse, ZkError> {
+    Ok(HttpResponse::Accepted().json(jobs.submit(data.into_inner())?))
+}
+
+pub async fn job_status(
+    jobs: web::Data<JobQueue>,
+    id: web::Path<String>,
+) -> Result<HttpResponse, ZkError> {
+    Ok(HttpResponse::Ok().json(jobs.get(&id)?))
+}
+
+pub async fn cancel_job(
+    jobs: web::Data<JobQueue>,
+    id: web::Path<String>,
+) -> Result<HttpResponse, ZkError> {
+    Ok(HttpResponse::Ok().json(jobs.cancel(&id)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arkworks::key_store::TestDir;
+    use std::time::{Duration, Instant};
+
+    // the jobs go to dir/jobs, the keys to dir/keys
+    fn keys(dir: &TestDir) -> Arc<KeyStore> {
+        Arc::new(KeyStore::new(dir.0.join("keys")))
+    }
+
+    fn fibonacci(num_of_rounds: usize) -> JobRequest {
+        serde_json::from_value(serde_json::json!({
+            "circuit": "fibonacci",
+            "input": {"a": "0", "b": "1", "num_of_rounds": num_of_rounds},
+        }))
+        .unwrap()
+    }
+
+    fn wait(queue: &JobQueue, id: &str) -> Job {
+        let start = Instant::now();
+        loop {
+            let job = queue.get(id).unwrap();
+            if job.status.is_finished() {
+                return job;
+            }
+            assert!(
+                start.elapsed() < Duration::from_secs(120),
+                "the job didn't finish"
+            );
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    #[test]
+    fn test_jobs_run_and_are_persisted() {
+        let test_dir = TestDir::new("jobs_run");
+        let dir = test_dir.0.join("jobs");
+        let queue = JobQueue::new(&dir, keys(&test_dir), 2);
+        let job = queue.submit(fibonacci(10)).unwrap();
+        assert_eq!(job.circuit, "fibonacci");
+        let job = wait(&queue, &job.id);
+        assert_eq!(job.status, JobStatus::Done);
+        assert_eq!(job.phase, None);
+        let result = job.result.clone().unwrap();
+        assert_eq!(result["fib_number"], "89");
+        assert_eq!(result["key_id"], "fibonacci-10");
+
+        // a new queue on the same directory still has the result
+        let restarted = JobQueue::new(&dir, keys(&test_dir), 0);
+        assert_eq!(restarted.get(&job.id).unwrap().result, job.result);
+        // and a finished job is deleted
+        restarted.cancel(&job.id).unwrap();
+        assert_eq!(
+            restarted.get(&job.id).unwrap_err(),
+            ZkError::UnknownJob(job.id.clone())
+        );
+        assert!(JobQueue::new(&dir, keys(&test_dir), 0)
+            .get(&job.id)
+            .is_err());
+    }
+
+    #[test]
+    fn test_failed_and_cancelled_jobs() {
+        let test_dir = TestDir::new("jobs_cancel");
+        let dir = test_dir.0.join("jobs");
+        // without workers every job stays queued
+        let queue = JobQueue::new(&dir, keys(&test_dir), 0);
+        let cancelled = queue.submit(fibonacci(10)).unwrap();
+        let interrupted = queue.submit(fibonacci(11)).unwrap();
+        assert_eq!(
+            queue.cancel(&cancelled.id).unwrap().status,
+            JobStatus::Cancelled
+        );
+
+        // the jobs that were queued when the server stopped have failed
+        let restarted = JobQueue::new(&dir, keys(&test_dir), 1);
+        assert_eq!(
+            restarted.get(&cancelled.id).unwrap().status,
+            JobStatus::Cancelled
+        );
+        let interrupted = restarted.get(&interrupted.id).unwrap();
+        assert_eq!(interrupted.status, JobStatus::Failed);
+        assert_eq!(interrupted.error.unwrap().code, "internal");
+
+        // invalid inputs are rejected before they are queued, false statements fail the job
+        let error = restarted.submit(fibonacci(0)).err().unwrap();
+        assert_eq!(error.field(), Some("num_of_rounds"));
+        let request = serde_json::from_value(serde_json::json!({
+            "circuit": "linear_equations",
+            "input": {"a": [[1, 1], [1, 1]], "b": [3, 4]},
+        }))
+        .unwrap();
+        let job = restarted.submit(request).unwrap();
+        let job = wait(&restarted, &job.id);
+        assert_eq!(job.status, JobStatus::Failed);
+        assert_eq!(job.error.unwrap().code, "unsatisfiable");
+    }
+
+    #[test]
+    fn test_full_queues_reject_jobs() {
+        let test_dir = TestDir::new("jobs_full");
+        let dir = test_dir.0.join("jobs");
+        let limits = JobLimits {
+            max_queued: 1,
+            ..JobLimits::default()
+        };
+        // without workers the first job keeps the only place in the queue
+        let queue = JobQueue::with_limits(&dir, keys(&test_dir), 0, limits);
+        queue.submit(fibonacci(10)).unwrap();
+        let error = queue.submit(fibonacci(10)).unwrap_err();
+        assert_eq!(error, ZkError::JobQueueFull);
+        assert_eq!(error.code(), "queue_full");
+        // the rejected job left no file behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_finished_jobs_are_pruned() {
+        let test_dir = TestDir::new("jobs_prune");
+        let dir = test_dir.0.join("jobs");
+        let limits = JobLimits {
+            max_finished: 1,
+            ..JobLimits::default()
+        };
+        let queue = JobQueue::with_limits(&dir, keys(&test_dir), 1, limits);
+        let first = queue.submit(fibonacci(10)).unwrap();
+        assert!(wait(&queue, &first.id).finished_at.is_some());
+        // the finish times are in milliseconds
+        thread::sleep(Duration::from_millis(5));
+        let second = queue.submit(fibonacci(10)).unwrap();
+        wait(&queue, &second.id);
+        assert_eq!(
+            queue.get(&first.id).unwrap_err(),
+            ZkError::UnknownJob(first.id.clone())
+        );
+        assert!(!dir.join(format!("{}.json", first.id)).exists());
+
+        // a restart with a ttl of zero deletes the rest
+        thread::sleep(Duration::from_millis(5));
+        let limits = JobLimits {
+            finished_ttl: Duration::ZERO,
+            ..limits
+        };
+        let restarted = JobQueue::with_limits(&dir, keys(&test_dir), 0, limits);
+        assert!(restarted.get(&second.id).is_err());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_phases_outside_of_jobs() {
+        assert_eq!(enter_phase(Phase::Prove), Ok(()));
+    }
+}
@@ -1,2 +1,6 @@
 pub mod arkworks;
+pub mod bundle;
+pub mod error;
+pub mod jobs;
+pub mod proof_system;
 pub mod sp1;
@@ -1,12 +1,42 @@
 use actix_cors::Cors;
 use actix_files::Files;
 use actix_web::{web, App, HttpServer};
+use prime_snarks::arkworks::backend::aggregate::{aggregate, verify_aggregate};
+use prime_snarks::arkworks::backend::conv2d::{prove_conv2d, verify_conv2d};
+use prime_snarks::arkworks::backend::evm::{evm_fibonacci_proof, evm_verifier};
 use prime_snarks::arkworks::backend::fibbonaci_handler::{
-    fibbonaci_snark_proof, fibbonaci_snark_verify,
+    fast_fibbonaci_snark_proof, fast_fibbonaci_snark_verify, fibbonaci_snark_proof,
+    fibbonaci_snark_verify,
+};
+use prime_snarks::arkworks::backend::folding::{
+    folding_fibbonaci_proof, folding_fibbonaci_verify, folding_prime_proof, folding_prime_verify,
+};
+use prime_snarks::arkworks::backend::linear_equations::{
+    prove_infeasible, prove_linear_equations, verify_infeasible, verify_linear_equations,
+};
+use prime_snarks::arkworks::backend::linear_recurrence::{
+    linear_recurrence_proof, linear_recurrence_verify,
+};
+use prime_snarks::arkworks::backend::matrix_linear_algebra::{
+    prove_determinant, prove_inverse, prove_rank, verify_linear_algebra,
+};
+use prime_snarks::arkworks::backend::matrix_proof::{
+    open_commitment, open_row, prove_matrix, verify_proof, verify_row, verify_sparse_proof,
+};
+use prime_snarks::arkworks::backend::merkle::{
+    merkle_membership_proof, merkle_membership_verify, merkle_path, merkle_tree,
+};
+use prime_snarks::arkworks::backend::poseidon_preimage::{
+    poseidon_preimage_proof, poseidon_preimage_verify,
 };
-use prime_snarks::arkworks::backend::linear_equations::prove_linear_equations;
-use prime_snarks::arkworks::backend::matrix_proof::{prove_matrix, verify_proof};
 use prime_snarks::arkworks::backend::prime_snark::{prove_prime, verify_prime};
+use prime_snarks::arkworks::backend::sha256_preimage::{
+    sha256_preimage_proof, sha256_preimage_verify,
+};
+use prime_snarks::arkworks::key_store::KeyStore;
+use prime_snarks::bundle;
+use prime_snarks::error::{json_error_handler, query_error_handler};
+use prime_snarks::jobs::{cancel_job, job_status, submit_job, JobQueue};
 use prime_snarks::sp1::miller_rabin::script::src::main::{generate_proof, prove, verify};
 
 fn configure_services(cfg: &mut web::ServiceConfig) {
@@ -16,27 +46,128 @@ fn configure_services(cfg: &mut web::ServiceConfig) {
                 "/prove_linear_equations",
                 web::post().to(prove_linear_equations),
             )
+            .route(
+                "/linear_equations/verify",
+                web::post().to(verify_linear_equations),
+            )
+            .route(
+                "/linear_equations/prove_infeasible",
+                web::post().to(prove_infeasible),
+            )
+            .route(
+                "/linear_equations/verify_infeasible",
+                web::post().to(verify_infeasible),
+            )
             .route("/prime_sp1", web::post().to(generate_proof))
             .route("/prime_sp1/verify", web::post().to(verify))
             .route("/prime_sp1/prove", web::post().to(prove))
             .route("/matrix_prove/verify", web::post().to(verify_proof))
             .route("/matrix_prove/prove", web::post().to(prove_matrix))
+            .route(
+                "/matrix_prove/sparse/verify",
+                web::post().to(verify_sparse_proof),
+            )
+            .route("/matrix/inverse/prove", web::post().to(prove_inverse))
+            .route(
+                "/matrix/determinant/prove",
+                web::post().to(prove_determinant),
+            )
+            .route("/matrix/rank/prove", web::post().to(prove_rank))
+            .route("/matrix/verify", web::post().to(verify_linear_algebra))
+            .route("/matrix/open", web::post().to(open_commitment))
+            .route("/matrix/merkle/open_row", web::post().to(open_row))
+            .route("/matrix/merkle/verify_row", web::post().to(verify_row))
+            .route("/conv2d/prove", web::post().to(prove_conv2d))
+            .route("/conv2d/verify", web::post().to(verify_conv2d))
             .route("/fibbonaci/verify", web::post().to(fibbonaci_snark_verify))
             .route("/fibbonaci/prove", web::post().to(fibbonaci_snark_proof))
+            .route(
+                "/fibbonaci/fast/prove",
+                web::post().to(fast_fibbonaci_snark_proof),
+            )
+            .route(
+                "/fibbonaci/fast/verify",
+                web::post().to(fast_fibbonaci_snark_verify),
+            )
+            .route(
+                "/fibbonaci/folding/prove",
+                web::post().to(folding_fibbonaci_proof),
+            )
+            .route(
+                "/fibbonaci/folding/verify",
+                web::post().to(folding_fibbonaci_verify),
+            )
+            .route(
+                "/linear_recurrence/prove",
+                web::post().to(linear_recurrence_proof),
+            )
+            .route(
+                "/linear_recurrence/verify",
+                web::post().to(linear_recurrence_verify),
+            )
+            .route(
+                "/poseidon_preimage/prove",
+                web::post().to(poseidon_preimage_proof),
+            )
+            .route(
+                "/poseidon_preimage/verify",
+                web::post().to(poseidon_preimage_verify),
+            )
+            .route(
+                "/sha256_preimage/prove",
+                web::post().to(sha256_preimage_proof),
+            )
+            .route(
+                "/sha256_preimage/verify",
+                web::post().to(sha256_preimage_verify),
+            )
+            .route("/merkle/tree", web::post().to(merkle_tree))
+            .route("/merkle/path", web::post().to(merkle_path))
+            .route("/merkle/prove", web::post().to(merkle_membership_proof))
+            .route("/merkle/verify", web::post().to(merkle_membership_verify))
             .route("/prime_arkworks/prove", web::post().to(prove_prime))
-            .route("/prime_arkworks/verify", web::post().to(verify_prime)),
+            .route("/prime_arkworks/verify", web::post().to(verify_prime))
+            .route(
+                "/prime_arkworks/folding/prove",
+                web::post().to(folding_prime_proof),
+            )
+            .route(
+                "/prime_arkworks/folding/verify",
+                web::post().to(folding_prime_verify),
+            )
+            .route("/evm/fibonacci/prove", web::post().to(evm_fibonacci_proof))
+            .route("/evm/verifier/{key_id}", web::get().to(evm_verifier))
+            .route("/verify", web::post().to(bundle::verify))
+            .route("/aggregate", web::post().to(aggregate))
+            .route("/aggregate/verify", web::post().to(verify_aggregate))
+            .route("/jobs", web::post().to(submit_job))
+            .route("/jobs/{id}", web::get().to(job_status))
+            .route("/jobs/{id}", web::delete().to(cancel_job)),
     );
 }
 
+const BUNDLE_LIMIT: usize = 64 << 20;
+
 fn configure_app(cfg: &mut web::ServiceConfig) {
     cfg.service(Files::new("/", "./build").index_file("index.html"));
 }
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    HttpServer::new(|| {
+    // one key store for all the workers, the keys are loaded on first use
+    let keys = web::Data::new(KeyStore::from_env());
+    // the long proofs run as jobs on their own threads, with the same keys
+    let jobs = web::Data::new(JobQueue::from_env(keys.clone().into_inner()));
+    HttpServer::new(move || {
         App::new()
             .wrap(Cors::permissive())
+            .app_data(keys.clone())
+            .app_data(jobs.clone())
+            // bodies and queries that don't parse get the JSON errors of the handlers
+            .app_data(web::JsonConfig::default().error_handler(json_error_handler))
+            .app_data(web::QueryConfig::default().error_handler(query_error_handler))
+            // the binary bundles of /verify, sp1 proofs are larger than the default limit
+            .app_data(web::PayloadConfig::default().limit(BUNDLE_LIMIT))
             .configure(configure_services)
     })
     .bind("127.0.0.1:8080")?
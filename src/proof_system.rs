@@ -0,0 +1,153 @@
+use ark_relations::r1cs::SynthesisError;
+use ark_serialize::SerializationError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+// the proof systems a prove request can pick with its `backend` field. groth16 and marlin
+// prove the R1CS circuits of crate::arkworks, sp1 proves RISC-V programs (see crate::sp1)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    #[default]
+    Groth16,
+    Marlin,
+    Sp1,
+}
+
+impl Backend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backend::Groth16 => "groth16",
+            Backend::Marlin => "marlin",
+            Backend::Sp1 => "sp1",
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        [Backend::Groth16, Backend::Marlin, Backend::Sp1]
+            .into_iter()
+            .find(|backend| backend.name() == name)
+            .ok_or_else(|| format!("unknown backend '{}', use groth16, marlin or sp1", name))
+    }
+}
+
+// the pairing engine of the SNARKs, a prove request picks it with its `curve` field. bn254 has
+// the precompiles of the EVM, bls12_377 is the inner curve of the recursive SNARKs over
+// bw6_761. every circuit is written over the scalar field of any of them (see
+// crate::arkworks::curves), marlin is only over bls12_381.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Curve {
+    #[default]
+    Bls12_381,
+    Bn254,
+    Bls12_377,
+}
+
+impl Curve {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Curve::Bls12_381 => "bls12_381",
+            Curve::Bn254 => "bn254",
+            Curve::Bls12_377 => "bls12_377",
+        }
+    }
+}
+
+impl fmt::Display for Curve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Curve {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        [Curve::Bls12_381, Curve::Bn254, Curve::Bls12_377]
+            .into_iter()
+            .find(|curve| curve.name() == name)
+            .ok_or_else(|| format!("unknown curve '{}', use bls12_381, bn254 or bls12_377", name))
+    }
+}
+
+#[derive(Debug)]
+pub enum ProofSystemError {
+    Synthesis(SynthesisError),
+    Serialization(SerializationError),
+    // errors of the marlin and sp1 libraries, they only share a message
+    Backend(String),
+}
+
+impl fmt::Display for ProofSystemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofSystemError::Synthesis(e) => write!(f, "synthesis error: {}", e),
+            ProofSystemError::Serialization(e) => write!(f, "serialization error: {}", e),
+            ProofSystemError::Backend(e) => write!(f, "proof system error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ProofSystemError {}
+
+impl From<SynthesisError> for ProofSystemError {
+    fn from(e: SynthesisError) -> Self {
+        ProofSystemError::Synthesis(e)
+    }
+}
+
+impl From<SerializationError> for ProofSystemError {
+    fn from(e: SerializationError) -> Self {
+        ProofSystemError::Serialization(e)
+    }
+}
+
+// a proof system without its circuits: the types of its keys and proofs, verification and
+// the byte encodings the key store and the handlers use. the bytes only have to round trip
+// through the same implementation.
+pub trait ProofSystem {
+    // the name in requests and the directory of the keys in the key store
+    const NAME: &'static str;
+
+    type ProvingKey: Send + Sync + 'static;
+    type VerifyingKey: Send + Sync + 'static;
+    type Proof;
+    // field elements for the SNARKs, the committed public values for sp1
+    type PublicInput: ?Sized;
+
+    fn verify(
+        &self,
+        vk: &Self::VerifyingKey,
+        public_input: &Self::PublicInput,
+        proof: &Self::Proof,
+    ) -> Result<bool, ProofSystemError>;
+
+    fn proving_key_to_bytes(pk: &Self::ProvingKey) -> Result<Vec<u8>, ProofSystemError>;
+    fn proving_key_from_bytes(bytes: &[u8]) -> Result<Self::ProvingKey, ProofSystemError>;
+    fn verifying_key_to_bytes(vk: &Self::VerifyingKey) -> Result<Vec<u8>, ProofSystemError>;
+    fn verifying_key_from_bytes(bytes: &[u8]) -> Result<Self::VerifyingKey, ProofSystemError>;
+    fn proof_to_bytes(proof: &Self::Proof) -> Result<Vec<u8>, ProofSystemError>;
+    fn proof_from_bytes(bytes: &[u8]) -> Result<Self::Proof, ProofSystemError>;
+}
+
+// the circuits a proof system can prove. a separate trait because verifying needs no circuit,
+// and C is a ConstraintSynthesizer for the SNARKs but the stdin of a program for sp1. a
+// circuit written once against ConstraintSynthesizer<Fr> is provable by every SNARK backend.
+pub trait Prover<C>: ProofSystem {
+    fn setup(&self, circuit: C)
+        -> Result<(Self::ProvingKey, Self::VerifyingKey), ProofSystemError>;
+
+    fn prove(&self, pk: &Self::ProvingKey, circuit: C) -> Result<Self::Proof, ProofSystemError>;
+}
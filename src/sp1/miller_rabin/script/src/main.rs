@@ -1,14 +1,17 @@
-use actix_web::{web, App, HttpResponse, HttpServer, Responder};
+use crate::arkworks::backend::proving::BackendVerification;
+use crate::bundle::{ProofBundle, PublicInput, PublicValue, VerifyingKeyRef};
+use crate::error::{Validate, ZkError};
+use crate::jobs::{enter_phase, Phase};
+use crate::proof_system::{Backend, Curve, ProofSystem, Prover};
+use crate::sp1::proof_system::Sp1Backend;
+use actix_web::{web, App, HttpResponse, HttpServer};
 use alloy_sol_types::{sol, SolType};
 use ark_serialize::SerializationError;
 use clap::Parser;
 use rand::SeedableRng;
 use serde::{ser::SerializeStructVariant, Deserialize, Serialize};
-use sp1_sdk::{
-    utils, ProverClient, SP1CompressedProof, SP1PlonkBn254Proof, SP1Proof, SP1Stdin,
-    SP1VerifyingKey,
-};
-use std::sync::Mutex;
+use sp1_sdk::{utils, SP1CompressedProof, SP1PlonkBn254Proof, SP1Proof, SP1Stdin, SP1VerifyingKey};
+use std::sync::{Mutex, OnceLock};
 
 /// The arguments for the prove command.
 #[derive(Parser, Debug)]
@@ -38,35 +41,88 @@ pub struct ProvePayload {
     seed: u64,
 }
 
+/// The program panics on more rounds than this.
+pub const MAX_ROUNDS: u32 = 50;
+
+impl Validate for ProvePayload {
+    fn validate(&self) -> Result<(), ZkError> {
+        if self.num_of_rounds > MAX_ROUNDS {
+            return Err(ZkError::invalid(
+                "num_of_rounds",
+                format!("num_of_rounds can be at most {}", MAX_ROUNDS),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The bytes of the verifying key of MILLER_ELF, it only depends on the program so it is set
+/// up once.
+fn program_vk_bytes() -> Result<&'static [u8], ZkError> {
+    static PROGRAM_VK: OnceLock<Vec<u8>> = OnceLock::new();
+    if let Some(vk) = PROGRAM_VK.get() {
+        return Ok(vk);
+    }
+    let (_, vk) = Sp1Backend::new(MILLER_ELF).setup(SP1Stdin::new())?;
+    let vk = Sp1Backend::verifying_key_to_bytes(&vk)?;
+    Ok(PROGRAM_VK.get_or_init(|| vk))
+}
+
+/// The verifying key of a request if it is the one of MILLER_ELF. The key of another program
+/// would verify the proofs of that program, so a key from the request is never trusted.
+fn program_vk(field: &str, bytes: &[u8]) -> Result<SP1VerifyingKey, ZkError> {
+    let vk = Sp1Backend::verifying_key_from_bytes(bytes)
+        .map_err(|e| ZkError::invalid(field, e.to_string()))?;
+    if Sp1Backend::verifying_key_to_bytes(&vk)? != program_vk_bytes()? {
+        return Err(ZkError::invalid(
+            field,
+            "not the verifying key of the miller rabin program",
+        ));
+    }
+    Ok(vk)
+}
+
+/// Decodes the public values the program committed to.
+fn public_values(bytes: &[u8]) -> Result<(u32, u32, u32, bool), ZkError> {
+    PublicValuesTuple::abi_decode(bytes, false)
+        .map_err(|e| ZkError::Internal(format!("invalid public values: {}", e)))
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, ZkError> {
+    serde_json::to_string(value).map_err(|e| ZkError::Internal(e.to_string()))
+}
+
 #[derive(Serialize, Deserialize)]
-struct ProofResponse {
+pub struct ProofResponse {
     is_prime: bool,
     prime: u32,
     vkey: String,
     proof: String,
     proof_size: usize,
     proof_time: f64,
+    bundle: ProofBundle,
 }
 
-pub async fn generate_proof(args: web::Json<ProvePayload>) -> impl Responder {
+pub async fn generate_proof(args: web::Json<ProvePayload>) -> Result<HttpResponse, ZkError> {
     // Setup logging.
     utils::setup_logger();
+    args.validate()?;
 
-    let client = ProverClient::new();
-    let (pk, vk) = client.setup(MILLER_ELF);
-
+    let system = Sp1Backend::new(MILLER_ELF);
     let mut stdin = SP1Stdin::new();
     stdin.write(&args.n);
     stdin.write(&args.num_of_rounds);
+    // the program reads the seed after the rounds
+    stdin.write(&args.seed.to_be_bytes());
+    let (pk, vk) = system.setup(SP1Stdin::new())?;
 
     // start timer:
     let start = std::time::Instant::now();
-    let proof = client.prove(&pk, stdin).expect("failed to generate proof");
+    let proof = system.prove(&pk, stdin)?;
     // end timer:
     let proof_time = start.elapsed().as_secs_f64();
 
-    let (n, num_of_rounds, prime, is_prime) =
-        PublicValuesTuple::abi_decode(proof.public_values.as_slice(), false).unwrap();
+    let (n, num_of_rounds, prime, is_prime) = public_values(proof.public_values.as_slice())?;
     println!("Successfully generated proof!");
     if is_prime {
         println!("Prime: {}", prime);
@@ -74,50 +130,61 @@ pub async fn generate_proof(args: web::Json<ProvePayload>) -> impl Responder {
         println!("Not found any prime within: {}", args.num_of_rounds);
     }
     // desirlized the proof:
-    client.verify(&proof, &vk).expect("failed to verify proof");
+    if !system.verify(&vk, proof.public_values.as_slice(), &proof)? {
+        return Err(ZkError::Internal("the proof doesn't verify".to_string()));
+    }
 
     // exctract the number of circles from the RUST_INFO:
-    let serializedproof = serde_json::to_string(&proof).expect("failed to serialize proof");
+    let serializedproof = to_json(&proof)?;
     let response = ProofResponse {
         is_prime,
         prime,
-        vkey: serde_json::to_string(&vk).expect("failed to serialize verifying key"),
+        vkey: to_json(&vk)?,
         proof_size: serializedproof.len(),
+        bundle: bundle(&proof, &vk)?,
         proof: serializedproof,
         proof_time,
     };
-    HttpResponse::Ok().json(response)
+    Ok(HttpResponse::Ok().json(response))
 }
 
-pub async fn prove(args: web::Json<ProvePayload>) -> impl Responder {
-    let client = ProverClient::new();
-    let (pk, vk) = client.setup(MILLER_ELF);
+pub async fn prove(args: web::Json<ProvePayload>) -> Result<HttpResponse, ZkError> {
+    Ok(HttpResponse::Ok().json(miller_rabin_proof(&args)?))
+}
 
+/// The proof of /prime_sp1/prove, also run by the prime_sp1 jobs.
+pub fn miller_rabin_proof(args: &ProvePayload) -> Result<ProofResponse, ZkError> {
+    args.validate()?;
+    let system = Sp1Backend::new(MILLER_ELF);
     let mut stdin = SP1Stdin::new();
     stdin.write(&args.n);
     stdin.write(&args.num_of_rounds);
     let seed_bytes = args.seed.to_be_bytes();
     println!("seed: {:?}", seed_bytes);
     stdin.write(&seed_bytes);
+    enter_phase(Phase::Setup)?;
+    let (pk, vk) = system.setup(SP1Stdin::new())?;
 
+    // the witness is the execution of the program, it is part of the proving
+    enter_phase(Phase::Prove)?;
     // open timer:
     let start = std::time::Instant::now();
 
     // let proof = client.prove(&pk, stdin).expect("failed to generate proof");
-    let proof = client.prove(&pk, stdin).expect("failed to generate proof");
+    let proof = system.prove(&pk, stdin)?;
     // end timer:
     let proof_time = start.elapsed().as_secs_f64();
-    let (n, num_of_rounds, prime, is_prime) =
-        PublicValuesTuple::abi_decode(proof.public_values.as_slice(), false).unwrap();
+    let (n, num_of_rounds, prime, is_prime) = public_values(proof.public_values.as_slice())?;
     println!("Successfully generated proof!");
 
     println!("Not found any prime within: {}", args.num_of_rounds);
-    let serializedproof = serde_json::to_string(&proof).expect("failed to serialize proof");
-    let serilized_vk = serde_json::to_string(&vk).expect("failed to serialize verifying key");
+    let serializedproof = to_json(&proof)?;
+    let serilized_vk = to_json(&vk)?;
     let proof_size = serializedproof.len();
     println!("Proof size: {}", serializedproof.len());
 
     let response = ProofResponse {
+        bundle: bundle(&proof, &vk)?,
         is_prime,
         prime,
         vkey: serilized_vk,
@@ -125,7 +192,65 @@ pub async fn prove(args: web::Json<ProvePayload>) -> impl Responder {
         proof: serializedproof,
         proof_time,
     };
-    HttpResponse::Ok().json(response)
+    Ok(response)
+}
+
+/// The keys only depend on the program, so the bundle has no params and embeds the
+/// verifying key. The public values are a single bytes input. SP1 proofs aren't over one of
+/// the curves, the bundle has the default one.
+fn bundle(proof: &SP1Proof, vk: &SP1VerifyingKey) -> Result<ProofBundle, ZkError> {
+    Ok(ProofBundle::new(
+        "prime_sp1",
+        vec![],
+        Curve::default(),
+        Backend::Sp1,
+        vec![PublicInput::new(
+            "public_values",
+            PublicValue::Bytes(proof.public_values.to_vec()),
+        )],
+        Sp1Backend::proof_to_bytes(proof)?,
+        VerifyingKeyRef::Embedded(Sp1Backend::verifying_key_to_bytes(vk)?),
+    ))
+}
+
+/// Verifies a prime_sp1 bundle of /verify.
+pub fn verify_bundle(bundle: &ProofBundle) -> Result<BackendVerification, ZkError> {
+    if bundle.circuit != "prime_sp1" {
+        return Err(ZkError::invalid(
+            "circuit",
+            format!("the sp1 backend has no program {}", bundle.circuit),
+        ));
+    }
+    let VerifyingKeyRef::Embedded(vk) = &bundle.verifying_key else {
+        return Err(ZkError::invalid(
+            "verifying_key",
+            "sp1 bundles embed their verifying key",
+        ));
+    };
+    let vk = program_vk("verifying_key", vk)?;
+    let proof = Sp1Backend::proof_from_bytes(&bundle.proof)
+        .map_err(|e| ZkError::invalid("proof", e.to_string()))?;
+    // the public values are the bytes inputs in order
+    let mut public_values = vec![];
+    for input in &bundle.public_inputs {
+        match &input.value {
+            PublicValue::Bytes(bytes) => public_values.extend_from_slice(bytes),
+            _ => {
+                return Err(ZkError::invalid(
+                    "public_inputs",
+                    format!("{} is not bytes", input.name),
+                ))
+            }
+        }
+    }
+    let start = std::time::Instant::now();
+    let valid = Sp1Backend::new(MILLER_ELF)
+        .verify(&vk, &public_values, &proof)
+        .unwrap_or(false);
+    Ok(BackendVerification {
+        valid,
+        verifying_time: start.elapsed().as_secs_f64(),
+    })
 }
 
 /// The payload structure for the proof generation request.
@@ -139,16 +264,21 @@ struct VerifyResponse {
     result: bool,
     verifying_time: f64,
 }
-pub async fn verify(args: web::Json<VerifyPayload>) -> impl Responder {
-    let client: ProverClient = ProverClient::new();
-    let desrilized_proof: SP1Proof =
-        serde_json::from_str(&args.proof).expect("failed to deserialize proof");
-    let desrilized_vkey: SP1VerifyingKey =
-        serde_json::from_str(&args.vkey).expect("failed to deserialize verifying key");
+pub async fn verify(args: web::Json<VerifyPayload>) -> Result<HttpResponse, ZkError> {
+    let system = Sp1Backend::new(MILLER_ELF);
+    let desrilized_proof = Sp1Backend::proof_from_bytes(args.proof.as_bytes())
+        .map_err(|e| ZkError::invalid("proof", e.to_string()))?;
+    let desrilized_vkey = program_vk("vkey", args.vkey.as_bytes())?;
     // open timer:
     let start = std::time::Instant::now();
-    // let result = client.verify(&desrilized_proof, &desrilized_vkey).is_ok();
-    let result = client.verify(&desrilized_proof, &desrilized_vkey).is_ok();
+    // the proof carries the public values it was made for, the result is in them
+    let result = system
+        .verify(
+            &desrilized_vkey,
+            desrilized_proof.public_values.as_slice(),
+            &desrilized_proof,
+        )
+        .unwrap_or(false);
     // end timer:
     let verifying_time = start.elapsed().as_secs_f64();
     // catch the expected error:
@@ -156,5 +286,5 @@ pub async fn verify(args: web::Json<VerifyPayload>) -> impl Responder {
         result,
         verifying_time,
     };
-    HttpResponse::Ok().json(response)
+    Ok(HttpResponse::Ok().json(response))
 }
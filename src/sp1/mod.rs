@@ -1 +1,2 @@
 pub mod miller_rabin;
+pub mod proof_system;
@@ -0,0 +1,81 @@
+use crate::proof_system::{ProofSystem, ProofSystemError, Prover};
+use sp1_sdk::{ProverClient, SP1Proof, SP1ProvingKey, SP1Stdin, SP1VerifyingKey};
+
+fn backend_error<E: std::fmt::Display>(e: E) -> ProofSystemError {
+    ProofSystemError::Backend(format!("sp1: {}", e))
+}
+
+// an SP1 program as a proof system. the "circuit" of a proof is the stdin of a run of the
+// program, the public input is what the program committed to. keys and proofs are json,
+// like the sp1 endpoints always returned them.
+#[derive(Debug, Clone, Copy)]
+pub struct Sp1Backend {
+    elf: &'static [u8],
+}
+
+impl Sp1Backend {
+    pub fn new(elf: &'static [u8]) -> Self {
+        Self { elf }
+    }
+}
+
+impl ProofSystem for Sp1Backend {
+    const NAME: &'static str = "sp1";
+
+    type ProvingKey = SP1ProvingKey;
+    type VerifyingKey = SP1VerifyingKey;
+    type Proof = SP1Proof;
+    type PublicInput = [u8];
+
+    fn verify(
+        &self,
+        vk: &Self::VerifyingKey,
+        public_values: &[u8],
+        proof: &Self::Proof,
+    ) -> Result<bool, ProofSystemError> {
+        Ok(proof.public_values.as_slice() == public_values
+            && ProverClient::new().verify(proof, vk).is_ok())
+    }
+
+    fn proving_key_to_bytes(pk: &Self::ProvingKey) -> Result<Vec<u8>, ProofSystemError> {
+        serde_json::to_vec(pk).map_err(backend_error)
+    }
+
+    fn proving_key_from_bytes(bytes: &[u8]) -> Result<Self::ProvingKey, ProofSystemError> {
+        serde_json::from_slice(bytes).map_err(backend_error)
+    }
+
+    fn verifying_key_to_bytes(vk: &Self::VerifyingKey) -> Result<Vec<u8>, ProofSystemError> {
+        serde_json::to_vec(vk).map_err(backend_error)
+    }
+
+    fn verifying_key_from_bytes(bytes: &[u8]) -> Result<Self::VerifyingKey, ProofSystemError> {
+        serde_json::from_slice(bytes).map_err(backend_error)
+    }
+
+    fn proof_to_bytes(proof: &Self::Proof) -> Result<Vec<u8>, ProofSystemError> {
+        serde_json::to_vec(proof).map_err(backend_error)
+    }
+
+    fn proof_from_bytes(bytes: &[u8]) -> Result<Self::Proof, ProofSystemError> {
+        serde_json::from_slice(bytes).map_err(backend_error)
+    }
+}
+
+// the keys only depend on the program, the stdin is only read by the prover
+impl Prover<SP1Stdin> for Sp1Backend {
+    fn setup(
+        &self,
+        _stdin: SP1Stdin,
+    ) -> Result<(Self::ProvingKey, Self::VerifyingKey), ProofSystemError> {
+        Ok(ProverClient::new().setup(self.elf))
+    }
+
+    fn prove(
+        &self,
+        pk: &Self::ProvingKey,
+        stdin: SP1Stdin,
+    ) -> Result<Self::Proof, ProofSystemError> {
+        ProverClient::new().prove(pk, stdin).map_err(backend_error)
+    }
+}